@@ -0,0 +1,107 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use figma_mcp::figma::{
+    layers, normalize,
+    pages::{self, NameFilter},
+    FileIndex,
+};
+use figma_mcp::output;
+use serde_json::{json, Map, Value};
+
+#[path = "../tests/common/corpus.rs"]
+mod corpus;
+
+fn bench_serialization(c: &mut Criterion) {
+    let file = corpus::generate(corpus::LARGE_FILE);
+    let body = output::to_compact_json(&file);
+
+    let mut group = c.benchmark_group("file_response");
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    group.sample_size(10);
+
+    group.bench_function("parse", |b| {
+        b.iter(|| serde_json::from_str::<Value>(black_box(&body)).unwrap())
+    });
+    group.bench_function("serialize_pretty", |b| {
+        b.iter(|| output::to_pretty_json(black_box(&file)))
+    });
+    group.bench_function("serialize_compact", |b| {
+        b.iter(|| output::to_compact_json(black_box(&file)))
+    });
+
+    group.finish();
+}
+
+/// The passes over a parsed file between Figma's response and the tool
+/// result, on clones made outside the measurement.
+fn bench_filtering(c: &mut Criterion) {
+    let file = corpus::generate(corpus::LARGE_FILE);
+    let body = output::to_compact_json(&file);
+
+    let mut group = c.benchmark_group("file_filtering");
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    group.sample_size(10);
+
+    // What get_file does with exclude_hidden, exclude_archived and
+    // deterministic output
+    group.bench_function("prune", |b| {
+        let archive = NameFilter::archive();
+        b.iter_batched(
+            || file.clone(),
+            |mut file| {
+                layers::strip_hidden(&mut file);
+                pages::strip_excluded(&mut file, &archive);
+                normalize::canonicalize(&mut file);
+                normalize::strip_volatile(&mut file);
+                file
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    // What get_file_pages does with a depth-1 outline and the pages'
+    // nodes, selecting every page
+    let (outline, nodes) = outline_and_nodes(&file);
+    let every_page = NameFilter::parse("*").unwrap();
+    group.bench_function("outline", |b| {
+        b.iter_batched(
+            || outline.clone(),
+            |mut outline| {
+                let page_ids = every_page.select(&outline);
+                pages::splice(&mut outline, black_box(&nodes), &page_ids);
+                outline
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    // Flattening the node tree into the design index's definitions and
+    // usages
+    group.bench_function("flatten", |b| {
+        b.iter(|| FileIndex::from_file("bench", black_box(&file), None))
+    });
+
+    group.finish();
+}
+
+/// Splits a file into its depth-1 outline and a `GET /files/:key/nodes`
+/// response holding its pages.
+fn outline_and_nodes(file: &Value) -> (Value, Value) {
+    let mut outline = file.clone();
+    let mut nodes = Map::new();
+    if let Some(Value::Array(pages)) = outline.pointer_mut("/document/children") {
+        for page in pages {
+            nodes.insert(
+                page["id"].as_str().unwrap().to_string(),
+                json!({ "document": page.clone() }),
+            );
+            if let Some(page) = page.as_object_mut() {
+                page.remove("children");
+            }
+        }
+    }
+
+    (outline, json!({ "nodes": nodes }))
+}
+
+criterion_group!(benches, bench_serialization, bench_filtering);
+criterion_main!(benches);
@@ -0,0 +1,151 @@
+// Answers requests from the figma-mcp server, relayed by ui.html.
+
+figma.showUI(__html__, { width: 240, height: 64 });
+
+const handlers = {
+  get_current_selection: () => ({
+    page: { id: figma.currentPage.id, name: figma.currentPage.name },
+    nodes: figma.currentPage.selection.map(serializeNode),
+  }),
+  get_open_file: () => ({
+    fileKey: figma.fileKey ?? null,
+    name: figma.root.name,
+    currentPage: { id: figma.currentPage.id, name: figma.currentPage.name },
+    pages: figma.root.children.map((page) => ({ id: page.id, name: page.name })),
+  }),
+  // Figma's REST JSON, so the server handles it like get_file_nodes output
+  export_selection: async () => {
+    const exports = await Promise.all(
+      figma.currentPage.selection.map((node) => node.exportAsync({ format: "JSON_REST_V1" })),
+    );
+
+    return { nodes: exports.map((exported) => exported.document) };
+  },
+  create_node: async ({ type, parentId, properties }) => {
+    const parent = parentId ? await getNode(parentId) : figma.currentPage;
+    if (!("appendChild" in parent)) {
+      throw new Error(`Node ${parentId} cannot have children`);
+    }
+
+    const node = createNode(type);
+    parent.appendChild(node);
+    await applyProperties(node, properties ?? {});
+
+    return serializeNode(node);
+  },
+  update_node: async ({ nodeId, properties }) => {
+    const node = await getNode(nodeId);
+    await applyProperties(node, properties ?? {});
+
+    return serializeNode(node);
+  },
+  // Each node on its own, so a node deleted since the report doesn't stop
+  // the others from being renamed
+  rename_nodes: async ({ renames }) => {
+    const renamed = [];
+    const failed = [];
+    for (const { nodeId, name } of renames ?? []) {
+      try {
+        const node = await getNode(nodeId);
+        const previousName = node.name;
+        node.name = name;
+        renamed.push({ nodeId, previousName, name });
+      } catch (error) {
+        failed.push({ nodeId, error: String(error?.message ?? error) });
+      }
+    }
+
+    return { renamed, failed };
+  },
+};
+
+figma.ui.onmessage = async ({ id, method, params }) => {
+  const handler = handlers[method];
+  if (!handler) {
+    figma.ui.postMessage({ id, error: `Unknown method: ${method}` });
+    return;
+  }
+
+  try {
+    const result = await handler(params ?? {});
+    figma.ui.postMessage({ id, result });
+  } catch (error) {
+    figma.ui.postMessage({ id, error: String(error?.message ?? error) });
+  }
+};
+
+function createNode(type) {
+  switch (type) {
+    case "FRAME":
+      return figma.createFrame();
+    case "TEXT":
+      return figma.createText();
+    case "RECTANGLE":
+      return figma.createRectangle();
+    default:
+      throw new Error(`Unsupported node type: ${type}`);
+  }
+}
+
+async function getNode(id) {
+  const node = await figma.getNodeByIdAsync(id);
+  if (!node) {
+    throw new Error(`Node not found: ${id}`);
+  }
+
+  return node;
+}
+
+async function applyProperties(node, properties) {
+  const { name, x, y, width, height, fill, opacity, cornerRadius, text, fontSize, visible } =
+    properties;
+
+  // Text can only be edited once its font is loaded
+  if (node.type === "TEXT" && (text !== undefined || fontSize !== undefined)) {
+    const font = node.fontName === figma.mixed ? { family: "Inter", style: "Regular" } : node.fontName;
+    await figma.loadFontAsync(font);
+    node.fontName = font;
+  }
+
+  if (name !== undefined) node.name = name;
+  if (x !== undefined) node.x = x;
+  if (y !== undefined) node.y = y;
+  if (width !== undefined || height !== undefined) {
+    node.resize(width ?? node.width, height ?? node.height);
+  }
+  if (fill !== undefined) node.fills = [solidPaint(fill)];
+  if (opacity !== undefined) node.opacity = opacity;
+  if (cornerRadius !== undefined && "cornerRadius" in node) node.cornerRadius = cornerRadius;
+  if (text !== undefined && node.type === "TEXT") node.characters = text;
+  if (fontSize !== undefined && node.type === "TEXT") node.fontSize = fontSize;
+  if (visible !== undefined) node.visible = visible;
+}
+
+function solidPaint(hex) {
+  const match = /^#?([0-9a-f]{6})$/i.exec(hex);
+  if (!match) {
+    throw new Error(`Invalid color: ${hex}, expected a hex color like #1E88E5`);
+  }
+
+  const value = parseInt(match[1], 16);
+  const color = {
+    r: ((value >> 16) & 255) / 255,
+    g: ((value >> 8) & 255) / 255,
+    b: (value & 255) / 255,
+  };
+
+  return { type: "SOLID", color };
+}
+
+function serializeNode(node) {
+  return {
+    id: node.id,
+    name: node.name,
+    type: node.type,
+    visible: node.visible,
+    x: "x" in node ? node.x : null,
+    y: "y" in node ? node.y : null,
+    width: "width" in node ? node.width : null,
+    height: "height" in node ? node.height : null,
+  };
+}
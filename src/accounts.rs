@@ -0,0 +1,186 @@
+//! Named Figma accounts. Besides `FIGMA_TOKEN` (or OAuth), tokens can be
+//! configured per account as `FIGMA_TOKEN_<NAME>`, and the `switch_account`
+//! tool picks the one later tool calls use.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::figma::{gateway::Gateway, oauth::OAuthSession, FigmaClient};
+use crate::{Error, Result};
+
+/// Name of the account authenticated by `FIGMA_TOKEN` or OAuth.
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// A client per account, and the account in use. Clones share the account
+/// in use; see `for_session` for one switched independently.
+#[derive(Debug, Clone)]
+pub struct Accounts {
+    /// Client of the default account, from which the others are derived
+    base: FigmaClient,
+    clients: BTreeMap<String, FigmaClient>,
+    active: Arc<RwLock<String>>,
+}
+
+impl Accounts {
+    /// Creates the accounts from the default account's client and the named
+    /// accounts' tokens, sent through their gateways if they have one. The
+    /// default account is used first if it has a token, otherwise the first
+    /// named account.
+    pub fn new(
+        base: FigmaClient,
+        tokens: &BTreeMap<String, String>,
+        gateways: &BTreeMap<String, Gateway>,
+    ) -> Result<Self> {
+        let mut clients = BTreeMap::new();
+        if !base.get_token().is_empty() {
+            clients.insert(DEFAULT_ACCOUNT.to_string(), base.clone());
+        }
+        for (name, token) in tokens {
+            let mut client = base.with_token(token.clone())?;
+            if let Some(gateway) = gateways.get(name) {
+                client = client.with_gateway(gateway)?;
+            }
+            clients.insert(name.clone(), client);
+        }
+
+        let active = match clients.keys().next() {
+            Some(name) if !clients.contains_key(DEFAULT_ACCOUNT) => name.clone(),
+            _ => DEFAULT_ACCOUNT.to_string(),
+        };
+
+        Ok(Self {
+            base,
+            clients,
+            active: Arc::new(RwLock::new(active)),
+        })
+    }
+
+    /// Authenticates the default account with OAuth and makes it the
+    /// account in use.
+    pub fn with_oauth(mut self, oauth: OAuthSession) -> Result<Self> {
+        self.base = self.base.with_oauth(oauth);
+        self.clients
+            .insert(DEFAULT_ACCOUNT.to_string(), self.base.clone());
+        *self.write()? = DEFAULT_ACCOUNT.to_string();
+        Ok(self)
+    }
+
+    /// Accounts with the same clients for another session, which switches
+    /// accounts without affecting this one. Starts with the account in use.
+    pub fn for_session(&self) -> Result<Self> {
+        Ok(Self {
+            active: Arc::new(RwLock::new(self.active()?)),
+            ..self.clone()
+        })
+    }
+
+    /// The client of the account in use. Calls keep the client they
+    /// started with when the account is switched meanwhile.
+    pub fn client(&self) -> Result<FigmaClient> {
+        let active = self.read()?;
+
+        Ok(self.clients.get(&*active).unwrap_or(&self.base).clone())
+    }
+
+    /// The clients of all accounts, including the default one.
+    pub fn clients(&self) -> impl Iterator<Item = &FigmaClient> {
+        std::iter::once(&self.base).chain(self.clients.values())
+    }
+
+    pub fn active(&self) -> Result<String> {
+        Ok(self.read()?.clone())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.clients.keys().map(String::as_str).collect()
+    }
+
+    /// Switches to the named account (case-insensitive). Returns `false`
+    /// if there is no such account.
+    pub fn switch(&self, name: &str) -> Result<bool> {
+        let name = name.trim().to_lowercase();
+        if !self.clients.contains_key(&name) {
+            return Ok(false);
+        }
+
+        *self.write()? = name;
+        Ok(true)
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, String>> {
+        self.active
+            .read()
+            .map_err(|_| Error::Internal("Active account lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, String>> {
+        self.active
+            .write()
+            .map_err(|_| Error::Internal("Active account lock poisoned".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(names: &[&str]) -> BTreeMap<String, String> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), format!("token-{}", name)))
+            .collect()
+    }
+
+    #[test]
+    fn test_switch_account() {
+        let base = FigmaClient::new("token-default".to_string()).unwrap();
+        let accounts =
+            Accounts::new(base, &tokens(&["work", "personal"]), &BTreeMap::new()).unwrap();
+        assert_eq!(accounts.names(), ["default", "personal", "work"]);
+        assert_eq!(accounts.active().unwrap(), "default");
+
+        let shared = accounts.clone();
+        assert!(accounts.switch("Work").unwrap());
+        assert_eq!(shared.active().unwrap(), "work");
+        assert_eq!(shared.client().unwrap().get_token(), "token-work");
+
+        assert!(!accounts.switch("agency").unwrap());
+        assert_eq!(accounts.active().unwrap(), "work");
+
+        // Sessions switch on their own
+        let session = accounts.for_session().unwrap();
+        assert!(session.switch("personal").unwrap());
+        assert_eq!(session.client().unwrap().get_token(), "token-personal");
+        assert_eq!(accounts.active().unwrap(), "work");
+    }
+
+    #[test]
+    fn test_poisoned_lock_is_an_error() {
+        let base = FigmaClient::new("token-default".to_string()).unwrap();
+        let accounts = Accounts::new(base, &tokens(&["work"]), &BTreeMap::new()).unwrap();
+        let poisoner = accounts.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.active.write().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+
+        assert!(matches!(accounts.client(), Err(Error::Internal(_))));
+        assert!(matches!(accounts.switch("work"), Err(Error::Internal(_))));
+        assert!(accounts.for_session().is_err());
+    }
+
+    #[test]
+    fn test_named_accounts_only() {
+        let base = FigmaClient::new(String::new()).unwrap();
+        let accounts =
+            Accounts::new(base, &tokens(&["work", "personal"]), &BTreeMap::new()).unwrap();
+        assert_eq!(accounts.names(), ["personal", "work"]);
+        assert_eq!(accounts.client().unwrap().get_token(), "token-personal");
+
+        let base = FigmaClient::new(String::new()).unwrap();
+        let accounts = Accounts::new(base, &BTreeMap::new(), &BTreeMap::new()).unwrap();
+        assert!(accounts.names().is_empty());
+        assert_eq!(accounts.client().unwrap().get_token(), "");
+    }
+}
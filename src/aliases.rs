@@ -0,0 +1,54 @@
+//! Old names of renamed tools. Calls by an old name still run the tool,
+//! with a deprecation notice in the result, so prompts written against
+//! earlier versions keep working.
+
+/// A former name of a tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolAlias {
+    /// The name clients may still call
+    pub name: &'static str,
+    /// The tool it now refers to
+    pub tool: &'static str,
+    /// The version the tool was renamed in
+    pub since: &'static str,
+}
+
+/// Renamed tools. When renaming a tool, add its old name here instead of
+/// breaking the clients that call it. The old names are not listed by
+/// `tools/list`.
+pub const TOOL_ALIASES: &[ToolAlias] = &[];
+
+impl ToolAlias {
+    /// The notice added to the results of calls by the old name.
+    pub fn notice(&self) -> String {
+        format!(
+            "Deprecated: the tool `{}` was renamed to `{}` in version {} and \
+             the old name will be removed; call `{}` instead.",
+            self.name, self.tool, self.since, self.tool
+        )
+    }
+}
+
+/// Looks up a tool name among the old names of `aliases`.
+pub fn resolve<'a>(aliases: &'a [ToolAlias], name: &str) -> Option<&'a ToolAlias> {
+    aliases.iter().find(|alias| alias.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_old_names() {
+        let aliases = [ToolAlias {
+            name: "get_file",
+            tool: "get_file_outline",
+            since: "0.9.0",
+        }];
+
+        let alias = resolve(&aliases, "get_file").unwrap();
+        assert_eq!(alias.tool, "get_file_outline");
+        assert!(alias.notice().contains("call `get_file_outline` instead"));
+        assert_eq!(resolve(&aliases, "get_file_outline"), None);
+    }
+}
@@ -0,0 +1,63 @@
+//! ZIP archives, written with the `zip` crate. Entries are stored
+//! uncompressed: the archives hold mostly PNG images, which are already
+//! compressed.
+
+use std::io::{Cursor, Write};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::{Error, Result};
+
+/// Builds a ZIP archive from `(path, content)` entries, in the given order.
+/// Entries carry the earliest date a ZIP entry can, 1980-01-01, so archives
+/// of identical content are byte-identical.
+pub fn zip(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .last_modified_time(DateTime::default());
+
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in entries {
+        archive
+            .start_file(name.as_str(), options)
+            .map_err(zip_error)?;
+        archive
+            .write_all(content)
+            .map_err(|e| Error::Internal(format!("Failed to write ZIP archive: {}", e)))?;
+    }
+
+    Ok(archive.finish().map_err(zip_error)?.into_inner())
+}
+
+fn zip_error(e: zip::result::ZipError) -> Error {
+    Error::Internal(format!("Failed to write ZIP archive: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_layout() {
+        let entries = vec![
+            ("SPEC.md".to_string(), b"# Spec\n".to_vec()),
+            ("images/a.png".to_string(), vec![1, 2, 3]),
+        ];
+
+        let archive = zip(&entries).unwrap();
+
+        assert!(archive.starts_with(b"PK\x03\x04"));
+        let end = &archive[archive.len() - 22..];
+        assert!(end.starts_with(b"PK\x05\x06"));
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+
+        let offset = u32::from_le_bytes([end[16], end[17], end[18], end[19]]) as usize;
+        assert!(archive[offset..].starts_with(b"PK\x01\x02"));
+        assert_eq!(zip(&entries).unwrap(), archive);
+
+        let mut read = zip::ZipArchive::new(Cursor::new(&archive)).unwrap();
+        let mut spec = String::new();
+        std::io::Read::read_to_string(&mut read.by_name("SPEC.md").unwrap(), &mut spec).unwrap();
+        assert_eq!(spec, "# Spec\n");
+    }
+}
@@ -0,0 +1,208 @@
+//! Design reference attachments for pull requests: PNG exports of frames plus
+//! a markdown spec that references them by relative path, written as a
+//! directory or a ZIP archive.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::{
+    archive,
+    figma::{
+        bounds::{Bounds, BoundsKind},
+        codegen,
+        export::ExportOptions,
+        image_cache, FigmaClient,
+    },
+    Error, Result,
+};
+
+pub const SPEC_FILE_NAME: &str = "SPEC.md";
+
+const DEFAULT_TITLE: &str = "Design reference";
+
+/// Files of an attachment, keyed by their path relative to its root.
+#[derive(Debug, Clone, Default)]
+pub struct Attachment {
+    pub entries: Vec<(String, Vec<u8>)>,
+}
+
+/// An exported frame and where its image lives in the attachment.
+struct Frame<'a> {
+    node_id: &'a str,
+    node: &'a Value,
+    image_path: String,
+    /// The box the image covers
+    bounds: Option<Bounds>,
+}
+
+impl Attachment {
+    /// Exports `node_ids` as PNG images covering their `bounds` and generates
+    /// a spec describing them.
+    pub async fn build(
+        client: &FigmaClient,
+        file_key: &str,
+        node_ids: &[String],
+        scale: Option<f64>,
+        bounds: BoundsKind,
+        title: Option<&str>,
+    ) -> Result<Self> {
+        let nodes = client.get_file_nodes(file_key, node_ids, Some(1)).await?;
+        let export = client
+            .export_images(
+                file_key,
+                node_ids,
+                "png",
+                ExportOptions {
+                    scale,
+                    use_absolute_bounds: bounds.use_absolute_bounds(),
+                    ..ExportOptions::default()
+                },
+            )
+            .await?;
+
+        let mut entries = Vec::new();
+        let mut frames = Vec::new();
+        for node_id in node_ids {
+            let node = nodes
+                .pointer(&format!("/nodes/{}/document", node_id))
+                .ok_or_else(|| Error::NotFound(format!("Node {} not found", node_id)))?;
+            let url = export
+                .pointer(&format!("/images/{}", node_id))
+                .and_then(Value::as_str)
+                .ok_or_else(|| Error::NotFound(format!("Figma did not render node {}", node_id)))?;
+
+            let image_path = format!("images/{}.png", image_stem(node_id, node));
+            entries.push((image_path.clone(), client.download_image(url, "png").await?));
+            frames.push(Frame {
+                node_id,
+                node,
+                image_path,
+                bounds: Bounds::of_kind(node, bounds),
+            });
+        }
+
+        let spec = spec_markdown(file_key, title.unwrap_or(DEFAULT_TITLE), &frames);
+        entries.insert(0, (SPEC_FILE_NAME.to_string(), spec.into_bytes()));
+
+        Ok(Self { entries })
+    }
+
+    /// The files writing below `dir` would replace.
+    pub fn existing_files(&self, dir: &Path) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .map(|(name, _)| dir.join(name))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Writes the files below `dir`, returning their paths.
+    pub fn write_directory(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for (name, content) in &self.entries {
+            let path = dir.join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| write_error(parent, e))?;
+            }
+            fs::write(&path, content).map_err(|e| write_error(&path, e))?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    /// Writes the files into a ZIP archive at `path`.
+    pub fn write_zip(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|e| write_error(parent, e))?;
+        }
+
+        let archive = archive::zip(&self.entries)?;
+
+        fs::write(path, archive).map_err(|e| write_error(path, e))
+    }
+}
+
+fn spec_markdown(file_key: &str, title: &str, frames: &[Frame]) -> String {
+    let mut spec = format!("# {}\n", title);
+
+    for frame in frames {
+        let name = frame
+            .node
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or(frame.node_id);
+        let link = format!(
+            "https://www.figma.com/design/{}?node-id={}",
+            file_key,
+            frame.node_id.replace(':', "-")
+        );
+
+        spec.push_str(&format!("\n## {}\n\n", name));
+        spec.push_str(&format!("![{}]({})\n\n", name, frame.image_path));
+        spec.push_str("| Property | Value |\n| --- | --- |\n");
+        spec.push_str(&format!("| Node | [{}]({}) |\n", frame.node_id, link));
+
+        if let Some(bounds) = frame.bounds {
+            spec.push_str(&format!(
+                "| Size | {} × {} |\n",
+                codegen::format_number(bounds.width),
+                codegen::format_number(bounds.height)
+            ));
+        }
+
+        for (property, value) in codegen::css_properties(frame.node, None) {
+            spec.push_str(&format!(
+                "| `{}` | `{}` |\n",
+                property,
+                value.replace('|', "\\|")
+            ));
+        }
+    }
+
+    spec
+}
+
+/// File name stem for a frame image, unique per node: `checkout-1-2`.
+fn image_stem(node_id: &str, node: &Value) -> String {
+    image_cache::file_stem(node_id, node.get("name").and_then(Value::as_str))
+}
+
+fn write_error(path: &Path, e: std::io::Error) -> Error {
+    Error::Internal(format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_spec_references_images_relatively() {
+        let node = json!({
+            "name": "Checkout",
+            "type": "FRAME",
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 375, "height": 812 },
+            "fills": [{ "type": "SOLID", "color": { "r": 1, "g": 1, "b": 1, "a": 1 } }]
+        });
+        let frames = vec![Frame {
+            node_id: "1:2",
+            node: &node,
+            image_path: format!("images/{}.png", image_stem("1:2", &node)),
+            bounds: Bounds::of_kind(&node, BoundsKind::Render),
+        }];
+
+        let spec = spec_markdown("ABC123", "Checkout redesign", &frames);
+
+        assert!(spec.starts_with("# Checkout redesign\n"));
+        assert!(spec.contains("![Checkout](images/checkout-1-2.png)"));
+        assert!(spec.contains("https://www.figma.com/design/ABC123?node-id=1-2"));
+        assert!(spec.contains("| Size | 375 × 812 |"));
+        assert!(spec.contains("| `background-color` | `#ffffff` |"));
+    }
+}
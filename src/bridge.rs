@@ -0,0 +1,386 @@
+//! WebSocket bridge to the companion Figma plugin in `plugin/`.
+//!
+//! The REST API only sees saved file contents. The plugin runs inside the
+//! designer's editor and answers requests about its live state, such as the
+//! current selection. The server sends `{"id", "method", "params"}` messages
+//! and the plugin replies with `{"id", "result"}` or `{"id", "error"}`.
+//!
+//! Browsers let any web page open a WebSocket to localhost, so handshakes
+//! carrying an `Origin` other than the plugin iframe's opaque `null` are
+//! refused.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header, StatusCode};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{Error, Result};
+
+type PendingRequests = HashMap<u64, oneshot::Sender<Result<Value>>>;
+
+/// Accepts a connection from the companion plugin and relays requests to it.
+/// Only the most recently connected plugin instance receives requests.
+#[derive(Clone)]
+pub struct PluginBridge {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    local_addr: std::net::SocketAddr,
+    connection: Mutex<Option<Connection>>,
+    pending: Mutex<PendingRequests>,
+    next_id: AtomicU64,
+}
+
+struct Connection {
+    id: u64,
+    sender: mpsc::UnboundedSender<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginResponse {
+    id: u64,
+    #[serde(default)]
+    result: Value,
+    error: Option<String>,
+}
+
+impl PluginBridge {
+    /// Listens for plugin connections on the given address, e.g.
+    /// `127.0.0.1:3055`.
+    pub async fn bind(addr: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::Bridge(format!("Failed to listen on {}: {}", addr, e)))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| Error::Bridge(e.to_string()))?;
+
+        let bridge = Self {
+            inner: Arc::new(Inner {
+                local_addr,
+                connection: Mutex::new(None),
+                pending: Mutex::new(HashMap::new()),
+                next_id: AtomicU64::new(1),
+            }),
+        };
+
+        tracing::info!("Plugin bridge listening on {}", local_addr);
+        tokio::spawn(bridge.clone().accept_loop(listener));
+
+        Ok(bridge)
+    }
+
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+        self.inner.local_addr
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.inner
+            .connection
+            .lock()
+            .map(|connection| connection.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Sends a request to the connected plugin and waits for its reply.
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let message = json!({ "id": id, "method": method, "params": params });
+
+        let (sender, receiver) = oneshot::channel();
+        self.pending()?.insert(id, sender);
+        let _pending = PendingGuard { bridge: self, id };
+
+        let sent = {
+            let connection = self
+                .inner
+                .connection
+                .lock()
+                .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+            connection.as_ref().is_some_and(|connection| {
+                connection
+                    .sender
+                    .send(Message::text(message.to_string()))
+                    .is_ok()
+            })
+        };
+
+        if !sent {
+            return Err(not_connected());
+        }
+
+        // The pending entry is removed by the reader, by a disconnect, or
+        // by the guard when the caller gives up waiting
+        let Ok(result) = receiver.await else {
+            return Err(not_connected());
+        };
+
+        result
+    }
+
+    async fn accept_loop(self, listener: TcpListener) {
+        let mut next_connection_id = 1;
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept plugin connection: {}", e);
+                    continue;
+                }
+            };
+
+            let connection_id = next_connection_id;
+            next_connection_id += 1;
+            tokio::spawn(self.clone().handle_connection(stream, connection_id));
+        }
+    }
+
+    async fn handle_connection(self, stream: TcpStream, connection_id: u64) {
+        let socket = match tokio_tungstenite::accept_hdr_async(stream, check_origin).await {
+            Ok(socket) => socket,
+            Err(e) => {
+                tracing::warn!("Plugin WebSocket handshake failed: {}", e);
+                return;
+            }
+        };
+
+        tracing::info!("Figma plugin connected");
+        let (mut sink, mut stream) = socket.split();
+        let (sender, mut outgoing) = mpsc::unbounded_channel::<Message>();
+
+        if let Ok(mut connection) = self.inner.connection.lock() {
+            *connection = Some(Connection {
+                id: connection_id,
+                sender,
+            });
+        }
+
+        // Dropping the sender, when another plugin instance connects, ends
+        // the writer and closes this socket
+        tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+            let _ = sink.close().await;
+        });
+
+        while let Some(Ok(message)) = stream.next().await {
+            let Message::Text(text) = message else {
+                continue;
+            };
+
+            match serde_json::from_str::<PluginResponse>(&text) {
+                Ok(response) => self.complete(response),
+                Err(e) => tracing::warn!("Invalid message from Figma plugin: {}", e),
+            }
+        }
+
+        tracing::info!("Figma plugin disconnected");
+        self.disconnect(connection_id);
+    }
+
+    fn complete(&self, response: PluginResponse) {
+        let Ok(mut pending) = self.pending() else {
+            return;
+        };
+        let Some(sender) = pending.remove(&response.id) else {
+            return;
+        };
+
+        let result = match response.error {
+            Some(error) => Err(Error::Bridge(error)),
+            None => Ok(response.result),
+        };
+
+        let _ = sender.send(result);
+    }
+
+    fn disconnect(&self, connection_id: u64) {
+        let Ok(mut connection) = self.inner.connection.lock() else {
+            return;
+        };
+        if connection.as_ref().is_none_or(|c| c.id != connection_id) {
+            return;
+        }
+        *connection = None;
+
+        // Requests sent to this plugin instance will not be answered anymore
+        if let Ok(mut pending) = self.pending() {
+            pending.clear();
+        }
+    }
+
+    fn pending(&self) -> Result<std::sync::MutexGuard<'_, PendingRequests>> {
+        self.inner
+            .pending
+            .lock()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))
+    }
+}
+
+/// Removes a request's pending entry when the request future is dropped,
+/// e.g. on a tool timeout or cancellation.
+struct PendingGuard<'a> {
+    bridge: &'a PluginBridge,
+    id: u64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = self.bridge.pending() {
+            pending.remove(&self.id);
+        }
+    }
+}
+
+/// Accepts handshakes from the plugin, whose iframe has the `null` origin,
+/// and from clients that are not browsers and send no `Origin`. The
+/// signature is the one tungstenite's handshake callback requires.
+#[allow(clippy::result_large_err)]
+fn check_origin(
+    request: &Request,
+    response: Response,
+) -> std::result::Result<Response, ErrorResponse> {
+    match request.headers().get(header::ORIGIN) {
+        None => Ok(response),
+        Some(origin) if origin.as_bytes() == b"null" => Ok(response),
+        Some(origin) => {
+            tracing::warn!("Refused plugin bridge connection from origin {:?}", origin);
+            let mut response = ErrorResponse::new(Some("Origin not allowed".to_string()));
+            *response.status_mut() = StatusCode::FORBIDDEN;
+            Err(response)
+        }
+    }
+}
+
+fn not_connected() -> Error {
+    Error::Bridge(
+        "No Figma plugin connected. Run the figma-mcp plugin in the Figma desktop app to share the editor state.".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio_tungstenite::connect_async;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    async fn wait_until_connected(bridge: &PluginBridge) {
+        for _ in 0..100 {
+            if bridge.is_connected() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Plugin did not connect");
+    }
+
+    #[tokio::test]
+    async fn test_request_without_plugin() {
+        let bridge = PluginBridge::bind("127.0.0.1:0").await.unwrap();
+
+        let result = bridge.request("get_current_selection", json!({})).await;
+        assert!(matches!(result, Err(Error::Bridge(_))));
+    }
+
+    #[tokio::test]
+    async fn test_request_round_trip() {
+        let bridge = PluginBridge::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", bridge.local_addr());
+        let (mut plugin, _) = connect_async(url).await.unwrap();
+        wait_until_connected(&bridge).await;
+
+        // Fake plugin answering a single request
+        tokio::spawn(async move {
+            let Some(Ok(Message::Text(text))) = plugin.next().await else {
+                return;
+            };
+            let request: Value = serde_json::from_str(&text).unwrap();
+            let response = match request["method"].as_str() {
+                Some("get_current_selection") => {
+                    json!({ "id": request["id"], "result": [{ "id": "1:2" }] })
+                }
+                _ => json!({ "id": request["id"], "error": "Unknown method" }),
+            };
+            plugin
+                .send(Message::text(response.to_string()))
+                .await
+                .unwrap();
+        });
+
+        let result = bridge
+            .request("get_current_selection", json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, json!([{ "id": "1:2" }]));
+    }
+
+    #[tokio::test]
+    async fn test_web_page_origins_are_refused() {
+        let bridge = PluginBridge::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", bridge.local_addr());
+        let connect = |origin: &'static str| {
+            let mut request = url.as_str().into_client_request().unwrap();
+            request
+                .headers_mut()
+                .insert(header::ORIGIN, origin.parse().unwrap());
+            connect_async(request)
+        };
+
+        assert!(connect("https://example.com").await.is_err());
+        assert!(!bridge.is_connected());
+
+        let _plugin = connect("null").await.unwrap();
+        wait_until_connected(&bridge).await;
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_request_is_forgotten() {
+        let bridge = PluginBridge::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", bridge.local_addr());
+        let (_plugin, _) = connect_async(url).await.unwrap();
+        wait_until_connected(&bridge).await;
+
+        // The plugin never answers
+        let request = bridge.request("get_current_selection", json!({}));
+        let result = tokio::time::timeout(Duration::from_millis(50), request).await;
+        assert!(result.is_err());
+        assert!(bridge.pending().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_error() {
+        let bridge = PluginBridge::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", bridge.local_addr());
+        let (mut plugin, _) = connect_async(url).await.unwrap();
+        wait_until_connected(&bridge).await;
+
+        tokio::spawn(async move {
+            let Some(Ok(Message::Text(text))) = plugin.next().await else {
+                return;
+            };
+            let request: Value = serde_json::from_str(&text).unwrap();
+            let response = json!({ "id": request["id"], "error": "Unknown method" });
+            plugin
+                .send(Message::text(response.to_string()))
+                .await
+                .unwrap();
+        });
+
+        let result = bridge.request("unknown", json!({})).await;
+        assert!(matches!(result, Err(Error::Bridge(message)) if message == "Unknown method"));
+    }
+}
@@ -0,0 +1,614 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{
+    credentials::Credentials,
+    descriptions::Descriptions,
+    encryption::AtRestKey,
+    figma::{
+        gateway::{self, Gateway},
+        oauth::{self, OAuthConfig},
+        pages::NameFilter,
+        roles::RoleRules,
+    },
+    sessions::Visibility,
+    Error, Result,
+};
+
+const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_MAX_PROJECTED_NODES: usize = 10_000;
+const DEFAULT_INLINE_LIMIT: usize = 50_000;
+/// Only the socket file's owner may connect, since every client can use the
+/// Figma token.
+const DEFAULT_SOCKET_MODE: u32 = 0o600;
+
+/// Server configuration, read from `FIGMA_MCP_*` environment variables.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub tool_timeouts: ToolTimeouts,
+    /// Prefix for the User-Agent header sent to Figma
+    pub user_agent: Option<String>,
+    /// Figma REST API version path segment, e.g. `v1`
+    pub api_version: Option<String>,
+    /// Projected node count above which deep fetches require `force`
+    pub max_projected_nodes: usize,
+    /// Size in bytes above which large tool results are returned as
+    /// resource links instead of inline; 0 always inlines them
+    pub inline_limit: usize,
+    /// Most pages a paginated listing follows in one call; unset follows
+    /// all of them
+    pub max_pages: Option<usize>,
+    /// Time allowed to connect to Figma; unset waits up to the tool timeout
+    pub connect_timeout: Option<Duration>,
+    /// Time allowed for a single Figma request; unset waits up to the tool
+    /// timeout
+    pub request_timeout: Option<Duration>,
+    /// Retries of reads failing with rate limits, server errors or dropped
+    /// connections; unset uses the client's default
+    pub retries: Option<u32>,
+    /// Proxy for all Figma requests, e.g. `http://proxy.example.com:8080`
+    pub proxy: Option<String>,
+    /// Figma API requests per minute; unset uses the client's default, 0
+    /// disables rate limiting
+    pub rate_limit: Option<u32>,
+    /// Bytes of API responses kept for conditional requests; unset uses
+    /// the client's default, 0 keeps none
+    pub etag_cache_size: Option<usize>,
+    /// How long file and node responses are reused before checking whether
+    /// the file changed; unset uses `response_cache::DEFAULT_TTL`, 0 reuses
+    /// none
+    pub response_cache_ttl: Option<Duration>,
+    /// Size in bytes above which image resources are read in chunks; 0
+    /// reads them whole
+    pub resource_chunk_size: usize,
+    /// Address to serve MCP over streamable HTTP on instead of stdio, e.g.
+    /// `0.0.0.0:3000`
+    pub http_addr: Option<String>,
+    /// Bearer token HTTP clients must send; unset accepts any client
+    pub http_token: Option<String>,
+    /// Unix socket file to serve MCP on instead of stdio
+    pub socket_path: Option<PathBuf>,
+    /// Permissions of the socket file, e.g. `0o660` to let the group connect
+    pub socket_mode: u32,
+    /// Whether HTTP and socket sessions see each other's resources
+    pub resource_visibility: Visibility,
+    /// Address the companion plugin bridge listens on, e.g. `127.0.0.1:3055`
+    pub bridge_addr: Option<String>,
+    /// Local port to receive Figma webhook callbacks on
+    pub webhook_port: Option<u16>,
+    /// Passcode the webhooks were registered with; deliveries without it
+    /// are refused
+    pub webhook_passcode: Option<String>,
+    /// Path of the workspace manifest, instead of searching from the
+    /// working directory
+    pub workspace: Option<PathBuf>,
+    /// Strip volatile fields and use stable URIs so repeated runs produce
+    /// identical output
+    pub deterministic: bool,
+    /// Leave hidden layers out of extracted node trees unless a tool call
+    /// asks otherwise
+    pub exclude_hidden: bool,
+    /// Redo exports and indexing of files that changed when they are next
+    /// used, instead of reporting them as outdated
+    pub refresh_stale: bool,
+    /// Text stamped on exported images, e.g. `CONFIDENTIAL - internal`
+    pub watermark: Option<String>,
+    /// Archived and scratch pages and frames to leave out of outlines, the
+    /// design index and synced code unless a tool call asks otherwise
+    pub exclude_names: Option<NameFilter>,
+    /// Keywords and thresholds for semantic role inference, from the rules
+    /// file at `FIGMA_MCP_RULES` if set
+    pub rules: RoleRules,
+    /// Translated tool descriptions, from the bundle at
+    /// `FIGMA_MCP_DESCRIPTIONS` if set
+    pub descriptions: Descriptions,
+    /// OAuth app to authenticate with instead of a personal access token
+    pub oauth: Option<OAuthConfig>,
+    /// Whether the OAuth token file is encrypted with a key kept in the OS
+    /// keychain, from `FIGMA_MCP_ENCRYPTION_KEYCHAIN`; the key is created
+    /// on first use
+    pub encryption_keychain: bool,
+    /// Tokens of named accounts besides the default one, from
+    /// `FIGMA_TOKEN_<NAME>` or the credentials file, by lowercased name
+    pub account_tokens: BTreeMap<String, String>,
+    /// API gateway of all accounts, from `FIGMA_MCP_API_HOST`,
+    /// `FIGMA_MCP_AUTH_SCHEME` and `FIGMA_MCP_API_HEADERS`
+    pub gateway: Gateway,
+    /// Gateway settings of named accounts from the credentials file, on
+    /// top of `gateway`
+    pub account_gateways: BTreeMap<String, Gateway>,
+    /// Tokens from the credentials file at `FIGMA_MCP_CONFIG` or
+    /// `~/.config/figma-mcp/config.toml`
+    pub credentials: Credentials,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self> {
+        let mut tool_timeouts = ToolTimeouts::default();
+
+        if let Ok(value) = env::var("FIGMA_MCP_TOOL_TIMEOUT") {
+            tool_timeouts.default = parse_secs("FIGMA_MCP_TOOL_TIMEOUT", &value)?;
+        }
+
+        if let Ok(value) = env::var("FIGMA_MCP_TOOL_TIMEOUTS") {
+            tool_timeouts.per_tool = parse_tool_timeouts(&value)?;
+        }
+
+        let mut max_projected_nodes = DEFAULT_MAX_PROJECTED_NODES;
+        if let Ok(value) = env::var("FIGMA_MCP_MAX_PROJECTED_NODES") {
+            max_projected_nodes = value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_MAX_PROJECTED_NODES: '{}' is not a number",
+                    value
+                ))
+            })?;
+        }
+
+        let mut inline_limit = DEFAULT_INLINE_LIMIT;
+        if let Ok(value) = env::var("FIGMA_MCP_INLINE_LIMIT") {
+            inline_limit = value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_INLINE_LIMIT: '{}' is not a number of bytes",
+                    value
+                ))
+            })?;
+        }
+
+        let mut max_pages = None;
+        if let Ok(value) = env::var("FIGMA_MCP_MAX_PAGES") {
+            max_pages = Some(value.parse().ok().filter(|pages| *pages > 0).ok_or_else(|| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_MAX_PAGES: '{}' is not a positive number",
+                    value
+                ))
+            })?);
+        }
+
+        let connect_timeout = match env::var("FIGMA_MCP_CONNECT_TIMEOUT") {
+            Ok(value) => Some(parse_secs("FIGMA_MCP_CONNECT_TIMEOUT", &value)?),
+            Err(_) => None,
+        };
+        let request_timeout = match env::var("FIGMA_MCP_REQUEST_TIMEOUT") {
+            Ok(value) => Some(parse_secs("FIGMA_MCP_REQUEST_TIMEOUT", &value)?),
+            Err(_) => None,
+        };
+
+        let mut retries = None;
+        if let Ok(value) = env::var("FIGMA_MCP_RETRIES") {
+            retries = Some(value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_RETRIES: '{}' is not a number",
+                    value
+                ))
+            })?);
+        }
+
+        let mut rate_limit = None;
+        if let Ok(value) = env::var("FIGMA_MCP_RATE_LIMIT") {
+            rate_limit = Some(value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_RATE_LIMIT: '{}' is not a number of requests per minute",
+                    value
+                ))
+            })?);
+        }
+
+        let mut etag_cache_size = None;
+        if let Ok(value) = env::var("FIGMA_MCP_ETAG_CACHE_SIZE") {
+            etag_cache_size = Some(value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_ETAG_CACHE_SIZE: '{}' is not a number of bytes",
+                    value
+                ))
+            })?);
+        }
+
+        let mut response_cache_ttl = None;
+        if let Ok(value) = env::var("FIGMA_MCP_RESPONSE_CACHE_TTL") {
+            let secs: u64 = value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_RESPONSE_CACHE_TTL: '{}' is not a number of seconds",
+                    value
+                ))
+            })?;
+            response_cache_ttl = Some(Duration::from_secs(secs));
+        }
+
+        let mut resource_chunk_size = 0;
+        if let Ok(value) = env::var("FIGMA_MCP_RESOURCE_CHUNK_SIZE") {
+            resource_chunk_size = value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid FIGMA_MCP_RESOURCE_CHUNK_SIZE: '{}' is not a number of bytes",
+                    value
+                ))
+            })?;
+        }
+
+        #[cfg(unix)]
+        let socket_mode = match env::var("FIGMA_MCP_SOCKET_MODE") {
+            Ok(value) => crate::socket::parse_mode(&value)?,
+            Err(_) => DEFAULT_SOCKET_MODE,
+        };
+        #[cfg(not(unix))]
+        let socket_mode = DEFAULT_SOCKET_MODE;
+
+        let resource_visibility = match env::var("FIGMA_MCP_RESOURCE_VISIBILITY") {
+            Ok(value) => value.parse()?,
+            Err(_) => Visibility::default(),
+        };
+
+        let mut webhook_port = None;
+        if let Ok(value) = env::var("FIGMA_MCP_WEBHOOK_PORT") {
+            webhook_port = Some(parse_port("FIGMA_MCP_WEBHOOK_PORT", &value)?);
+        }
+
+        let deterministic = match env::var("FIGMA_MCP_DETERMINISTIC") {
+            Ok(value) => parse_bool("FIGMA_MCP_DETERMINISTIC", &value)?,
+            Err(_) => false,
+        };
+
+        let exclude_hidden = match env::var("FIGMA_MCP_EXCLUDE_HIDDEN") {
+            Ok(value) => parse_bool("FIGMA_MCP_EXCLUDE_HIDDEN", &value)?,
+            Err(_) => false,
+        };
+
+        let refresh_stale = match env::var("FIGMA_MCP_REFRESH_STALE") {
+            Ok(value) => parse_bool("FIGMA_MCP_REFRESH_STALE", &value)?,
+            Err(_) => false,
+        };
+
+        // `true` selects the built-in heuristics, anything else is a list of
+        // patterns
+        let exclude_names = match env::var("FIGMA_MCP_EXCLUDE_NAMES") {
+            Ok(value) => match parse_bool("FIGMA_MCP_EXCLUDE_NAMES", &value) {
+                Ok(true) => Some(NameFilter::archive()),
+                Ok(false) => None,
+                Err(_) => NameFilter::parse(&value),
+            },
+            Err(_) => None,
+        };
+
+        let rules = match env::var_os("FIGMA_MCP_RULES") {
+            Some(path) => RoleRules::load(&PathBuf::from(path))?,
+            None => RoleRules::default(),
+        };
+
+        let descriptions = match env::var_os("FIGMA_MCP_DESCRIPTIONS") {
+            Some(path) => Descriptions::load(&PathBuf::from(path))?,
+            None => Descriptions::default(),
+        };
+
+        let oauth = oauth_from_env()?;
+        let encryption_keychain = match env::var("FIGMA_MCP_ENCRYPTION_KEYCHAIN") {
+            Ok(value) => parse_bool("FIGMA_MCP_ENCRYPTION_KEYCHAIN", &value)?,
+            Err(_) => false,
+        };
+        if encryption_keychain && !cfg!(feature = "keychain") {
+            return Err(Error::Config(
+                "FIGMA_MCP_ENCRYPTION_KEYCHAIN needs the `keychain` feature".to_string(),
+            ));
+        }
+        if encryption_keychain && env::var_os("FIGMA_MCP_ENCRYPTION_KEY").is_some() {
+            return Err(Error::Config(
+                "Set either FIGMA_MCP_ENCRYPTION_KEY or FIGMA_MCP_ENCRYPTION_KEYCHAIN".to_string(),
+            ));
+        }
+        let gateway = gateway_from_env()?;
+
+        let credentials_path = env::var_os("FIGMA_MCP_CONFIG")
+            .map(PathBuf::from)
+            .or_else(Credentials::default_path);
+        let credentials = match credentials_path {
+            Some(path) => Credentials::load(&path)?.unwrap_or_default(),
+            None => Credentials::default(),
+        };
+        // Tokens in the environment take precedence
+        let mut account_tokens = credentials.accounts.clone();
+        account_tokens.extend(parse_account_tokens(env::vars()));
+
+        Ok(Self {
+            tool_timeouts,
+            user_agent: env::var("FIGMA_MCP_USER_AGENT").ok(),
+            api_version: env::var("FIGMA_MCP_API_VERSION").ok(),
+            max_projected_nodes,
+            inline_limit,
+            max_pages,
+            connect_timeout,
+            request_timeout,
+            retries,
+            proxy: env::var("FIGMA_MCP_PROXY").ok().filter(|proxy| !proxy.is_empty()),
+            rate_limit,
+            etag_cache_size,
+            response_cache_ttl,
+            resource_chunk_size,
+            http_addr: env::var("FIGMA_MCP_HTTP_ADDR").ok(),
+            http_token: env::var("FIGMA_MCP_HTTP_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+            socket_path: env::var_os("FIGMA_MCP_SOCKET").map(PathBuf::from),
+            socket_mode,
+            resource_visibility,
+            bridge_addr: env::var("FIGMA_MCP_BRIDGE_ADDR").ok(),
+            webhook_port,
+            // An empty passcode would accept deliveries carrying an empty one
+            webhook_passcode: env::var("FIGMA_MCP_WEBHOOK_PASSCODE")
+                .ok()
+                .filter(|passcode| !passcode.is_empty()),
+            workspace: env::var_os("FIGMA_MCP_WORKSPACE").map(PathBuf::from),
+            deterministic,
+            exclude_hidden,
+            refresh_stale,
+            watermark: env::var("FIGMA_MCP_WATERMARK")
+                .ok()
+                .filter(|text| !text.trim().is_empty()),
+            exclude_names,
+            rules,
+            descriptions,
+            oauth,
+            encryption_keychain,
+            account_tokens,
+            gateway,
+            account_gateways: credentials.gateways.clone(),
+            credentials,
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tool_timeouts: ToolTimeouts::default(),
+            user_agent: None,
+            api_version: None,
+            max_projected_nodes: DEFAULT_MAX_PROJECTED_NODES,
+            inline_limit: DEFAULT_INLINE_LIMIT,
+            max_pages: None,
+            connect_timeout: None,
+            request_timeout: None,
+            retries: None,
+            proxy: None,
+            rate_limit: None,
+            etag_cache_size: None,
+            response_cache_ttl: None,
+            resource_chunk_size: 0,
+            http_addr: None,
+            http_token: None,
+            socket_path: None,
+            socket_mode: DEFAULT_SOCKET_MODE,
+            resource_visibility: Visibility::default(),
+            bridge_addr: None,
+            webhook_port: None,
+            webhook_passcode: None,
+            workspace: None,
+            deterministic: false,
+            exclude_hidden: false,
+            refresh_stale: false,
+            watermark: None,
+            exclude_names: None,
+            rules: RoleRules::default(),
+            descriptions: Descriptions::default(),
+            oauth: None,
+            encryption_keychain: false,
+            account_tokens: BTreeMap::new(),
+            gateway: Gateway::default(),
+            account_gateways: BTreeMap::new(),
+            credentials: Credentials::default(),
+        }
+    }
+}
+
+/// Maximum execution time per tool, with an optional override per tool name.
+#[derive(Debug, Clone)]
+pub struct ToolTimeouts {
+    pub default: Duration,
+    pub per_tool: HashMap<String, Duration>,
+}
+
+impl ToolTimeouts {
+    pub fn for_tool(&self, tool: &str) -> Duration {
+        self.per_tool.get(tool).copied().unwrap_or(self.default)
+    }
+}
+
+impl Default for ToolTimeouts {
+    fn default() -> Self {
+        Self {
+            default: Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS),
+            per_tool: HashMap::new(),
+        }
+    }
+}
+
+/// Parses a list like `export_images=30,generate_alt_text=300`.
+fn parse_tool_timeouts(value: &str) -> Result<HashMap<String, Duration>> {
+    let mut timeouts = HashMap::new();
+
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((tool, secs)) = entry.split_once('=') else {
+            return Err(Error::Config(format!(
+                "Invalid tool timeout '{}', expected tool=seconds",
+                entry
+            )));
+        };
+
+        let timeout = parse_secs(tool.trim(), secs.trim())?;
+        timeouts.insert(tool.trim().to_string(), timeout);
+    }
+
+    Ok(timeouts)
+}
+
+/// Reads the OAuth app from `FIGMA_MCP_OAUTH_*`, set when its client ID
+/// is.
+fn oauth_from_env() -> Result<Option<OAuthConfig>> {
+    let Ok(client_id) = env::var("FIGMA_MCP_OAUTH_CLIENT_ID") else {
+        return Ok(None);
+    };
+    let client_secret = env::var("FIGMA_MCP_OAUTH_CLIENT_SECRET").map_err(|_| {
+        Error::Config(
+            "FIGMA_MCP_OAUTH_CLIENT_SECRET must be set along with FIGMA_MCP_OAUTH_CLIENT_ID"
+                .to_string(),
+        )
+    })?;
+
+    let mut redirect_port = oauth::DEFAULT_REDIRECT_PORT;
+    if let Ok(value) = env::var("FIGMA_MCP_OAUTH_REDIRECT_PORT") {
+        redirect_port = parse_port("FIGMA_MCP_OAUTH_REDIRECT_PORT", &value)?;
+    }
+
+    let token_file = env::var_os("FIGMA_MCP_OAUTH_TOKEN_FILE")
+        .map(PathBuf::from)
+        .or_else(OAuthConfig::default_token_file)
+        .ok_or_else(|| {
+            Error::Config(
+                "Set FIGMA_MCP_OAUTH_TOKEN_FILE, no configuration directory was found"
+                    .to_string(),
+            )
+        })?;
+
+    Ok(Some(OAuthConfig {
+        client_id,
+        client_secret,
+        scopes: env::var("FIGMA_MCP_OAUTH_SCOPES")
+            .unwrap_or_else(|_| oauth::DEFAULT_SCOPES.to_string()),
+        redirect_port,
+        token_file,
+        encryption: env::var("FIGMA_MCP_ENCRYPTION_KEY")
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty())
+            .map(AtRestKey::Passphrase),
+    }))
+}
+
+/// Reads the API gateway from `FIGMA_MCP_API_HOST`, `FIGMA_MCP_AUTH_SCHEME`
+/// and `FIGMA_MCP_API_HEADERS`.
+fn gateway_from_env() -> Result<Gateway> {
+    let gateway = Gateway {
+        api_host: env::var("FIGMA_MCP_API_HOST")
+            .ok()
+            .filter(|host| !host.is_empty()),
+        auth_scheme: match env::var("FIGMA_MCP_AUTH_SCHEME") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        },
+        headers: match env::var("FIGMA_MCP_API_HEADERS") {
+            Ok(value) => gateway::parse_headers(&value)?,
+            Err(_) => BTreeMap::new(),
+        },
+    };
+    // Report a bad host or header at startup rather than on the first call
+    gateway.host()?;
+    gateway.header_map()?;
+
+    Ok(gateway)
+}
+
+/// Collects the tokens of named accounts, e.g. `FIGMA_TOKEN_WORK` as
+/// account `work`.
+fn parse_account_tokens(vars: impl Iterator<Item = (String, String)>) -> BTreeMap<String, String> {
+    vars.filter_map(|(name, token)| {
+        let account = name.strip_prefix("FIGMA_TOKEN_")?.to_lowercase();
+        (!account.is_empty() && !token.is_empty()).then_some((account, token))
+    })
+    .collect()
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" | "" => Ok(false),
+        _ => Err(Error::Config(format!(
+            "Invalid {}: '{}' is not a boolean",
+            name, value
+        ))),
+    }
+}
+
+/// Parses a port number given by the environment variable or command line
+/// option `name`.
+pub fn parse_port(name: &str, value: &str) -> Result<u16> {
+    value.trim().parse().map_err(|_| {
+        Error::Config(format!(
+            "Invalid {}: '{}' is not a port number",
+            name, value
+        ))
+    })
+}
+
+fn parse_secs(name: &str, value: &str) -> Result<Duration> {
+    let secs: u64 = value.parse().map_err(|_| {
+        Error::Config(format!(
+            "Invalid timeout for {}: '{}' is not a number of seconds",
+            name, value
+        ))
+    })?;
+
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_timeouts() {
+        let timeouts = parse_tool_timeouts("export_images=30, generate_alt_text=300").unwrap();
+        assert_eq!(
+            timeouts.get("export_images"),
+            Some(&Duration::from_secs(30))
+        );
+        assert_eq!(
+            timeouts.get("generate_alt_text"),
+            Some(&Duration::from_secs(300))
+        );
+    }
+
+    #[test]
+    fn test_parse_account_tokens() {
+        let vars = [
+            ("FIGMA_TOKEN", "default"),
+            ("FIGMA_TOKEN_WORK", "work-token"),
+            ("FIGMA_TOKEN_CLIENT_A", "client-token"),
+            ("FIGMA_TOKEN_EMPTY", ""),
+            ("FIGMA_MCP_TOOL_TIMEOUT", "30"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let tokens = parse_account_tokens(vars.into_iter());
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens["work"], "work-token");
+        assert_eq!(tokens["client_a"], "client-token");
+    }
+
+    #[test]
+    fn test_parse_tool_timeouts_invalid() {
+        assert!(parse_tool_timeouts("export_images").is_err());
+        assert!(parse_tool_timeouts("export_images=soon").is_err());
+    }
+
+    #[test]
+    fn test_empty_webhook_passcode_is_unset() {
+        // The only test setting this variable
+        env::set_var("FIGMA_MCP_WEBHOOK_PASSCODE", "");
+        let config = Config::from_env().unwrap();
+        env::remove_var("FIGMA_MCP_WEBHOOK_PASSCODE");
+
+        assert_eq!(config.webhook_passcode, None);
+    }
+
+    #[test]
+    fn test_timeout_falls_back_to_default() {
+        let mut timeouts = ToolTimeouts::default();
+        timeouts
+            .per_tool
+            .insert("get_file".to_string(), Duration::from_secs(5));
+
+        assert_eq!(timeouts.for_tool("get_file"), Duration::from_secs(5));
+        assert_eq!(
+            timeouts.for_tool("get_me"),
+            Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS)
+        );
+    }
+}
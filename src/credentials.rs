@@ -0,0 +1,269 @@
+//! Figma tokens kept out of the environment, where they show up in process
+//! listings and MCP client configurations: in a credentials file, or with
+//! the `keychain` feature in the OS keychain.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+#[cfg(feature = "keychain")]
+use crate::accounts::DEFAULT_ACCOUNT;
+#[cfg(feature = "keychain")]
+use crate::encryption::{self, AtRestKey};
+use crate::figma::gateway::{AuthScheme, Gateway};
+use crate::{Error, Result};
+
+/// Keychain service the tokens are stored under, with the account name as
+/// user.
+pub const KEYCHAIN_SERVICE: &str = "figma-mcp";
+
+/// Keychain service of the key files on disk are encrypted with, apart
+/// from the tokens so no account name can clash with it.
+pub const ENCRYPTION_KEYCHAIN_SERVICE: &str = "figma-mcp-encryption";
+
+/// Tokens from the credentials file, `~/.config/figma-mcp/config.toml`
+/// unless `FIGMA_MCP_CONFIG` names another:
+///
+/// ```toml
+/// token = "figd_..."
+///
+/// [accounts]
+/// work = "figd_..."
+///
+/// # Accounts behind an API gateway
+/// [accounts.corp]
+/// token = "..."
+/// api_host = "https://figma-gateway.example.com"
+/// auth_scheme = "bearer"
+/// headers = { "X-Org" = "acme" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Credentials {
+    /// Token of the default account, used when `FIGMA_TOKEN` is not set
+    pub token: Option<String>,
+    /// Tokens of named accounts, like `FIGMA_TOKEN_<NAME>`
+    pub accounts: BTreeMap<String, String>,
+    /// API gateways of the named accounts that set one
+    pub gateways: BTreeMap<String, Gateway>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CredentialsFile {
+    token: Option<String>,
+    #[serde(default)]
+    accounts: BTreeMap<String, AccountEntry>,
+}
+
+/// An account's token, alone or with the gateway it goes through.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AccountEntry {
+    Token(String),
+    Gateway(GatewayAccount),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GatewayAccount {
+    token: String,
+    api_host: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+}
+
+impl Credentials {
+    /// Reads the credentials file, if there is one.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(Error::Config(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        warn_if_shared(path);
+
+        Self::parse(&content)
+            .map(Some)
+            .map_err(|e| Error::Config(format!("Invalid {}: {}", path.display(), e)))
+    }
+
+    pub fn parse(content: &str) -> std::result::Result<Self, toml::de::Error> {
+        let file: CredentialsFile = toml::from_str(content)?;
+        let mut credentials = Self {
+            token: file.token,
+            ..Self::default()
+        };
+        for (name, entry) in file.accounts {
+            let name = name.to_lowercase();
+            match entry {
+                AccountEntry::Token(token) => {
+                    credentials.accounts.insert(name, token);
+                }
+                AccountEntry::Gateway(account) => {
+                    let gateway = Gateway {
+                        api_host: account.api_host,
+                        auth_scheme: account.auth_scheme,
+                        headers: account.headers,
+                    };
+                    credentials.accounts.insert(name.clone(), account.token);
+                    credentials.gateways.insert(name, gateway);
+                }
+            }
+        }
+
+        Ok(credentials)
+    }
+
+    /// The default credentials file: `figma-mcp/config.toml` in the user's
+    /// configuration directory.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(config_dir()?.join("figma-mcp").join("config.toml"))
+    }
+}
+
+/// The user's configuration directory: `$XDG_CONFIG_HOME`, `~/.config` or,
+/// on Windows, `%APPDATA%`.
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+}
+
+/// Reads the token of an account from the OS keychain. Blocks, so call it
+/// outside of async code.
+#[cfg(feature = "keychain")]
+pub fn keychain_token(account: &str) -> Result<Option<String>> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, account).map_err(keychain_error)?;
+
+    match entry.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(keychain_error(e)),
+    }
+}
+
+/// Stores the token of an account in the OS keychain. Blocks, so call it
+/// outside of async code.
+#[cfg(feature = "keychain")]
+pub fn store_keychain_token(account: &str, token: &str) -> Result<()> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, account)
+        .and_then(|entry| entry.set_password(token))
+        .map_err(keychain_error)
+}
+
+/// Deletes an entry from the OS keychain, returning whether there was one.
+/// Blocks, so call it outside of async code.
+#[cfg(feature = "keychain")]
+pub fn delete_keychain_entry(service: &str, account: &str) -> Result<bool> {
+    let entry = keyring::Entry::new(service, account).map_err(keychain_error)?;
+
+    match entry.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(keychain_error(e)),
+    }
+}
+
+/// Reads the key files on disk are encrypted with from the OS keychain,
+/// storing a new random one there the first time. Blocks, so call it
+/// outside of async code.
+#[cfg(feature = "keychain")]
+pub fn keychain_encryption_key() -> Result<AtRestKey> {
+    let entry =
+        keyring::Entry::new(ENCRYPTION_KEYCHAIN_SERVICE, DEFAULT_ACCOUNT).map_err(keychain_error)?;
+
+    match entry.get_password() {
+        Ok(hex) => encryption::parse_hex_key(&hex).ok_or_else(|| {
+            Error::Auth("OS keychain: the stored encryption key is invalid".to_string())
+        }),
+        Err(keyring::Error::NoEntry) => {
+            let key = encryption::random_key();
+            entry
+                .set_password(&encryption::to_hex(&key))
+                .map_err(keychain_error)?;
+            Ok(AtRestKey::Key(key))
+        }
+        Err(e) => Err(keychain_error(e)),
+    }
+}
+
+#[cfg(feature = "keychain")]
+fn keychain_error(e: keyring::Error) -> Error {
+    Error::Auth(format!("OS keychain: {}", e))
+}
+
+/// Tokens in a file others can read are not much safer than in the
+/// environment.
+#[cfg(unix)]
+fn warn_if_shared(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        if metadata.permissions().mode() & 0o077 != 0 {
+            tracing::warn!(
+                "{} is readable by other users; restrict it with `chmod 600`",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_shared(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_credentials() {
+        let credentials = Credentials::parse(
+            r#"
+            token = "figd_default"
+
+            [accounts]
+            Work = "figd_work"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(credentials.token.as_deref(), Some("figd_default"));
+        assert_eq!(credentials.accounts["work"], "figd_work");
+        assert!(credentials.gateways.is_empty());
+
+        assert_eq!(Credentials::parse("").unwrap(), Credentials::default());
+        assert!(Credentials::parse("tokens = \"figd_default\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_gateway_account() {
+        let credentials = Credentials::parse(
+            r#"
+            [accounts.Corp]
+            token = "corp-token"
+            api_host = "https://figma.example.com"
+            auth_scheme = "header:X-Gateway-Token"
+            headers = { "X-Org" = "acme" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(credentials.accounts["corp"], "corp-token");
+        let gateway = &credentials.gateways["corp"];
+        assert_eq!(
+            gateway.auth_scheme,
+            Some(AuthScheme::Header("X-Gateway-Token".to_string()))
+        );
+        assert_eq!(gateway.headers["X-Org"], "acme");
+
+        assert!(Credentials::parse("[accounts.corp]\ntoken = \"t\"\nhost = \"x\"").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Translated tool descriptions. The descriptions compiled into the server
+//! are English; teams working in another language can load a bundle that
+//! replaces them, so models pick tools from descriptions in the language of
+//! the conversation.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use rmcp::model::Tool;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Descriptions replacing the built-in ones. Tools and parameters left out
+/// of a bundle keep their English description.
+///
+/// Bundles are TOML, with a table per tool:
+///
+/// ```toml
+/// instructions = "Ein MCP-Server für Figma-Dateien. Hilfe gibt das Tool 'help'."
+///
+/// [tools.get_file]
+/// description = "Lädt eine Figma-Datei über ihren Schlüssel"
+///
+/// [tools.get_file.parameters]
+/// file_key = "Schlüssel der Datei aus der Figma-URL"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Descriptions {
+    /// Replaces the instructions sent to clients on initialization
+    pub instructions: Option<String>,
+    #[serde(default)]
+    tools: BTreeMap<String, ToolDescriptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolDescriptions {
+    description: Option<String>,
+    #[serde(default)]
+    parameters: BTreeMap<String, String>,
+}
+
+impl Descriptions {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        Self::parse(&content)
+            .map_err(|e| Error::Config(format!("Invalid {}: {}", path.display(), e)))
+    }
+
+    pub fn parse(content: &str) -> std::result::Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Checks that the bundle only names tools and parameters that exist,
+    /// so a typo does not silently leave a description untranslated.
+    pub fn check(&self, tools: &[Tool]) -> Result<()> {
+        for (name, descriptions) in &self.tools {
+            let Some(tool) = tools.iter().find(|tool| tool.name == name.as_str()) else {
+                return Err(Error::Config(format!(
+                    "Description bundle names unknown tool '{}'",
+                    name
+                )));
+            };
+
+            let properties = tool
+                .input_schema
+                .get("properties")
+                .and_then(Value::as_object);
+            for parameter in descriptions.parameters.keys() {
+                if !properties.is_some_and(|properties| properties.contains_key(parameter)) {
+                    return Err(Error::Config(format!(
+                        "Description bundle names unknown parameter '{}' of tool '{}'",
+                        parameter, name
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the descriptions of tools and their parameters with the
+    /// bundle's.
+    pub fn apply(&self, mut tools: Vec<Tool>) -> Vec<Tool> {
+        for tool in &mut tools {
+            let Some(descriptions) = self.tools.get(tool.name.as_ref()) else {
+                continue;
+            };
+
+            if let Some(description) = &descriptions.description {
+                tool.description = Some(description.clone().into());
+            }
+            if descriptions.parameters.is_empty() {
+                continue;
+            }
+
+            let schema = Arc::make_mut(&mut tool.input_schema);
+            let properties = schema.get_mut("properties").and_then(Value::as_object_mut);
+            for (name, property) in properties.into_iter().flatten() {
+                if let (Some(description), Some(property)) =
+                    (descriptions.parameters.get(name), property.as_object_mut())
+                {
+                    property.insert("description".into(), description.clone().into());
+                }
+            }
+        }
+
+        tools
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "file_key": { "type": "string", "description": "The Figma file key" },
+                "pretty": { "type": "boolean", "description": "Pretty-print the JSON" }
+            }
+        });
+        Tool {
+            name: "get_file".into(),
+            description: Some("Get Figma file information".into()),
+            input_schema: Arc::new(schema.as_object().unwrap().clone()),
+            annotations: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_bundle() {
+        let descriptions = Descriptions::parse(
+            r#"
+            [tools.get_file]
+            description = "Lädt eine Figma-Datei"
+
+            [tools.get_file.parameters]
+            file_key = "Schlüssel der Datei"
+            "#,
+        )
+        .unwrap();
+        assert!(descriptions.check(&[tool()]).is_ok());
+
+        let tools = descriptions.apply(vec![tool()]);
+        assert_eq!(
+            tools[0].description.as_deref(),
+            Some("Lädt eine Figma-Datei")
+        );
+        let properties = &tools[0].input_schema["properties"];
+        assert_eq!(properties["file_key"]["description"], "Schlüssel der Datei");
+        assert_eq!(properties["pretty"]["description"], "Pretty-print the JSON");
+    }
+
+    #[test]
+    fn test_check_rejects_unknown_names() {
+        let descriptions = Descriptions::parse("[tools.get_fiel]\ndescription = \"x\"").unwrap();
+        assert!(descriptions.check(&[tool()]).is_err());
+
+        let descriptions =
+            Descriptions::parse("[tools.get_file.parameters]\nfile = \"x\"").unwrap();
+        assert!(descriptions.check(&[tool()]).is_err());
+
+        assert!(Descriptions::parse("[tool.get_file]").is_err());
+    }
+}
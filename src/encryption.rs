@@ -0,0 +1,183 @@
+//! At-rest encryption of what the server keeps on disk, so far the OAuth
+//! token file: ChaCha20-Poly1305 with a key from a passphrase
+//! (`FIGMA_MCP_ENCRYPTION_KEY`) or, with the `keychain` feature, a random
+//! key kept in the OS keychain.
+//!
+//! An encrypted file is `MAGIC`, a salt, a nonce and the ciphertext with
+//! its tag. Passphrases are stretched with PBKDF2 and the file's salt, so
+//! every write uses a fresh key as well as a fresh nonce.
+
+use std::fmt;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::Sha256;
+
+use crate::{Error, Result};
+
+/// Start of every encrypted file, telling it apart from a plaintext one.
+pub const MAGIC: &[u8] = b"FMCPENC1";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+pub const KEY_LEN: usize = 32;
+
+/// PBKDF2-HMAC-SHA256 iterations for passphrases, as recommended by OWASP.
+#[cfg(not(test))]
+const PBKDF2_ROUNDS: u32 = 600_000;
+/// Unoptimized, the real number of rounds takes seconds.
+#[cfg(test)]
+const PBKDF2_ROUNDS: u32 = 1_000;
+
+/// The key files are encrypted with.
+#[derive(Clone, PartialEq)]
+pub enum AtRestKey {
+    /// A passphrase, stretched with each file's salt
+    Passphrase(String),
+    /// A random key, used as is
+    Key([u8; KEY_LEN]),
+}
+
+impl AtRestKey {
+    /// A new random key.
+    pub fn generate() -> Self {
+        Self::Key(random_key())
+    }
+
+    fn cipher(&self, salt: &[u8]) -> ChaCha20Poly1305 {
+        let key = match self {
+            Self::Passphrase(passphrase) => pbkdf2::pbkdf2_hmac_array::<Sha256, KEY_LEN>(
+                passphrase.as_bytes(),
+                salt,
+                PBKDF2_ROUNDS,
+            ),
+            Self::Key(key) => *key,
+        };
+        ChaCha20Poly1305::new(Key::from_slice(&key))
+    }
+}
+
+/// Keys never show up in logs.
+impl fmt::Debug for AtRestKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Passphrase(_) => f.write_str("Passphrase(..)"),
+            Self::Key(_) => f.write_str("Key(..)"),
+        }
+    }
+}
+
+/// 32 random bytes from the OS.
+pub fn random_key() -> [u8; KEY_LEN] {
+    let mut key = [0; KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// Whether `data` was written by [`encrypt`].
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn encrypt(key: &AtRestKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = key
+        .cipher(&salt)
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| Error::Internal("Encryption failed".to_string()))?;
+
+    let mut data = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypts what [`encrypt`] wrote, failing for another key or altered
+/// data.
+pub fn decrypt(key: &AtRestKey, data: &[u8]) -> Result<Vec<u8>> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if !is_encrypted(data) || data.len() < header {
+        return Err(Error::Internal("Not an encrypted file".to_string()));
+    }
+    let (salt, rest) = data[MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    key.cipher(salt)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            Error::Internal("Decryption failed: wrong key or corrupted file".to_string())
+        })
+}
+
+/// Parses a key stored in the keychain: its bytes in hex.
+pub fn parse_hex_key(hex: &str) -> Option<AtRestKey> {
+    let hex = hex.trim();
+    if hex.len() != KEY_LEN * 2 {
+        return None;
+    }
+    let mut key = [0; KEY_LEN];
+    for (byte, pair) in key.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(AtRestKey::Key(key))
+}
+
+/// The hex form [`parse_hex_key`] reads, for raw keys.
+pub fn to_hex(key: &[u8; KEY_LEN]) -> String {
+    key.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let key = AtRestKey::generate();
+        let data = encrypt(&key, b"refresh token").unwrap();
+
+        assert!(is_encrypted(&data));
+        assert!(!data.windows(7).any(|w| w == b"refresh"));
+        assert_eq!(decrypt(&key, &data).unwrap(), b"refresh token");
+        // Fresh nonce every time
+        assert_ne!(encrypt(&key, b"refresh token").unwrap(), data);
+    }
+
+    #[test]
+    fn test_wrong_key_and_tampering_are_refused() {
+        let key = AtRestKey::generate();
+        let mut data = encrypt(&key, b"refresh token").unwrap();
+
+        assert!(decrypt(&AtRestKey::generate(), &data).is_err());
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(decrypt(&key, &data).is_err());
+        assert!(decrypt(&key, &data[..MAGIC.len() + 4]).is_err());
+        assert!(decrypt(&key, b"{\"access_token\": \"\"}").is_err());
+    }
+
+    #[test]
+    fn test_passphrase() {
+        let key = AtRestKey::Passphrase("correct horse".to_string());
+        let data = encrypt(&key, b"tokens").unwrap();
+
+        assert_eq!(decrypt(&key, &data).unwrap(), b"tokens");
+        assert!(decrypt(&AtRestKey::Passphrase("wrong".to_string()), &data).is_err());
+    }
+
+    #[test]
+    fn test_hex_key() {
+        let raw = random_key();
+
+        assert_eq!(parse_hex_key(&to_hex(&raw)), Some(AtRestKey::Key(raw)));
+        assert_eq!(parse_hex_key("abc"), None);
+        assert_eq!(parse_hex_key(&"zz".repeat(KEY_LEN)), None);
+    }
+}
@@ -1,12 +1,21 @@
 use anyhow::Error as AnyhowError;
+use serde_json::Value;
+use std::fmt;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+// Raw (non-JSON) error bodies such as HTML gateway pages are cut off at this
+// length to keep tool errors readable.
+const MAX_RAW_ERROR_LEN: usize = 500;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Figma API error: {0}")]
     FigmaApi(String),
     
+    #[error("Figma API error: {0}")]
+    Api(ApiError),
+    
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
     
@@ -30,4 +39,129 @@ pub enum Error {
     
     #[error("Not found: {0}")]
     NotFound(String),
-}
\ No newline at end of file
+    
+    #[error("Operation cancelled")]
+    Cancelled,
+    
+    #[error("Timed out after {} seconds", .0.as_secs())]
+    Timeout(std::time::Duration),
+    
+    #[error("Invalid image: {0}")]
+    InvalidImage(String),
+    
+    #[error("Configuration error: {0}")]
+    Config(String),
+    
+    #[error("Plugin bridge error: {0}")]
+    Bridge(String),
+    
+    #[error("Webhook receiver error: {0}")]
+    Webhook(String),
+}
+
+impl Error {
+    /// A copy of the error for another caller waiting on the same request.
+    /// Errors of other libraries can't be copied and keep only their message.
+    pub fn duplicate(&self) -> Self {
+        match self {
+            Self::FigmaApi(message) => Self::FigmaApi(message.clone()),
+            Self::Api(error) => Self::Api(error.clone()),
+            Self::InvalidUrl(message) => Self::InvalidUrl(message.clone()),
+            Self::Auth(message) => Self::Auth(message.clone()),
+            Self::Internal(message) => Self::Internal(message.clone()),
+            Self::NotFound(message) => Self::NotFound(message.clone()),
+            Self::Cancelled => Self::Cancelled,
+            Self::Timeout(duration) => Self::Timeout(*duration),
+            Self::InvalidImage(message) => Self::InvalidImage(message.clone()),
+            Self::Config(message) => Self::Config(message.clone()),
+            Self::Bridge(message) => Self::Bridge(message.clone()),
+            Self::Webhook(message) => Self::Webhook(message.clone()),
+            Self::Network(_) | Self::Json(_) | Self::UrlParse(_) | Self::Mcp(_) => {
+                Self::FigmaApi(self.to_string())
+            }
+        }
+    }
+}
+
+/// An error response from the Figma REST API.
+///
+/// Figma reports errors as `{"status": 403, "err": "Invalid token"}`, while
+/// newer endpoints use `{"error": true, "status": 404, "message": "..."}`,
+/// sometimes with an additional `reason`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+    pub reason: Option<String>,
+}
+
+impl ApiError {
+    pub fn from_body(status: u16, body: &[u8]) -> Self {
+        let Ok(json) = serde_json::from_slice::<Value>(body) else {
+            let text = String::from_utf8_lossy(body);
+            let message = text.trim().chars().take(MAX_RAW_ERROR_LEN).collect();
+            return Self {
+                status,
+                message,
+                reason: None,
+            };
+        };
+
+        Self::from_json(status, &json)
+    }
+
+    pub fn from_json(status: u16, json: &Value) -> Self {
+        let status = json
+            .get("status")
+            .and_then(Value::as_u64)
+            .and_then(|status| u16::try_from(status).ok())
+            .unwrap_or(status);
+
+        let message = ["err", "message"]
+            .iter()
+            .filter_map(|field| json.get(field))
+            .find_map(|value| match value {
+                Value::String(message) => Some(message.clone()),
+                Value::Null | Value::Bool(_) => None,
+                other => Some(other.to_string()),
+            })
+            .unwrap_or_else(|| "Unknown error".to_string());
+
+        let reason = json.get("reason").and_then(Value::as_str).map(str::to_string);
+
+        Self {
+            status,
+            message,
+            reason,
+        }
+    }
+
+    /// Suggests how to resolve the error, based on the status code.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self.status {
+            400 => Some("Check the request parameters, such as node IDs, export format and scale."),
+            401 => Some("The token is invalid or expired. Create a new personal access token and update FIGMA_TOKEN."),
+            403 => Some("The token has no access to this resource. Check that the file is shared with the token's account and the token has the required scopes."),
+            404 => Some("The file or node was not found. Check the file key (use parse_figma_url) and the node IDs."),
+            429 => Some("Rate limit exceeded. Wait a minute before retrying and reduce the number of requests."),
+            500..=599 => Some("Figma had a server error. For large files, retry with a lower depth or fewer node IDs."),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP {}: {}", self.status, self.message)?;
+
+        if let Some(reason) = &self.reason {
+            write!(f, " ({})", reason)?;
+        }
+
+        if let Some(hint) = self.hint() {
+            write!(f, ". {}", hint)?;
+        }
+
+        Ok(())
+    }
+}
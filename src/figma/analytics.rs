@@ -0,0 +1,234 @@
+//! Library analytics: how often the components, styles and variables of a
+//! library file are inserted, detached and used across an organization.
+
+use serde_json::{Map, Value};
+
+use super::tables::csv_field;
+
+/// Assets accepted by [`Asset::parse`], for error messages.
+pub const ASSET_NAMES: &str = "component, style or variable";
+
+/// Output formats accepted by [`AnalyticsFormat::parse`], for error
+/// messages.
+pub const ANALYTICS_FORMAT_NAMES: &str = "json or csv";
+
+/// Rows returned by default, after sorting.
+pub const DEFAULT_ROW_LIMIT: usize = 100;
+
+/// Pages fetched at most for one call. Figma pages analytics by a few
+/// hundred rows.
+pub const MAX_PAGES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Asset {
+    Component,
+    Style,
+    Variable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Weekly insertions and detachments
+    Actions,
+    /// Current usages
+    Usages,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalyticsFormat {
+    Json,
+    Csv,
+}
+
+impl Asset {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "component" | "components" => Some(Self::Component),
+            "style" | "styles" => Some(Self::Style),
+            "variable" | "variables" => Some(Self::Variable),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Component => "component",
+            Self::Style => "style",
+            Self::Variable => "variable",
+        }
+    }
+}
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Actions => "actions",
+            Self::Usages => "usages",
+        }
+    }
+
+    /// The grouping besides per asset: actions per team, usages per file.
+    pub fn other_grouping(&self) -> &'static str {
+        match self {
+            Self::Actions => "team",
+            Self::Usages => "file",
+        }
+    }
+
+    /// The `group_by` value Figma expects for a requested grouping: per
+    /// asset by default (also given as the asset's name), or
+    /// [`Metric::other_grouping`]. `None` for other groupings.
+    pub fn group_by(&self, asset: Asset, value: Option<&str>) -> Option<&'static str> {
+        let value = value.map(|value| value.trim().to_lowercase());
+        match value.as_deref() {
+            None | Some("") | Some("asset") => Some(asset.name()),
+            Some(value) if Asset::parse(value) == Some(asset) => Some(asset.name()),
+            Some(value) if value == self.other_grouping() => Some(self.other_grouping()),
+            Some(_) => None,
+        }
+    }
+}
+
+impl AnalyticsFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Checks a `YYYY-MM-DD` date, the format Figma takes for date ranges.
+pub fn is_date(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    let number = |part: &str, digits: usize| {
+        (part.len() == digits && part.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| part.parse::<u32>().ok())
+            .flatten()
+    };
+
+    number(year, 4).is_some()
+        && number(month, 2).is_some_and(|month| (1..=12).contains(&month))
+        && number(day, 2).is_some_and(|day| (1..=31).contains(&day))
+}
+
+/// Reads the rows of an analytics response page.
+pub fn rows(response: &Value) -> Vec<Map<String, Value>> {
+    let rows = response.get("rows").and_then(Value::as_array);
+
+    rows.into_iter()
+        .flatten()
+        .filter_map(Value::as_object)
+        .cloned()
+        .collect()
+}
+
+/// The cursor for the next page of an analytics response, if there is one.
+pub fn next_cursor(response: &Value) -> Option<String> {
+    if response.get("next_page").and_then(Value::as_bool) != Some(true) {
+        return None;
+    }
+
+    response
+        .get("cursor")
+        .and_then(Value::as_str)
+        .filter(|cursor| !cursor.is_empty())
+        .map(String::from)
+}
+
+/// Sorts rows by a numeric column, largest first; rows without it go last.
+/// Returns whether any row has the column.
+pub fn sort_rows(rows: &mut [Map<String, Value>], column: &str) -> bool {
+    let value = |row: &Map<String, Value>| row.get(column).and_then(Value::as_f64);
+    if !rows.iter().any(|row| value(row).is_some()) {
+        return false;
+    }
+
+    rows.sort_by(|a, b| {
+        let (a, b) = (value(a), value(b));
+        b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    true
+}
+
+/// Renders rows as CSV, with a header naming the columns of all rows
+/// (alphabetically within each row, as rows keep their fields sorted).
+pub fn to_csv(rows: &[Map<String, Value>]) -> String {
+    let mut columns: Vec<&str> = Vec::new();
+    for key in rows.iter().flat_map(|row| row.keys()) {
+        if !columns.contains(&key.as_str()) {
+            columns.push(key);
+        }
+    }
+
+    let mut csv = columns
+        .iter()
+        .map(|c| csv_field(c))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for row in rows {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| match row.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(value)) => csv_field(value),
+                Some(value) => csv_field(&value.to_string()),
+            })
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sort_and_render_rows() {
+        let response = json!({
+            "rows": [
+                { "week": "2024-05-06", "component_name": "Button", "detachments": 3, "insertions": 40 },
+                { "week": "2024-05-06", "component_name": "Card, large", "detachments": 12, "insertions": 8 },
+                { "week": "2024-05-06", "component_name": "Tag", "insertions": 1 }
+            ],
+            "next_page": true,
+            "cursor": "abc"
+        });
+        assert_eq!(next_cursor(&response).as_deref(), Some("abc"));
+
+        let mut rows = rows(&response);
+        assert!(sort_rows(&mut rows, "detachments"));
+        assert!(!sort_rows(&mut rows, "usages"));
+        assert_eq!(
+            to_csv(&rows),
+            "component_name,detachments,insertions,week\n\
+             \"Card, large\",12,8,2024-05-06\n\
+             Button,3,40,2024-05-06\n\
+             Tag,,1,2024-05-06\n"
+        );
+    }
+
+    #[test]
+    fn test_group_by_and_dates() {
+        let asset = Asset::Component;
+        assert_eq!(Metric::Actions.group_by(asset, None), Some("component"));
+        assert_eq!(Metric::Actions.group_by(asset, Some("Team")), Some("team"));
+        assert_eq!(Metric::Usages.group_by(asset, Some("team")), None);
+        assert_eq!(Metric::Usages.group_by(asset, Some("style")), None);
+
+        assert!(is_date("2024-05-06"));
+        assert!(!is_date("2024-5-6"));
+        assert!(!is_date("2024-13-01"));
+        assert!(!is_date("last week"));
+    }
+}
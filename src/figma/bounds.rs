@@ -0,0 +1,335 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::layers::is_hidden;
+
+/// Bounds kinds accepted by [`BoundsKind::parse`], for error messages.
+pub const BOUNDS_NAMES: &str = "layout or render";
+
+/// A node's absolute bounding box in canvas coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Which of a node's two boxes to measure or export.
+///
+/// Shadows, blurs and outside strokes make the rendered pixels extend past
+/// the layout box, so the two differ for most decorated nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoundsKind {
+    /// `absoluteBoundingBox`: the node's geometry, as used for layout
+    Layout,
+    /// `absoluteRenderBounds`: everything drawn, including effects
+    #[default]
+    Render,
+}
+
+/// How far the render bounds extend past the layout box on each side.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Outset {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+/// Both boxes of a node, for measurement tools.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeBounds {
+    pub node_id: String,
+    pub name: String,
+    pub layout: Option<Bounds>,
+    pub render: Option<Bounds>,
+    pub outset: Option<Outset>,
+}
+
+/// A node overlapping a region, for region queries.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegionNode {
+    pub node_id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    /// Levels below the node the region was searched in, starting at 1
+    pub depth: usize,
+    pub bounds: Bounds,
+}
+
+impl Bounds {
+    pub fn of(node: &Value, key: &str) -> Option<Self> {
+        let bounds = node.get(key)?;
+        let number = |name: &str| bounds.get(name).and_then(Value::as_f64);
+
+        Some(Self {
+            x: number("x")?,
+            y: number("y")?,
+            width: number("width")?,
+            height: number("height")?,
+        })
+    }
+
+    /// Reads the box of the given kind. Figma leaves out render bounds for
+    /// invisible nodes, which then fall back to the layout box.
+    pub fn of_kind(node: &Value, kind: BoundsKind) -> Option<Self> {
+        Self::of(node, kind.key()).or_else(|| Self::of(node, BoundsKind::Layout.key()))
+    }
+
+    pub(crate) fn contains(&self, other: &Bounds) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.x + other.width <= self.x + self.width
+            && other.y + other.height <= self.y + self.height
+    }
+
+    /// Whether the boxes overlap. Boxes that only touch do not.
+    pub(crate) fn intersects(&self, other: &Bounds) -> bool {
+        other.x < self.x + self.width
+            && self.x < other.x + other.width
+            && other.y < self.y + self.height
+            && self.y < other.y + other.height
+    }
+}
+
+impl BoundsKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "layout" | "absoluteboundingbox" => Some(Self::Layout),
+            "render" | "absoluterenderbounds" => Some(Self::Render),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Layout => "layout",
+            Self::Render => "render",
+        }
+    }
+
+    /// The node property holding this box.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Layout => "absoluteBoundingBox",
+            Self::Render => "absoluteRenderBounds",
+        }
+    }
+
+    /// Whether image exports should cover the layout box. Figma crops
+    /// exports to the render bounds unless asked to use absolute bounds.
+    pub fn use_absolute_bounds(&self) -> bool {
+        *self == Self::Layout
+    }
+}
+
+/// Reads both boxes of a node and how far its rendering spills past its
+/// layout box.
+pub fn measure(node: &Value) -> NodeBounds {
+    let layout = Bounds::of(node, BoundsKind::Layout.key());
+    let render = Bounds::of(node, BoundsKind::Render.key());
+    let outset = layout.zip(render).map(|(layout, render)| Outset {
+        top: layout.y - render.y,
+        right: (render.x + render.width) - (layout.x + layout.width),
+        bottom: (render.y + render.height) - (layout.y + layout.height),
+        left: layout.x - render.x,
+    });
+    let field = |key: &str| {
+        node.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    NodeBounds {
+        node_id: field("id"),
+        name: field("name"),
+        layout,
+        render,
+        outset,
+    }
+}
+
+/// Lists the visible nodes below `root` whose layout box intersects
+/// `region`, or lies within it if `contained`, in tree order.
+///
+/// `reference` is the ID of a node the region was taken from. It and its
+/// ancestors overlap the region by definition, so they are left out.
+pub fn nodes_in_region(
+    root: &Value,
+    region: &Bounds,
+    contained: bool,
+    reference: Option<&str>,
+) -> Vec<RegionNode> {
+    let mut found = Vec::new();
+    let children = root.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect_in_region(child, region, contained, reference, 1, &mut found);
+    }
+
+    found.into_iter().flatten().collect()
+}
+
+/// Walks `node` and its descendants, returning whether the subtree holds
+/// the reference node. Entries of the reference and its ancestors are
+/// cleared on the way back up.
+fn collect_in_region(
+    node: &Value,
+    region: &Bounds,
+    contained: bool,
+    reference: Option<&str>,
+    depth: usize,
+    found: &mut Vec<Option<RegionNode>>,
+) -> bool {
+    if is_hidden(node) {
+        return false;
+    }
+
+    let field = |key: &str| {
+        node.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let node_id = field("id");
+    let bounds = Bounds::of(node, BoundsKind::Layout.key());
+
+    let entry = found.len();
+    found.push(bounds.and_then(|bounds| {
+        let matches = if contained {
+            region.contains(&bounds)
+        } else {
+            region.intersects(&bounds)
+        };
+        matches.then(|| RegionNode {
+            node_id: node_id.clone(),
+            name: field("name"),
+            node_type: field("type"),
+            depth,
+            bounds,
+        })
+    }));
+
+    let mut holds_reference = reference == Some(node_id.as_str());
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        holds_reference |= collect_in_region(child, region, contained, reference, depth + 1, found);
+    }
+    if holds_reference {
+        found[entry] = None;
+    }
+
+    holds_reference
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_measure_reports_shadow_outset() {
+        let node = json!({
+            "id": "1:2",
+            "name": "Card",
+            "absoluteBoundingBox": { "x": 100, "y": 100, "width": 200, "height": 120 },
+            "absoluteRenderBounds": { "x": 92, "y": 96, "width": 216, "height": 140 }
+        });
+
+        let measured = measure(&node);
+
+        assert_eq!(
+            measured.outset,
+            Some(Outset {
+                top: 4.0,
+                right: 8.0,
+                bottom: 16.0,
+                left: 8.0
+            })
+        );
+        assert_eq!(
+            Bounds::of_kind(&node, BoundsKind::Render).map(|b| b.width),
+            Some(216.0)
+        );
+    }
+
+    #[test]
+    fn test_nodes_in_region() {
+        let page = json!({
+            "id": "0:1",
+            "children": [{
+                "id": "1:1",
+                "name": "Screen",
+                "type": "FRAME",
+                "absoluteBoundingBox": { "x": 0, "y": 0, "width": 400, "height": 800 },
+                "children": [
+                    {
+                        "id": "1:2",
+                        "name": "Menu",
+                        "type": "INSTANCE",
+                        "absoluteBoundingBox": { "x": 340, "y": 10, "width": 50, "height": 50 }
+                    },
+                    {
+                        "id": "1:3",
+                        "name": "Title",
+                        "type": "TEXT",
+                        "absoluteBoundingBox": { "x": 20, "y": 10, "width": 330, "height": 40 }
+                    },
+                    {
+                        "id": "1:4",
+                        "name": "Old badge",
+                        "type": "FRAME",
+                        "visible": false,
+                        "absoluteBoundingBox": { "x": 360, "y": 0, "width": 40, "height": 20 }
+                    }
+                ]
+            }]
+        });
+        let top_right = Bounds {
+            x: 300.0,
+            y: 0.0,
+            width: 100.0,
+            height: 100.0,
+        };
+
+        let ids = |nodes: Vec<RegionNode>| -> Vec<String> {
+            nodes.into_iter().map(|node| node.node_id).collect()
+        };
+        assert_eq!(
+            ids(nodes_in_region(&page, &top_right, false, None)),
+            vec!["1:1", "1:2", "1:3"]
+        );
+        assert_eq!(
+            ids(nodes_in_region(&page, &top_right, true, None)),
+            vec!["1:2"]
+        );
+
+        // Around the menu button: the title overlaps, the screen holds it
+        let around_menu = Bounds {
+            x: 340.0,
+            y: 10.0,
+            width: 50.0,
+            height: 50.0,
+        };
+        assert_eq!(
+            ids(nodes_in_region(&page, &around_menu, false, Some("1:2"))),
+            vec!["1:3"]
+        );
+    }
+
+    #[test]
+    fn test_render_bounds_fall_back_to_layout() {
+        let node = json!({
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 10, "height": 10 },
+            "absoluteRenderBounds": null
+        });
+
+        assert_eq!(measure(&node).outset, None);
+        assert_eq!(
+            Bounds::of_kind(&node, BoundsKind::Render).map(|b| b.height),
+            Some(10.0)
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Branches of a file, from the `branches` list Figma adds to a file when it
+//! is fetched with `branch_data=true`. A branch has its own file key, which
+//! works wherever a file key does.
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Branch {
+    pub key: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+/// Reads the branches of a file response, in the order Figma lists them.
+pub fn branches(file: &Value) -> Vec<Branch> {
+    let branches = file.get("branches").and_then(Value::as_array);
+
+    branches
+        .into_iter()
+        .flatten()
+        .map(|branch| {
+            let field = |key: &str| {
+                branch
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+            };
+            Branch {
+                key: field("key").unwrap_or_default(),
+                name: field("name").unwrap_or_default(),
+                last_modified: field("last_modified"),
+            }
+        })
+        .collect()
+}
+
+/// The key of the main file a branch file was created from, if the file is
+/// a branch.
+pub fn main_file_key(file: &Value) -> Option<&str> {
+    file.get("mainFileKey")
+        .and_then(Value::as_str)
+        .filter(|key| !key.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_branches_and_main_file_key() {
+        let file = json!({
+            "name": "App",
+            "branches": [
+                { "key": "br1", "name": "New checkout", "thumbnail_url": "", "last_modified": "2024-05-01T10:00:00Z" },
+                { "key": "br2", "name": "Dark mode" }
+            ]
+        });
+        let found = branches(&file);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "New checkout");
+        assert_eq!(found[1].last_modified, None);
+        assert_eq!(main_file_key(&file), None);
+
+        let branch = json!({ "name": "New checkout", "mainFileKey": "abc" });
+        assert_eq!(main_file_key(&branch), Some("abc"));
+    }
+}
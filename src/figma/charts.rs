@@ -0,0 +1,668 @@
+//! Best-effort extraction of the data behind simple chart mockups: bar
+//! charts drawn with rectangles and line charts drawn with vectors or point
+//! markers. Values are read back from geometry, so they are approximate.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::bounds::{Bounds, BoundsKind};
+use super::layers::is_hidden;
+
+/// Fewest bars or points read as a chart.
+const MIN_POINTS: usize = 3;
+
+/// Bars of one chart differ at most this much in thickness.
+const MAX_THICKNESS_RATIO: f64 = 1.5;
+
+/// Shapes thinner than this are grid lines or axes, not bars.
+const MIN_BAR_THICKNESS: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartKind {
+    Bar,
+    Line,
+}
+
+/// How values were derived from geometry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartScale {
+    /// Mapped through the numeric tick labels of the value axis
+    Axis,
+    /// Bar lengths or heights above the lowest point in pixels, relative
+    /// to each other only
+    Pixels,
+}
+
+/// A chart found in a frame. Values are always approximate.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chart {
+    /// The smallest container holding the chart
+    pub node_id: String,
+    pub name: String,
+    pub kind: ChartKind,
+    /// `vertical` or `horizontal` for bar charts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<&'static str>,
+    pub scale: ChartScale,
+    pub series: Vec<Series>,
+    pub approximate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Series {
+    /// Layer name of a line, if the chart has several
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub points: Vec<DataPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataPoint {
+    /// The category label under (or beside) the bar or point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub value: f64,
+}
+
+/// A position in canvas coordinates.
+type Point = (f64, f64);
+
+/// Shapes and labels of a candidate chart container.
+#[derive(Default)]
+struct Elements {
+    bars: Vec<Bounds>,
+    markers: Vec<Bounds>,
+    lines: Vec<(String, Vec<Point>)>,
+    texts: Vec<(String, Bounds)>,
+}
+
+/// Maps positions along one axis to values, from two tick labels.
+struct Axis {
+    position: f64,
+    value: f64,
+    per_unit: f64,
+}
+
+/// Finds charts below `root`, each reported for the smallest container
+/// holding it. Line charts need vector geometry (`geometry=paths`) unless
+/// their points are drawn as markers.
+pub fn detect(root: &Value) -> Vec<Chart> {
+    let mut charts = Vec::new();
+    find(root, &mut charts);
+
+    charts
+}
+
+fn find(node: &Value, charts: &mut Vec<Chart>) -> bool {
+    if is_hidden(node) || !has_children(node) {
+        return false;
+    }
+
+    let mut found = false;
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        found |= find(child, charts);
+    }
+    if found {
+        return true;
+    }
+
+    let mut elements = Elements::default();
+    collect(node, &mut elements);
+    let Some(mut chart) = bar_chart(&elements).or_else(|| line_chart(&elements)) else {
+        return false;
+    };
+
+    let field = |key: &str| {
+        node.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    chart.node_id = field("id");
+    chart.name = field("name");
+    charts.push(chart);
+
+    true
+}
+
+fn collect(node: &Value, elements: &mut Elements) {
+    if is_hidden(node) {
+        return;
+    }
+
+    let node_type = node.get("type").and_then(Value::as_str).unwrap_or_default();
+    let bounds = Bounds::of(node, BoundsKind::Layout.key());
+    match (node_type, bounds) {
+        ("TEXT", Some(bounds)) => {
+            let text = node
+                .get("characters")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            if !text.is_empty() {
+                elements.texts.push((text, bounds));
+            }
+        }
+        ("RECTANGLE", Some(bounds)) => elements.bars.push(bounds),
+        // Bars are often frames, e.g. to round only the top corners
+        ("FRAME", Some(bounds)) if !has_children(node) => elements.bars.push(bounds),
+        ("ELLIPSE", Some(bounds)) => elements.markers.push(bounds),
+        ("VECTOR" | "LINE", Some(bounds)) => {
+            let points = line_points(node, &bounds);
+            if !points.is_empty() {
+                let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+                elements.lines.push((name.to_string(), points));
+            }
+        }
+        _ => {}
+    }
+
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect(child, elements);
+    }
+}
+
+/// Reads bars sharing a baseline, either standing on it (vertical) or
+/// growing from a common left edge (horizontal).
+fn bar_chart(elements: &Elements) -> Option<Chart> {
+    // Backgrounds and plot areas enclose the bars
+    let bars: Vec<Bounds> = elements
+        .bars
+        .iter()
+        .filter(|bar| bar.width >= MIN_BAR_THICKNESS && bar.height >= MIN_BAR_THICKNESS)
+        .filter(|bar| {
+            !elements
+                .bars
+                .iter()
+                .any(|other| !std::ptr::eq(other, *bar) && bar.contains(other))
+        })
+        .copied()
+        .collect();
+
+    let vertical = aligned(
+        &bars,
+        |bar| bar.y + bar.height,
+        |bar| (bar.width, bar.height),
+    );
+    let horizontal = aligned(&bars, |bar| bar.x, |bar| (bar.height, bar.width));
+    let (vertical, mut bars) = match (vertical, horizontal) {
+        (Some(vertical), Some(horizontal)) if horizontal.len() > vertical.len() => {
+            (false, horizontal)
+        }
+        (Some(vertical), _) => (true, vertical),
+        (None, Some(horizontal)) => (false, horizontal),
+        (None, None) => return None,
+    };
+
+    let ticks: Vec<&(String, Bounds)>;
+    let axis = if vertical {
+        bars.sort_by(|a, b| a.x.total_cmp(&b.x));
+        let left = bars[0].x;
+        ticks = numeric_texts(elements, |bounds| bounds.x + bounds.width <= left);
+        Axis::fit(&ticks, |bounds| bounds.y + bounds.height / 2.0)
+    } else {
+        bars.sort_by(|a, b| a.y.total_cmp(&b.y));
+        let bottom = bars
+            .iter()
+            .map(|bar| bar.y + bar.height)
+            .fold(f64::MIN, f64::max);
+        ticks = numeric_texts(elements, |bounds| bounds.y >= bottom);
+        Axis::fit(&ticks, |bounds| bounds.x + bounds.width / 2.0)
+    };
+
+    let points = bars
+        .iter()
+        .map(|bar| {
+            let (end, length) = if vertical {
+                (bar.y, bar.height)
+            } else {
+                (bar.x + bar.width, bar.width)
+            };
+            let value = axis.as_ref().map_or(length, |axis| axis.value_at(end));
+            let label = if vertical {
+                label_near(elements, &ticks, |bounds| {
+                    let below = bounds.y >= bar.y + bar.height - 1.0;
+                    let offset = (bounds.x + bounds.width / 2.0 - (bar.x + bar.width / 2.0)).abs();
+                    (below && offset <= bar.width).then_some(offset)
+                })
+            } else {
+                label_near(elements, &ticks, |bounds| {
+                    let before = bounds.x + bounds.width <= bar.x + 1.0;
+                    let offset =
+                        (bounds.y + bounds.height / 2.0 - (bar.y + bar.height / 2.0)).abs();
+                    (before && offset <= bar.height / 2.0).then_some(bar.x - bounds.x)
+                })
+            };
+            DataPoint {
+                label,
+                value: round(value),
+            }
+        })
+        .collect();
+
+    Some(Chart {
+        node_id: String::new(),
+        name: String::new(),
+        kind: ChartKind::Bar,
+        orientation: Some(if vertical { "vertical" } else { "horizontal" }),
+        scale: if axis.is_some() {
+            ChartScale::Axis
+        } else {
+            ChartScale::Pixels
+        },
+        series: vec![Series { name: None, points }],
+        approximate: true,
+    })
+}
+
+/// Finds the largest group of bars sharing a baseline, with similar
+/// thickness and different lengths. `size` gives a bar's thickness and
+/// length.
+fn aligned(
+    bars: &[Bounds],
+    baseline: impl Fn(&Bounds) -> f64,
+    size: impl Fn(&Bounds) -> (f64, f64),
+) -> Option<Vec<Bounds>> {
+    let mut groups: Vec<Vec<Bounds>> = Vec::new();
+    for bar in bars {
+        match groups
+            .iter_mut()
+            .find(|group| (baseline(&group[0]) - baseline(bar)).abs() <= 1.0)
+        {
+            Some(group) => group.push(*bar),
+            None => groups.push(vec![*bar]),
+        }
+    }
+
+    let group = groups.into_iter().max_by_key(Vec::len)?;
+    if group.len() < MIN_POINTS {
+        return None;
+    }
+
+    let thickness = |bar: &Bounds| size(bar).0;
+    let thinnest = group.iter().map(thickness).fold(f64::MAX, f64::min);
+    let thickest = group.iter().map(thickness).fold(f64::MIN, f64::max);
+    // Rows of equal cards or buttons share a baseline too
+    let length = size(&group[0]).1;
+    let same_length = group.iter().all(|bar| (size(bar).1 - length).abs() < 1.0);
+    if thickest > thinnest * MAX_THICKNESS_RATIO || same_length {
+        return None;
+    }
+
+    Some(group)
+}
+
+/// Reads lines drawn as vectors, or else as a row of point markers.
+fn line_chart(elements: &Elements) -> Option<Chart> {
+    let mut lines: Vec<(Option<String>, Vec<Point>)> = elements
+        .lines
+        .iter()
+        .filter(|(_, points)| points.len() >= MIN_POINTS)
+        .map(|(name, points)| (Some(name.clone()), points.clone()))
+        .collect();
+
+    if lines.is_empty() {
+        let size = elements.markers.first()?;
+        let mut markers: Vec<Point> = elements
+            .markers
+            .iter()
+            .filter(|marker| (marker.width - size.width).abs() <= 1.0)
+            .map(|marker| {
+                (
+                    marker.x + marker.width / 2.0,
+                    marker.y + marker.height / 2.0,
+                )
+            })
+            .collect();
+        markers.sort_by(|a, b| a.0.total_cmp(&b.0));
+        markers.dedup_by(|a, b| (a.0 - b.0).abs() < 1.0);
+        if markers.len() < MIN_POINTS {
+            return None;
+        }
+        lines.push((None, markers));
+    }
+    if lines.len() == 1 {
+        lines[0].0 = None;
+    }
+
+    let left = lines
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|(x, _)| *x))
+        .fold(f64::MAX, f64::min);
+    let lowest = lines
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|(_, y)| *y))
+        .fold(f64::MIN, f64::max);
+    let ticks = numeric_texts(elements, |bounds| bounds.x + bounds.width <= left);
+    let axis = Axis::fit(&ticks, |bounds| bounds.y + bounds.height / 2.0);
+
+    let series = lines
+        .into_iter()
+        .map(|(name, points)| {
+            let spacing = points
+                .windows(2)
+                .map(|pair| pair[1].0 - pair[0].0)
+                .fold(f64::MAX, f64::min);
+            let points = points
+                .iter()
+                .map(|&(x, y)| {
+                    let value = axis.as_ref().map_or(lowest - y, |axis| axis.value_at(y));
+                    let label = label_near(elements, &ticks, |bounds| {
+                        let offset = (bounds.x + bounds.width / 2.0 - x).abs();
+                        (bounds.y >= lowest && offset <= spacing / 2.0).then_some(offset)
+                    });
+                    DataPoint {
+                        label,
+                        value: round(value),
+                    }
+                })
+                .collect();
+            Series { name, points }
+        })
+        .collect();
+
+    Some(Chart {
+        node_id: String::new(),
+        name: String::new(),
+        kind: ChartKind::Line,
+        orientation: None,
+        scale: if axis.is_some() {
+            ChartScale::Axis
+        } else {
+            ChartScale::Pixels
+        },
+        series,
+        approximate: true,
+    })
+}
+
+/// Reads the vertices of a vector's stroke in canvas coordinates. Figma
+/// only returns the outline of the stroke, which runs along both sides of
+/// the line, so vertices closer than the stroke are averaged.
+fn line_points(node: &Value, bounds: &Bounds) -> Vec<Point> {
+    let Some(path) = node
+        .pointer("/strokeGeometry/0/path")
+        .and_then(Value::as_str)
+    else {
+        return Vec::new();
+    };
+    let tolerance = node
+        .get("strokeWeight")
+        .and_then(Value::as_f64)
+        .unwrap_or(1.0)
+        * 2.0;
+
+    let mut vertices: Vec<Point> = path_vertices(path)
+        .into_iter()
+        .map(|(x, y)| (bounds.x + x, bounds.y + y))
+        .collect();
+    vertices.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut points: Vec<(f64, f64, usize)> = Vec::new();
+    for (x, y) in vertices {
+        match points.last_mut() {
+            Some((px, py, count)) if x - *px <= tolerance => {
+                *py = (*py * *count as f64 + y) / (*count + 1) as f64;
+                *count += 1;
+            }
+            _ => points.push((x, y, 1)),
+        }
+    }
+
+    points.into_iter().map(|(x, y, _)| (x, y)).collect()
+}
+
+/// The end points of the segments of an SVG path with absolute commands,
+/// as Figma writes them.
+fn path_vertices(path: &str) -> Vec<Point> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+    for character in path.chars() {
+        let command = character.is_ascii_alphabetic() && character != 'e';
+        let separator = character.is_whitespace() || character == ',';
+        // A minus sign starts a new number unless it is an exponent's
+        let sign = character == '-' && !number.is_empty() && !number.ends_with('e');
+        if (command || separator || sign) && !number.is_empty() {
+            tokens.push(std::mem::take(&mut number));
+        }
+        if command {
+            tokens.push(character.to_string());
+        } else if !separator {
+            number.push(character);
+        }
+    }
+    if !number.is_empty() {
+        tokens.push(number);
+    }
+
+    let mut vertices = Vec::new();
+    let mut command = 'M';
+    let mut numbers: Vec<f64> = Vec::new();
+    let mut current = (0.0, 0.0);
+    for token in tokens {
+        if let Ok(value) = token.parse::<f64>() {
+            numbers.push(value);
+        } else if let Some(next) = token.chars().next() {
+            command = next;
+            numbers.clear();
+            continue;
+        }
+
+        let arity = match command {
+            'M' | 'L' => 2,
+            'H' | 'V' => 1,
+            'Q' => 4,
+            'C' => 6,
+            _ => continue,
+        };
+        if numbers.len() < arity {
+            continue;
+        }
+        current = match command {
+            'H' => (numbers[0], current.1),
+            'V' => (current.0, numbers[0]),
+            _ => (numbers[arity - 2], numbers[arity - 1]),
+        };
+        vertices.push(current);
+        numbers.clear();
+    }
+
+    vertices
+}
+
+impl Axis {
+    /// Fits an axis through the two tick labels farthest apart.
+    fn fit(ticks: &[&(String, Bounds)], position: impl Fn(&Bounds) -> f64) -> Option<Self> {
+        let mut ticks: Vec<Point> = ticks
+            .iter()
+            .filter_map(|(text, bounds)| Some((position(bounds), parse_number(text)?)))
+            .collect();
+        ticks.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (first, last) = (ticks.first()?, ticks.last()?);
+        if (last.0 - first.0).abs() < f64::EPSILON || last.1 == first.1 {
+            return None;
+        }
+
+        Some(Self {
+            position: first.0,
+            value: first.1,
+            per_unit: (last.1 - first.1) / (last.0 - first.0),
+        })
+    }
+
+    fn value_at(&self, position: f64) -> f64 {
+        self.value + (position - self.position) * self.per_unit
+    }
+}
+
+fn numeric_texts(elements: &Elements, placed: impl Fn(&Bounds) -> bool) -> Vec<&(String, Bounds)> {
+    elements
+        .texts
+        .iter()
+        .filter(|(text, bounds)| placed(bounds) && parse_number(text).is_some())
+        .collect()
+}
+
+/// The text closest to a bar or point by `distance`, which rejects texts
+/// that are not placed like its label. Tick labels are skipped.
+fn label_near(
+    elements: &Elements,
+    ticks: &[&(String, Bounds)],
+    distance: impl Fn(&Bounds) -> Option<f64>,
+) -> Option<String> {
+    elements
+        .texts
+        .iter()
+        .filter(|text| !ticks.iter().any(|tick| std::ptr::eq(*tick, *text)))
+        .filter_map(|(text, bounds)| Some((distance(bounds)?, text)))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, text)| text.clone())
+}
+
+/// Parses tick labels such as `1,200`, `$40`, `75%` or `1.5k`.
+fn parse_number(text: &str) -> Option<f64> {
+    let cleaned: String = text
+        .chars()
+        .filter(|character| !matches!(character, ',' | '$' | '€' | '£' | '%' | ' '))
+        .collect();
+    let (digits, multiplier) = match cleaned.chars().last()? {
+        'k' | 'K' => (&cleaned[..cleaned.len() - 1], 1e3),
+        'M' => (&cleaned[..cleaned.len() - 1], 1e6),
+        _ => (cleaned.as_str(), 1.0),
+    };
+
+    digits.parse::<f64>().ok().map(|value| value * multiplier)
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn has_children(node: &Value) -> bool {
+    node.get("children")
+        .and_then(Value::as_array)
+        .is_some_and(|children| !children.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn shape(node_type: &str, x: f64, y: f64, width: f64, height: f64) -> Value {
+        json!({
+            "type": node_type,
+            "absoluteBoundingBox": { "x": x, "y": y, "width": width, "height": height }
+        })
+    }
+
+    fn text(characters: &str, x: f64, y: f64, width: f64) -> Value {
+        let mut text = shape("TEXT", x, y, width, 12.0);
+        text["characters"] = json!(characters);
+        text
+    }
+
+    fn values(chart: &Chart) -> Vec<f64> {
+        chart.series[0]
+            .points
+            .iter()
+            .map(|point| point.value)
+            .collect()
+    }
+
+    #[test]
+    fn test_vertical_bars_with_axis() {
+        // Plot from y=0 (value 100) to y=200 (value 0), bars standing on y=200
+        let chart = json!({
+            "id": "1:1",
+            "name": "Revenue",
+            "type": "FRAME",
+            "children": [
+                shape("RECTANGLE", 0.0, 0.0, 300.0, 230.0),
+                text("100", 0.0, -6.0, 20.0),
+                text("50", 0.0, 94.0, 20.0),
+                text("0", 0.0, 194.0, 20.0),
+                shape("RECTANGLE", 40.0, 50.0, 30.0, 150.0),
+                shape("RECTANGLE", 100.0, 150.0, 30.0, 50.0),
+                shape("RECTANGLE", 160.0, 100.0, 30.0, 100.0),
+                text("Q1", 45.0, 210.0, 20.0),
+                text("Q2", 105.0, 210.0, 20.0),
+                text("Q3", 165.0, 210.0, 20.0)
+            ]
+        });
+
+        let charts = detect(&chart);
+
+        assert_eq!(charts.len(), 1);
+        assert_eq!(charts[0].node_id, "1:1");
+        assert_eq!(charts[0].kind, ChartKind::Bar);
+        assert_eq!(charts[0].scale, ChartScale::Axis);
+        assert_eq!(values(&charts[0]), vec![75.0, 25.0, 50.0]);
+        assert_eq!(charts[0].series[0].points[1].label.as_deref(), Some("Q2"));
+    }
+
+    #[test]
+    fn test_horizontal_bars_without_axis() {
+        let chart = json!({
+            "type": "FRAME",
+            "children": [
+                text("Chrome", 0.0, 4.0, 50.0),
+                shape("RECTANGLE", 60.0, 0.0, 120.0, 20.0),
+                text("Safari", 0.0, 34.0, 50.0),
+                shape("RECTANGLE", 60.0, 30.0, 60.0, 20.0),
+                text("Firefox", 0.0, 64.0, 50.0),
+                shape("RECTANGLE", 60.0, 60.0, 30.0, 20.0)
+            ]
+        });
+
+        let chart = &detect(&chart)[0];
+
+        assert_eq!(chart.orientation, Some("horizontal"));
+        assert_eq!(chart.scale, ChartScale::Pixels);
+        assert_eq!(values(chart), vec![120.0, 60.0, 30.0]);
+        assert_eq!(chart.series[0].points[0].label.as_deref(), Some("Chrome"));
+    }
+
+    #[test]
+    fn test_line_from_stroke_outline() {
+        let mut line = shape("VECTOR", 0.0, 0.0, 200.0, 100.0);
+        line["strokeWeight"] = json!(2);
+        // Outline of a 2px stroke through (0,100), (100,0), (200,50)
+        line["strokeGeometry"] = json!([{
+            "path": "M0 99L100 -1L200 49L200 51L100 1L0 101Z"
+        }]);
+        let chart = json!({ "type": "FRAME", "children": [line] });
+
+        let chart = &detect(&chart)[0];
+
+        assert_eq!(chart.kind, ChartKind::Line);
+        assert_eq!(values(chart), vec![0.0, 100.0, 50.0]);
+    }
+
+    #[test]
+    fn test_equal_shapes_are_not_bars() {
+        let buttons = json!({
+            "type": "FRAME",
+            "children": [
+                shape("RECTANGLE", 0.0, 0.0, 80.0, 40.0),
+                shape("RECTANGLE", 100.0, 0.0, 80.0, 40.0),
+                shape("RECTANGLE", 200.0, 0.0, 80.0, 40.0)
+            ]
+        });
+
+        assert!(detect(&buttons).is_empty());
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("1,200"), Some(1200.0));
+        assert_eq!(parse_number("$40"), Some(40.0));
+        assert_eq!(parse_number("1.5k"), Some(1500.0));
+        assert_eq!(parse_number("Q1"), None);
+    }
+}
@@ -0,0 +1,816 @@
+use serde_json::Value;
+
+use super::layers::is_hidden;
+use super::roles::{self, Role, RoleRules};
+
+/// Node types drawn as vector paths, which cannot be expressed as CSS boxes.
+const VECTOR_TYPES: &[&str] = &["VECTOR", "BOOLEAN_OPERATION", "STAR", "LINE", "POLYGON"];
+
+/// Matrix entries closer than this to their identity values are rounding
+/// noise from Figma's float math.
+const TRANSFORM_EPSILON: f64 = 1e-4;
+
+/// Markup flavor produced by [`generate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeFormat {
+    /// HTML with inline `style` attributes
+    Html,
+    /// A React function component with inline style objects
+    React,
+}
+
+impl CodeFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "html" => Some(Self::Html),
+            "react" | "jsx" => Some(Self::React),
+            _ => None,
+        }
+    }
+}
+
+/// Generates markup for a node in Figma's REST JSON format, including its
+/// visible descendants. `rules` decide which frames get semantic elements.
+pub fn generate(node: &Value, format: CodeFormat, rules: &RoleRules) -> String {
+    match format {
+        CodeFormat::Html => {
+            let mut html = String::new();
+            render_node(node, None, format, rules, 0, &mut html);
+            html
+        }
+        CodeFormat::React => {
+            let mut jsx = String::new();
+            render_node(node, None, format, rules, 2, &mut jsx);
+
+            let name = component_name(node_name(node));
+
+            format!(
+                "export function {}() {{\n  return (\n{}  );\n}}\n",
+                name, jsx
+            )
+        }
+    }
+}
+
+/// Extracts the CSS properties describing a node's layout and appearance.
+/// `parent` is needed to position children of frames without auto layout.
+pub fn css_properties(node: &Value, parent: Option<&Value>) -> Vec<(&'static str, String)> {
+    let mut properties = Vec::new();
+
+    layout_properties(node, parent, &mut properties);
+    if node_type(node) == "TEXT" {
+        text_properties(node, &mut properties);
+    } else {
+        box_properties(node, &mut properties);
+    }
+
+    if let Some(opacity) = node.get("opacity").and_then(Value::as_f64) {
+        if opacity < 1.0 {
+            properties.push(("opacity", format_number(opacity)));
+        }
+    }
+
+    properties
+}
+
+fn render_node(
+    node: &Value,
+    parent: Option<&Value>,
+    format: CodeFormat,
+    rules: &RoleRules,
+    indent: usize,
+    out: &mut String,
+) {
+    if is_hidden(node) {
+        return;
+    }
+
+    let padding = "  ".repeat(indent);
+    let node_type = node_type(node);
+
+    if VECTOR_TYPES.contains(&node_type) {
+        let note = format!("{} \"{}\": export as SVG", node_type, node_name(node));
+        match format {
+            CodeFormat::Html => out.push_str(&format!("{}<!-- {} -->\n", padding, note)),
+            CodeFormat::React => out.push_str(&format!("{}{{/* {} */}}\n", padding, note)),
+        }
+        return;
+    }
+
+    // Frames recognized as buttons, lists and the like get their element
+    let role = roles::infer(node, parent, rules).map(|inference| inference.role);
+    let (tag, attributes) = match role {
+        _ if node_type == "TEXT" => ("p", &[][..]),
+        Some(role) => role.element(),
+        None => ("div", &[][..]),
+    };
+    let mut properties = css_properties(node, parent);
+    if let Some(role) = role {
+        reset_properties(role, &mut properties);
+    }
+    let attributes: String = attributes
+        .iter()
+        .map(|(name, value)| format!(" {}=\"{}\"", name, value))
+        .collect();
+    let style = format!("{}{}", attributes, style_attribute(&properties, format));
+
+    if node_type == "TEXT" {
+        let text = node.get("characters").and_then(Value::as_str).unwrap_or("");
+        let text = match format {
+            CodeFormat::Html => escape_html(text),
+            CodeFormat::React => format!("{{{}}}", Value::String(text.to_string())),
+        };
+        out.push_str(&format!(
+            "{}<{}{}>{}</{}>\n",
+            padding, tag, style, text, tag
+        ));
+        return;
+    }
+
+    let children: Vec<&Value> = node
+        .get("children")
+        .and_then(Value::as_array)
+        .map(|children| children.iter().filter(|child| !is_hidden(child)).collect())
+        .unwrap_or_default();
+
+    if children.is_empty() {
+        out.push_str(&format!("{}<{}{} />\n", padding, tag, style));
+        return;
+    }
+
+    out.push_str(&format!("{}<{}{}>\n", padding, tag, style));
+    for child in children {
+        if role == Some(Role::List) {
+            out.push_str(&format!("{}  <li>\n", padding));
+            render_node(child, Some(node), format, rules, indent + 2, out);
+            out.push_str(&format!("{}  </li>\n", padding));
+        } else {
+            render_node(child, Some(node), format, rules, indent + 1, out);
+        }
+    }
+    out.push_str(&format!("{}</{}>\n", padding, tag));
+}
+
+fn layout_properties(
+    node: &Value,
+    parent: Option<&Value>,
+    properties: &mut Vec<(&'static str, String)>,
+) {
+    let bounds = node.get("absoluteBoundingBox");
+    let parent_layout = parent
+        .and_then(|parent| parent.get("layoutMode"))
+        .and_then(Value::as_str);
+    let transform = Transform::of(node);
+
+    // Figma transforms around a node's top left corner, CSS around its
+    // center. The box is laid out untransformed with the center of its
+    // bounding box, which the transform keeps in place
+    let (inset_x, inset_y) = transform
+        .as_ref()
+        .and_then(|transform| transform.inset(bounds))
+        .unwrap_or((0.0, 0.0));
+
+    // Children of frames without auto layout keep their absolute offsets
+    if let (Some(parent), None | Some("NONE")) = (parent, parent_layout) {
+        let parent_bounds = parent.get("absoluteBoundingBox");
+        if let (Some(left), Some(top)) = (
+            offset(bounds, parent_bounds, "x"),
+            offset(bounds, parent_bounds, "y"),
+        ) {
+            properties.push(("position", "absolute".to_string()));
+            properties.push(("left", px(left + inset_x)));
+            properties.push(("top", px(top + inset_y)));
+        }
+    } else if parent.is_some() && (inset_x != 0.0 || inset_y != 0.0) {
+        // Auto layout flows the transformed bounding box; margins grow or
+        // shrink the untransformed box to the same footprint
+        properties.push(("margin", shorthand(&[inset_y, inset_x, inset_y, inset_x])));
+    }
+
+    let sizing = |axis: &str| node.get(axis).and_then(Value::as_str).unwrap_or("FIXED");
+    for (property, axis, field) in [
+        ("width", "layoutSizingHorizontal", "width"),
+        ("height", "layoutSizingVertical", "height"),
+    ] {
+        match sizing(axis) {
+            "HUG" => {}
+            "FILL" if parent_layout == Some(main_axis_layout(field)) => {
+                properties.push(("flex", "1".to_string()));
+            }
+            "FILL" => properties.push(("align-self", "stretch".to_string())),
+            _ => {
+                // Text grows with its content unless Figma fixed its size
+                if node_type(node) == "TEXT" && field == "height" {
+                    continue;
+                }
+                let size = match &transform {
+                    Some(transform) => Some(transform.size(field)),
+                    None => bounds.and_then(|b| b.get(field)).and_then(Value::as_f64),
+                };
+                if let Some(size) = size {
+                    properties.push((property, px(size)));
+                }
+            }
+        }
+    }
+
+    if let Some(transform) = &transform {
+        properties.push(("transform", transform.css()));
+    }
+
+    let direction = match node.get("layoutMode").and_then(Value::as_str) {
+        Some("HORIZONTAL") => "row",
+        Some("VERTICAL") => "column",
+        _ => {
+            // Containing block for absolutely positioned children
+            let positioned = properties
+                .iter()
+                .any(|(property, _)| *property == "position");
+            if has_children(node) && !positioned {
+                properties.push(("position", "relative".to_string()));
+            }
+            return;
+        }
+    };
+
+    properties.push(("display", "flex".to_string()));
+    properties.push(("flex-direction", direction.to_string()));
+
+    if let Some(gap) = node.get("itemSpacing").and_then(Value::as_f64) {
+        if gap > 0.0 {
+            properties.push(("gap", px(gap)));
+        }
+    }
+
+    let padding: Vec<f64> = ["paddingTop", "paddingRight", "paddingBottom", "paddingLeft"]
+        .iter()
+        .map(|side| node.get(side).and_then(Value::as_f64).unwrap_or(0.0))
+        .collect();
+    if padding.iter().any(|&value| value > 0.0) {
+        properties.push(("padding", shorthand(&padding)));
+    }
+
+    if let Some(justify) = node
+        .get("primaryAxisAlignItems")
+        .and_then(Value::as_str)
+        .and_then(flex_alignment)
+    {
+        properties.push(("justify-content", justify.to_string()));
+    }
+
+    if let Some(align) = node
+        .get("counterAxisAlignItems")
+        .and_then(Value::as_str)
+        .and_then(flex_alignment)
+    {
+        properties.push(("align-items", align.to_string()));
+    }
+}
+
+fn box_properties(node: &Value, properties: &mut Vec<(&'static str, String)>) {
+    if let Some(background) = solid_fill(node.get("fills")) {
+        properties.push(("background-color", background));
+    }
+
+    if let Some(border) = solid_fill(node.get("strokes")) {
+        let weight = node
+            .get("strokeWeight")
+            .and_then(Value::as_f64)
+            .unwrap_or(1.0);
+        properties.push(("border", format!("{} solid {}", px(weight), border)));
+    }
+
+    if node_type(node) == "ELLIPSE" {
+        properties.push(("border-radius", "50%".to_string()));
+    } else if let Some(radii) = node.get("rectangleCornerRadii").and_then(Value::as_array) {
+        let radii: Vec<f64> = radii.iter().filter_map(Value::as_f64).collect();
+        if radii.len() == 4 && radii.iter().any(|&radius| radius > 0.0) {
+            properties.push(("border-radius", shorthand(&radii)));
+        }
+    } else if let Some(radius) = node.get("cornerRadius").and_then(Value::as_f64) {
+        if radius > 0.0 {
+            properties.push(("border-radius", px(radius)));
+        }
+    }
+
+    let shadows: Vec<String> = node
+        .get("effects")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|effect| !is_hidden(effect))
+        .filter_map(box_shadow)
+        .collect();
+    if !shadows.is_empty() {
+        properties.push(("box-shadow", shadows.join(", ")));
+    }
+
+    if node.get("clipsContent").and_then(Value::as_bool) == Some(true) {
+        properties.push(("overflow", "hidden".to_string()));
+    }
+}
+
+fn text_properties(node: &Value, properties: &mut Vec<(&'static str, String)>) {
+    if !properties.iter().any(|(property, _)| *property == "margin") {
+        properties.push(("margin", "0".to_string()));
+    }
+
+    if let Some(color) = solid_fill(node.get("fills")) {
+        properties.push(("color", color));
+    }
+
+    let Some(style) = node.get("style") else {
+        return;
+    };
+
+    if let Some(family) = style.get("fontFamily").and_then(Value::as_str) {
+        properties.push(("font-family", format!("'{}'", family)));
+    }
+    if let Some(size) = style.get("fontSize").and_then(Value::as_f64) {
+        properties.push(("font-size", px(size)));
+    }
+    if let Some(weight) = style.get("fontWeight").and_then(Value::as_f64) {
+        properties.push(("font-weight", format_number(weight)));
+    }
+    if style.get("italic").and_then(Value::as_bool) == Some(true) {
+        properties.push(("font-style", "italic".to_string()));
+    }
+    if let Some(line_height) = style.get("lineHeightPx").and_then(Value::as_f64) {
+        properties.push(("line-height", px(line_height)));
+    }
+    if let Some(spacing) = style.get("letterSpacing").and_then(Value::as_f64) {
+        if spacing != 0.0 {
+            properties.push(("letter-spacing", px(spacing)));
+        }
+    }
+
+    let align = match style.get("textAlignHorizontal").and_then(Value::as_str) {
+        Some("CENTER") => Some("center"),
+        Some("RIGHT") => Some("right"),
+        Some("JUSTIFIED") => Some("justify"),
+        _ => None,
+    };
+    if let Some(align) = align {
+        properties.push(("text-align", align.to_string()));
+    }
+
+    let transform = match style.get("textCase").and_then(Value::as_str) {
+        Some("UPPER") => Some("uppercase"),
+        Some("LOWER") => Some("lowercase"),
+        Some("TITLE") => Some("capitalize"),
+        _ => None,
+    };
+    if let Some(transform) = transform {
+        properties.push(("text-transform", transform.to_string()));
+    }
+
+    let decoration = match style.get("textDecoration").and_then(Value::as_str) {
+        Some("UNDERLINE") => Some("underline"),
+        Some("STRIKETHROUGH") => Some("line-through"),
+        _ => None,
+    };
+    if let Some(decoration) = decoration {
+        properties.push(("text-decoration", decoration.to_string()));
+    }
+}
+
+/// Overrides the browser styles of semantic elements that the design does
+/// not set itself.
+fn reset_properties(role: Role, properties: &mut Vec<(&'static str, String)>) {
+    let resets: &[(&'static str, &str)] = match role {
+        Role::List => &[("list-style", "none"), ("margin", "0"), ("padding", "0")],
+        Role::Button => &[("border", "none"), ("padding", "0"), ("font", "inherit")],
+        _ => &[],
+    };
+
+    for (property, value) in resets {
+        if !properties.iter().any(|(existing, _)| existing == property) {
+            properties.push((property, value.to_string()));
+        }
+    }
+}
+
+fn style_attribute(properties: &[(&'static str, String)], format: CodeFormat) -> String {
+    if properties.is_empty() {
+        return String::new();
+    }
+
+    match format {
+        CodeFormat::Html => {
+            let declarations: Vec<String> = properties
+                .iter()
+                .map(|(property, value)| format!("{}: {}", property, value))
+                .collect();
+            format!(" style=\"{}\"", escape_html(&declarations.join("; ")))
+        }
+        CodeFormat::React => {
+            let entries: Vec<String> = properties
+                .iter()
+                .map(|(property, value)| {
+                    format!("{}: {}", camel_case(property), Value::String(value.clone()))
+                })
+                .collect();
+            format!(" style={{{{ {} }}}}", entries.join(", "))
+        }
+    }
+}
+
+fn node_type(node: &Value) -> &str {
+    node.get("type").and_then(Value::as_str).unwrap_or("")
+}
+
+fn node_name(node: &Value) -> &str {
+    node.get("name").and_then(Value::as_str).unwrap_or("")
+}
+
+fn has_children(node: &Value) -> bool {
+    node.get("children")
+        .and_then(Value::as_array)
+        .is_some_and(|children| !children.is_empty())
+}
+
+fn main_axis_layout(field: &str) -> &'static str {
+    if field == "width" {
+        "HORIZONTAL"
+    } else {
+        "VERTICAL"
+    }
+}
+
+fn offset(bounds: Option<&Value>, parent_bounds: Option<&Value>, axis: &str) -> Option<f64> {
+    let position = bounds?.get(axis)?.as_f64()?;
+    let parent_position = parent_bounds?.get(axis)?.as_f64()?;
+
+    Some(position - parent_position)
+}
+
+/// The rotation, skew or flip of a node: the linear part of its
+/// `relativeTransform` (which maps `(x, y)` to `(a x + c y, b x + d y)`)
+/// and its untransformed size. Only present when fetched with
+/// `geometry=paths`.
+struct Transform {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Transform {
+    /// Reads a node's transform, or `None` when it is only translated.
+    fn of(node: &Value) -> Option<Self> {
+        let rows = node.get("relativeTransform")?.as_array()?;
+        let entry = |row: usize, column: usize| rows.get(row)?.get(column)?.as_f64();
+        let size = node.get("size")?;
+
+        let transform = Self {
+            a: entry(0, 0)?,
+            b: entry(1, 0)?,
+            c: entry(0, 1)?,
+            d: entry(1, 1)?,
+            width: size.get("x")?.as_f64()?,
+            height: size.get("y")?.as_f64()?,
+        };
+        let is_identity = (transform.a - 1.0).abs() < TRANSFORM_EPSILON
+            && transform.b.abs() < TRANSFORM_EPSILON
+            && transform.c.abs() < TRANSFORM_EPSILON
+            && (transform.d - 1.0).abs() < TRANSFORM_EPSILON;
+
+        (!is_identity).then_some(transform)
+    }
+
+    fn size(&self, field: &str) -> f64 {
+        if field == "width" {
+            self.width
+        } else {
+            self.height
+        }
+    }
+
+    /// How far the untransformed box sits inside the bounding box of the
+    /// transformed one, per axis. Negative when it sticks out.
+    fn inset(&self, bounds: Option<&Value>) -> Option<(f64, f64)> {
+        let bounds = bounds?;
+        let width = bounds.get("width")?.as_f64()?;
+        let height = bounds.get("height")?.as_f64()?;
+
+        Some(((width - self.width) / 2.0, (height - self.height) / 2.0))
+    }
+
+    /// The CSS transform: `rotate()` for pure rotations, else `matrix()`.
+    fn css(&self) -> String {
+        let is_rotation = (self.a - self.d).abs() < TRANSFORM_EPSILON
+            && (self.b + self.c).abs() < TRANSFORM_EPSILON
+            && (self.a * self.a + self.b * self.b - 1.0).abs() < TRANSFORM_EPSILON;
+        if is_rotation {
+            // Figma's rotations are counterclockwise, so the y axis flip
+            // makes CSS degrees come out negated
+            let degrees = self.b.atan2(self.a).to_degrees();
+            return format!("rotate({}deg)", format_number(degrees));
+        }
+
+        let entry = |value: f64| format!("{}", (value * 10000.0).round() / 10000.0 + 0.0);
+        format!(
+            "matrix({}, {}, {}, {}, 0, 0)",
+            entry(self.a),
+            entry(self.b),
+            entry(self.c),
+            entry(self.d)
+        )
+    }
+}
+
+fn flex_alignment(alignment: &str) -> Option<&'static str> {
+    match alignment {
+        "CENTER" => Some("center"),
+        "MAX" => Some("flex-end"),
+        "SPACE_BETWEEN" => Some("space-between"),
+        "BASELINE" => Some("baseline"),
+        _ => None,
+    }
+}
+
+/// Returns the CSS color of the topmost visible solid paint.
+pub(crate) fn solid_fill(paints: Option<&Value>) -> Option<String> {
+    paints?
+        .as_array()?
+        .iter()
+        .rev()
+        .filter(|paint| !is_hidden(paint))
+        .find(|paint| paint.get("type").and_then(Value::as_str) == Some("SOLID"))
+        .and_then(|paint| {
+            let opacity = paint.get("opacity").and_then(Value::as_f64).unwrap_or(1.0);
+            css_color(paint.get("color")?, opacity)
+        })
+}
+
+pub(crate) fn box_shadow(effect: &Value) -> Option<String> {
+    let inset = match effect.get("type").and_then(Value::as_str)? {
+        "DROP_SHADOW" => "",
+        "INNER_SHADOW" => "inset ",
+        _ => return None,
+    };
+
+    let offset = effect.get("offset");
+    let x = offset
+        .and_then(|o| o.get("x"))
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0);
+    let y = offset
+        .and_then(|o| o.get("y"))
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0);
+    let blur = effect.get("radius").and_then(Value::as_f64).unwrap_or(0.0);
+    let spread = effect.get("spread").and_then(Value::as_f64).unwrap_or(0.0);
+    let color = css_color(effect.get("color")?, 1.0)?;
+
+    Some(format!(
+        "{}{} {} {} {} {}",
+        inset,
+        px(x),
+        px(y),
+        px(blur),
+        px(spread),
+        color
+    ))
+}
+
+/// Converts a Figma color with 0-1 channels into `#rrggbb` or `rgba(...)`.
+pub fn css_color(color: &Value, opacity: f64) -> Option<String> {
+    let channel = |name: &str| color.get(name).and_then(Value::as_f64);
+    let (r, g, b) = (channel("r")?, channel("g")?, channel("b")?);
+    let alpha = channel("a").unwrap_or(1.0) * opacity;
+
+    let to_byte = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let (r, g, b) = (to_byte(r), to_byte(g), to_byte(b));
+
+    if alpha >= 1.0 {
+        return Some(format!("#{:02x}{:02x}{:02x}", r, g, b));
+    }
+
+    Some(format!(
+        "rgba({}, {}, {}, {})",
+        r,
+        g,
+        b,
+        format_number(alpha)
+    ))
+}
+
+/// Collapses four side or corner values (in CSS order) into the shortest
+/// equivalent shorthand.
+fn shorthand(values: &[f64]) -> String {
+    let values = match values {
+        [a, b, c, d] if a == b && b == c && c == d => vec![*a],
+        [a, b, c, d] if a == c && b == d => vec![*a, *b],
+        [a, b, c, d] if b == d => vec![*a, *b, *c],
+        values => values.to_vec(),
+    };
+
+    values
+        .iter()
+        .map(|&value| px(value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn px(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+
+    format!("{}px", format_number(value))
+}
+
+pub(crate) fn format_number(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+
+    format!("{}", rounded)
+}
+
+fn camel_case(property: &str) -> String {
+    let mut parts = property.split('-');
+    let first = parts.next().unwrap_or_default().to_string();
+
+    parts.fold(first, |mut name, part| {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            name.extend(c.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+        name
+    })
+}
+
+pub fn component_name(name: &str) -> String {
+    let name: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().map(|c| c.to_ascii_uppercase());
+            first.into_iter().chain(chars).collect::<String>()
+        })
+        .collect();
+
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        Some(_) => format!("Component{}", name),
+        None => "Component".to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn button() -> Value {
+        json!({
+            "id": "1:2",
+            "name": "Primary button",
+            "type": "FRAME",
+            "layoutMode": "HORIZONTAL",
+            "layoutSizingHorizontal": "HUG",
+            "layoutSizingVertical": "HUG",
+            "itemSpacing": 8,
+            "paddingTop": 12, "paddingRight": 24, "paddingBottom": 12, "paddingLeft": 24,
+            "primaryAxisAlignItems": "CENTER",
+            "counterAxisAlignItems": "CENTER",
+            "cornerRadius": 8,
+            "fills": [{ "type": "SOLID", "color": { "r": 0.1176, "g": 0.5333, "b": 0.898, "a": 1 } }],
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 120, "height": 44 },
+            "children": [{
+                "id": "1:3",
+                "name": "Label",
+                "type": "TEXT",
+                "characters": "Save <draft>",
+                "fills": [{ "type": "SOLID", "color": { "r": 1, "g": 1, "b": 1, "a": 1 } }],
+                "style": { "fontFamily": "Inter", "fontSize": 16, "fontWeight": 600 },
+                "absoluteBoundingBox": { "x": 24, "y": 12, "width": 72, "height": 20 }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_auto_layout_properties() {
+        let properties = css_properties(&button(), None);
+
+        assert!(properties.contains(&("display", "flex".to_string())));
+        assert!(properties.contains(&("gap", "8px".to_string())));
+        assert!(properties.contains(&("padding", "12px 24px".to_string())));
+        assert!(properties.contains(&("justify-content", "center".to_string())));
+        assert!(properties.contains(&("background-color", "#1e88e5".to_string())));
+        assert!(properties.contains(&("border-radius", "8px".to_string())));
+        assert!(!properties.iter().any(|(property, _)| *property == "width"));
+    }
+
+    #[test]
+    fn test_absolute_children_are_positioned() {
+        let parent = json!({
+            "type": "FRAME",
+            "absoluteBoundingBox": { "x": 100, "y": 100, "width": 300, "height": 200 }
+        });
+        let child = json!({
+            "type": "RECTANGLE",
+            "absoluteBoundingBox": { "x": 110, "y": 130, "width": 50, "height": 50 }
+        });
+
+        let properties = css_properties(&child, Some(&parent));
+        assert!(properties.contains(&("position", "absolute".to_string())));
+        assert!(properties.contains(&("left", "10px".to_string())));
+        assert!(properties.contains(&("top", "30px".to_string())));
+    }
+
+    #[test]
+    fn test_rotated_child_keeps_its_center() {
+        let parent = json!({
+            "type": "FRAME",
+            "absoluteBoundingBox": { "x": 100, "y": 100, "width": 300, "height": 200 }
+        });
+        // A 100x20 bar rotated 90 degrees counterclockwise in Figma
+        let child = json!({
+            "type": "RECTANGLE",
+            "relativeTransform": [[0, 1, 10], [-1, 0, 130]],
+            "size": { "x": 100, "y": 20 },
+            "absoluteBoundingBox": { "x": 110, "y": 30, "width": 20, "height": 100 }
+        });
+
+        let properties = css_properties(&child, Some(&parent));
+        assert!(properties.contains(&("left", "-30px".to_string())));
+        assert!(properties.contains(&("top", "-30px".to_string())));
+        assert!(properties.contains(&("width", "100px".to_string())));
+        assert!(properties.contains(&("height", "20px".to_string())));
+        assert!(properties.contains(&("transform", "rotate(-90deg)".to_string())));
+    }
+
+    #[test]
+    fn test_skewed_child_in_auto_layout() {
+        let parent = json!({ "type": "FRAME", "layoutMode": "HORIZONTAL" });
+        let child = json!({
+            "type": "RECTANGLE",
+            "relativeTransform": [[1, 0.5, 0], [0, 1, 0]],
+            "size": { "x": 40, "y": 40 },
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 60, "height": 40 }
+        });
+
+        let properties = css_properties(&child, Some(&parent));
+        assert!(properties.contains(&("margin", "0 10px".to_string())));
+        assert!(properties.contains(&("width", "40px".to_string())));
+        assert!(properties.contains(&("transform", "matrix(1, 0, 0.5, 1, 0, 0)".to_string())));
+    }
+
+    #[test]
+    fn test_generate_html_escapes_text() {
+        let html = generate(&button(), CodeFormat::Html, &RoleRules::default());
+
+        assert!(html.starts_with("<button type=\"button\" style=\""));
+        assert!(html.contains("border: none"));
+        assert!(html.contains(">Save &lt;draft&gt;</p>"));
+        assert!(html.contains("font-family: 'Inter'"));
+    }
+
+    #[test]
+    fn test_generate_react_component() {
+        let jsx = generate(&button(), CodeFormat::React, &RoleRules::default());
+
+        assert!(jsx.starts_with("export function PrimaryButton() {"));
+        assert!(jsx.contains("flexDirection: \"row\""));
+        assert!(jsx.contains("{\"Save <draft>\"}"));
+    }
+
+    #[test]
+    fn test_generate_wraps_list_items() {
+        let item = json!({ "type": "INSTANCE", "name": "Row", "componentId": "2:1" });
+        let list = json!({
+            "type": "FRAME",
+            "name": "Results",
+            "layoutMode": "VERTICAL",
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 320, "height": 400 },
+            "children": [item, item, item]
+        });
+
+        let html = generate(&list, CodeFormat::Html, &RoleRules::default());
+
+        assert!(html.starts_with("<ul style=\""));
+        assert!(html.contains("list-style: none"));
+        assert_eq!(html.matches("  <li>\n").count(), 3);
+    }
+
+    #[test]
+    fn test_css_color_with_alpha() {
+        let color = json!({ "r": 0, "g": 0, "b": 0, "a": 0.5 });
+        assert_eq!(
+            css_color(&color, 1.0),
+            Some("rgba(0, 0, 0, 0.5)".to_string())
+        );
+    }
+}
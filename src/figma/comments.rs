@@ -0,0 +1,260 @@
+//! Comment threads from Figma's comments endpoint, so agents can read the
+//! feedback designers left on frames and leave their own.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// A top-level comment with its replies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommentThread {
+    pub id: String,
+    /// The node the comment is pinned to, if any; comments can also be
+    /// pinned to a canvas position
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    pub author: String,
+    pub message: String,
+    pub created_at: String,
+    pub resolved: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<String>,
+    pub replies: Vec<Reply>,
+}
+
+/// Where a new comment is pinned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommentAnchor {
+    /// A position on the canvas
+    Canvas { x: f64, y: f64 },
+    /// A node, at an offset from its top-left corner
+    Node { node_id: String, x: f64, y: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reply {
+    pub id: String,
+    pub author: String,
+    pub message: String,
+    pub created_at: String,
+}
+
+/// Groups the comments of a `GET /files/:key/comments` response into
+/// threads, oldest first. Only threads pinned to one of `node_ids` are
+/// kept unless it is empty, and only threads in the given resolved state
+/// if `resolved` is set.
+pub fn threads(
+    response: &Value,
+    node_ids: &[String],
+    resolved: Option<bool>,
+) -> Vec<CommentThread> {
+    let comments: Vec<&Value> = response
+        .get("comments")
+        .and_then(Value::as_array)
+        .map(|comments| comments.iter().collect())
+        .unwrap_or_default();
+
+    let mut threads: Vec<CommentThread> = comments
+        .iter()
+        .filter(|comment| parent_id(comment).is_none())
+        .map(|comment| thread(comment))
+        .filter(|thread| {
+            node_ids.is_empty()
+                || thread
+                    .node_id
+                    .as_ref()
+                    .is_some_and(|node_id| node_ids.contains(node_id))
+        })
+        .filter(|thread| resolved.is_none_or(|resolved| thread.resolved == resolved))
+        .collect();
+
+    for comment in &comments {
+        let Some(parent_id) = parent_id(comment) else {
+            continue;
+        };
+        if let Some(thread) = threads.iter_mut().find(|thread| thread.id == parent_id) {
+            thread.replies.push(reply(comment));
+        }
+    }
+
+    // Timestamps are ISO 8601, so they sort as strings
+    threads.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    for thread in &mut threads {
+        thread
+            .replies
+            .sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    }
+
+    threads
+}
+
+/// Reads a top-level comment, such as the one returned when posting a
+/// comment, as a thread without replies.
+pub fn thread(comment: &Value) -> CommentThread {
+    let resolved_at = comment
+        .get("resolved_at")
+        .and_then(Value::as_str)
+        .map(String::from);
+
+    CommentThread {
+        id: field(comment, "id"),
+        node_id: comment
+            .pointer("/client_meta/node_id")
+            .and_then(Value::as_str)
+            .map(String::from),
+        author: author(comment),
+        message: field(comment, "message"),
+        created_at: field(comment, "created_at"),
+        resolved: resolved_at.is_some(),
+        resolved_at,
+        replies: Vec::new(),
+    }
+}
+
+/// Reads a reply, such as the one returned when replying to a thread.
+pub fn reply(comment: &Value) -> Reply {
+    Reply {
+        id: field(comment, "id"),
+        author: author(comment),
+        message: field(comment, "message"),
+        created_at: field(comment, "created_at"),
+    }
+}
+
+/// Builds the body of a `POST /files/:key/comments` request. Figma places
+/// comments without an anchor itself.
+pub fn new_comment(message: &str, anchor: Option<&CommentAnchor>) -> Value {
+    let mut body = json!({ "message": message });
+    match anchor {
+        Some(CommentAnchor::Canvas { x, y }) => body["client_meta"] = json!({ "x": x, "y": y }),
+        Some(CommentAnchor::Node { node_id, x, y }) => {
+            body["client_meta"] = json!({
+                "node_id": node_id,
+                "node_offset": { "x": x, "y": y }
+            })
+        }
+        None => {}
+    }
+
+    body
+}
+
+/// Builds the body of a `POST /files/:key/comments` request replying to
+/// the thread of `comment_id`. Replies can only be added to top-level
+/// comments and take their position from them.
+pub fn new_reply(comment_id: &str, message: &str) -> Value {
+    json!({ "message": message, "comment_id": comment_id })
+}
+
+/// Figma sends an empty `parent_id` for top-level comments.
+fn parent_id(comment: &Value) -> Option<&str> {
+    comment
+        .get("parent_id")
+        .and_then(Value::as_str)
+        .filter(|parent_id| !parent_id.is_empty())
+}
+
+fn author(comment: &Value) -> String {
+    comment
+        .pointer("/user/handle")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn field(comment: &Value, key: &str) -> String {
+    comment
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> Value {
+        json!({
+            "comments": [
+                {
+                    "id": "3",
+                    "parent_id": "1",
+                    "user": { "handle": "Sam" },
+                    "message": "Fixed in v2",
+                    "created_at": "2024-05-02T09:00:00Z",
+                    "resolved_at": null
+                },
+                {
+                    "id": "1",
+                    "parent_id": "",
+                    "user": { "handle": "Ana" },
+                    "message": "Button label is cut off",
+                    "created_at": "2024-05-01T09:00:00Z",
+                    "resolved_at": "2024-05-03T09:00:00Z",
+                    "client_meta": { "node_id": "1:2", "node_offset": { "x": 10, "y": 4 } }
+                },
+                {
+                    "id": "2",
+                    "parent_id": "",
+                    "user": { "handle": "Ana" },
+                    "message": "Too much padding here",
+                    "created_at": "2024-05-01T10:00:00Z",
+                    "resolved_at": null,
+                    "client_meta": { "x": 120, "y": 300 }
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_threads_group_replies() {
+        let threads = threads(&response(), &[], None);
+
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].id, "1");
+        assert!(threads[0].resolved);
+        assert_eq!(threads[0].node_id.as_deref(), Some("1:2"));
+        assert_eq!(threads[0].replies[0].message, "Fixed in v2");
+        assert!(!threads[1].resolved);
+        assert_eq!(threads[1].node_id, None);
+    }
+
+    #[test]
+    fn test_threads_filter_by_node_and_state() {
+        let response = response();
+
+        let pinned = threads(&response, &["1:2".to_string()], None);
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].id, "1");
+
+        let open = threads(&response, &[], Some(false));
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].id, "2");
+    }
+
+    #[test]
+    fn test_new_comment_anchors() {
+        let anchor = CommentAnchor::Node {
+            node_id: "1:2".to_string(),
+            x: 10.0,
+            y: 4.0,
+        };
+        assert_eq!(
+            new_comment("Contrast is too low", Some(&anchor)),
+            json!({
+                "message": "Contrast is too low",
+                "client_meta": { "node_id": "1:2", "node_offset": { "x": 10.0, "y": 4.0 } }
+            })
+        );
+
+        let anchor = CommentAnchor::Canvas { x: 120.0, y: 300.0 };
+        let body = new_comment("Missing state", Some(&anchor));
+        assert_eq!(body["client_meta"], json!({ "x": 120.0, "y": 300.0 }));
+
+        assert_eq!(new_comment("Nice", None), json!({ "message": "Nice" }));
+        assert_eq!(
+            new_reply("1", "Done"),
+            json!({ "message": "Done", "comment_id": "1" })
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::bounds::Bounds;
+use super::layers::is_hidden;
+
+/// WCAG 2.x AA minimum contrast for body text.
+pub const AA_NORMAL: f64 = 4.5;
+/// WCAG 2.x AA minimum contrast for large text.
+pub const AA_LARGE: f64 = 3.0;
+
+/// Text at least this size counts as large, or at `LARGE_BOLD_TEXT_SIZE`
+/// when bold (18pt and 14pt in CSS pixels).
+const LARGE_TEXT_SIZE: f64 = 24.0;
+const LARGE_BOLD_TEXT_SIZE: f64 = 18.66;
+
+const MAX_TEXT_LENGTH: usize = 80;
+
+/// Color shown behind the top-level frame when it has no solid fill.
+const CANVAS: Rgb = [1.0, 1.0, 1.0];
+
+type Rgb = [f64; 3];
+
+/// A text node whose contrast against its background is below WCAG AA.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContrastIssue {
+    pub node_id: String,
+    pub name: String,
+    pub text: String,
+    pub ratio: f64,
+    pub required: f64,
+    pub foreground: String,
+    pub background: String,
+    #[serde(skip)]
+    pub bounds: Bounds,
+}
+
+/// Checks the contrast of every visible text node below `root`.
+///
+/// A text node's background is the solid fill of the nearest sibling drawn
+/// below it that fully covers it, or else its parent's fill, composited
+/// over the backgrounds further up. Gradients and images are ignored, so
+/// text over them is checked against the color beneath.
+pub fn audit(root: &Value) -> Vec<ContrastIssue> {
+    let mut issues = Vec::new();
+    let background = paint_over(root, CANVAS).unwrap_or(CANVAS);
+
+    check_children(root, background, &mut issues);
+
+    issues
+}
+
+/// WCAG contrast ratio between two colors.
+pub fn contrast_ratio(a: Rgb, b: Rgb) -> f64 {
+    let (a, b) = (luminance(a), luminance(b));
+    let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+fn check_children(node: &Value, background: Rgb, issues: &mut Vec<ContrastIssue>) {
+    let children = node.get("children").and_then(Value::as_array);
+
+    // Filled siblings drawn so far, which later siblings may sit on
+    let mut backdrops: Vec<(Bounds, Rgb)> = Vec::new();
+    for child in children.into_iter().flatten() {
+        if is_hidden(child) {
+            continue;
+        }
+
+        let bounds = Bounds::of(child, "absoluteBoundingBox");
+        let behind = bounds
+            .and_then(|bounds| {
+                backdrops
+                    .iter()
+                    .rev()
+                    .find(|(backdrop, _)| backdrop.contains(&bounds))
+            })
+            .map(|(_, color)| *color)
+            .unwrap_or(background);
+
+        if child.get("type").and_then(Value::as_str) == Some("TEXT") {
+            if let Some(issue) = check_text(child, behind, bounds.unwrap_or_default()) {
+                issues.push(issue);
+            }
+            continue;
+        }
+
+        let fill = paint_over(child, behind);
+        check_children(child, fill.unwrap_or(behind), issues);
+        if let (Some(bounds), Some(fill)) = (bounds, fill) {
+            backdrops.push((bounds, fill));
+        }
+    }
+}
+
+fn check_text(node: &Value, background: Rgb, bounds: Bounds) -> Option<ContrastIssue> {
+    let foreground = paint_over(node, background)?;
+    let ratio = contrast_ratio(foreground, background);
+
+    let style = node.get("style");
+    let size = style
+        .and_then(|s| s.get("fontSize"))
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0);
+    let weight = style
+        .and_then(|s| s.get("fontWeight"))
+        .and_then(Value::as_f64)
+        .unwrap_or(400.0);
+    let is_large = size >= LARGE_TEXT_SIZE || (size >= LARGE_BOLD_TEXT_SIZE && weight >= 700.0);
+    let required = if is_large { AA_LARGE } else { AA_NORMAL };
+
+    if ratio >= required {
+        return None;
+    }
+
+    let text = node.get("characters").and_then(Value::as_str).unwrap_or("");
+
+    Some(ContrastIssue {
+        node_id: string_field(node, "id"),
+        name: string_field(node, "name"),
+        text: text.chars().take(MAX_TEXT_LENGTH).collect(),
+        ratio: (ratio * 100.0).floor() / 100.0,
+        required,
+        foreground: hex(foreground),
+        background: hex(background),
+        bounds,
+    })
+}
+
+/// Composites a node's visible solid fills over `backdrop`, or returns
+/// `None` when it has none.
+fn paint_over(node: &Value, backdrop: Rgb) -> Option<Rgb> {
+    let node_opacity = node.get("opacity").and_then(Value::as_f64).unwrap_or(1.0);
+    let fills = node.get("fills").and_then(Value::as_array)?;
+
+    let mut color = backdrop;
+    let mut painted = false;
+    for fill in fills {
+        if is_hidden(fill) || fill.get("type").and_then(Value::as_str) != Some("SOLID") {
+            continue;
+        }
+        let Some(paint) = fill.get("color") else {
+            continue;
+        };
+
+        let channel = |name: &str| paint.get(name).and_then(Value::as_f64).unwrap_or(0.0);
+        let alpha = paint.get("a").and_then(Value::as_f64).unwrap_or(1.0)
+            * fill.get("opacity").and_then(Value::as_f64).unwrap_or(1.0)
+            * node_opacity;
+        let paint = [channel("r"), channel("g"), channel("b")];
+        for (value, paint) in color.iter_mut().zip(paint) {
+            *value = paint * alpha + *value * (1.0 - alpha);
+        }
+        painted = true;
+    }
+
+    painted.then_some(color)
+}
+
+fn luminance(color: Rgb) -> f64 {
+    let linear = color.map(|value| {
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    });
+
+    0.2126 * linear[0] + 0.7152 * linear[1] + 0.0722 * linear[2]
+}
+
+fn hex(color: Rgb) -> String {
+    let [r, g, b] = color.map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8);
+
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn string_field(node: &Value, key: &str) -> String {
+    node.get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(id: &str, gray: f64, size: f64, bounds: (f64, f64)) -> Value {
+        json!({
+            "id": id,
+            "name": id,
+            "type": "TEXT",
+            "characters": "Hello",
+            "style": { "fontSize": size, "fontWeight": 400 },
+            "fills": [{ "type": "SOLID", "color": { "r": gray, "g": gray, "b": gray, "a": 1 } }],
+            "absoluteBoundingBox": { "x": bounds.0, "y": bounds.1, "width": 50, "height": 20 }
+        })
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((contrast_ratio([0.0; 3], [1.0; 3]) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio([1.0; 3], [1.0; 3]) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_audit_uses_covering_sibling_as_background() {
+        let frame = json!({
+            "type": "FRAME",
+            "fills": [{ "type": "SOLID", "color": { "r": 1, "g": 1, "b": 1, "a": 1 } }],
+            "children": [
+                {
+                    "type": "RECTANGLE",
+                    "fills": [{ "type": "SOLID", "color": { "r": 0, "g": 0, "b": 0, "a": 1 } }],
+                    "absoluteBoundingBox": { "x": 0, "y": 0, "width": 100, "height": 100 }
+                },
+                // Light gray on the black rectangle passes
+                text("1:1", 0.9, 14.0, (10.0, 10.0)),
+                // Light gray on the white frame fails
+                text("1:2", 0.8, 14.0, (10.0, 200.0)),
+                // The same gray at a large size only needs 3:1, and fails too
+                text("1:3", 0.8, 32.0, (10.0, 300.0))
+            ]
+        });
+
+        let issues = audit(&frame);
+
+        let ids: Vec<&str> = issues.iter().map(|issue| issue.node_id.as_str()).collect();
+        assert_eq!(ids, vec!["1:2", "1:3"]);
+        assert_eq!(issues[0].required, AA_NORMAL);
+        assert_eq!(issues[1].required, AA_LARGE);
+        assert_eq!(issues[0].background, "#ffffff");
+        assert_eq!(issues[0].bounds.y, 200.0);
+    }
+}
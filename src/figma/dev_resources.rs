@@ -0,0 +1,123 @@
+//! Dev resources: links from nodes to code, such as pull requests or
+//! Storybook stories, which Figma shows next to the node in Dev Mode.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DevResource {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub node_id: String,
+}
+
+/// Reads a `GET /files/:key/dev_resources` response, sorted by node and
+/// then by name.
+pub fn resources(response: &Value) -> Vec<DevResource> {
+    let resources = response.get("dev_resources").and_then(Value::as_array);
+
+    let mut resources: Vec<DevResource> = resources.into_iter().flatten().map(resource).collect();
+    resources.sort_by(|a, b| (&a.node_id, &a.name).cmp(&(&b.node_id, &b.name)));
+
+    resources
+}
+
+/// Builds the body of a `POST /dev_resources` request linking a node to
+/// `url`.
+pub fn new_resource(file_key: &str, node_id: &str, name: &str, url: &str) -> Value {
+    json!({
+        "dev_resources": [{
+            "file_key": file_key,
+            "node_id": node_id,
+            "name": name,
+            "url": url
+        }]
+    })
+}
+
+/// Builds the body of a `PUT /dev_resources` request. Fields left out keep
+/// their current value.
+pub fn resource_update(id: &str, name: Option<&str>, url: Option<&str>) -> Value {
+    let mut update = json!({ "id": id });
+    if let Some(name) = name {
+        update["name"] = json!(name);
+    }
+    if let Some(url) = url {
+        update["url"] = json!(url);
+    }
+
+    json!({ "dev_resources": [update] })
+}
+
+/// Reads the resource created by a `POST /dev_resources` request. Figma
+/// answers requests it rejects per item with status 200 and an `errors`
+/// list, whose message is returned as the error.
+pub fn created(response: &Value) -> Result<DevResource, String> {
+    let created = response.pointer("/links_created/0");
+
+    match (created, first_error(response)) {
+        (Some(created), _) => Ok(resource(created)),
+        (None, Some(error)) => Err(error),
+        (None, None) => Err("Figma created no dev resource".to_string()),
+    }
+}
+
+/// The error of a `PUT /dev_resources` request rejected per item, if any.
+pub fn update_error(response: &Value) -> Option<String> {
+    first_error(response)
+}
+
+fn resource(value: &Value) -> DevResource {
+    DevResource {
+        id: field(value, "id"),
+        name: field(value, "name"),
+        url: field(value, "url"),
+        node_id: field(value, "node_id"),
+    }
+}
+
+fn first_error(response: &Value) -> Option<String> {
+    response
+        .pointer("/errors/0/error")
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+fn field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resources_and_created() {
+        let response = json!({
+            "dev_resources": [
+                { "id": "2", "name": "Storybook", "url": "https://sb.example/button", "node_id": "1:2" },
+                { "id": "1", "name": "PR #12", "url": "https://github.com/o/r/pull/12", "node_id": "1:2" },
+                { "id": "3", "name": "Docs", "url": "https://docs.example", "node_id": "0:1" }
+            ]
+        });
+        let ids: Vec<String> = resources(&response).into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["3", "1", "2"]);
+
+        let response = json!({
+            "links_created": [],
+            "errors": [{ "file_key": "abc", "node_id": "1:2", "error": "Invalid URL" }]
+        });
+        assert_eq!(created(&response), Err("Invalid URL".to_string()));
+
+        let update = resource_update("7", None, Some("https://example.com"));
+        assert_eq!(
+            update,
+            json!({ "dev_resources": [{ "id": "7", "url": "https://example.com" }] })
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! API responses kept with their `ETag`, so reads of unchanged files are
+//! answered with a `304 Not Modified` instead of downloading them again.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+
+use crate::{Error, Result};
+
+/// Total size of the responses kept when no budget is configured.
+pub const DEFAULT_MAX_BYTES: usize = 128 * 1024 * 1024;
+
+/// Bodies of recent responses by URL, least recently used first, dropped
+/// once they exceed the budget. Clones share the responses.
+#[derive(Debug, Clone)]
+pub struct EtagCache {
+    responses: Arc<Mutex<Responses>>,
+    max_bytes: usize,
+}
+
+#[derive(Debug, Default)]
+struct Responses {
+    items: VecDeque<CachedResponse>,
+    bytes: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: String,
+    pub body: Bytes,
+}
+
+impl EtagCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            responses: Arc::new(Mutex::new(Responses::default())),
+            max_bytes,
+        }
+    }
+
+    /// An empty cache with the same budget, for responses read with other
+    /// credentials.
+    pub fn fresh(&self) -> Self {
+        Self::new(self.max_bytes)
+    }
+
+    /// The response kept for `url`, if any, which becomes the most
+    /// recently used.
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        let mut responses = self.responses.lock().ok()?;
+        let index = responses.items.iter().position(|item| item.url == url)?;
+        let response = responses.items.remove(index)?;
+        responses.items.push_back(response.clone());
+
+        Some(response)
+    }
+
+    /// Keeps a response, replacing the one kept for its URL. Responses
+    /// larger than the whole budget are not kept.
+    pub fn insert(&self, url: &str, etag: &str, body: Bytes) {
+        let Ok(mut responses) = self.responses.lock() else {
+            return;
+        };
+        if let Some(index) = responses.items.iter().position(|item| item.url == url) {
+            if let Some(previous) = responses.items.remove(index) {
+                responses.bytes -= previous.body.len();
+            }
+        }
+        if body.len() > self.max_bytes {
+            return;
+        }
+
+        responses.bytes += body.len();
+        responses.items.push_back(CachedResponse {
+            url: url.to_string(),
+            etag: etag.to_string(),
+            body,
+        });
+        while responses.bytes > self.max_bytes {
+            match responses.items.pop_front() {
+                Some(oldest) => responses.bytes -= oldest.body.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Drops all responses. Returns how many were kept.
+    pub fn clear(&self) -> Result<usize> {
+        let mut responses = self
+            .responses
+            .lock()
+            .map_err(|_| Error::Internal("Response cache lock poisoned".to_string()))?;
+        let count = responses.items.len();
+        *responses = Responses::default();
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_are_dropped() {
+        let cache = EtagCache::new(10);
+        cache.insert("files/A", "\"a1\"", Bytes::from_static(b"aaaa"));
+        cache.insert("files/B", "\"b1\"", Bytes::from_static(b"bbbb"));
+
+        // Reading A makes B the least recently used
+        assert_eq!(cache.get("files/A").unwrap().etag, "\"a1\"");
+        cache.insert("files/C", "\"c1\"", Bytes::from_static(b"cccc"));
+
+        assert!(cache.get("files/B").is_none());
+        assert!(cache.get("files/A").is_some());
+        assert!(cache.get("files/C").is_some());
+    }
+
+    #[test]
+    fn test_insert_replaces_and_skips_oversized() {
+        let cache = EtagCache::new(10);
+        cache.insert("files/A", "\"a1\"", Bytes::from_static(b"aaaa"));
+        cache.insert("files/A", "\"a2\"", Bytes::from_static(b"aaaaaa"));
+        assert_eq!(cache.get("files/A").unwrap().etag, "\"a2\"");
+
+        // A response too large to keep drops the outdated one
+        cache.insert("files/A", "\"a3\"", Bytes::from_static(b"aaaaaaaaaaaa"));
+        assert!(cache.get("files/A").is_none());
+        assert_eq!(cache.clear().unwrap(), 0);
+    }
+}
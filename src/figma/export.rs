@@ -0,0 +1,371 @@
+//! Validation of image export parameters, so mistakes are reported before
+//! Figma answers them with a bare HTTP 400.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::bounds::{Bounds, BoundsKind};
+use super::layers::is_hidden;
+
+/// Scale range accepted by Figma's images endpoint.
+pub const MIN_SCALE: f64 = 0.01;
+pub const MAX_SCALE: f64 = 4.0;
+
+/// Renders above this many pixels tend to fail on Figma's side.
+const MAX_RENDER_PIXELS: f64 = 32_000_000.0;
+
+const UNKNOWN_FAILURE: &str =
+    "Figma returned no image, possibly because rendering timed out; retry the node on its own";
+
+/// Node types Figma cannot render as images.
+const UNEXPORTABLE_TYPES: &[(&str, &str)] = &[
+    ("DOCUMENT", "documents cannot be exported"),
+    (
+        "CANVAS",
+        "pages cannot be exported, export their top-level frames instead",
+    ),
+];
+
+/// Optional parameters of Figma's images endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportOptions {
+    pub scale: Option<f64>,
+    /// Render the node's full layout box instead of cropping to what is
+    /// drawn, e.g. to keep an icon's padding
+    pub use_absolute_bounds: bool,
+    /// Leave out other nodes overlapping the node, such as the frame
+    /// background behind it. Figma's default
+    pub contents_only: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            scale: None,
+            use_absolute_bounds: false,
+            contents_only: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Query parameters for the non-default options, each prefixed by `&`.
+    pub fn query(&self) -> String {
+        let mut query = String::new();
+        if let Some(scale) = self.scale {
+            query.push_str(&format!("&scale={}", scale));
+        }
+        if self.use_absolute_bounds {
+            query.push_str("&use_absolute_bounds=true");
+        }
+        if !self.contents_only {
+            query.push_str("&contents_only=false");
+        }
+
+        query
+    }
+}
+
+/// A requested node Figma returned no image for, and the likely reason.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportFailure {
+    pub node_id: String,
+    pub reason: String,
+}
+
+/// Image formats of Figma's images endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    Jpg,
+    Svg,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpg),
+            "svg" => Some(Self::Svg),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpg => "jpg",
+            Self::Svg => "svg",
+            Self::Pdf => "pdf",
+        }
+    }
+
+    /// Vector formats are resolution independent, so Figma ignores `scale`.
+    pub fn is_vector(&self) -> bool {
+        matches!(self, Self::Svg | Self::Pdf)
+    }
+}
+
+/// Checks an export's format and scale, returning the parsed format or a
+/// message explaining what to change.
+pub fn validate(format: &str, scale: Option<f64>) -> Result<ExportFormat, String> {
+    let Some(export_format) = ExportFormat::parse(format) else {
+        return Err(format!(
+            "unsupported export format '{}', expected png, jpg, svg or pdf",
+            format
+        ));
+    };
+
+    let Some(scale) = scale else {
+        return Ok(export_format);
+    };
+    if !(MIN_SCALE..=MAX_SCALE).contains(&scale) {
+        return Err(format!(
+            "scale {} is out of range, Figma exports between {} and {}",
+            scale, MIN_SCALE, MAX_SCALE
+        ));
+    }
+    if export_format.is_vector() && scale > 1.0 {
+        return Err(format!(
+            "scale {} has no effect on {} exports, which are resolution independent; omit it or export png",
+            scale,
+            export_format.name()
+        ));
+    }
+
+    Ok(export_format)
+}
+
+/// Checks the formats of an export, `format` or the distinct `formats`. Vector
+/// formats are resolution independent; when exported alongside raster
+/// formats, `scale` applies to the raster formats only.
+pub fn validate_formats(
+    format: Option<&str>,
+    formats: Option<&[String]>,
+    scale: Option<f64>,
+) -> Result<Vec<ExportFormat>, String> {
+    let formats = match (format, formats) {
+        (Some(_), Some(_)) => return Err("pass either format or formats, not both".to_string()),
+        (_, Some([])) => return Err("formats is empty".to_string()),
+        (Some(format), None) => return validate(format, scale).map(|format| vec![format]),
+        (_, Some([format])) => return validate(format, scale).map(|format| vec![format]),
+        (None, None) => return validate("png", scale).map(|format| vec![format]),
+        (None, Some(formats)) => formats,
+    };
+
+    let mut parsed = Vec::new();
+    for format in formats {
+        let export_format = validate(format, None)?;
+        if !export_format.is_vector() {
+            validate(format, scale)?;
+        }
+        if !parsed.contains(&export_format) {
+            parsed.push(export_format);
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Explains why nodes in a `get_file_nodes` response cannot be exported,
+/// as `(node_id, reason)` pairs.
+pub fn unexportable_nodes(nodes: &Value, node_ids: &[String]) -> Vec<(String, String)> {
+    node_ids
+        .iter()
+        .filter_map(|node_id| {
+            let Some(node) = nodes.pointer(&format!("/nodes/{}/document", node_id)) else {
+                return Some((node_id.clone(), "node not found".to_string()));
+            };
+            unexportable_reason(node).map(|reason| (node_id.clone(), reason.to_string()))
+        })
+        .collect()
+}
+
+/// Lists the requested nodes an images response has no URL for. Figma
+/// answers `null` for nodes it failed to render.
+pub fn failed_nodes(result: &Value, node_ids: &[String]) -> Vec<String> {
+    node_ids
+        .iter()
+        .filter(|node_id| {
+            result
+                .pointer(&format!("/images/{}", node_id))
+                .and_then(Value::as_str)
+                .is_none()
+        })
+        .cloned()
+        .collect()
+}
+
+/// Explains the failed nodes of an export at `scale`, using a
+/// `get_file_nodes` response for them when one could be fetched.
+pub fn explain_failures(
+    nodes: Option<&Value>,
+    failed: &[String],
+    scale: f64,
+) -> Vec<ExportFailure> {
+    failed
+        .iter()
+        .map(|node_id| {
+            let reason = match nodes {
+                Some(nodes) => failure_reason(
+                    nodes.pointer(&format!("/nodes/{}/document", node_id)),
+                    scale,
+                ),
+                None => UNKNOWN_FAILURE.to_string(),
+            };
+            ExportFailure {
+                node_id: node_id.clone(),
+                reason,
+            }
+        })
+        .collect()
+}
+
+/// Lists failures on one line, e.g. for tool output next to the images.
+pub fn describe_failures(failures: &[ExportFailure]) -> String {
+    let failures: Vec<String> = failures
+        .iter()
+        .map(|failure| format!("{} ({})", failure.node_id, failure.reason))
+        .collect();
+
+    format!("Figma returned no image for {}", failures.join("; "))
+}
+
+fn failure_reason(node: Option<&Value>, scale: f64) -> String {
+    let Some(node) = node else {
+        return "node not found".to_string();
+    };
+    if let Some(reason) = unexportable_reason(node) {
+        return reason.to_string();
+    }
+    if is_hidden(node) {
+        return "the node is hidden, so nothing is rendered".to_string();
+    }
+    // Figma sets the render bounds to null for nodes that draw nothing
+    if node.get("absoluteRenderBounds").is_some_and(Value::is_null) {
+        return "the node is empty or fully transparent".to_string();
+    }
+
+    match Bounds::of_kind(node, BoundsKind::Render) {
+        Some(bounds) if bounds.width <= 0.0 || bounds.height <= 0.0 => {
+            "the node has zero width or height".to_string()
+        }
+        Some(bounds) if bounds.width * bounds.height * scale * scale > MAX_RENDER_PIXELS => {
+            format!(
+                "the image would be {} x {} px, too large to render; export at a lower scale",
+                (bounds.width * scale).round(),
+                (bounds.height * scale).round()
+            )
+        }
+        _ => UNKNOWN_FAILURE.to_string(),
+    }
+}
+
+fn unexportable_reason(node: &Value) -> Option<&'static str> {
+    let node_type = node.get("type").and_then(Value::as_str).unwrap_or_default();
+
+    UNEXPORTABLE_TYPES
+        .iter()
+        .find(|(unexportable, _)| *unexportable == node_type)
+        .map(|(_, reason)| *reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("JPEG", Some(2.0)), Ok(ExportFormat::Jpg));
+        assert_eq!(validate("svg", Some(1.0)), Ok(ExportFormat::Svg));
+        assert!(validate("gif", None).unwrap_err().contains("gif"));
+        assert!(validate("png", Some(8.0))
+            .unwrap_err()
+            .contains("out of range"));
+        assert!(validate("png", Some(0.0)).is_err());
+        assert!(validate("pdf", Some(2.0))
+            .unwrap_err()
+            .contains("no effect"));
+    }
+
+    #[test]
+    fn test_validate_formats() {
+        let formats = ["svg".to_string(), "PNG".to_string(), "png".to_string()];
+        assert_eq!(
+            validate_formats(None, Some(&formats), Some(2.0)),
+            Ok(vec![ExportFormat::Svg, ExportFormat::Png])
+        );
+        assert_eq!(
+            validate_formats(None, None, None),
+            Ok(vec![ExportFormat::Png])
+        );
+        // A single format is checked as is
+        assert!(validate_formats(None, Some(&formats[..1]), Some(2.0)).is_err());
+        assert!(validate_formats(Some("png"), Some(&formats), None).is_err());
+        assert!(validate_formats(None, Some(&[]), None).is_err());
+        assert!(validate_formats(None, Some(&formats), Some(8.0)).is_err());
+    }
+
+    #[test]
+    fn test_options_query() {
+        assert_eq!(ExportOptions::default().query(), "");
+
+        let options = ExportOptions {
+            scale: Some(2.0),
+            use_absolute_bounds: true,
+            contents_only: false,
+        };
+        assert_eq!(
+            options.query(),
+            "&scale=2&use_absolute_bounds=true&contents_only=false"
+        );
+    }
+
+    #[test]
+    fn test_explain_failures() {
+        let result =
+            json!({ "images": { "1:1": "https://example.com/1.png", "1:2": null, "1:3": null } });
+        let node_ids: Vec<String> = ["1:1", "1:2", "1:3", "1:4"].map(String::from).to_vec();
+        let nodes = json!({
+            "nodes": {
+                "1:2": { "document": { "type": "GROUP", "absoluteRenderBounds": null } },
+                "1:3": { "document": {
+                    "type": "FRAME",
+                    "absoluteRenderBounds": { "x": 0, "y": 0, "width": 8000, "height": 6000 }
+                } },
+                "1:4": null
+            }
+        });
+
+        let failed = failed_nodes(&result, &node_ids);
+        assert_eq!(failed, vec!["1:2", "1:3", "1:4"]);
+
+        let failures = explain_failures(Some(&nodes), &failed, 2.0);
+        assert!(failures[0].reason.contains("empty"));
+        assert!(failures[1].reason.contains("16000 x 12000 px"));
+        assert_eq!(failures[2].reason, "node not found");
+
+        let failures = explain_failures(None, &failed[..1], 1.0);
+        assert_eq!(failures[0].reason, UNKNOWN_FAILURE);
+    }
+
+    #[test]
+    fn test_unexportable_nodes() {
+        let nodes = json!({
+            "nodes": {
+                "0:1": { "document": { "id": "0:1", "type": "CANVAS" } },
+                "1:2": { "document": { "id": "1:2", "type": "FRAME" } }
+            }
+        });
+        let node_ids = vec!["0:1".to_string(), "1:2".to_string(), "9:9".to_string()];
+
+        let reasons = unexportable_nodes(&nodes, &node_ids);
+
+        let ids: Vec<&str> = reasons.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["0:1", "9:9"]);
+        assert!(reasons[0].1.contains("pages"));
+    }
+}
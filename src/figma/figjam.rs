@@ -0,0 +1,315 @@
+//! The stickies, shapes and connectors of FigJam boards as a graph, and
+//! the stamps placed on them as a vote tally, so workshop outputs can be
+//! synthesized without reading the canvas.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::bounds::{Bounds, BoundsKind};
+use super::codegen::solid_fill;
+
+/// Stroke cap of a connector end without an arrow head.
+const NO_ARROW: &str = "NONE";
+
+/// The stickies and connectors of a board.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BoardGraph {
+    pub nodes: Vec<BoardNode>,
+    pub edges: Vec<BoardEdge>,
+}
+
+/// A sticky note or a shape with text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BoardNode {
+    pub id: String,
+    /// `sticky` or `shape`
+    pub kind: &'static str,
+    pub text: String,
+    /// The fill as `#rrggbb`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Name of the section the node sits in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+}
+
+/// A connector between two nodes. Connectors with a single arrow head run
+/// from the tail to the head; others keep the order they were drawn in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BoardEdge {
+    pub id: String,
+    /// The connected node; missing for ends left loose on the canvas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub directed: bool,
+}
+
+/// The stamps placed on a board item.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tally {
+    #[serde(flatten)]
+    pub node: BoardNode,
+    pub votes: usize,
+    /// Votes per stamp name, such as `+1` or `Heart`
+    pub stamps: BTreeMap<String, usize>,
+}
+
+/// Vote tallies of a board, most votes first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VoteTally {
+    pub items: Vec<Tally>,
+    /// Stamps not placed on any sticky or shape
+    pub unattached: usize,
+}
+
+/// What a walk over a board collects.
+#[derive(Default)]
+struct Board {
+    graph: BoardGraph,
+    bounds: HashMap<String, Bounds>,
+    stamps: Vec<(String, Bounds)>,
+}
+
+/// Collects the stickies, shapes with text and connectors below `roots`.
+pub fn graph<'a>(roots: impl IntoIterator<Item = &'a Value>) -> BoardGraph {
+    walk(roots).graph
+}
+
+/// Counts the stamps on each sticky and shape below `roots`. Stamps are
+/// separate layers in FigJam, so each is counted for the smallest item
+/// under its center; items without stamps are left out.
+pub fn tally<'a>(roots: impl IntoIterator<Item = &'a Value>) -> VoteTally {
+    let board = walk(roots);
+    let mut tally = VoteTally::default();
+    let mut items: Vec<Tally> = Vec::new();
+
+    for (name, stamp) in &board.stamps {
+        let (x, y) = (stamp.x + stamp.width / 2.0, stamp.y + stamp.height / 2.0);
+        let item = board
+            .graph
+            .nodes
+            .iter()
+            .filter_map(|node| Some((node, board.bounds.get(&node.id)?)))
+            .filter(|(_, bounds)| {
+                x >= bounds.x
+                    && x <= bounds.x + bounds.width
+                    && y >= bounds.y
+                    && y <= bounds.y + bounds.height
+            })
+            .min_by(|(_, a), (_, b)| (a.width * a.height).total_cmp(&(b.width * b.height)));
+        let Some((node, _)) = item else {
+            tally.unattached += 1;
+            continue;
+        };
+
+        let index = match items.iter().position(|item| item.node.id == node.id) {
+            Some(index) => index,
+            None => {
+                items.push(Tally {
+                    node: node.clone(),
+                    votes: 0,
+                    stamps: BTreeMap::new(),
+                });
+                items.len() - 1
+            }
+        };
+        items[index].votes += 1;
+        *items[index].stamps.entry(name.clone()).or_default() += 1;
+    }
+
+    // Stable, so ties keep the board order
+    items.sort_by_key(|item| std::cmp::Reverse(item.votes));
+    tally.items = items;
+
+    tally
+}
+
+fn walk<'a>(roots: impl IntoIterator<Item = &'a Value>) -> Board {
+    let mut board = Board::default();
+    for root in roots {
+        collect(root, None, &mut board);
+    }
+
+    board
+}
+
+fn collect(node: &Value, section: Option<&str>, board: &mut Board) {
+    let field = |key: &str| {
+        node.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let text = || {
+        let text = field("characters");
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    };
+
+    let mut section = section;
+    match node.get("type").and_then(Value::as_str).unwrap_or_default() {
+        kind @ ("STICKY" | "SHAPE_WITH_TEXT") => {
+            // Shapes without text are decoration
+            let text = text();
+            if kind == "STICKY" || text.is_some() {
+                if let Some(bounds) = Bounds::of(node, BoundsKind::Layout.key()) {
+                    board.bounds.insert(field("id"), bounds);
+                }
+                board.graph.nodes.push(BoardNode {
+                    id: field("id"),
+                    kind: if kind == "STICKY" { "sticky" } else { "shape" },
+                    text: text.unwrap_or_default(),
+                    color: solid_fill(node.get("fills")),
+                    section: section.map(String::from),
+                });
+            }
+        }
+        "CONNECTOR" => {
+            let endpoint = |key: &str| {
+                node.get(key)
+                    .and_then(|end| end.get("endpointNodeId"))
+                    .and_then(Value::as_str)
+                    .map(String::from)
+            };
+            let arrow = |key: &str| {
+                node.get(key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|cap| cap != NO_ARROW)
+            };
+            let (mut from, mut to) = (endpoint("connectorStart"), endpoint("connectorEnd"));
+            let start_arrow = arrow("connectorStartStrokeCap");
+            let end_arrow = arrow("connectorEndStrokeCap");
+            if start_arrow && !end_arrow {
+                std::mem::swap(&mut from, &mut to);
+            }
+            board.graph.edges.push(BoardEdge {
+                id: field("id"),
+                from,
+                to,
+                label: text(),
+                directed: start_arrow != end_arrow,
+            });
+        }
+        "STAMP" => {
+            if let Some(bounds) = Bounds::of(node, BoundsKind::Layout.key()) {
+                board.stamps.push((field("name"), bounds));
+            }
+        }
+        "SECTION" => section = node.get("name").and_then(Value::as_str),
+        _ => {}
+    }
+
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect(child, section, board);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn board() -> Value {
+        json!({
+            "type": "CANVAS",
+            "children": [
+                {
+                    "id": "1:1",
+                    "type": "SECTION",
+                    "name": "Ideas",
+                    "children": [
+                        {
+                            "id": "1:2",
+                            "type": "STICKY",
+                            "characters": "Onboarding is too long ",
+                            "fills": [{ "type": "SOLID", "color": { "r": 1, "g": 0.85, "b": 0.4, "a": 1 } }]
+                        },
+                        { "id": "1:3", "type": "SHAPE_WITH_TEXT", "characters": "Shorten signup" }
+                    ]
+                },
+                { "id": "1:4", "type": "SHAPE_WITH_TEXT", "characters": "" },
+                {
+                    "id": "1:5",
+                    "type": "CONNECTOR",
+                    "characters": "leads to",
+                    "connectorStart": { "endpointNodeId": "1:3", "magnet": "AUTO" },
+                    "connectorEnd": { "endpointNodeId": "1:2", "magnet": "AUTO" },
+                    "connectorStartStrokeCap": "ARROW_LINES",
+                    "connectorEndStrokeCap": "NONE"
+                },
+                {
+                    "id": "1:6",
+                    "type": "CONNECTOR",
+                    "connectorStart": { "endpointNodeId": "1:2" },
+                    "connectorEnd": { "position": { "x": 10, "y": 10 } }
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_graph_nodes() {
+        let board = board();
+        let graph = graph([&board]);
+
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.nodes[0].kind, "sticky");
+        assert_eq!(graph.nodes[0].text, "Onboarding is too long");
+        assert_eq!(graph.nodes[0].color.as_deref(), Some("#ffd966"));
+        assert_eq!(graph.nodes[0].section.as_deref(), Some("Ideas"));
+        assert_eq!(graph.nodes[1].kind, "shape");
+    }
+
+    #[test]
+    fn test_graph_edges_follow_arrows() {
+        let board = board();
+        let graph = graph([&board]);
+
+        assert_eq!(
+            graph.edges[0],
+            BoardEdge {
+                id: "1:5".to_string(),
+                from: Some("1:2".to_string()),
+                to: Some("1:3".to_string()),
+                label: Some("leads to".to_string()),
+                directed: true,
+            }
+        );
+        assert_eq!(graph.edges[1].to, None);
+        assert!(!graph.edges[1].directed);
+    }
+
+    #[test]
+    fn test_tally_ranks_stamped_items() {
+        let bounds =
+            |x: f64, y: f64, size: f64| json!({ "x": x, "y": y, "width": size, "height": size });
+        let board = json!({
+            "type": "CANVAS",
+            "children": [
+                { "id": "1:1", "type": "STICKY", "characters": "Dark mode", "absoluteBoundingBox": bounds(0.0, 0.0, 200.0) },
+                { "id": "1:2", "type": "STICKY", "characters": "Offline", "absoluteBoundingBox": bounds(300.0, 0.0, 200.0) },
+                { "type": "STAMP", "name": "+1", "absoluteBoundingBox": bounds(340.0, 20.0, 32.0) },
+                { "type": "STAMP", "name": "+1", "absoluteBoundingBox": bounds(380.0, 20.0, 32.0) },
+                { "type": "STAMP", "name": "Heart", "absoluteBoundingBox": bounds(420.0, 20.0, 32.0) },
+                { "type": "STAMP", "name": "+1", "absoluteBoundingBox": bounds(20.0, 20.0, 32.0) },
+                { "type": "STAMP", "name": "+1", "absoluteBoundingBox": bounds(900.0, 900.0, 32.0) }
+            ]
+        });
+
+        let tally = tally([&board]);
+
+        assert_eq!(tally.items.len(), 2);
+        assert_eq!(tally.items[0].node.text, "Offline");
+        assert_eq!(tally.items[0].votes, 3);
+        assert_eq!(tally.items[0].stamps["+1"], 2);
+        assert_eq!(tally.items[1].votes, 1);
+        assert_eq!(tally.unattached, 1);
+    }
+}
@@ -0,0 +1,191 @@
+//! Version and modification time of the files cached data was derived
+//! from, compared with the files' current state, so agents can tell when
+//! an index or export is out of date.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// How long fetched file metadata is trusted before asking Figma again, so
+/// results covering many files don't cost a request per file on every call.
+pub const META_TTL: Duration = Duration::from_secs(60);
+
+/// Version of a file at some point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileMeta {
+    pub version: String,
+    pub last_modified: String,
+}
+
+impl FileMeta {
+    /// Reads the version of a `GET /files/:key` response.
+    pub fn from_file(file: &Value) -> Self {
+        Self {
+            version: text(file.get("version")),
+            last_modified: text(file.get("lastModified")),
+        }
+    }
+
+    /// Reads the version of a `GET /files/:key/meta` response.
+    pub fn from_meta(response: &Value) -> Self {
+        Self {
+            version: text(response.pointer("/file/version")),
+            last_modified: text(response.pointer("/file/last_touched_at")),
+        }
+    }
+}
+
+fn text(value: Option<&Value>) -> String {
+    value
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// How cached data of a file compares with the file on Figma.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Freshness {
+    pub file_key: String,
+    /// Version the cached data was derived from
+    pub version: String,
+    pub last_modified: String,
+    /// Version on Figma, when it differs from the cached one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_last_modified: Option<String>,
+    /// Whether the file changed since; unset when it could not be checked
+    pub stale: Option<bool>,
+}
+
+impl Freshness {
+    /// Compares cached data of a file with its `current` version, if known.
+    pub fn new(file_key: &str, cached: &FileMeta, current: Option<&FileMeta>) -> Self {
+        // Files without a version in the response can't be compared
+        let current = current.filter(|current| !current.version.is_empty());
+        let stale = current.map(|current| current.version != cached.version);
+        let changed = current.filter(|_| stale == Some(true));
+
+        Self {
+            file_key: file_key.to_string(),
+            version: cached.version.clone(),
+            last_modified: cached.last_modified.clone(),
+            current_version: changed.map(|current| current.version.clone()),
+            current_last_modified: changed.map(|current| current.last_modified.clone()),
+            stale,
+        }
+    }
+}
+
+/// Latest known version of each file, shared by all sessions.
+#[derive(Clone, Default)]
+pub struct FileMetaCache {
+    files: Arc<RwLock<HashMap<String, (FileMeta, Instant)>>>,
+}
+
+impl FileMetaCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The version of the file seen within `META_TTL`, if any.
+    pub fn get(&self, file_key: &str) -> Option<FileMeta> {
+        let files = self.files.read().ok()?;
+        let (meta, seen) = files.get(file_key)?;
+
+        (seen.elapsed() < META_TTL).then(|| meta.clone())
+    }
+
+    /// Records the version of the file just read from Figma. Returns
+    /// `true` when it differs from the version seen before, so data derived
+    /// from the earlier one is outdated.
+    pub fn insert(&self, file_key: &str, meta: FileMeta) -> Result<bool> {
+        if meta.version.is_empty() {
+            return Ok(false);
+        }
+        let previous = self
+            .write()?
+            .insert(file_key.to_string(), (meta.clone(), Instant::now()));
+
+        Ok(previous.is_some_and(|(previous, _)| previous.version != meta.version))
+    }
+
+    /// Forgets the file's version, e.g. after a webhook reported a change.
+    pub fn invalidate(&self, file_key: &str) -> Result<()> {
+        self.write()?.remove(file_key);
+
+        Ok(())
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.write()?.clear();
+
+        Ok(())
+    }
+
+    fn write(
+        &self,
+    ) -> Result<std::sync::RwLockWriteGuard<'_, HashMap<String, (FileMeta, Instant)>>> {
+        self.files
+            .write()
+            .map_err(|_| Error::Internal("File metadata lock poisoned".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn meta(version: &str) -> FileMeta {
+        FileMeta {
+            version: version.to_string(),
+            last_modified: format!("2026-01-0{}T10:00:00Z", version),
+        }
+    }
+
+    #[test]
+    fn test_read_file_meta() {
+        let file =
+            json!({ "name": "App", "version": "42", "lastModified": "2026-01-01T10:00:00Z" });
+        let response =
+            json!({ "file": { "version": "42", "last_touched_at": "2026-01-01T10:00:00Z" } });
+
+        assert_eq!(FileMeta::from_file(&file), FileMeta::from_meta(&response));
+        assert_eq!(FileMeta::from_file(&file).version, "42");
+    }
+
+    #[test]
+    fn test_freshness() {
+        let fresh = Freshness::new("ABC", &meta("1"), Some(&meta("1")));
+        assert_eq!(fresh.stale, Some(false));
+        assert_eq!(fresh.current_version, None);
+
+        let stale = Freshness::new("ABC", &meta("1"), Some(&meta("2")));
+        assert_eq!(stale.stale, Some(true));
+        assert_eq!(stale.current_version.as_deref(), Some("2"));
+
+        let unknown = Freshness::new("ABC", &meta("1"), None);
+        assert_eq!(unknown.stale, None);
+    }
+
+    #[test]
+    fn test_meta_cache() {
+        let cache = FileMetaCache::new();
+        assert!(!cache.insert("ABC", meta("1")).unwrap());
+        assert!(!cache.insert("ABC", meta("1")).unwrap());
+        assert!(!cache.insert("DEF", FileMeta::default()).unwrap());
+
+        assert_eq!(cache.get("ABC"), Some(meta("1")));
+        assert_eq!(cache.get("DEF"), None);
+
+        assert!(cache.insert("ABC", meta("2")).unwrap());
+        cache.invalidate("ABC").unwrap();
+        assert_eq!(cache.get("ABC"), None);
+    }
+}
@@ -0,0 +1,197 @@
+//! API gateways some organizations route Figma requests through instead of
+//! calling `api.figma.com` directly: another host, another header carrying
+//! the token and extra headers the gateway requires.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+use url::Url;
+
+use crate::{Error, Result};
+
+pub const FIGMA_API_HOST: &str = "https://api.figma.com";
+
+/// Where API requests go and how they are authenticated. Unset fields keep
+/// the defaults, or those of the gateway this one is merged into.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Gateway {
+    /// Host to send API requests to, e.g. `https://figma-gateway.example.com`
+    pub api_host: Option<String>,
+    /// How the token is sent
+    pub auth_scheme: Option<AuthScheme>,
+    /// Extra headers sent with every API request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Gateway {
+    /// This gateway with the settings `other` sets on top; headers of both
+    /// are sent.
+    pub fn merge(&self, other: &Gateway) -> Gateway {
+        let mut headers = self.headers.clone();
+        headers.extend(other.headers.clone());
+
+        Gateway {
+            api_host: other.api_host.clone().or_else(|| self.api_host.clone()),
+            auth_scheme: other
+                .auth_scheme
+                .clone()
+                .or_else(|| self.auth_scheme.clone()),
+            headers,
+        }
+    }
+
+    /// The API host without trailing slash, checked to be an HTTPS URL.
+    /// Plain HTTP is only accepted for loopback hosts, so tokens don't
+    /// cross networks unencrypted.
+    pub fn host(&self) -> Result<String> {
+        match &self.api_host {
+            Some(host) => check_url(host),
+            None => Ok(FIGMA_API_HOST.to_string()),
+        }
+    }
+
+    /// The extra headers, checked to be valid.
+    pub fn header_map(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let invalid = || Error::Config(format!("Invalid API header '{}'", name));
+            headers.insert(
+                HeaderName::from_str(name).map_err(|_| invalid())?,
+                HeaderValue::from_str(value).map_err(|_| invalid())?,
+            );
+        }
+
+        Ok(headers)
+    }
+}
+
+/// How the token is sent to the API.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum AuthScheme {
+    /// In `X-Figma-Token`, as Figma expects personal access tokens
+    #[default]
+    Token,
+    /// As `Authorization: Bearer <token>`
+    Bearer,
+    /// In a header of the gateway's choosing, from `header:<name>`
+    Header(String),
+}
+
+impl FromStr for AuthScheme {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "token" => Ok(AuthScheme::Token),
+            "bearer" => Ok(AuthScheme::Bearer),
+            _ => match value.split_once(':') {
+                Some((scheme, name))
+                    if scheme.eq_ignore_ascii_case("header")
+                        && HeaderName::from_str(name.trim()).is_ok() =>
+                {
+                    Ok(AuthScheme::Header(name.trim().to_string()))
+                }
+                _ => Err(Error::Config(format!(
+                    "Invalid auth scheme '{}', expected token, bearer or header:<name>",
+                    value
+                ))),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for AuthScheme {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+/// The URL without trailing slash, checked to be HTTPS, or plain HTTP on a
+/// loopback host such as a local mock server.
+pub fn check_url(value: &str) -> Result<String> {
+    let url = Url::parse(value)
+        .map_err(|e| Error::Config(format!("Invalid API host '{}': {}", value, e)))?;
+    let loopback = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    if url.scheme() != "https" && !(url.scheme() == "http" && loopback) {
+        return Err(Error::Config(format!(
+            "Invalid API host '{}': use an https:// URL",
+            value
+        )));
+    }
+
+    Ok(value.trim_end_matches('/').to_string())
+}
+
+/// Parses a list of headers like `X-Org=acme, X-Env=prod`.
+pub fn parse_headers(value: &str) -> Result<BTreeMap<String, String>> {
+    let mut headers = BTreeMap::new();
+
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((name, value)) = entry.split_once('=') else {
+            return Err(Error::Config(format!(
+                "Invalid API header '{}', expected name=value",
+                entry
+            )));
+        };
+        headers.insert(name.trim().to_string(), value.trim().to_string());
+    }
+
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_auth_scheme() {
+        assert_eq!("Bearer".parse::<AuthScheme>().unwrap(), AuthScheme::Bearer);
+        assert_eq!(
+            "header:X-Gateway-Token".parse::<AuthScheme>().unwrap(),
+            AuthScheme::Header("X-Gateway-Token".to_string())
+        );
+        assert!("basic".parse::<AuthScheme>().is_err());
+        assert!("header:".parse::<AuthScheme>().is_err());
+    }
+
+    #[test]
+    fn test_merge_gateways() {
+        let org = Gateway {
+            api_host: Some("https://figma.example.com/".to_string()),
+            auth_scheme: Some(AuthScheme::Bearer),
+            headers: parse_headers("X-Org=acme, X-Env=prod").unwrap(),
+        };
+        let account = Gateway {
+            headers: parse_headers("X-Env=staging").unwrap(),
+            ..Gateway::default()
+        };
+
+        let merged = org.merge(&account);
+        assert_eq!(merged.host().unwrap(), "https://figma.example.com");
+        assert_eq!(merged.auth_scheme, Some(AuthScheme::Bearer));
+        assert_eq!(merged.headers["X-Env"], "staging");
+        assert_eq!(merged.header_map().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_host_requires_https() {
+        let gateway = |host: &str| Gateway {
+            api_host: Some(host.to_string()),
+            ..Gateway::default()
+        };
+
+        assert_eq!(Gateway::default().host().unwrap(), FIGMA_API_HOST);
+        assert!(gateway("http://127.0.0.1:8080").host().is_ok());
+        assert!(gateway("http://figma.example.com").host().is_err());
+        assert!(gateway("figma.example.com").host().is_err());
+        assert!(parse_headers("X-Org").is_err());
+    }
+}
@@ -1,12 +1,46 @@
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::SystemTime;
 
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+
+use crate::figma::tokens::token_name;
 use crate::{Error, Result};
 
+/// Query selecting one chunk of a resource, e.g. `?chunk=2`.
+const CHUNK_QUERY: &str = "?chunk=";
+
+/// Total size of the base64 encodings kept for repeated reads; the least
+/// recently read ones are dropped first.
+pub const MAX_ENCODED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Shared registry of exported images.
+///
+/// The cache is cloned into every tool call and may be used by several calls at
+/// once. Locks are only held for the duration of a single map operation (never
+/// across an `.await`), and downloaded data is only stored when the entry still
+/// points at the URL it was downloaded from, so a re-export racing with a read
+/// never ends up with stale image data.
 #[derive(Clone)]
 pub struct ImageCache {
     entries: Arc<RwLock<HashMap<String, ImageEntry>>>,
+    encoded: Arc<Mutex<EncodedImages>>,
+}
+
+/// Base64 encodings of recently read resources, least recently read first,
+/// so agents reading the same image in a loop don't re-encode megabytes on
+/// every read. Each one remembers the export it was made from.
+#[derive(Default)]
+struct EncodedImages {
+    items: VecDeque<EncodedImage>,
+    bytes: usize,
+}
+
+struct EncodedImage {
+    uri: String,
+    export_time: SystemTime,
+    base64: String,
 }
 
 #[derive(Clone, Debug)]
@@ -18,12 +52,56 @@ pub struct ImageEntry {
     pub figma_url: String,
     pub cached_data: Option<Vec<u8>>,
     pub export_time: SystemTime,
+    /// The file changed since the export, so the image may be outdated
+    pub stale: bool,
+    /// Name of the node in Figma, when known
+    pub name: Option<String>,
+}
+
+impl ImageEntry {
+    /// File name for saving the image stored under `uri`, from the node's
+    /// name and the variant, scale and format in the URI:
+    /// `checkout-1-2@2x.png`.
+    pub fn filename(&self, uri: &str) -> String {
+        let (uri, _) = ImageCache::split_chunk(uri);
+        let stem = file_stem(&self.node_id, self.name.as_deref());
+        let last = uri.rsplit('/').next().unwrap_or_default();
+
+        match last.strip_prefix(self.node_id.as_str()) {
+            Some(suffix) if !suffix.is_empty() => format!("{}{}", stem, suffix),
+            _ => format!("{}.{}", stem, self.format),
+        }
+    }
+}
+
+/// File name stem for an image of a node, unique per node and readable
+/// when its name is known: `checkout-1-2`.
+pub fn file_stem(node_id: &str, name: Option<&str>) -> String {
+    let id = node_id.replace([':', ';'], "-");
+
+    match name.map(token_name) {
+        Some(name) if !name.is_empty() => format!("{}-{}", name, id),
+        _ => id,
+    }
+}
+
+/// Returned instead of a resource too large to read at once: the URIs of
+/// its chunks, each holding `chunk_size` bytes of the data (the last one
+/// possibly fewer) to be decoded and concatenated in order.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ChunkManifest {
+    pub uri: String,
+    pub mime_type: String,
+    pub size: usize,
+    pub chunk_size: usize,
+    pub chunks: Vec<String>,
 }
 
 impl ImageCache {
     pub fn new() -> Self {
         Self {
             entries: Arc::new(RwLock::new(HashMap::new())),
+            encoded: Arc::new(Mutex::new(EncodedImages::default())),
         }
     }
 
@@ -36,7 +114,11 @@ impl ImageCache {
         figma_url: String,
     ) -> Result<String> {
         let uri = Self::generate_uri(&file_key, &node_id, &format, scale);
-        
+
+        let mut entries = self.entries.write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        // A re-export keeps the name learned from the first one
+        let name = entries.get(&uri).and_then(|entry| entry.name.clone());
         let entry = ImageEntry {
             file_key,
             node_id,
@@ -45,22 +127,83 @@ impl ImageCache {
             figma_url,
             cached_data: None,
             export_time: SystemTime::now(),
+            stale: false,
+            name,
+        };
+        entries.insert(uri.clone(), entry);
+
+        Ok(uri)
+    }
+
+    /// Registers an image generated by the server, such as an annotated
+    /// export. `variant` tells it apart from the plain export of the node.
+    pub fn register_generated(
+        &self,
+        file_key: String,
+        node_id: String,
+        variant: &str,
+        data: Vec<u8>,
+    ) -> Result<String> {
+        self.register_generated_as(file_key, node_id, variant, "png", data)
+    }
+
+    /// Registers a generated image of another format than PNG, such as an
+    /// animation.
+    pub fn register_generated_as(
+        &self,
+        file_key: String,
+        node_id: String,
+        variant: &str,
+        format: &str,
+        data: Vec<u8>,
+    ) -> Result<String> {
+        let uri = format!("figma://file/{}/node/{}.{}.{}", file_key, node_id, variant, format);
+
+        let entry = ImageEntry {
+            file_key,
+            node_id,
+            format: format.to_string(),
+            scale: 1.0,
+            figma_url: String::new(),
+            cached_data: Some(data),
+            export_time: SystemTime::now(),
+            stale: false,
+            name: None,
         };
 
         let mut entries = self.entries.write()
             .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
-        entries.insert(uri.clone(), entry);
+        // Named after the export it was generated from
+        let name = entries.values()
+            .find(|export| export.file_key == entry.file_key && export.node_id == entry.node_id)
+            .and_then(|export| export.name.clone());
+        entries.insert(uri.clone(), ImageEntry { name, ..entry });
 
         Ok(uri)
     }
 
+    /// Records the name of the exported node, for the file name clients
+    /// save the image under. Unknown URIs are ignored.
+    pub fn set_name(&self, uri: &str, name: &str) -> Result<()> {
+        let mut entries = self.entries.write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        if let Some(entry) = entries.get_mut(uri) {
+            entry.name = Some(name.to_string());
+        }
+
+        Ok(())
+    }
+
     pub fn list_all(&self) -> Result<Vec<(String, ImageEntry)>> {
         let entries = self.entries.read()
             .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
         
-        Ok(entries.iter()
+        let mut entries: Vec<(String, ImageEntry)> = entries.iter()
             .map(|(uri, entry)| (uri.clone(), entry.clone()))
-            .collect())
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(entries)
     }
 
     pub fn get_entry(&self, uri: &str) -> Result<Option<ImageEntry>> {
@@ -70,16 +213,131 @@ impl ImageCache {
         Ok(entries.get(uri).cloned())
     }
 
-    pub fn update_cached_data(&self, uri: &str, data: Vec<u8>) -> Result<()> {
+    /// Stores downloaded data for an entry. Returns `false` without storing
+    /// anything when the entry was re-exported to a different URL since
+    /// `source_url` was read.
+    pub fn update_cached_data(&self, uri: &str, source_url: &str, data: Vec<u8>) -> Result<bool> {
         let mut entries = self.entries.write()
             .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
         
-        if let Some(entry) = entries.get_mut(uri) {
-            entry.cached_data = Some(data);
-            Ok(())
-        } else {
-            Err(Error::NotFound(format!("Resource not found: {}", uri)))
+        let Some(entry) = entries.get_mut(uri) else {
+            return Err(Error::NotFound(format!("Resource not found: {}", uri)));
+        };
+
+        if entry.figma_url != source_url {
+            return Ok(false);
+        }
+
+        entry.cached_data = Some(data);
+
+        Ok(true)
+    }
+
+    /// The base64 encoding of `data`, read as `uri` from the entry exported
+    /// at `export_time`. Reuses the encoding of an earlier read of the same
+    /// export, and keeps this one for later reads.
+    pub fn encode(&self, uri: &str, export_time: SystemTime, data: &[u8]) -> Result<String> {
+        let mut encoded = self.encoded.lock()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+
+        let hit = encoded.items.iter()
+            .position(|item| item.uri == uri && item.export_time == export_time);
+        if let Some(item) = hit.and_then(|index| encoded.items.remove(index)) {
+            let base64 = item.base64.clone();
+            encoded.items.push_back(item);
+            return Ok(base64);
+        }
+
+        // Encoding megabytes takes a while, so don't hold the lock meanwhile
+        drop(encoded);
+        let base64 = general_purpose::STANDARD.encode(data);
+        if base64.len() > MAX_ENCODED_BYTES {
+            return Ok(base64);
+        }
+
+        let mut encoded = self.encoded.lock()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        // Drops the encoding of an earlier export of the same URI
+        encoded.items.retain(|item| item.uri != uri);
+        encoded.bytes = encoded.items.iter().map(|item| item.base64.len()).sum();
+        while encoded.bytes + base64.len() > MAX_ENCODED_BYTES {
+            let Some(item) = encoded.items.pop_front() else {
+                break;
+            };
+            encoded.bytes -= item.base64.len();
+        }
+        encoded.bytes += base64.len();
+        encoded.items.push_back(EncodedImage {
+            uri: uri.to_string(),
+            export_time,
+            base64: base64.clone(),
+        });
+
+        Ok(base64)
+    }
+
+    /// Drops all entries with their image data and encodings. Returns the
+    /// number of entries dropped.
+    pub fn clear(&self) -> Result<usize> {
+        let mut entries = self.entries.write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        let count = entries.len();
+        entries.clear();
+        drop(entries);
+
+        let mut encoded = self.encoded.lock()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        *encoded = EncodedImages::default();
+
+        Ok(count)
+    }
+
+    /// Drops the entries of the given URIs with their image data and
+    /// encodings. Returns the number of entries dropped.
+    pub fn remove(&self, uris: &[String]) -> Result<usize> {
+        let mut entries = self.entries.write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        let before = entries.len();
+        entries.retain(|uri, _| !uris.contains(uri));
+        let count = before - entries.len();
+        drop(entries);
+
+        let mut encoded = self.encoded.lock()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        encoded.items.retain(|item| {
+            let (uri, _) = Self::split_chunk(&item.uri);
+            !uris.iter().any(|removed| removed == uri)
+        });
+        encoded.bytes = encoded.items.iter().map(|item| item.base64.len()).sum();
+
+        Ok(count)
+    }
+
+    /// Marks the exports of a file that changed as stale, dropping their
+    /// downloaded data and encodings, and drops the images generated from
+    /// them. Returns the number of entries affected.
+    pub fn invalidate_file(&self, file_key: &str) -> Result<usize> {
+        let mut entries = self.entries.write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+
+        let before = entries.len();
+        // Generated images have no export to redo
+        entries.retain(|_, entry| entry.file_key != file_key || !entry.figma_url.is_empty());
+        let mut count = before - entries.len();
+        for entry in entries.values_mut().filter(|entry| entry.file_key == file_key) {
+            entry.stale = true;
+            entry.cached_data = None;
+            count += 1;
         }
+        drop(entries);
+
+        let prefix = format!("figma://file/{}/", file_key);
+        let mut encoded = self.encoded.lock()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        encoded.items.retain(|item| !item.uri.starts_with(&prefix));
+        encoded.bytes = encoded.items.iter().map(|item| item.base64.len()).sum();
+
+        Ok(count)
     }
 
     pub fn is_expired(&self, entry: &ImageEntry) -> bool {
@@ -91,10 +349,54 @@ impl ImageCache {
         }
     }
 
+    /// Splits a chunk query off a resource URI: `uri?chunk=2` gives the URI
+    /// and `Some(2)`. URIs without a valid chunk query are returned whole.
+    pub fn split_chunk(uri: &str) -> (&str, Option<usize>) {
+        let Some((base, index)) = uri.rsplit_once(CHUNK_QUERY) else {
+            return (uri, None);
+        };
+
+        match index.parse() {
+            Ok(index) => (base, Some(index)),
+            Err(_) => (uri, None),
+        }
+    }
+
+    /// Lists the chunks of a resource of `size` bytes.
+    pub fn chunk_manifest(
+        uri: &str,
+        mime_type: &str,
+        size: usize,
+        chunk_size: usize,
+    ) -> ChunkManifest {
+        let count = size.div_ceil(chunk_size.max(1));
+
+        ChunkManifest {
+            uri: uri.to_string(),
+            mime_type: mime_type.to_string(),
+            size,
+            chunk_size,
+            chunks: (0..count)
+                .map(|index| format!("{}{}{}", uri, CHUNK_QUERY, index))
+                .collect(),
+        }
+    }
+
+    /// The bytes of chunk `index`, or `None` past the end of the data.
+    /// A `chunk_size` of 0 makes the whole data chunk 0.
+    pub fn chunk(data: &[u8], index: usize, chunk_size: usize) -> Option<&[u8]> {
+        if chunk_size == 0 {
+            return (index == 0).then_some(data);
+        }
+
+        data.chunks(chunk_size).nth(index)
+    }
+
     pub fn get_mime_type(format: &str) -> &'static str {
         match format.to_lowercase().as_str() {
             "png" => "image/png",
             "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
             "svg" => "image/svg+xml",
             "pdf" => "application/pdf",
             _ => "application/octet-stream",
@@ -103,7 +405,8 @@ impl ImageCache {
 
     fn generate_uri(file_key: &str, node_id: &str, format: &str, scale: f64) -> String {
         if scale != 1.0 {
-            format!("figma://file/{}/node/{}@{}x.{}", file_key, node_id, scale as u32, format)
+            // The exact scale, so 2.5x does not replace the 2x export
+            format!("figma://file/{}/node/{}@{}x.{}", file_key, node_id, scale, format)
         } else {
             format!("figma://file/{}/node/{}.{}", file_key, node_id, format)
         }
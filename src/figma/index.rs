@@ -0,0 +1,524 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::freshness::FileMeta;
+use crate::{Error, Result};
+
+/// Cross-file map of the components, styles and variables defined in and
+/// used by a set of indexed files. Published assets are matched by their
+/// global key, so an instance in one file links to the component defined in
+/// another.
+#[derive(Clone, Default)]
+pub struct DesignIndex {
+    files: Arc<RwLock<HashMap<String, FileIndex>>>,
+}
+
+/// Assets defined in and used by a single file.
+#[derive(Debug, Clone, Default)]
+pub struct FileIndex {
+    pub file_key: String,
+    pub name: String,
+    /// Version of the file when it was indexed
+    pub meta: FileMeta,
+    pub definitions: Vec<Definition>,
+    /// Node IDs using each asset, by asset kind and key
+    pub usages: HashMap<(AssetKind, String), Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Component,
+    Style,
+    Variable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Definition {
+    pub kind: AssetKind,
+    pub key: String,
+    pub name: String,
+    /// Node ID for components and styles, variable ID for variables
+    pub id: String,
+    /// Usage guidance designers wrote on the asset
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Links to documentation set on components
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub documentation_links: Vec<String>,
+}
+
+/// An asset matching a query, with every indexed file defining or using it.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetReport {
+    pub kind: AssetKind,
+    pub key: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub documentation_links: Vec<String>,
+    pub defined_in: Vec<DefinitionSite>,
+    pub used_in: Vec<UsageSite>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DefinitionSite {
+    pub file_key: String,
+    pub file_name: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSite {
+    pub file_key: String,
+    pub file_name: String,
+    pub count: usize,
+    /// The first few consuming nodes, at most `MAX_USAGE_NODE_IDS`
+    pub node_ids: Vec<String>,
+}
+
+/// Summary of a file after indexing.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSummary {
+    pub file_key: String,
+    pub name: String,
+    pub version: String,
+    pub last_modified: String,
+    pub components: usize,
+    pub styles: usize,
+    pub variables: usize,
+    pub usages: usize,
+}
+
+impl AssetKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "component" | "components" => Some(Self::Component),
+            "style" | "styles" => Some(Self::Style),
+            "variable" | "variables" => Some(Self::Variable),
+            _ => None,
+        }
+    }
+}
+
+// Node IDs listed per file in query results; the count covers the rest
+const MAX_USAGE_NODE_IDS: usize = 20;
+
+impl DesignIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a file in the index.
+    pub fn insert(&self, file_index: FileIndex) -> Result<FileSummary> {
+        let summary = file_index.summary();
+
+        let mut files = self
+            .files
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        files.insert(file_index.file_key.clone(), file_index);
+
+        Ok(summary)
+    }
+
+    /// Removes all files from the index. Returns the number removed.
+    pub fn clear(&self) -> Result<usize> {
+        let mut files = self
+            .files
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+        let count = files.len();
+        files.clear();
+
+        Ok(count)
+    }
+
+    /// The assets defined in an indexed file, or `None` if it has not been
+    /// indexed.
+    pub fn definitions(&self, file_key: &str) -> Result<Option<Vec<Definition>>> {
+        let files = self
+            .files
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+
+        Ok(files.get(file_key).map(|file| file.definitions.clone()))
+    }
+
+    pub fn summaries(&self) -> Result<Vec<FileSummary>> {
+        let files = self
+            .files
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+
+        let mut summaries: Vec<FileSummary> = files.values().map(FileIndex::summary).collect();
+        summaries.sort_by(|a, b| a.file_key.cmp(&b.file_key));
+
+        Ok(summaries)
+    }
+
+    /// Finds assets whose name contains `query` (case-insensitive) or whose
+    /// key equals it, optionally limited to one kind.
+    pub fn query(&self, query: &str, kind: Option<AssetKind>) -> Result<Vec<AssetReport>> {
+        let files = self
+            .files
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+
+        let query = query.trim().to_lowercase();
+        let mut reports: BTreeMap<(AssetKind, String), AssetReport> = BTreeMap::new();
+
+        for file in files.values() {
+            for definition in &file.definitions {
+                if kind.is_some_and(|kind| kind != definition.kind) {
+                    continue;
+                }
+                let matches = definition.key.to_lowercase() == query
+                    || definition.name.to_lowercase().contains(&query);
+                if !matches {
+                    continue;
+                }
+
+                let report = reports
+                    .entry((definition.kind, definition.key.clone()))
+                    .or_insert_with(|| AssetReport {
+                        kind: definition.kind,
+                        key: definition.key.clone(),
+                        name: definition.name.clone(),
+                        description: definition.description.clone(),
+                        documentation_links: definition.documentation_links.clone(),
+                        defined_in: Vec::new(),
+                        used_in: Vec::new(),
+                    });
+                report.defined_in.push(DefinitionSite {
+                    file_key: file.file_key.clone(),
+                    file_name: file.name.clone(),
+                    id: definition.id.clone(),
+                });
+            }
+        }
+
+        for ((kind, key), report) in reports.iter_mut() {
+            for file in files.values() {
+                let Some(node_ids) = file.usages.get(&(*kind, key.clone())) else {
+                    continue;
+                };
+                report.used_in.push(UsageSite {
+                    file_key: file.file_key.clone(),
+                    file_name: file.name.clone(),
+                    count: node_ids.len(),
+                    node_ids: node_ids.iter().take(MAX_USAGE_NODE_IDS).cloned().collect(),
+                });
+            }
+
+            report
+                .defined_in
+                .sort_by(|a, b| a.file_key.cmp(&b.file_key));
+            report.used_in.sort_by(|a, b| a.file_key.cmp(&b.file_key));
+        }
+
+        Ok(reports.into_values().collect())
+    }
+}
+
+impl FileIndex {
+    /// Indexes a full `GET /v1/files/:key` response, plus the response of
+    /// `GET /v1/files/:key/variables/local` when available (it requires an
+    /// Enterprise plan).
+    pub fn from_file(file_key: &str, file: &Value, local_variables: Option<&Value>) -> Self {
+        let mut index = Self {
+            file_key: file_key.to_string(),
+            name: file
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            meta: FileMeta::from_file(file),
+            ..Self::default()
+        };
+
+        let components = asset_keys(file.get("components"));
+        let styles = asset_keys(file.get("styles"));
+        let variables = local_variables
+            .and_then(|response| response.pointer("/meta/variables"))
+            .and_then(Value::as_object);
+
+        for (map, kind) in [
+            (file.get("components"), AssetKind::Component),
+            (file.get("componentSets"), AssetKind::Component),
+            (file.get("styles"), AssetKind::Style),
+        ] {
+            index.add_definitions(map.and_then(Value::as_object), kind);
+        }
+        index.add_definitions(variables, AssetKind::Variable);
+
+        let Some(document) = file.get("document") else {
+            return index;
+        };
+
+        let mut stack = vec![document];
+        while let Some(node) = stack.pop() {
+            let node_id = node.get("id").and_then(Value::as_str).unwrap_or_default();
+
+            if let Some(component_id) = node.get("componentId").and_then(Value::as_str) {
+                if let Some(key) = components.get(component_id) {
+                    index.add_usage(AssetKind::Component, key, node_id);
+                }
+            }
+
+            let style_ids = node.get("styles").and_then(Value::as_object);
+            for style_id in style_ids.into_iter().flat_map(|s| s.values()) {
+                if let Some(key) = style_id.as_str().and_then(|id| styles.get(id)) {
+                    index.add_usage(AssetKind::Style, key, node_id);
+                }
+            }
+
+            if let Some(bound) = node.get("boundVariables") {
+                let mut ids = Vec::new();
+                collect_variable_ids(bound, &mut ids);
+                for id in ids {
+                    let key = variable_key(id, variables);
+                    index.add_usage(AssetKind::Variable, &key, node_id);
+                }
+            }
+
+            if let Some(children) = node.get("children").and_then(Value::as_array) {
+                stack.extend(children.iter().rev());
+            }
+        }
+
+        index
+    }
+
+    pub fn summary(&self) -> FileSummary {
+        let count = |kind| self.definitions.iter().filter(|d| d.kind == kind).count();
+
+        FileSummary {
+            file_key: self.file_key.clone(),
+            name: self.name.clone(),
+            version: self.meta.version.clone(),
+            last_modified: self.meta.last_modified.clone(),
+            components: count(AssetKind::Component),
+            styles: count(AssetKind::Style),
+            variables: count(AssetKind::Variable),
+            usages: self.usages.values().map(Vec::len).sum(),
+        }
+    }
+
+    fn add_definitions(&mut self, map: Option<&serde_json::Map<String, Value>>, kind: AssetKind) {
+        for (id, asset) in map.into_iter().flatten() {
+            // Remote assets are defined in another file
+            if asset.get("remote").and_then(Value::as_bool) == Some(true) {
+                continue;
+            }
+            let Some(key) = asset.get("key").and_then(Value::as_str) else {
+                continue;
+            };
+
+            let text = |key: &str| {
+                asset
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let links = asset.get("documentationLinks").and_then(Value::as_array);
+            self.definitions.push(Definition {
+                kind,
+                key: key.to_string(),
+                name: text("name"),
+                id: id.clone(),
+                description: text("description"),
+                documentation_links: links
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|link| link.get("uri").and_then(Value::as_str))
+                    .map(String::from)
+                    .collect(),
+            });
+        }
+    }
+
+    fn add_usage(&mut self, kind: AssetKind, key: &str, node_id: &str) {
+        // Dedupe nodes binding the same variable to several properties
+        let node_ids = self.usages.entry((kind, key.to_string())).or_default();
+        if node_ids.last().map(String::as_str) != Some(node_id) {
+            node_ids.push(node_id.to_string());
+        }
+    }
+}
+
+/// Maps the IDs in a file's `components` or `styles` metadata to the
+/// assets' global keys.
+fn asset_keys(map: Option<&Value>) -> HashMap<String, String> {
+    let Some(map) = map.and_then(Value::as_object) else {
+        return HashMap::new();
+    };
+
+    map.iter()
+        .filter_map(|(id, asset)| {
+            let key = asset.get("key").and_then(Value::as_str)?;
+            Some((id.clone(), key.to_string()))
+        })
+        .collect()
+}
+
+fn collect_variable_ids<'a>(value: &'a Value, ids: &mut Vec<&'a str>) {
+    match value {
+        Value::Object(map) => {
+            if map.get("type").and_then(Value::as_str) == Some("VARIABLE_ALIAS") {
+                if let Some(id) = map.get("id").and_then(Value::as_str) {
+                    ids.push(id);
+                }
+                return;
+            }
+            map.values()
+                .for_each(|value| collect_variable_ids(value, ids));
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_variable_ids(item, ids)),
+        _ => {}
+    }
+}
+
+/// Resolves a variable ID to its key. Remote variables are referenced as
+/// `VariableID:<key>/<id>`; local ones are looked up in the file's variables.
+fn variable_key(id: &str, variables: Option<&serde_json::Map<String, Value>>) -> String {
+    let local_id = id.strip_prefix("VariableID:").unwrap_or(id);
+    if let Some((key, _)) = local_id.split_once('/') {
+        return key.to_string();
+    }
+
+    variables
+        .and_then(|variables| variables.get(id))
+        .and_then(|variable| variable.get("key"))
+        .and_then(Value::as_str)
+        .unwrap_or(id)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn library() -> Value {
+        json!({
+            "name": "Library",
+            "document": { "id": "0:0", "children": [] },
+            "components": {
+                "1:2": {
+                    "key": "button-key",
+                    "name": "Button",
+                    "description": "Use for the main action of a screen",
+                    "documentationLinks": [{ "uri": "https://storybook.example.com/button" }]
+                }
+            },
+            "styles": {
+                "1:3": { "key": "primary-key", "name": "Primary", "styleType": "FILL" }
+            }
+        })
+    }
+
+    fn app() -> Value {
+        json!({
+            "name": "App",
+            "document": {
+                "id": "0:0",
+                "children": [{
+                    "id": "5:1",
+                    "type": "INSTANCE",
+                    "componentId": "9:9",
+                    "styles": { "fill": "S:remote-primary" },
+                    "boundVariables": {
+                        "fills": [{ "type": "VARIABLE_ALIAS", "id": "VariableID:spacing-key/12:3" }]
+                    }
+                }]
+            },
+            "components": {
+                "9:9": { "key": "button-key", "name": "Button", "remote": true }
+            },
+            "styles": {
+                "S:remote-primary": { "key": "primary-key", "name": "Primary", "remote": true }
+            }
+        })
+    }
+
+    #[test]
+    fn test_remote_assets_are_not_definitions() {
+        let index = FileIndex::from_file("APP", &app(), None);
+
+        assert!(index.definitions.is_empty());
+        assert_eq!(
+            index
+                .usages
+                .get(&(AssetKind::Component, "button-key".to_string())),
+            Some(&vec!["5:1".to_string()])
+        );
+        assert!(index
+            .usages
+            .contains_key(&(AssetKind::Variable, "spacing-key".to_string())));
+    }
+
+    #[test]
+    fn test_query_links_definition_and_usage_across_files() {
+        let design_index = DesignIndex::new();
+        design_index
+            .insert(FileIndex::from_file("LIB", &library(), None))
+            .unwrap();
+        design_index
+            .insert(FileIndex::from_file("APP", &app(), None))
+            .unwrap();
+
+        let reports = design_index.query("button", None).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].defined_in[0].file_key, "LIB");
+        assert_eq!(reports[0].used_in[0].file_key, "APP");
+        assert_eq!(reports[0].used_in[0].count, 1);
+        assert_eq!(
+            reports[0].description,
+            "Use for the main action of a screen"
+        );
+        assert_eq!(
+            reports[0].documentation_links,
+            vec!["https://storybook.example.com/button"]
+        );
+
+        let styles = design_index
+            .query("primary-key", Some(AssetKind::Style))
+            .unwrap();
+        assert_eq!(styles.len(), 1);
+        assert!(design_index
+            .query("button", Some(AssetKind::Style))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_local_variable_keys() {
+        let file = json!({
+            "name": "Tokens",
+            "document": {
+                "id": "0:0",
+                "children": [{
+                    "id": "2:1",
+                    "boundVariables": { "itemSpacing": { "type": "VARIABLE_ALIAS", "id": "VariableID:3:4" } }
+                }]
+            }
+        });
+        let variables = json!({
+            "meta": { "variables": { "VariableID:3:4": { "key": "gap-key", "name": "space/md" } } }
+        });
+
+        let index = FileIndex::from_file("TOKENS", &file, Some(&variables));
+        assert_eq!(index.definitions[0].name, "space/md");
+        assert!(index
+            .usages
+            .contains_key(&(AssetKind::Variable, "gap-key".to_string())));
+    }
+}
@@ -0,0 +1,145 @@
+//! Coalescing of identical reads in flight, so parallel tool calls asking
+//! for the same file at once share one download.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::sync::OnceCell;
+
+use crate::{Error, Result};
+
+type Outcome = std::result::Result<Arc<Value>, Arc<Error>>;
+
+/// Reads in flight by request signature. Clones share the calls, so reads
+/// made with the same credentials are coalesced.
+#[derive(Debug, Clone, Default)]
+pub struct InFlight {
+    calls: Arc<Mutex<HashMap<String, Arc<OnceCell<Outcome>>>>>,
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `fetch` unless a read with the same `key` is in flight, in
+    /// which case its outcome is shared instead. When the caller running
+    /// `fetch` stops waiting, one of the others runs it in its place.
+    pub async fn run<F, Fut>(&self, key: &str, fetch: F) -> Result<Value>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value>>,
+    {
+        let call = self
+            .calls
+            .lock()
+            .ok()
+            .map(|mut calls| calls.entry(key.to_string()).or_default().clone());
+        let Some(call) = call else {
+            return fetch().await;
+        };
+
+        let outcome = call
+            .get_or_init(|| async { fetch().await.map(Arc::new).map_err(Arc::new) })
+            .await
+            .clone();
+
+        // The first caller to get the outcome ends the call, so later reads
+        // see changes made since
+        if let Ok(mut calls) = self.calls.lock() {
+            if calls
+                .get(key)
+                .is_some_and(|current| Arc::ptr_eq(current, &call))
+            {
+                calls.remove(key);
+            }
+        }
+        drop(call);
+
+        // The last caller takes the outcome as is, the others copy it
+        match outcome {
+            Ok(value) => Ok(Arc::try_unwrap(value).unwrap_or_else(|value| Value::clone(&value))),
+            Err(error) => Err(Arc::try_unwrap(error).unwrap_or_else(|error| error.duplicate())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::json;
+    use tokio::sync::Notify;
+
+    #[tokio::test]
+    async fn test_identical_reads_share_one_fetch() {
+        let in_flight = InFlight::new();
+        let fetches = AtomicUsize::new(0);
+        let release = Notify::new();
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            release.notified().await;
+            Ok(json!({ "name": "App" }))
+        };
+
+        let first = in_flight.run("files/ABC", fetch);
+        let second = in_flight.run("files/ABC", fetch);
+        let release_later = async {
+            tokio::task::yield_now().await;
+            release.notify_one();
+        };
+        let (first, second, _) = tokio::join!(first, second, release_later);
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(first.unwrap(), json!({ "name": "App" }));
+        assert_eq!(second.unwrap(), json!({ "name": "App" }));
+
+        // Finished reads are not reused
+        in_flight.run("files/ABC", fetch_now).await.unwrap();
+        assert!(in_flight.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_shared() {
+        let in_flight = InFlight::new();
+        let release = Notify::new();
+        let fetch = || async {
+            release.notified().await;
+            Err(Error::NotFound("File ABC not found".to_string()))
+        };
+
+        let release_later = async {
+            tokio::task::yield_now().await;
+            release.notify_one();
+        };
+        let (first, second, _) = tokio::join!(
+            in_flight.run("files/ABC", fetch),
+            in_flight.run("files/ABC", fetch),
+            release_later
+        );
+
+        assert!(matches!(first, Err(Error::NotFound(_))));
+        assert!(matches!(second, Err(Error::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_waiting_caller_takes_over_abandoned_read() {
+        let in_flight = InFlight::new();
+        let mut abandoned = Box::pin(in_flight.run("files/ABC", std::future::pending));
+        let mut waiting = Box::pin(in_flight.run("files/ABC", fetch_now));
+        assert!(futures_util::poll!(abandoned.as_mut()).is_pending());
+        assert!(futures_util::poll!(waiting.as_mut()).is_pending());
+
+        // E.g. the first tool call timed out
+        drop(abandoned);
+
+        assert_eq!(waiting.await.unwrap(), json!({}));
+    }
+
+    async fn fetch_now() -> Result<Value> {
+        Ok(json!({}))
+    }
+}
@@ -0,0 +1,168 @@
+//! Checks that downloaded exports are images of the requested format.
+//!
+//! Export URLs sometimes answer with an HTML or XML error page, or a body
+//! cut short, with a success status. Cached and returned as is, such a
+//! download shows up as a corrupt image, so it is caught here instead.
+
+use crate::raster::png;
+use crate::{Error, Result};
+
+/// Length of the start of a rejected download quoted in the error.
+const SNIPPET_LEN: usize = 120;
+
+const JPEG_START: &[u8] = b"\xff\xd8\xff";
+const JPEG_END: &[u8] = b"\xff\xd9";
+const PDF_START: &[u8] = b"%PDF-";
+const PDF_END: &[u8] = b"%%EOF";
+
+/// Checks that `data` is a complete image of `format` (png, jpg, svg or
+/// pdf). Other formats are only checked for being non-empty.
+pub fn verify(data: &[u8], format: &str) -> Result<()> {
+    if data.is_empty() {
+        return Err(invalid(format, "the download is empty"));
+    }
+    if let Some(page) = error_page(data) {
+        return Err(Error::InvalidImage(format!(
+            "Figma returned an error page instead of the {} image: {}",
+            format.to_uppercase(),
+            page
+        )));
+    }
+
+    match format.to_lowercase().as_str() {
+        "png" => verify_png(data),
+        "jpg" | "jpeg" => {
+            if !data.starts_with(JPEG_START) {
+                return Err(invalid(format, "missing JPEG signature"));
+            }
+            if !trim_end(data).ends_with(JPEG_END) {
+                return Err(invalid(format, "the image is truncated"));
+            }
+            Ok(())
+        }
+        "svg" => {
+            let text = std::str::from_utf8(data)
+                .map_err(|_| invalid(format, "the document is not UTF-8"))?;
+            if !text.contains("<svg") {
+                return Err(invalid(format, "missing <svg> element"));
+            }
+            if !text.trim_end().ends_with('>') {
+                return Err(invalid(format, "the document is truncated"));
+            }
+            Ok(())
+        }
+        "pdf" => {
+            if !data.starts_with(PDF_START) {
+                return Err(invalid(format, "missing PDF header"));
+            }
+            // The trailer ends the file, possibly followed by a line break
+            let tail = &data[data.len().saturating_sub(32)..];
+            if !tail.windows(PDF_END.len()).any(|window| window == PDF_END) {
+                return Err(invalid(format, "the document is truncated"));
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The signature, then an `IHDR` chunk with a non-zero size, as a decoder
+/// needs before anything else.
+fn verify_png(data: &[u8]) -> Result<()> {
+    let Some(rest) = data.strip_prefix(png::SIGNATURE) else {
+        return Err(invalid("png", "missing PNG signature"));
+    };
+    // Length, type, width and height
+    if rest.len() < 16 || &rest[4..8] != b"IHDR" {
+        return Err(invalid("png", "missing IHDR chunk"));
+    }
+    let width = u32::from_be_bytes([rest[8], rest[9], rest[10], rest[11]]);
+    let height = u32::from_be_bytes([rest[12], rest[13], rest[14], rest[15]]);
+    if width == 0 || height == 0 {
+        return Err(invalid("png", "the image has no pixels"));
+    }
+    // The IEND chunk is the last 12 bytes of a complete file
+    if data.len() < 12 || &data[data.len() - 8..data.len() - 4] != b"IEND" {
+        return Err(invalid("png", "the image is truncated"));
+    }
+
+    Ok(())
+}
+
+/// The start of the body if it is an HTML or XML page other than an SVG,
+/// or a JSON error, as storage and proxies answer failed requests with.
+fn error_page(data: &[u8]) -> Option<String> {
+    let start = String::from_utf8_lossy(&data[..data.len().min(512)]).to_string();
+    let text = start.trim_start().to_lowercase();
+    let is_page = text.starts_with("<!doctype html")
+        || text.starts_with("<html")
+        || (text.starts_with("<?xml") && !text.contains("<svg"))
+        || text.starts_with('{');
+    if !is_page {
+        return None;
+    }
+
+    let snippet: String = start.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(snippet.chars().take(SNIPPET_LEN).collect())
+}
+
+fn trim_end(data: &[u8]) -> &[u8] {
+    let end = data
+        .iter()
+        .rposition(|byte| !byte.is_ascii_whitespace())
+        .map_or(0, |index| index + 1);
+
+    &data[..end]
+}
+
+fn invalid(format: &str, reason: &str) -> Error {
+    Error::InvalidImage(format!(
+        "The downloaded {} export is invalid: {}",
+        format.to_uppercase(),
+        reason
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raster::Image;
+
+    fn png_data() -> Vec<u8> {
+        png::encode(&Image {
+            width: 2,
+            height: 1,
+            pixels: vec![0; 8],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_images() {
+        assert!(verify(&png_data(), "png").is_ok());
+        assert!(verify(b"\xff\xd8\xff\xe0rest\xff\xd9", "jpg").is_ok());
+        assert!(verify(b"<?xml version=\"1.0\"?>\n<svg></svg>\n", "svg").is_ok());
+        assert!(verify(b"%PDF-1.7\n...\n%%EOF\n", "pdf").is_ok());
+    }
+
+    #[test]
+    fn test_error_page_is_rejected() {
+        let page = b"<!DOCTYPE html><html><body>AccessDenied: Request has expired</body></html>";
+
+        let error = verify(page, "png").unwrap_err().to_string();
+        assert!(error.contains("error page"));
+        assert!(error.contains("Request has expired"));
+        assert!(verify(b"<?xml version=\"1.0\"?><Error>NoSuchKey</Error>", "svg").is_err());
+    }
+
+    #[test]
+    fn test_broken_images_are_rejected() {
+        let data = png_data();
+
+        assert!(verify(b"", "png").is_err());
+        assert!(verify(&data[..data.len() - 6], "png").is_err());
+        assert!(verify(b"\xff\xd8\xff\xe0rest", "jpg").is_err());
+        assert!(verify(b"%PDF-1.7\n...", "pdf").is_err());
+        assert!(verify(&data, "jpg").is_err());
+    }
+}
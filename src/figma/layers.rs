@@ -0,0 +1,128 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// A hidden or locked layer and where it sits in the tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayerReport {
+    pub node_id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    /// Names of the layer and its ancestors, e.g. `Page 1 / Checkout / Old CTA`
+    pub path: String,
+    pub hidden: bool,
+    pub locked: bool,
+}
+
+/// Lists hidden and locked layers below `root`, in tree order. Descendants of
+/// hidden layers are hidden too and are not listed separately. `path` is the
+/// path of `root`'s parent, if any.
+pub fn hidden_and_locked(root: &Value, path: Option<&str>) -> Vec<LayerReport> {
+    let mut reports = Vec::new();
+    collect(root, path.unwrap_or_default(), &mut reports);
+
+    reports
+}
+
+/// Removes hidden layers, along with their descendants, from a node tree or
+/// a Figma response.
+pub fn strip_hidden(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Array(children)) = map.get_mut("children") {
+                children.retain(|child| !is_hidden(child));
+            }
+            map.values_mut().for_each(strip_hidden);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_hidden),
+        _ => {}
+    }
+}
+
+fn collect(node: &Value, parent_path: &str, reports: &mut Vec<LayerReport>) {
+    let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+    let path = if parent_path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} / {}", parent_path, name)
+    };
+
+    let hidden = is_hidden(node);
+    let locked = node.get("locked").and_then(Value::as_bool) == Some(true);
+    if hidden || locked {
+        let field = |key: &str| {
+            node.get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        reports.push(LayerReport {
+            node_id: field("id"),
+            name: name.to_string(),
+            node_type: field("type"),
+            path: path.clone(),
+            hidden,
+            locked,
+        });
+    }
+    if hidden {
+        return;
+    }
+
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect(child, &path, reports);
+    }
+}
+
+/// Whether a node, or one of its paints or effects, is set to
+/// `visible: false`. Figma leaves the field out for visible ones.
+pub(crate) fn is_hidden(node: &Value) -> bool {
+    node.get("visible").and_then(Value::as_bool) == Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tree() -> Value {
+        json!({
+            "id": "1:1",
+            "name": "Checkout",
+            "type": "FRAME",
+            "children": [
+                { "id": "1:2", "name": "Header", "type": "FRAME", "locked": true, "children": [
+                    { "id": "1:3", "name": "Logo", "type": "VECTOR" }
+                ] },
+                { "id": "1:4", "name": "Old CTA", "type": "FRAME", "visible": false, "children": [
+                    { "id": "1:5", "name": "Label", "type": "TEXT", "locked": true }
+                ] }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_hidden_and_locked() {
+        let reports = hidden_and_locked(&tree(), Some("Page 1"));
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].path, "Page 1 / Checkout / Header");
+        assert!(reports[0].locked && !reports[0].hidden);
+        assert_eq!(reports[1].node_id, "1:4");
+        assert!(reports[1].hidden);
+    }
+
+    #[test]
+    fn test_strip_hidden() {
+        let mut response = json!({ "nodes": { "1:1": { "document": tree() } } });
+
+        strip_hidden(&mut response);
+
+        let children = response["nodes"]["1:1"]["document"]["children"]
+            .as_array()
+            .unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0]["id"], "1:2");
+    }
+}
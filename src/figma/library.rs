@@ -0,0 +1,378 @@
+//! The components, component sets and styles published to team libraries,
+//! from Figma's library endpoints for a file or a whole team, so their keys
+//! and variant properties can be mapped to code components and tokens.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Items requested per page of a team library listing.
+pub const TEAM_PAGE_SIZE: usize = 100;
+
+/// A component published from a file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublishedComponent {
+    /// The global key instances and other files refer to the component by
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    /// The file the component is published from
+    pub file_key: String,
+    /// Links to documentation, known once the file has been indexed since
+    /// the components endpoint leaves them out
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub documentation_links: Vec<String>,
+    pub node_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    /// The frame or section holding the component
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<String>,
+    /// The component set of a variant, e.g. `Button` for `Size=Large`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component_set: Option<String>,
+    pub updated_at: String,
+}
+
+/// A published component set: a group of variants with the properties
+/// that select between them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublishedComponentSet {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub file_key: String,
+    pub node_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<String>,
+    /// Property definitions, read from the set's node; left out of team
+    /// listings
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<ComponentProperty>,
+    /// Names of the variants, e.g. `Size=Large, State=Hover`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<String>,
+    pub updated_at: String,
+}
+
+/// A property of a component set, from `componentPropertyDefinitions`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentProperty {
+    /// The name without the `#id` suffix Figma adds to non-variant
+    /// properties
+    pub name: String,
+    /// `VARIANT`, `BOOLEAN`, `TEXT` or `INSTANCE_SWAP`
+    #[serde(rename = "type")]
+    pub property_type: String,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub default: Value,
+    /// The values of a variant property
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// A published color, text, effect or grid style.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublishedStyle {
+    pub key: String,
+    /// `FILL`, `TEXT`, `EFFECT` or `GRID`
+    #[serde(rename = "type")]
+    pub style_type: String,
+    pub name: String,
+    pub description: String,
+    pub file_key: String,
+    pub node_id: String,
+    pub updated_at: String,
+}
+
+/// Reads a `GET /files/:key/components` response, or a page of a
+/// `GET /teams/:id/components` response, sorted by component set and name.
+pub fn components(response: &Value) -> Vec<PublishedComponent> {
+    let components = response
+        .pointer("/meta/components")
+        .and_then(Value::as_array);
+
+    let mut components: Vec<PublishedComponent> = components
+        .into_iter()
+        .flatten()
+        .map(|component| {
+            let text = |pointer: &str| text(component, pointer);
+            PublishedComponent {
+                key: text("/key").unwrap_or_default(),
+                name: text("/name").unwrap_or_default(),
+                description: text("/description").unwrap_or_default(),
+                file_key: text("/file_key").unwrap_or_default(),
+                documentation_links: Vec::new(),
+                node_id: text("/node_id").unwrap_or_default(),
+                page: text("/containing_frame/pageName"),
+                frame: text("/containing_frame/name"),
+                component_set: text("/containing_frame/containingStateGroup/name"),
+                updated_at: text("/updated_at").unwrap_or_default(),
+            }
+        })
+        .collect();
+    components.sort_by(|a, b| (&a.component_set, &a.name).cmp(&(&b.component_set, &b.name)));
+
+    components
+}
+
+/// Reads a `GET /files/:key/component_sets` response, or a page of a
+/// `GET /teams/:id/component_sets` response, sorted by name. The endpoints
+/// leave out properties and variants; [`add_properties`] fills them in from
+/// the sets' nodes.
+pub fn component_sets(response: &Value) -> Vec<PublishedComponentSet> {
+    let sets = response
+        .pointer("/meta/component_sets")
+        .and_then(Value::as_array);
+
+    let mut sets: Vec<PublishedComponentSet> = sets
+        .into_iter()
+        .flatten()
+        .map(|set| {
+            let text = |pointer: &str| text(set, pointer);
+            PublishedComponentSet {
+                key: text("/key").unwrap_or_default(),
+                name: text("/name").unwrap_or_default(),
+                description: text("/description").unwrap_or_default(),
+                file_key: text("/file_key").unwrap_or_default(),
+                node_id: text("/node_id").unwrap_or_default(),
+                page: text("/containing_frame/pageName"),
+                frame: text("/containing_frame/name"),
+                properties: Vec::new(),
+                variants: Vec::new(),
+                updated_at: text("/updated_at").unwrap_or_default(),
+            }
+        })
+        .collect();
+    sets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    sets
+}
+
+/// Reads a `GET /files/:key/styles` response, or a page of a
+/// `GET /teams/:id/styles` response, sorted by type and name.
+pub fn styles(response: &Value) -> Vec<PublishedStyle> {
+    let styles = response.pointer("/meta/styles").and_then(Value::as_array);
+
+    let mut styles: Vec<PublishedStyle> = styles
+        .into_iter()
+        .flatten()
+        .map(|style| {
+            let text = |pointer: &str| text(style, pointer).unwrap_or_default();
+            PublishedStyle {
+                key: text("/key"),
+                style_type: text("/style_type"),
+                name: text("/name"),
+                description: text("/description"),
+                file_key: text("/file_key"),
+                node_id: text("/node_id"),
+                updated_at: text("/updated_at"),
+            }
+        })
+        .collect();
+    styles.sort_by(|a, b| (&a.style_type, &a.name).cmp(&(&b.style_type, &b.name)));
+
+    styles
+}
+
+/// Fills in the properties and variants of component sets from a
+/// `GET /files/:key/nodes` response for their node IDs (depth 1).
+pub fn add_properties(sets: &mut [PublishedComponentSet], nodes: &Value) {
+    for set in sets {
+        let Some(node) = nodes.pointer(&format!("/nodes/{}/document", set.node_id)) else {
+            continue;
+        };
+
+        let definitions = node
+            .get("componentPropertyDefinitions")
+            .and_then(Value::as_object);
+        set.properties = definitions
+            .into_iter()
+            .flatten()
+            .map(|(name, definition)| {
+                let options = definition.get("variantOptions").and_then(Value::as_array);
+                ComponentProperty {
+                    name: name.split('#').next().unwrap_or(name).to_string(),
+                    property_type: text(definition, "/type").unwrap_or_default(),
+                    default: definition.get("defaultValue").cloned().unwrap_or_default(),
+                    options: options
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let children = node.get("children").and_then(Value::as_array);
+        set.variants = children
+            .into_iter()
+            .flatten()
+            .filter_map(|child| text(child, "/name"))
+            .collect();
+    }
+}
+
+/// The cursor to pass as `after` for the next page of a team library
+/// listing, if there is one. Figma sends it as a number.
+pub fn next_cursor(response: &Value) -> Option<String> {
+    match response.pointer("/meta/cursor/after")? {
+        Value::Number(after) => Some(after.to_string()),
+        Value::String(after) if !after.is_empty() => Some(after.clone()),
+        _ => None,
+    }
+}
+
+fn text(value: &Value, pointer: &str) -> Option<String> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_components() {
+        let response = json!({
+            "status": 200,
+            "error": false,
+            "meta": {
+                "components": [
+                    {
+                        "key": "b2",
+                        "name": "Size=Small",
+                        "description": "",
+                        "node_id": "4:2",
+                        "updated_at": "2024-05-01T09:00:00Z",
+                        "containing_frame": {
+                            "name": "Buttons",
+                            "pageName": "Components",
+                            "containingStateGroup": { "name": "Button", "nodeId": "4:1" }
+                        }
+                    },
+                    {
+                        "key": "a1",
+                        "name": "Avatar",
+                        "description": "User photo with initials fallback",
+                        "node_id": "3:1",
+                        "updated_at": "2024-04-01T09:00:00Z",
+                        "containing_frame": { "name": "Media", "pageName": "Components" }
+                    }
+                ]
+            }
+        });
+
+        let components = components(&response);
+
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].key, "a1");
+        assert_eq!(
+            components[0].description,
+            "User photo with initials fallback"
+        );
+        assert_eq!(components[0].component_set, None);
+        assert_eq!(components[1].component_set.as_deref(), Some("Button"));
+        assert_eq!(components[1].page.as_deref(), Some("Components"));
+    }
+
+    #[test]
+    fn test_styles_sorted_by_type() {
+        let response = json!({
+            "meta": {
+                "styles": [
+                    {
+                        "key": "b",
+                        "style_type": "TEXT",
+                        "name": "Body/Regular",
+                        "description": "",
+                        "node_id": "2:1"
+                    },
+                    {
+                        "key": "a",
+                        "style_type": "FILL",
+                        "name": "Brand/Primary",
+                        "description": "Buttons and links",
+                        "node_id": "2:2"
+                    }
+                ]
+            }
+        });
+
+        let styles = styles(&response);
+
+        assert_eq!(styles[0].name, "Brand/Primary");
+        assert_eq!(styles[0].style_type, "FILL");
+        assert_eq!(styles[0].description, "Buttons and links");
+        assert_eq!(styles[1].key, "b");
+    }
+
+    #[test]
+    fn test_component_sets_with_properties() {
+        let response = json!({
+            "meta": {
+                "component_sets": [{
+                    "key": "set-key",
+                    "name": "Button",
+                    "description": "Primary actions",
+                    "node_id": "4:1",
+                    "updated_at": "2024-05-01T09:00:00Z",
+                    "containing_frame": { "name": "Buttons", "pageName": "Components" }
+                }]
+            }
+        });
+        let nodes = json!({
+            "nodes": {
+                "4:1": {
+                    "document": {
+                        "id": "4:1",
+                        "type": "COMPONENT_SET",
+                        "componentPropertyDefinitions": {
+                            "Size": {
+                                "type": "VARIANT",
+                                "defaultValue": "Large",
+                                "variantOptions": ["Large", "Small"]
+                            },
+                            "Label#12:0": { "type": "TEXT", "defaultValue": "Continue" }
+                        },
+                        "children": [
+                            { "id": "4:2", "name": "Size=Large" },
+                            { "id": "4:3", "name": "Size=Small" }
+                        ]
+                    }
+                }
+            }
+        });
+
+        assert_eq!(next_cursor(&response), None);
+        assert_eq!(
+            next_cursor(&json!({ "meta": { "cursor": { "before": 1, "after": 31 } } })),
+            Some("31".to_string())
+        );
+
+        let mut sets = component_sets(&response);
+        add_properties(&mut sets, &nodes);
+
+        assert_eq!(sets[0].description, "Primary actions");
+        assert_eq!(sets[0].variants, vec!["Size=Large", "Size=Small"]);
+        let label = sets[0]
+            .properties
+            .iter()
+            .find(|property| property.name == "Label")
+            .unwrap();
+        assert_eq!(label.property_type, "TEXT");
+        assert_eq!(label.default, json!("Continue"));
+        let size = sets[0]
+            .properties
+            .iter()
+            .find(|property| property.name == "Size")
+            .unwrap();
+        assert_eq!(size.options, vec!["Large", "Small"]);
+    }
+}
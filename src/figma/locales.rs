@@ -0,0 +1,418 @@
+//! Frames that are translations of each other, named with a locale suffix
+//! such as `Checkout / en` and `Checkout / de`, paired up so their texts can
+//! be compared for localization QA.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::bounds::{Bounds, BoundsKind};
+use super::layers::is_hidden;
+
+/// How much longer than the reference a translation may get before it is
+/// flagged, by default.
+pub const DEFAULT_MAX_LENGTH_RATIO: f64 = 1.3;
+
+/// ISO 639-1 codes accepted as locale suffixes. Any two letters would also
+/// match size suffixes such as `/ xl`, so only common UI languages count.
+const LANGUAGES: &[&str] = &[
+    "ar", "bg", "bn", "ca", "cs", "da", "de", "el", "en", "es", "et", "fa", "fi", "fr", "he", "hi",
+    "hr", "hu", "id", "it", "ja", "ko", "lt", "lv", "ms", "nb", "nl", "pl", "pt", "ro", "ru", "sk",
+    "sl", "sr", "sv", "th", "tr", "uk", "vi", "zh",
+];
+
+/// Characters separating a frame name from its locale suffix.
+const SEPARATORS: &[char] = &['/', '-', '–', '—', '_', '|', '(', '['];
+
+/// Node types that can hold a translated screen.
+const FRAME_TYPES: &[&str] = &["FRAME", "COMPONENT", "INSTANCE", "SECTION", "GROUP"];
+
+/// Frames sharing a name apart from their locale suffix.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocaleGroup {
+    /// The name without the locale suffix
+    pub name: String,
+    /// The locale the others are compared with
+    pub reference: String,
+    pub frames: Vec<LocaleFrame>,
+    /// Text layers found in the reference frame
+    pub texts: usize,
+    pub issues: Vec<TextIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocaleFrame {
+    /// The suffix as written, e.g. `de` or `pt-BR`
+    pub locale: String,
+    pub node_id: String,
+}
+
+/// A translated text layer that may not fit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextIssue {
+    pub locale: String,
+    /// The text layer in the translated frame; missing if it has none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    /// Layer names from the frame down to the text layer
+    pub layer: String,
+    pub reference_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Characters relative to the reference text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<f64>,
+    pub reason: IssueReason,
+}
+
+/// Why a text is flagged, most severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueReason {
+    /// The layer extends past its parent's box in the translated frame
+    ExceedsParent,
+    /// The layer truncates with an ellipsis and the text got longer
+    Truncated,
+    /// The layer has a fixed size and the text got a lot longer
+    FixedBox,
+    /// The layer resizes with its text, which got a lot longer and may
+    /// push the layout around
+    Longer,
+    /// The translated frame has no matching text layer
+    Missing,
+}
+
+/// A text layer with what is needed to compare it.
+struct Text<'a> {
+    node: &'a Value,
+    layer: String,
+    characters: String,
+    /// Whether the box extends past the parent's box
+    exceeds_parent: bool,
+}
+
+/// Splits a locale suffix off a name: `Checkout / de` gives
+/// `("Checkout", "de")`. Accepts region or script subtags (`pt-BR`,
+/// `zh_Hans`) and suffixes in brackets (`Checkout (fr)`).
+pub fn split_locale(name: &str) -> Option<(&str, &str)> {
+    let name = name.trim_end().trim_end_matches([')', ']']).trim_end();
+    let is_boundary = |c: char| c.is_whitespace() || SEPARATORS.contains(&c);
+
+    // The locale starts after the last or, for `pt-BR`, the second to last
+    // boundary
+    let last = name.rfind(is_boundary)?;
+    let second = name[..last].rfind(is_boundary);
+
+    [second, Some(last)]
+        .into_iter()
+        .flatten()
+        .find_map(|start| {
+            let locale = name[start..].trim_start_matches(is_boundary);
+            let base = name[..start].trim_end_matches(is_boundary);
+            let gap = &name[base.len()..name.len() - locale.len()];
+
+            (is_locale(locale) && !base.is_empty() && gap.contains(SEPARATORS))
+                .then_some((base, locale))
+        })
+}
+
+/// Pairs the locale variants of frames below `roots` and compares the text
+/// layers of each against the `reference` locale (default: English if
+/// present, otherwise the first variant).
+pub fn compare<'a>(
+    roots: impl IntoIterator<Item = &'a Value>,
+    reference: Option<&str>,
+    max_ratio: f64,
+) -> Vec<LocaleGroup> {
+    let mut frames: Vec<(&str, &str, &Value)> = Vec::new();
+    for root in roots {
+        collect(root, &mut frames);
+    }
+
+    let mut groups: Vec<(String, Vec<(&str, &Value)>)> = Vec::new();
+    for (base, locale, node) in frames {
+        let key = base.to_lowercase();
+        match groups
+            .iter_mut()
+            .find(|(name, _)| name.to_lowercase() == key)
+        {
+            Some((_, variants)) => variants.push((locale, node)),
+            None => groups.push((base.to_string(), vec![(locale, node)])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, variants)| variants.len() > 1)
+        .map(|(name, variants)| compare_group(name, &variants, reference, max_ratio))
+        .collect()
+}
+
+fn compare_group(
+    name: String,
+    variants: &[(&str, &Value)],
+    reference: Option<&str>,
+    max_ratio: f64,
+) -> LocaleGroup {
+    let same = |a: &str, b: &str| a.eq_ignore_ascii_case(b);
+    let reference_index = reference
+        .and_then(|reference| {
+            variants
+                .iter()
+                .position(|(locale, _)| same(locale, reference))
+                .or_else(|| {
+                    variants
+                        .iter()
+                        .position(|(locale, _)| same(language(locale), language(reference)))
+                })
+        })
+        .or_else(|| {
+            variants
+                .iter()
+                .position(|(locale, _)| same(language(locale), "en"))
+        })
+        .unwrap_or(0);
+    let (reference_locale, reference_frame) = variants[reference_index];
+    let reference_texts = texts(reference_frame);
+
+    let mut issues = Vec::new();
+    for (index, (locale, frame)) in variants.iter().enumerate() {
+        if index == reference_index {
+            continue;
+        }
+
+        // Texts are matched by layer path, in order for repeated paths
+        let mut translated: HashMap<String, Vec<Text>> = HashMap::new();
+        for text in texts(frame) {
+            translated.entry(text.layer.clone()).or_default().push(text);
+        }
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+
+        for original in &reference_texts {
+            let occurrence = seen.entry(original.layer.as_str()).or_default();
+            let text = translated
+                .get(original.layer.as_str())
+                .and_then(|texts| texts.get(*occurrence));
+            *occurrence += 1;
+
+            let issue = |text: Option<&Text>, ratio, reason| TextIssue {
+                locale: locale.to_string(),
+                node_id: text.and_then(|text| string_field(text.node, "id")),
+                layer: original.layer.clone(),
+                reference_text: original.characters.clone(),
+                text: text.map(|text| text.characters.clone()),
+                ratio,
+                reason,
+            };
+            let Some(text) = text else {
+                issues.push(issue(None, None, IssueReason::Missing));
+                continue;
+            };
+
+            let length = |text: &str| text.chars().count().max(1) as f64;
+            let ratio = length(&text.characters) / length(&original.characters);
+            let style = |key: &str| text.node.pointer(&format!("/style/{}", key));
+            let truncates = style("textTruncation").and_then(Value::as_str) == Some("ENDING");
+            let auto_resize = style("textAutoResize").and_then(Value::as_str);
+
+            let reason = if text.exceeds_parent {
+                Some(IssueReason::ExceedsParent)
+            } else if truncates && ratio > 1.0 {
+                Some(IssueReason::Truncated)
+            } else if ratio > max_ratio {
+                // Figma leaves out `textAutoResize` for fixed boxes
+                Some(match auto_resize {
+                    None | Some("NONE") | Some("TRUNCATE") => IssueReason::FixedBox,
+                    _ => IssueReason::Longer,
+                })
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                let ratio = (ratio * 100.0).round() / 100.0;
+                issues.push(issue(Some(text), Some(ratio), reason));
+            }
+        }
+    }
+    issues.sort_by_key(|issue| issue.reason);
+
+    LocaleGroup {
+        name,
+        reference: reference_locale.to_string(),
+        frames: variants
+            .iter()
+            .map(|(locale, node)| LocaleFrame {
+                locale: locale.to_string(),
+                node_id: string_field(node, "id").unwrap_or_default(),
+            })
+            .collect(),
+        texts: reference_texts.len(),
+        issues,
+    }
+}
+
+/// The language part of a locale, `pt` for `pt-BR`.
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+fn is_locale(code: &str) -> bool {
+    let mut parts = code.split(['-', '_']);
+    let language = parts.next().unwrap_or_default().to_lowercase();
+    let subtag = parts.next();
+
+    LANGUAGES.contains(&language.as_str())
+        && parts.next().is_none()
+        && subtag.is_none_or(|subtag| {
+            matches!(subtag.len(), 2 | 4) && subtag.chars().all(|c| c.is_ascii_alphabetic())
+        })
+}
+
+/// Collects locale-suffixed frames, without looking inside them.
+fn collect<'a>(node: &'a Value, frames: &mut Vec<(&'a str, &'a str, &'a Value)>) {
+    let node_type = node.get("type").and_then(Value::as_str).unwrap_or_default();
+    if FRAME_TYPES.contains(&node_type) {
+        let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+        if let Some((base, locale)) = split_locale(name) {
+            frames.push((base, locale, node));
+            return;
+        }
+    }
+
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect(child, frames);
+    }
+}
+
+/// The visible text layers of a frame, in layer order.
+fn texts(frame: &Value) -> Vec<Text<'_>> {
+    let mut texts = Vec::new();
+    let children = frame.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect_texts(child, frame, "", &mut texts);
+    }
+
+    texts
+}
+
+fn collect_texts<'a>(node: &'a Value, parent: &Value, path: &str, texts: &mut Vec<Text<'a>>) {
+    if is_hidden(node) {
+        return;
+    }
+
+    let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+    let layer = if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} / {}", path, name)
+    };
+
+    if node.get("type").and_then(Value::as_str) == Some("TEXT") {
+        let key = BoundsKind::Layout.key();
+        let exceeds_parent = match (Bounds::of(parent, key), Bounds::of(node, key)) {
+            (Some(parent), Some(text)) => !parent.contains(&text),
+            _ => false,
+        };
+        texts.push(Text {
+            node,
+            layer,
+            characters: string_field(node, "characters").unwrap_or_default(),
+            exceeds_parent,
+        });
+        return;
+    }
+
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect_texts(child, node, &layer, texts);
+    }
+}
+
+fn string_field(node: &Value, key: &str) -> Option<String> {
+    node.get(key).and_then(Value::as_str).map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_split_locale() {
+        assert_eq!(split_locale("Checkout / de"), Some(("Checkout", "de")));
+        assert_eq!(
+            split_locale("Checkout - pt-BR"),
+            Some(("Checkout", "pt-BR"))
+        );
+        assert_eq!(split_locale("Checkout (FR)"), Some(("Checkout", "FR")));
+        assert_eq!(split_locale("Card / xl"), None);
+        assert_eq!(split_locale("de"), None);
+        assert_eq!(split_locale("Checkout v2"), None);
+    }
+
+    #[test]
+    fn test_compare_flags_long_translations() {
+        let bounds =
+            |x: f64, width: f64| json!({ "x": x, "y": 0.0, "width": width, "height": 40.0 });
+        let frame = |name: &str, id: &str, label: &str, width: f64, title: Option<&str>| {
+            let mut children = vec![json!({
+                "id": format!("{}:2", id),
+                "type": "FRAME",
+                "name": "Button",
+                "absoluteBoundingBox": bounds(0.0, 120.0),
+                "children": [{
+                    "id": format!("{}:3", id),
+                    "type": "TEXT",
+                    "name": "Label",
+                    "characters": label,
+                    "style": { "textAutoResize": "WIDTH_AND_HEIGHT" },
+                    "absoluteBoundingBox": bounds(10.0, width)
+                }]
+            })];
+            if let Some(title) = title {
+                children.push(json!({
+                    "id": format!("{}:4", id),
+                    "type": "TEXT",
+                    "name": "Title",
+                    "characters": title,
+                    "style": {}
+                }));
+            }
+            json!({ "id": format!("{}:1", id), "type": "FRAME", "name": name, "children": children })
+        };
+        let page = json!({
+            "type": "CANVAS",
+            "children": [
+                frame("Checkout / de", "2", "Zahlungspflichtig bestellen", 180.0, Some("Warenkorb")),
+                frame("Checkout / en", "1", "Place order", 80.0, Some("Cart")),
+                frame("Checkout / fr", "3", "Commander", 70.0, None),
+                frame("Settings", "4", "Save", 40.0, None)
+            ]
+        });
+
+        let groups = compare([&page], None, DEFAULT_MAX_LENGTH_RATIO);
+
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.reference, "en");
+        assert_eq!(group.frames.len(), 3);
+        assert_eq!(group.texts, 2);
+
+        let reasons: Vec<(&str, &str, IssueReason)> = group
+            .issues
+            .iter()
+            .map(|issue| (issue.locale.as_str(), issue.layer.as_str(), issue.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("de", "Button / Label", IssueReason::ExceedsParent),
+                ("de", "Title", IssueReason::FixedBox),
+                ("fr", "Title", IssueReason::Missing),
+            ]
+        );
+        assert_eq!(group.issues[0].node_id.as_deref(), Some("2:3"));
+    }
+}
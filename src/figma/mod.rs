@@ -1,7 +1,45 @@
+pub mod analytics;
+pub mod bounds;
+pub mod branches;
+pub mod charts;
 pub mod client;
+pub mod codegen;
+pub mod comments;
+pub mod contrast;
+pub mod dev_resources;
+pub mod etag_cache;
+pub mod export;
+pub mod figjam;
+pub mod freshness;
+pub mod gateway;
 pub mod url_parser;
+pub mod variables;
+pub mod versions;
 pub mod image_cache;
+pub mod index;
+pub mod inflight;
+pub mod integrity;
+pub mod layers;
+pub mod locales;
+pub mod library;
+pub mod naming;
+pub mod normalize;
+pub mod oauth;
+pub mod pages;
+pub mod projects;
+pub mod rate_limit;
+pub mod response_cache;
+pub mod retry;
+pub mod roles;
+pub mod schema;
+pub mod status;
+pub mod tables;
+pub mod text;
+pub mod tokens;
+pub mod node_stats;
 
-pub use client::FigmaClient;
+pub use client::{FigmaClient, FigmaClientBuilder};
 pub use url_parser::{FigmaUrlParser, FigmaUrlInfo, FigmaUrlType};
-pub use image_cache::{ImageCache, ImageEntry};
\ No newline at end of file
+pub use image_cache::{ImageCache, ImageEntry};
+pub use index::{DesignIndex, FileIndex};
+pub use node_stats::NodeStats;
\ No newline at end of file
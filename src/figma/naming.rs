@@ -0,0 +1,418 @@
+//! Finds layers still carrying the names Figma gave them ("Frame 4821",
+//! "Group 12") and suggests names from what they contain and the role they
+//! play, as a worksheet designers can work through or apply with the
+//! companion plugin.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::figma::layers::is_hidden;
+use crate::figma::roles::{self, Role, RoleRules};
+use crate::figma::tables::csv_field;
+
+/// Names Figma gives new layers, optionally followed by a number.
+const DEFAULT_NAMES: &[&str] = &[
+    "Frame",
+    "Group",
+    "Section",
+    "Component",
+    "Rectangle",
+    "Ellipse",
+    "Line",
+    "Arrow",
+    "Vector",
+    "Polygon",
+    "Star",
+    "Union",
+    "Subtract",
+    "Intersect",
+    "Exclude",
+    "Image",
+    "Slice",
+];
+
+/// Node types drawn as shapes, which make up icons.
+const SHAPE_TYPES: &[&str] = &[
+    "VECTOR",
+    "BOOLEAN_OPERATION",
+    "STAR",
+    "POLYGON",
+    "ELLIPSE",
+    "LINE",
+    "RECTANGLE",
+    "REGULAR_POLYGON",
+];
+
+/// Icons are at most this wide and tall.
+const MAX_ICON_SIZE: f64 = 48.0;
+
+/// Words of text content kept in a suggested name.
+const MAX_NAME_WORDS: usize = 4;
+
+/// A layer with a default name, where it sits in the tree and what to call
+/// it instead.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NamingIssue {
+    pub node_id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    /// Names of the layer and its ancestors, e.g. `Checkout / Frame 4821`
+    pub path: String,
+    /// Suggested name; unset when nothing gives the layer's purpose away
+    pub suggestion: Option<String>,
+    /// What the suggestion was inferred from
+    pub reason: Option<&'static str>,
+}
+
+/// Whether `name` is one Figma gives new layers, such as `Frame` or
+/// `Group 12`.
+pub fn is_default_name(name: &str) -> bool {
+    let name = name.trim();
+    let base = match name.rsplit_once(' ') {
+        Some((base, number)) if number.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => name,
+    };
+
+    DEFAULT_NAMES.contains(&base)
+}
+
+/// Lists the visible layers below `root`, `root` included, that have a
+/// default name, in tree order. Text layers are named after their content
+/// already, layers inside instances after their component, and the shapes
+/// of an icon need no names of their own, so none of them are listed.
+/// `path` is the path of `root`'s parent, if any.
+pub fn report(root: &Value, path: Option<&str>, rules: &RoleRules) -> Vec<NamingIssue> {
+    let mut issues = Vec::new();
+    collect(root, None, path.unwrap_or_default(), rules, &mut issues);
+
+    issues
+}
+
+/// The issues as a CSV worksheet, one layer per row with its suggested
+/// name, for designers to work through.
+pub fn to_csv(issues: &[NamingIssue]) -> String {
+    let mut csv = String::from("node_id,path,name,suggestion,reason\n");
+    for issue in issues {
+        let fields = [
+            issue.node_id.as_str(),
+            &issue.path,
+            &issue.name,
+            issue.suggestion.as_deref().unwrap_or_default(),
+            issue.reason.unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+fn collect(
+    node: &Value,
+    parent: Option<&Value>,
+    parent_path: &str,
+    rules: &RoleRules,
+    issues: &mut Vec<NamingIssue>,
+) {
+    if is_hidden(node) {
+        return;
+    }
+
+    let name = field(node, "name");
+    let node_type = field(node, "type");
+    let path = if parent_path.is_empty() {
+        name.clone()
+    } else {
+        format!("{} / {}", parent_path, name)
+    };
+
+    let suggested = suggest(node, parent, rules);
+    if node_type != "TEXT" && is_default_name(&name) {
+        issues.push(NamingIssue {
+            node_id: field(node, "id"),
+            name,
+            node_type: node_type.clone(),
+            path: path.clone(),
+            suggestion: suggested.as_ref().map(|(name, _)| name.clone()),
+            reason: suggested.as_ref().map(|(_, reason)| *reason),
+        });
+    }
+
+    let is_icon = suggested.is_some_and(|(name, _)| name == "Icon");
+    if node_type == "INSTANCE" || is_icon {
+        return;
+    }
+    for child in children(node) {
+        collect(child, Some(node), &path, rules, issues);
+    }
+}
+
+/// A name for the node from its fills, shapes, role, text and layout, and
+/// what it was inferred from.
+fn suggest(
+    node: &Value,
+    parent: Option<&Value>,
+    rules: &RoleRules,
+) -> Option<(String, &'static str)> {
+    let node_type = field(node, "type");
+    let size = size(node);
+
+    if has_image_fill(node) {
+        return Some(("Image".to_string(), "image fill"));
+    }
+    if node_type == "LINE" || size.is_some_and(|(width, height)| is_divider(width, height)) {
+        return Some(("Divider".to_string(), "thin line"));
+    }
+    let small =
+        size.is_some_and(|(width, height)| width <= MAX_ICON_SIZE && height <= MAX_ICON_SIZE);
+    if small && is_drawing(node) && node_type != "RECTANGLE" && node_type != "ELLIPSE" {
+        return Some(("Icon".to_string(), "small vector drawing"));
+    }
+    if matches!(node_type.as_str(), "RECTANGLE" | "ELLIPSE") && fills_parent(node, parent) {
+        return Some(("Background".to_string(), "shape covering its parent"));
+    }
+
+    let label = text_label(node);
+    if let Some(inference) = roles::infer(node, parent, rules) {
+        let role = capitalize(inference.role.name());
+        let name = match (inference.role, label) {
+            (Role::Button | Role::Input | Role::Card | Role::Modal, Some(label)) => {
+                format!("{} {}", label, inference.role.name())
+            }
+            _ => role,
+        };
+        return Some((name, inference.reason));
+    }
+    if let Some(label) = label {
+        return Some((label, "text content"));
+    }
+
+    match node.get("layoutMode").and_then(Value::as_str) {
+        Some("HORIZONTAL") => Some(("Row".to_string(), "horizontal auto layout")),
+        Some("VERTICAL") => Some(("Stack".to_string(), "vertical auto layout")),
+        _ => None,
+    }
+}
+
+/// The first words of the first text below the node.
+fn text_label(node: &Value) -> Option<String> {
+    let text = first_text(node)?;
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let words: Vec<&str> = line.split_whitespace().take(MAX_NAME_WORDS).collect();
+    let label = words
+        .join(" ")
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .to_string();
+
+    (!label.is_empty()).then_some(label)
+}
+
+fn first_text(node: &Value) -> Option<&str> {
+    if field(node, "type") == "TEXT" {
+        return node.get("characters").and_then(Value::as_str);
+    }
+
+    children(node).into_iter().find_map(first_text)
+}
+
+/// Whether the node is a shape, or a container of shapes only.
+fn is_drawing(node: &Value) -> bool {
+    let node_type = field(node, "type");
+    if SHAPE_TYPES.contains(&node_type.as_str()) {
+        return true;
+    }
+    let children = children(node);
+
+    matches!(node_type.as_str(), "FRAME" | "GROUP")
+        && !children.is_empty()
+        && children.into_iter().all(is_drawing)
+}
+
+fn is_divider(width: f64, height: f64) -> bool {
+    let (long, short) = if width >= height {
+        (width, height)
+    } else {
+        (height, width)
+    };
+
+    short <= 2.0 && long >= 10.0 * short.max(1.0)
+}
+
+fn fills_parent(node: &Value, parent: Option<&Value>) -> bool {
+    let bounds = |node: &Value| node.get("absoluteBoundingBox").cloned();
+
+    match (bounds(node), parent.and_then(bounds)) {
+        (Some(bounds), Some(parent_bounds)) => bounds == parent_bounds,
+        _ => false,
+    }
+}
+
+fn has_image_fill(node: &Value) -> bool {
+    node.get("fills")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .any(|fill| fill.get("type").and_then(Value::as_str) == Some("IMAGE") && !is_hidden(fill))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn field(node: &Value, key: &str) -> String {
+    node.get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn children(node: &Value) -> Vec<&Value> {
+    node.get("children")
+        .and_then(Value::as_array)
+        .map(|children| children.iter().filter(|child| !is_hidden(child)).collect())
+        .unwrap_or_default()
+}
+
+fn size(node: &Value) -> Option<(f64, f64)> {
+    let bounds = node.get("absoluteBoundingBox")?;
+    let width = bounds.get("width")?.as_f64()?;
+    let height = bounds.get("height")?.as_f64()?;
+
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bounds(x: f64, y: f64, width: f64, height: f64) -> Value {
+        json!({ "x": x, "y": y, "width": width, "height": height })
+    }
+
+    #[test]
+    fn test_default_names() {
+        assert!(is_default_name("Frame 4821"));
+        assert!(is_default_name("Group 12"));
+        assert!(is_default_name("Rectangle"));
+        assert!(!is_default_name("Checkout"));
+        assert!(!is_default_name("Frame of mind"));
+        assert!(!is_default_name("Hero Image 2"));
+    }
+
+    #[test]
+    fn test_report_suggests_names() {
+        let screen = json!({
+            "id": "1:1",
+            "name": "Checkout",
+            "type": "FRAME",
+            "absoluteBoundingBox": bounds(0.0, 0.0, 400.0, 800.0),
+            "children": [
+                {
+                    "id": "1:2",
+                    "name": "Frame 4821",
+                    "type": "FRAME",
+                    "cornerRadius": 8.0,
+                    "fills": [{ "type": "SOLID", "color": { "r": 0.0, "g": 0.0, "b": 1.0, "a": 1.0 } }],
+                    "absoluteBoundingBox": bounds(20.0, 700.0, 360.0, 48.0),
+                    "children": [
+                        { "id": "1:3", "name": "Pay now", "type": "TEXT", "characters": "Pay now!" }
+                    ]
+                },
+                {
+                    "id": "1:4",
+                    "name": "Group 12",
+                    "type": "GROUP",
+                    "absoluteBoundingBox": bounds(20.0, 20.0, 24.0, 24.0),
+                    "children": [
+                        { "id": "1:5", "name": "Vector", "type": "VECTOR" },
+                        { "id": "1:6", "name": "Vector 2", "type": "VECTOR" }
+                    ]
+                },
+                {
+                    "id": "1:7",
+                    "name": "Frame 9",
+                    "type": "FRAME",
+                    "layoutMode": "VERTICAL",
+                    "absoluteBoundingBox": bounds(20.0, 100.0, 360.0, 300.0)
+                },
+                { "id": "1:8", "name": "Frame 10", "type": "FRAME", "visible": false },
+                { "id": "1:9", "name": "Instance", "type": "INSTANCE", "children": [
+                    { "id": "I1:9;1:1", "name": "Frame 1", "type": "FRAME" }
+                ] }
+            ]
+        });
+
+        let issues = report(&screen, Some("Page 1"), &RoleRules::default());
+        let suggestions: Vec<(&str, Option<&str>)> = issues
+            .iter()
+            .map(|issue| (issue.node_id.as_str(), issue.suggestion.as_deref()))
+            .collect();
+
+        assert_eq!(
+            suggestions,
+            vec![
+                ("1:2", Some("Pay now button")),
+                ("1:4", Some("Icon")),
+                ("1:7", Some("Stack")),
+            ]
+        );
+        assert_eq!(issues[0].path, "Page 1 / Checkout / Frame 4821");
+        assert_eq!(issues[0].reason, Some("small filled box around a label"));
+
+        let csv = to_csv(&issues);
+        assert!(csv.starts_with("node_id,path,name,suggestion,reason\n"));
+        assert!(csv.contains(
+            "1:2,Page 1 / Checkout / Frame 4821,Frame 4821,Pay now button,small filled box around a label\n"
+        ));
+    }
+
+    #[test]
+    fn test_shapes_and_content() {
+        let card = json!({
+            "id": "2:1",
+            "name": "Frame 3",
+            "type": "FRAME",
+            "absoluteBoundingBox": bounds(0.0, 0.0, 300.0, 200.0),
+            "children": [
+                {
+                    "id": "2:2",
+                    "name": "Rectangle 1",
+                    "type": "RECTANGLE",
+                    "absoluteBoundingBox": bounds(0.0, 0.0, 300.0, 200.0)
+                },
+                {
+                    "id": "2:3",
+                    "name": "Rectangle 2",
+                    "type": "RECTANGLE",
+                    "fills": [{ "type": "IMAGE", "imageRef": "abc" }],
+                    "absoluteBoundingBox": bounds(0.0, 0.0, 300.0, 120.0)
+                },
+                { "id": "2:4", "name": "Line 1", "type": "LINE" },
+                { "id": "2:5", "name": "Title", "type": "TEXT", "characters": "Summer sale on all shoes" }
+            ]
+        });
+
+        let issues = report(&card, None, &RoleRules::default());
+        let suggestions: Vec<Option<&str>> = issues
+            .iter()
+            .map(|issue| issue.suggestion.as_deref())
+            .collect();
+
+        assert_eq!(
+            suggestions,
+            vec![
+                Some("Summer sale on all"),
+                Some("Background"),
+                Some("Image"),
+                Some("Divider"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Node ID Figma uses for the document root of every file.
+pub const DOCUMENT_NODE_ID: &str = "0:0";
+
+/// Per-level node counts observed in earlier responses, used to project how
+/// large a deeper fetch of the same node would be.
+#[derive(Clone, Default)]
+pub struct NodeStats {
+    entries: Arc<RwLock<HashMap<(String, String), LevelCounts>>>,
+}
+
+/// Number of descendants per level below a node: `counts[0]` are its direct
+/// children, `counts[1]` its grandchildren, and so on. Only levels included
+/// in a fetched response are known.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelCounts {
+    pub counts: Vec<usize>,
+}
+
+impl NodeStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the levels of a node tree fetched with the given depth.
+    pub fn record(&self, file_key: &str, node: &Value, depth: u32) -> Result<()> {
+        let Some(node_id) = node.get("id").and_then(Value::as_str) else {
+            return Ok(());
+        };
+
+        let observed = LevelCounts::from_node(node, depth as usize);
+
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+
+        let key = (file_key.to_string(), node_id.to_string());
+        let known_levels = entries.get(&key).map_or(0, |known| known.counts.len());
+        if observed.counts.len() >= known_levels {
+            entries.insert(key, observed);
+        }
+
+        Ok(())
+    }
+
+    /// Forgets all observed node counts.
+    pub fn clear(&self) -> Result<()> {
+        self.entries
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?
+            .clear();
+
+        Ok(())
+    }
+
+    /// Forgets the node counts of a file, e.g. after it changed.
+    pub fn forget(&self, file_key: &str) -> Result<()> {
+        self.entries
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?
+            .retain(|(key, _), _| key != file_key);
+
+        Ok(())
+    }
+
+    /// Records the document tree of a `GET /v1/files/:key` response.
+    pub fn record_file(&self, file_key: &str, file: &Value, depth: u32) -> Result<()> {
+        let Some(document) = file.get("document") else {
+            return Ok(());
+        };
+
+        self.record(file_key, document, depth)
+    }
+
+    /// Records every node of a `GET /v1/files/:key/nodes` response.
+    pub fn record_nodes(&self, file_key: &str, response: &Value, depth: u32) -> Result<()> {
+        let Some(nodes) = response.get("nodes").and_then(Value::as_object) else {
+            return Ok(());
+        };
+
+        for node in nodes.values() {
+            if let Some(document) = node.get("document") {
+                self.record(file_key, document, depth)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Projects the number of nodes a fetch of `node_id` at `depth` would
+    /// return, or `None` when nothing is known about the node yet.
+    pub fn estimate(&self, file_key: &str, node_id: &str, depth: u32) -> Result<Option<usize>> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire lock".to_string()))?;
+
+        let key = (file_key.to_string(), node_id.to_string());
+
+        Ok(entries
+            .get(&key)
+            .map(|counts| counts.project(depth as usize)))
+    }
+}
+
+impl LevelCounts {
+    fn from_node(node: &Value, depth: usize) -> Self {
+        let mut counts = vec![0; depth];
+        let mut level: Vec<&Value> = vec![node];
+
+        for count in counts.iter_mut() {
+            level = level
+                .iter()
+                .filter_map(|node| node.get("children").and_then(Value::as_array))
+                .flatten()
+                .collect();
+            *count = level.len();
+        }
+
+        Self { counts }
+    }
+
+    /// Sums the known levels up to `depth` and extrapolates unknown deeper
+    /// levels using the growth rate between the deepest known levels.
+    pub fn project(&self, depth: usize) -> usize {
+        let known = self.counts.len().min(depth);
+        let mut total: usize = self.counts[..known].iter().sum();
+
+        let Some(&last) = self.counts.last() else {
+            return total;
+        };
+        if depth <= self.counts.len() || last == 0 {
+            return total;
+        }
+
+        let growth = match self.counts.len() {
+            1 => last as f64,
+            n => last as f64 / self.counts[n - 2].max(1) as f64,
+        };
+
+        let mut level = last as f64;
+        for _ in self.counts.len()..depth {
+            level *= growth.max(1.0);
+            total = total.saturating_add(level as usize);
+        }
+
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn page(frames: usize, children_per_frame: usize) -> Value {
+        let frames: Vec<Value> = (0..frames)
+            .map(|frame| {
+                let children: Vec<Value> = (0..children_per_frame)
+                    .map(|child| json!({ "id": format!("{}:{}", frame, child) }))
+                    .collect();
+                json!({ "id": format!("{}:0", frame), "children": children })
+            })
+            .collect();
+
+        json!({ "id": "1:0", "children": frames })
+    }
+
+    #[test]
+    fn test_level_counts_from_node() {
+        let counts = LevelCounts::from_node(&page(3, 4), 3);
+        assert_eq!(counts.counts, vec![3, 12, 0]);
+    }
+
+    #[test]
+    fn test_projection_within_known_levels_is_exact() {
+        let counts = LevelCounts {
+            counts: vec![3, 12, 0],
+        };
+        assert_eq!(counts.project(2), 15);
+        assert_eq!(counts.project(5), 15);
+    }
+
+    #[test]
+    fn test_projection_extrapolates_growth() {
+        let counts = LevelCounts {
+            counts: vec![10, 100],
+        };
+        // 10 + 100 + 1000
+        assert_eq!(counts.project(3), 1110);
+    }
+
+    #[test]
+    fn test_estimate_uses_recorded_stats() {
+        let stats = NodeStats::new();
+        stats.record("ABC123", &page(3, 4), 2).unwrap();
+
+        assert_eq!(stats.estimate("ABC123", "1:0", 2).unwrap(), Some(15));
+        assert_eq!(stats.estimate("ABC123", "9:9", 2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_shallower_fetch_keeps_deeper_stats() {
+        let stats = NodeStats::new();
+        stats.record("ABC123", &page(3, 4), 2).unwrap();
+        stats.record("ABC123", &page(3, 4), 1).unwrap();
+
+        assert_eq!(stats.estimate("ABC123", "1:0", 2).unwrap(), Some(15));
+    }
+}
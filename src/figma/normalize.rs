@@ -0,0 +1,143 @@
+use serde_json::Value;
+
+/// Array properties whose element order carries no meaning, paired with the
+/// field used to order their elements. Figma returns these in whatever order
+/// its backend produced them, which would otherwise show up as spurious diffs.
+const UNORDERED_ARRAYS: &[(&str, &str)] = &[
+    ("flowStartingPoints", "nodeId"),
+    ("branches", "key"),
+    ("componentPropertyReferences", "name"),
+];
+
+/// Fields that change on every save or request without the design changing:
+/// edit timestamps, version IDs and signed, expiring thumbnail URLs.
+const VOLATILE_FIELDS: &[&str] = &[
+    "lastModified",
+    "version",
+    "thumbnailUrl",
+    "created_at",
+    "updated_at",
+    "thumbnail_url",
+];
+
+/// Rewrites a Figma response into canonical form: object keys sorted and
+/// semantically unordered arrays sorted by their identifying field. Ordered
+/// data such as `children` (z-order) and `fills` (paint stack) is left intact.
+pub fn canonicalize(value: &mut Value) {
+    sort_unordered_arrays(value);
+    value.sort_all_objects();
+}
+
+/// Removes volatile fields at any level, for deterministic output that only
+/// changes when the design does.
+pub fn strip_volatile(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !VOLATILE_FIELDS.contains(&key.as_str()));
+            map.values_mut().for_each(strip_volatile);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_volatile),
+        _ => {}
+    }
+}
+
+fn sort_unordered_arrays(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if let Some((_, sort_field)) = UNORDERED_ARRAYS.iter().find(|(k, _)| k == key) {
+                    if let Value::Array(items) = child {
+                        items.sort_by_key(|item| sort_key(item, sort_field));
+                    }
+                }
+                sort_unordered_arrays(child);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sort_unordered_arrays),
+        _ => {}
+    }
+}
+
+fn sort_key(value: &Value, field: &str) -> String {
+    match value.get(field) {
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonicalize_sorts_unordered_arrays() {
+        let mut value = json!({
+            "flowStartingPoints": [
+                { "nodeId": "2:1", "name": "Checkout" },
+                { "nodeId": "1:1", "name": "Onboarding" }
+            ]
+        });
+
+        canonicalize(&mut value);
+
+        assert_eq!(value["flowStartingPoints"][0]["nodeId"], "1:1");
+        assert_eq!(value["flowStartingPoints"][1]["nodeId"], "2:1");
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_children_order() {
+        let mut value = json!({
+            "children": [{ "id": "3:1" }, { "id": "1:1" }, { "id": "2:1" }]
+        });
+
+        canonicalize(&mut value);
+
+        let ids: Vec<&str> = value["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|child| child["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["3:1", "1:1", "2:1"]);
+    }
+
+    #[test]
+    fn test_strip_volatile() {
+        let mut value = json!({
+            "name": "File",
+            "lastModified": "2024-01-01T00:00:00Z",
+            "version": "123",
+            "thumbnailUrl": "https://s3.example.com/thumb.png?signature=abc",
+            "components": { "1:2": { "key": "abc", "updated_at": "2024-01-01" } }
+        });
+
+        strip_volatile(&mut value);
+
+        assert_eq!(
+            value,
+            json!({ "name": "File", "components": { "1:2": { "key": "abc" } } })
+        );
+    }
+
+    #[test]
+    fn test_canonical_output_is_independent_of_input_order() {
+        let mut first: Value = serde_json::from_str(
+            r#"{"name":"File","branches":[{"key":"b"},{"key":"a"}],"document":{"type":"DOCUMENT","id":"0:0"}}"#,
+        )
+        .unwrap();
+        let mut second: Value = serde_json::from_str(
+            r#"{"document":{"id":"0:0","type":"DOCUMENT"},"branches":[{"key":"a"},{"key":"b"}],"name":"File"}"#,
+        )
+        .unwrap();
+
+        canonicalize(&mut first);
+        canonicalize(&mut second);
+
+        assert_eq!(
+            serde_json::to_string(&first).unwrap(),
+            serde_json::to_string(&second).unwrap()
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Figma OAuth2, for organizations that do not allow personal access
+//! tokens. The authorization-code flow runs once with a local redirect
+//! listener; the tokens it yields are kept in a file, encrypted when a key
+//! is configured, and access tokens are refreshed before they expire.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::encryption::{self, AtRestKey};
+use crate::{credentials::config_dir, error::ApiError, Error, Result};
+
+const AUTHORIZE_URL: &str = "https://www.figma.com/oauth";
+const TOKEN_URL: &str = "https://api.figma.com/v1/oauth/token";
+const REFRESH_URL: &str = "https://api.figma.com/v1/oauth/refresh";
+
+/// Scopes requested unless configured: read access to everything the
+/// read-only tools use, without the scopes that need an Enterprise plan.
+pub const DEFAULT_SCOPES: &str = "current_user:read,file_content:read,file_metadata:read,\
+file_comments:read,file_versions:read,file_dev_resources:read,library_assets:read,\
+library_content:read,team_library_content:read,projects:read";
+
+/// Port of the redirect listener unless configured. The redirect URL
+/// registered for the OAuth app must match it.
+pub const DEFAULT_REDIRECT_PORT: u16 = 3057;
+
+/// Access tokens are refreshed when they expire within this time.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Time the user gets to approve access in the browser.
+const AUTHORIZE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// The OAuth app and where its tokens are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Comma-separated scopes to request
+    pub scopes: String,
+    pub redirect_port: u16,
+    /// File holding the refresh token between runs
+    pub token_file: PathBuf,
+    /// Key the token file is encrypted with; unset writes it in plaintext
+    pub encryption: Option<AtRestKey>,
+}
+
+impl OAuthConfig {
+    /// The redirect URL to register for the OAuth app.
+    pub fn redirect_uri(&self) -> String {
+        format!("http://localhost:{}/callback", self.redirect_port)
+    }
+
+    /// The default token file: `figma-mcp/oauth.json` in the user's
+    /// configuration directory.
+    pub fn default_token_file() -> Option<PathBuf> {
+        Some(config_dir()?.join("figma-mcp").join("oauth.json"))
+    }
+}
+
+/// Tokens as stored in the token file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix time in seconds the access token expires at
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    /// Only sent by the code exchange
+    refresh_token: Option<String>,
+    expires_in: u64,
+}
+
+impl StoredTokens {
+    /// Reads the tokens, decrypting them with `key`. A plaintext file is
+    /// read either way, so setting a key keeps earlier logins.
+    pub fn load(path: &Path, key: Option<&AtRestKey>) -> Result<Option<Self>> {
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(Error::Auth(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let content = match key {
+            _ if !encryption::is_encrypted(&content) => content,
+            Some(key) => encryption::decrypt(key, &content)
+                .map_err(|e| Error::Auth(format!("{}: {}", path.display(), e)))?,
+            None => {
+                return Err(Error::Auth(format!(
+                    "{} is encrypted; set FIGMA_MCP_ENCRYPTION_KEY or \
+                     FIGMA_MCP_ENCRYPTION_KEYCHAIN to read it",
+                    path.display()
+                )))
+            }
+        };
+
+        serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|e| Error::Auth(format!("Invalid {}: {}", path.display(), e)))
+    }
+
+    /// Writes the tokens, readable only by the current user and encrypted
+    /// with `key` if given.
+    pub fn save(&self, path: &Path, key: Option<&AtRestKey>) -> Result<()> {
+        let mut content = serde_json::to_vec_pretty(self)?;
+        if let Some(key) = key {
+            content = encryption::encrypt(key, &content)?;
+        }
+
+        let write = || -> std::io::Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            std::io::Write::write_all(&mut options.open(path)?, &content)
+        };
+
+        write().map_err(|e| Error::Auth(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    /// Whether the access token expires within `margin` of `now` (Unix
+    /// seconds).
+    pub fn expires_within(&self, margin: Duration, now: u64) -> bool {
+        self.expires_at <= now + margin.as_secs()
+    }
+
+    fn from_response(response: TokenResponse, refresh_token: Option<String>) -> Result<Self> {
+        let refresh_token = response
+            .refresh_token
+            .or(refresh_token)
+            .ok_or_else(|| Error::Auth("Figma returned no refresh token".to_string()))?;
+
+        Ok(Self {
+            access_token: response.access_token,
+            refresh_token,
+            expires_at: unix_now() + response.expires_in,
+        })
+    }
+}
+
+/// The tokens of an authorized OAuth app, refreshed as needed. Clones share
+/// the same tokens.
+#[derive(Debug, Clone)]
+pub struct OAuthSession {
+    config: OAuthConfig,
+    http: Client,
+    tokens: Arc<Mutex<StoredTokens>>,
+}
+
+impl OAuthSession {
+    pub fn new(config: OAuthConfig, tokens: StoredTokens) -> Self {
+        Self {
+            config,
+            http: Client::new(),
+            tokens: Arc::new(Mutex::new(tokens)),
+        }
+    }
+
+    /// Resumes the session stored in the token file, if there is one,
+    /// encrypting a plaintext file once a key is configured.
+    pub fn load(config: &OAuthConfig) -> Result<Option<Self>> {
+        let key = config.encryption.as_ref();
+        let tokens = StoredTokens::load(&config.token_file, key)?;
+        if let (Some(tokens), Some(key)) = (&tokens, key) {
+            let stored = std::fs::read(&config.token_file).unwrap_or_default();
+            if !encryption::is_encrypted(&stored) {
+                tokens.save(&config.token_file, Some(key))?;
+                tracing::info!("Encrypted {}", config.token_file.display());
+            }
+        }
+
+        Ok(tokens.map(|tokens| Self::new(config.clone(), tokens)))
+    }
+
+    /// An access token valid for a while, refreshed first if it is about
+    /// to expire.
+    pub async fn access_token(&self) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.expires_within(REFRESH_MARGIN, unix_now()) {
+            *tokens = self.refresh(&tokens.refresh_token).await?;
+        }
+
+        Ok(tokens.access_token.clone())
+    }
+
+    /// Refreshes the access token even though it has not expired, after
+    /// Figma rejected it. `rejected` is the token that was rejected, so
+    /// requests failing at the same time refresh only once.
+    pub async fn refresh_rejected(&self, rejected: &str) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.access_token == rejected {
+            *tokens = self.refresh(&tokens.refresh_token).await?;
+        }
+
+        Ok(tokens.access_token.clone())
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<StoredTokens> {
+        tracing::info!("Refreshing the Figma OAuth access token");
+        let request = self
+            .http
+            .post(REFRESH_URL)
+            .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
+            .form(&[("refresh_token", refresh_token)]);
+        let response = token_request(request).await.map_err(|e| {
+            Error::Auth(format!(
+                "Failed to refresh the Figma access token ({}). Authorize again with \
+                 `figma-mcp --oauth-login`",
+                e
+            ))
+        })?;
+
+        let tokens = StoredTokens::from_response(response, Some(refresh_token.to_string()))?;
+        tokens.save(&self.config.token_file, self.config.encryption.as_ref())?;
+
+        Ok(tokens)
+    }
+}
+
+/// A running authorization-code flow: the user opens [`Authorization::url`]
+/// and Figma redirects the browser to the local listener with a code.
+pub struct Authorization {
+    config: OAuthConfig,
+    listener: TcpListener,
+    state: String,
+    pub url: String,
+}
+
+impl Authorization {
+    /// Starts listening for the redirect.
+    pub async fn start(config: &OAuthConfig) -> Result<Self> {
+        let addr = format!("127.0.0.1:{}", config.redirect_port);
+        let listener = TcpListener::bind(&addr)
+            .await
+            .map_err(|e| Error::Auth(format!("Failed to listen on {}: {}", addr, e)))?;
+        let state = random_state();
+
+        let mut url = Url::parse(AUTHORIZE_URL)?;
+        url.query_pairs_mut()
+            .append_pair("client_id", &config.client_id)
+            .append_pair("redirect_uri", &config.redirect_uri())
+            .append_pair("scope", &config.scopes)
+            .append_pair("state", &state)
+            .append_pair("response_type", "code");
+
+        Ok(Self {
+            config: config.clone(),
+            listener,
+            state,
+            url: url.to_string(),
+        })
+    }
+
+    /// Waits for the redirect, exchanges its code for tokens and stores
+    /// them in the token file.
+    pub async fn finish(self) -> Result<OAuthSession> {
+        let code = tokio::time::timeout(AUTHORIZE_TIMEOUT, self.receive_code())
+            .await
+            .map_err(|_| Error::Auth("Timed out waiting for the authorization".to_string()))??;
+
+        let http = Client::new();
+        let redirect_uri = self.config.redirect_uri();
+        let request = http
+            .post(TOKEN_URL)
+            .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
+            .form(&[
+                ("redirect_uri", redirect_uri.as_str()),
+                ("code", code.as_str()),
+                ("grant_type", "authorization_code"),
+            ]);
+        let response = token_request(request).await.map_err(|e| {
+            Error::Auth(format!("Failed to exchange the authorization code: {}", e))
+        })?;
+
+        let tokens = StoredTokens::from_response(response, None)?;
+        tokens.save(&self.config.token_file, self.config.encryption.as_ref())?;
+        tracing::info!(
+            "Stored the Figma OAuth tokens in {}",
+            self.config.token_file.display()
+        );
+
+        Ok(OAuthSession::new(self.config, tokens))
+    }
+
+    /// Answers requests to the listener until one is the redirect with a
+    /// code for this flow.
+    async fn receive_code(&self) -> Result<String> {
+        loop {
+            let (mut stream, _) = self
+                .listener
+                .accept()
+                .await
+                .map_err(|e| Error::Auth(format!("Failed to accept the redirect: {}", e)))?;
+
+            let mut line = String::new();
+            let mut reader = BufReader::new(&mut stream);
+            if reader.read_line(&mut line).await.is_err() {
+                continue;
+            }
+
+            let outcome = redirect_code(&line, &self.state);
+            let message = match &outcome {
+                Ok(_) => "Figma access was granted. You can close this window.",
+                Err(RedirectError::Ignored) => "Not found.",
+                Err(RedirectError::Denied(_)) => "Figma access was not granted.",
+            };
+            let status = match outcome {
+                Err(RedirectError::Ignored) => "404 Not Found",
+                _ => "200 OK",
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                message.len(),
+                message
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+
+            match outcome {
+                Ok(code) => return Ok(code),
+                Err(RedirectError::Denied(reason)) => {
+                    return Err(Error::Auth(format!("Authorization failed: {}", reason)))
+                }
+                // Browsers also ask for favicons
+                Err(RedirectError::Ignored) => continue,
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum RedirectError {
+    /// Not the redirect, such as a favicon request
+    Ignored,
+    /// The redirect, without a usable code
+    Denied(String),
+}
+
+/// Reads the code from the request line of the redirect, checking its
+/// state against the flow's.
+fn redirect_code(request_line: &str, state: &str) -> std::result::Result<String, RedirectError> {
+    let target = request_line
+        .strip_prefix("GET ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .ok_or(RedirectError::Ignored)?;
+    let url =
+        Url::parse(&format!("http://localhost{}", target)).map_err(|_| RedirectError::Ignored)?;
+    if url.path() != "/callback" {
+        return Err(RedirectError::Ignored);
+    }
+
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    if param("state").as_deref() != Some(state) {
+        return Err(RedirectError::Denied(
+            "the state does not match this authorization".to_string(),
+        ));
+    }
+    if let Some(error) = param("error") {
+        return Err(RedirectError::Denied(error));
+    }
+
+    param("code")
+        .filter(|code| !code.is_empty())
+        .ok_or_else(|| RedirectError::Denied("the redirect carries no code".to_string()))
+}
+
+async fn token_request(request: reqwest::RequestBuilder) -> Result<TokenResponse> {
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.bytes().await?;
+    if !status.is_success() {
+        return Err(Error::Api(ApiError::from_body(status.as_u16(), &body)));
+    }
+
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// An unguessable value tying the redirect to the flow that started it.
+fn random_state() -> String {
+    (0..2)
+        .map(|_| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos(),
+            );
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_code() {
+        let line = "GET /callback?code=abc&state=s1 HTTP/1.1\r\n";
+        assert_eq!(redirect_code(line, "s1"), Ok("abc".to_string()));
+        assert!(matches!(
+            redirect_code(line, "s2"),
+            Err(RedirectError::Denied(_))
+        ));
+        assert_eq!(
+            redirect_code("GET /callback?error=access_denied&state=s1 HTTP/1.1", "s1"),
+            Err(RedirectError::Denied("access_denied".to_string()))
+        );
+        assert_eq!(
+            redirect_code("GET /favicon.ico HTTP/1.1", "s1"),
+            Err(RedirectError::Ignored)
+        );
+    }
+
+    #[test]
+    fn test_stored_tokens_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("figma-mcp-oauth-{}", random_state()))
+            .join("oauth.json");
+        assert_eq!(StoredTokens::load(&path, None).unwrap(), None);
+
+        let tokens = StoredTokens {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_at: 1_000,
+        };
+        tokens.save(&path, None).unwrap();
+        assert_eq!(StoredTokens::load(&path, None).unwrap(), Some(tokens.clone()));
+        assert!(tokens.expires_within(REFRESH_MARGIN, 800));
+        assert!(!tokens.expires_within(REFRESH_MARGIN, 600));
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_token_file() {
+        let path = std::env::temp_dir()
+            .join(format!("figma-mcp-oauth-{}", random_state()))
+            .join("oauth.json");
+        let tokens = StoredTokens {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_at: 1_000,
+        };
+        let config = OAuthConfig {
+            client_id: "id".to_string(),
+            client_secret: "secret".to_string(),
+            scopes: DEFAULT_SCOPES.to_string(),
+            redirect_port: DEFAULT_REDIRECT_PORT,
+            token_file: path.clone(),
+            encryption: Some(AtRestKey::generate()),
+        };
+        let key = config.encryption.as_ref();
+
+        // A plaintext file from before the key was set is encrypted on load
+        tokens.save(&path, None).unwrap();
+        assert!(OAuthSession::load(&config).unwrap().is_some());
+        let stored = std::fs::read(&path).unwrap();
+        assert!(encryption::is_encrypted(&stored));
+        assert!(!String::from_utf8_lossy(&stored).contains("refresh"));
+        assert_eq!(StoredTokens::load(&path, key).unwrap(), Some(tokens.clone()));
+
+        assert!(StoredTokens::load(&path, None).is_err());
+        assert!(StoredTokens::load(&path, Some(&AtRestKey::generate())).is_err());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+//! Restricting file fetches to pages picked by name, and leaving archived
+//! and scratch pages and frames out of what agents see.
+
+use serde_json::{Map, Value};
+
+/// Names of archived and scratch pages and frames, excluded when
+/// `FIGMA_MCP_EXCLUDE_NAMES` is `true`.
+pub const ARCHIVE_PATTERNS: &[&str] = &[
+    "*🗑*",
+    "*archive*",
+    "old",
+    "old *",
+    "* old",
+    "*(old)*",
+    "*[old]*",
+    "*deprecated*",
+    "*do not use*",
+    "scratch*",
+    "*scratchpad*",
+];
+
+/// Page or frame name patterns: exact names or globs with `*` and `?`,
+/// matched without regard to case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameFilter {
+    patterns: Vec<String>,
+}
+
+impl NameFilter {
+    /// Parses comma-separated patterns. Returns `None` if there are none.
+    pub fn parse(value: &str) -> Option<Self> {
+        let patterns: Vec<String> = value
+            .split(',')
+            .map(|pattern| pattern.trim().to_lowercase())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+
+        (!patterns.is_empty()).then_some(Self { patterns })
+    }
+
+    /// The [`ARCHIVE_PATTERNS`] heuristics.
+    pub fn archive() -> Self {
+        Self {
+            patterns: ARCHIVE_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let name: Vec<char> = name.trim().to_lowercase().chars().collect();
+
+        self.patterns.iter().any(|pattern| {
+            let pattern: Vec<char> = pattern.chars().collect();
+            glob_matches(&pattern, &name)
+        })
+    }
+
+    /// The IDs of the pages in a depth 1 file outline whose names match.
+    pub fn select(&self, outline: &Value) -> Vec<String> {
+        pages(outline)
+            .filter(|page| self.matches(string_field(page, "name")))
+            .map(|page| string_field(page, "id").to_string())
+            .collect()
+    }
+
+    /// Describes the patterns and the pages available, for when none
+    /// matched.
+    pub fn describe_miss(&self, outline: &Value) -> String {
+        let names: Vec<&str> = pages(outline)
+            .map(|page| string_field(page, "name"))
+            .collect();
+
+        format!(
+            "no page matches {}; the file has {}",
+            self.patterns.join(", "),
+            names.join(", ")
+        )
+    }
+}
+
+/// Replaces the pages of a file outline with the page nodes of a
+/// `GET /files/:key/nodes` response, in outline order, and merges the
+/// components and styles they use into the file's maps.
+pub fn splice(outline: &mut Value, nodes: &Value, page_ids: &[String]) {
+    let mut children = Vec::new();
+    for page_id in page_ids {
+        let Some(entry) = nodes.pointer(&format!("/nodes/{}", page_id)) else {
+            continue;
+        };
+        if let Some(document) = entry.get("document") {
+            children.push(document.clone());
+        }
+        for key in ["components", "componentSets", "styles"] {
+            let Some(entries) = entry.get(key).and_then(Value::as_object) else {
+                continue;
+            };
+            let merged = outline
+                .as_object_mut()
+                .map(|file| file.entry(key).or_insert_with(|| Value::Object(Map::new())));
+            if let Some(Value::Object(merged)) = merged {
+                merged.extend(entries.clone());
+            }
+        }
+    }
+
+    if let Some(document) = outline.get_mut("document") {
+        document["children"] = Value::Array(children);
+    }
+}
+
+/// Removes pages, and the frames and sections at the top of pages, whose
+/// names match `filter` from a node tree or a Figma response. Layers deeper
+/// down are kept, so a button labelled "Old price" stays.
+pub fn strip_excluded(value: &mut Value, filter: &NameFilter) {
+    match value {
+        Value::Object(map) => {
+            let node_type = map.get("type").and_then(Value::as_str);
+            if matches!(node_type, Some("DOCUMENT" | "CANVAS")) {
+                if let Some(Value::Array(children)) = map.get_mut("children") {
+                    children.retain(|child| !filter.matches(string_field(child, "name")));
+                }
+            }
+            map.values_mut()
+                .for_each(|value| strip_excluded(value, filter));
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|value| strip_excluded(value, filter)),
+        _ => {}
+    }
+}
+
+/// Keeps only the listed pages of a file outline.
+pub fn retain(outline: &mut Value, page_ids: &[String]) {
+    if let Some(Value::Array(pages)) = outline.pointer_mut("/document/children") {
+        pages.retain(|page| page_ids.iter().any(|id| id == string_field(page, "id")));
+    }
+}
+
+fn pages(outline: &Value) -> impl Iterator<Item = &Value> {
+    outline
+        .pointer("/document/children")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn string_field<'a>(node: &'a Value, key: &str) -> &'a str {
+    node.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// Matches `*` (any run of characters) and `?` (one character).
+fn glob_matches(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            glob_matches(&pattern[1..], name)
+                || (!name.is_empty() && glob_matches(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => glob_matches(&pattern[1..], &name[1..]),
+        (Some(a), Some(b)) if a == b => glob_matches(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn outline() -> Value {
+        json!({
+            "name": "App",
+            "document": {
+                "id": "0:0",
+                "children": [
+                    { "id": "1:0", "name": "Checkout", "type": "CANVAS" },
+                    { "id": "2:0", "name": "Checkout v2", "type": "CANVAS" },
+                    { "id": "3:0", "name": "Archive / 2023", "type": "CANVAS" }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn test_filter_matches_exact_and_glob() {
+        let filter = NameFilter::parse("checkout, Archive*").unwrap();
+
+        assert_eq!(filter.select(&outline()), vec!["1:0", "3:0"]);
+        assert!(NameFilter::parse("Checkout ?2")
+            .unwrap()
+            .matches("Checkout v2"));
+        assert!(NameFilter::parse(" , ").is_none());
+    }
+
+    #[test]
+    fn test_strip_excluded_pages_and_top_level_frames() {
+        let mut file = json!({
+            "document": {
+                "type": "DOCUMENT",
+                "children": [
+                    { "type": "CANVAS", "name": "🗑 Trash", "children": [] },
+                    {
+                        "type": "CANVAS",
+                        "name": "Checkout",
+                        "children": [
+                            { "type": "FRAME", "name": "Cart (old)", "children": [] },
+                            {
+                                "type": "FRAME",
+                                "name": "Cart",
+                                "children": [{ "type": "TEXT", "name": "Old price" }]
+                            }
+                        ]
+                    }
+                ]
+            }
+        });
+
+        strip_excluded(&mut file, &NameFilter::archive());
+
+        let pages = file["document"]["children"].as_array().unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0]["children"].as_array().unwrap().len(), 1);
+        assert_eq!(pages[0]["children"][0]["children"][0]["name"], "Old price");
+    }
+
+    #[test]
+    fn test_splice_replaces_pages() {
+        let mut file = outline();
+        let nodes = json!({
+            "nodes": {
+                "2:0": {
+                    "document": { "id": "2:0", "name": "Checkout v2", "children": [] },
+                    "components": { "5:1": { "name": "Button" } }
+                }
+            }
+        });
+
+        splice(&mut file, &nodes, &["2:0".to_string()]);
+
+        assert_eq!(file["document"]["children"].as_array().unwrap().len(), 1);
+        assert_eq!(file["document"]["children"][0]["children"], json!([]));
+        assert_eq!(file["components"]["5:1"]["name"], "Button");
+    }
+}
@@ -0,0 +1,93 @@
+//! The projects of a team and the files in a project, so agents can find
+//! file keys starting from a team ID.
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectFile {
+    /// The file key other tools take
+    pub key: String,
+    pub name: String,
+    pub last_modified: String,
+}
+
+/// Reads a `GET /teams/:id/projects` response, in Figma's order.
+pub fn projects(response: &Value) -> Vec<Project> {
+    let projects = response.get("projects").and_then(Value::as_array);
+
+    projects
+        .into_iter()
+        .flatten()
+        .map(|project| Project {
+            // Figma sends project IDs as strings, but has used numbers
+            id: match project.get("id") {
+                Some(Value::Number(id)) => id.to_string(),
+                id => id.and_then(Value::as_str).unwrap_or_default().to_string(),
+            },
+            name: field(project, "name"),
+        })
+        .collect()
+}
+
+/// Reads a `GET /projects/:id/files` response, most recently modified
+/// first.
+pub fn files(response: &Value) -> Vec<ProjectFile> {
+    let files = response.get("files").and_then(Value::as_array);
+
+    let mut files: Vec<ProjectFile> = files
+        .into_iter()
+        .flatten()
+        .map(|file| ProjectFile {
+            key: field(file, "key"),
+            name: field(file, "name"),
+            last_modified: field(file, "last_modified"),
+        })
+        .collect();
+    // Timestamps are ISO 8601, so they sort as strings
+    files.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+
+    files
+}
+
+fn field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_projects_and_files() {
+        let response = json!({
+            "name": "Design",
+            "projects": [{ "id": "101", "name": "App" }, { "id": 102, "name": "Web" }]
+        });
+        let projects = projects(&response);
+        assert_eq!(projects[1].id, "102");
+        assert_eq!(projects[0].name, "App");
+
+        let response = json!({
+            "name": "App",
+            "files": [
+                { "key": "a", "name": "Onboarding", "last_modified": "2024-01-02T10:00:00Z" },
+                { "key": "b", "name": "Checkout", "last_modified": "2024-03-01T10:00:00Z" }
+            ]
+        });
+        let files = files(&response);
+        assert_eq!(files[0].key, "b");
+        assert_eq!(files[1].name, "Onboarding");
+    }
+}
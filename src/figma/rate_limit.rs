@@ -0,0 +1,123 @@
+//! Client-side rate limiting, so bursts of requests from an agent are
+//! spread out before Figma starts refusing them with 429s.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Requests per minute when no budget is configured.
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 60;
+
+/// Share of the per-minute budget that may be spent at once.
+const BURST_SECONDS: f64 = 10.0;
+
+/// A token bucket refilled at the configured rate. Clones share the bucket,
+/// so all requests made with one token draw from the same budget.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    requests_per_minute: u32,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    /// Tokens added per second
+    rate: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled = now;
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+    }
+}
+
+impl RateLimiter {
+    /// A limiter allowing `requests_per_minute` on average, in bursts of up
+    /// to ten seconds' worth. Starts with a full burst available.
+    pub fn new(requests_per_minute: u32) -> Self {
+        let rate = requests_per_minute.max(1) as f64 / 60.0;
+        let capacity = (rate * BURST_SECONDS).max(1.0);
+
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: capacity,
+                capacity,
+                rate,
+                refilled: Instant::now(),
+            })),
+            requests_per_minute,
+        }
+    }
+
+    /// A limiter with the same budget but a bucket of its own, for
+    /// requests made with another token.
+    pub fn fresh(&self) -> Self {
+        Self::new(self.requests_per_minute)
+    }
+
+    /// Waits until the budget allows another request.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = match self.bucket.lock() {
+                Ok(mut bucket) => bucket.take(Instant::now()),
+                // A panic while holding the lock leaves the budget unknown;
+                // don't block requests on it
+                Err(_) => None,
+            };
+            match wait {
+                Some(wait) => {
+                    tracing::debug!("Rate limit reached, waiting {:?}", wait);
+                    tokio::time::sleep(wait).await;
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_at_rate() {
+        let limiter = RateLimiter::new(60);
+        let mut bucket = limiter.bucket.lock().unwrap();
+        let start = bucket.refilled;
+
+        // A burst of ten seconds' worth, then one per second
+        for _ in 0..10 {
+            assert_eq!(bucket.take(start), None);
+        }
+        let wait = bucket.take(start).unwrap();
+        assert!((wait.as_secs_f64() - 1.0).abs() < 1e-6);
+        assert_eq!(bucket.take(start + Duration::from_secs(1)), None);
+        assert!(bucket.take(start + Duration::from_secs(1)).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_budget() {
+        // Ten requests per second, with the burst spent
+        let limiter = RateLimiter::new(600);
+        limiter.bucket.lock().unwrap().tokens = 0.0;
+        let start = Instant::now();
+
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
@@ -0,0 +1,249 @@
+//! Parsed file and node responses, reused while the file is unchanged, so
+//! repeated reads of a large file skip both the download and the parsing.
+//!
+//! Within the TTL a response is reused as is. After it, a cheap metadata
+//! read tells whether the file was modified since; if not, the response is
+//! trusted for another TTL.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use super::freshness::FileMeta;
+use crate::{Error, Result};
+
+/// How long a response is reused without asking Figma, when the server
+/// does not configure another TTL.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// Responses kept at most; the least recently checked are dropped first.
+const MAX_ENTRIES: usize = 32;
+
+/// What a response was read for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResponseKey {
+    pub file_key: String,
+    /// Requested nodes, `None` for the whole file
+    pub node_ids: Option<Vec<String>>,
+    pub depth: Option<u32>,
+    /// Version from the history, `None` for the file's current state
+    pub version: Option<String>,
+}
+
+/// A cached response, if any, and whether it can be used without asking
+/// Figma.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+    Fresh(Value),
+    /// Kept past its TTL, to be revalidated
+    Expired,
+    Missing,
+}
+
+/// Responses by request. Clones share the responses.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    entries: Arc<RwLock<HashMap<ResponseKey, Entry>>>,
+    ttl: Duration,
+}
+
+#[derive(Debug)]
+struct Entry {
+    response: Arc<Value>,
+    meta: FileMeta,
+    checked: Instant,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    /// An empty cache with the same TTL, for responses read with other
+    /// credentials.
+    pub fn fresh(&self) -> Self {
+        Self::new(self.ttl)
+    }
+
+    /// The response read for `key`. Versions from the history never
+    /// change, so their responses don't expire.
+    pub fn get(&self, key: &ResponseKey) -> Lookup {
+        let Ok(entries) = self.entries.read() else {
+            return Lookup::Missing;
+        };
+        match entries.get(key) {
+            Some(entry) if key.version.is_some() || entry.checked.elapsed() < self.ttl => {
+                Lookup::Fresh(Value::clone(&entry.response))
+            }
+            Some(_) => Lookup::Expired,
+            None => Lookup::Missing,
+        }
+    }
+
+    /// Keeps a `GET /files/:key` or `GET /files/:key/nodes` response, with
+    /// the file version it reports.
+    pub fn insert(&self, key: ResponseKey, response: &Value) {
+        let Ok(mut entries) = self.entries.write() else {
+            return;
+        };
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.checked)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        let entry = Entry {
+            response: Arc::new(response.clone()),
+            meta: FileMeta::from_file(response),
+            checked: Instant::now(),
+        };
+        entries.insert(key, entry);
+    }
+
+    /// Compares the responses of a file with its `current` metadata: the
+    /// ones read since its last modification are trusted for another TTL,
+    /// the others dropped.
+    pub fn revalidate(&self, file_key: &str, current: &FileMeta) {
+        let Ok(mut entries) = self.entries.write() else {
+            return;
+        };
+        entries.retain(|key, entry| {
+            if key.file_key != file_key || key.version.is_some() {
+                return true;
+            }
+            let unchanged = is_unchanged(&entry.meta, current);
+            if unchanged {
+                entry.checked = Instant::now();
+            }
+            unchanged
+        });
+    }
+
+    /// Drops the responses of a file, e.g. after a webhook reported a
+    /// change. Returns how many were kept.
+    pub fn invalidate_file(&self, file_key: &str) -> Result<usize> {
+        let mut entries = self.write()?;
+        let before = entries.len();
+        entries.retain(|key, _| key.file_key != file_key);
+
+        Ok(before - entries.len())
+    }
+
+    /// Drops all responses. Returns how many were kept.
+    pub fn clear(&self) -> Result<usize> {
+        let mut entries = self.write()?;
+        let count = entries.len();
+        entries.clear();
+
+        Ok(count)
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, HashMap<ResponseKey, Entry>>> {
+        self.entries
+            .write()
+            .map_err(|_| Error::Internal("Response cache lock poisoned".to_string()))
+    }
+}
+
+/// Whether the file was not modified since the `cached` response was
+/// read. Unknown modification times count as modified.
+fn is_unchanged(cached: &FileMeta, current: &FileMeta) -> bool {
+    if cached.last_modified.is_empty() || current.last_modified.is_empty() {
+        return false;
+    }
+    // Versions are only created now and then, but never without a change
+    let same_version = cached.version.is_empty()
+        || current.version.is_empty()
+        || cached.version == current.version;
+
+    same_version && cached.last_modified == current.last_modified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(file_key: &str, version: Option<&str>) -> ResponseKey {
+        ResponseKey {
+            file_key: file_key.to_string(),
+            node_ids: None,
+            depth: Some(1),
+            version: version.map(str::to_string),
+        }
+    }
+
+    fn file(last_modified: &str) -> Value {
+        json!({ "name": "App", "version": "42", "lastModified": last_modified })
+    }
+
+    fn meta(last_modified: &str) -> FileMeta {
+        FileMeta {
+            version: "42".to_string(),
+            last_modified: last_modified.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_expired_responses_are_revalidated() {
+        let cache = ResponseCache::new(Duration::ZERO);
+        cache.insert(key("ABC", None), &file("2026-01-01T10:00:00Z"));
+        cache.insert(key("XYZ", None), &file("2026-01-01T10:00:00Z"));
+        assert_eq!(cache.get(&key("ABC", None)), Lookup::Expired);
+
+        // Still the same file, trusted again
+        let cache = ResponseCache {
+            ttl: Duration::from_secs(60),
+            ..cache
+        };
+        cache.revalidate("ABC", &meta("2026-01-01T10:00:00Z"));
+        assert_eq!(
+            cache.get(&key("ABC", None)),
+            Lookup::Fresh(file("2026-01-01T10:00:00Z"))
+        );
+
+        // Modified since
+        cache.revalidate("ABC", &meta("2026-01-02T09:30:00Z"));
+        assert_eq!(cache.get(&key("ABC", None)), Lookup::Missing);
+        assert_eq!(cache.clear().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_history_versions_never_expire() {
+        let cache = ResponseCache::new(Duration::ZERO);
+        cache.insert(key("ABC", Some("7")), &file("2025-06-01T08:00:00Z"));
+
+        cache.revalidate("ABC", &meta("2026-01-02T09:30:00Z"));
+
+        assert!(matches!(
+            cache.get(&key("ABC", Some("7"))),
+            Lookup::Fresh(_)
+        ));
+        assert_eq!(cache.get(&key("ABC", None)), Lookup::Missing);
+        assert_eq!(cache.invalidate_file("ABC").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_checked_are_dropped() {
+        let cache = ResponseCache::new(Duration::from_secs(60));
+        for index in 0..=MAX_ENTRIES {
+            cache.insert(
+                key(&format!("F{}", index), None),
+                &file("2026-01-01T10:00:00Z"),
+            );
+        }
+
+        assert_eq!(cache.get(&key("F0", None)), Lookup::Missing);
+        assert!(matches!(cache.get(&key("F1", None)), Lookup::Fresh(_)));
+        assert_eq!(cache.clear().unwrap(), MAX_ENTRIES);
+    }
+}
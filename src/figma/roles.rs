@@ -0,0 +1,593 @@
+//! Heuristics labelling frames with the UI role they play, so generated
+//! code can use matching elements instead of nested `div`s. Teams can tune
+//! them with a rules file, see [`RoleRules`].
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::layers::is_hidden;
+use crate::{Error, Result};
+
+/// Roles accepted by [`Role::parse`], for error messages.
+pub const ROLE_NAMES: &str = "header, nav, button, card, list, input or modal";
+
+/// Layer name words that mark a role, checked in this order. Names are the
+/// strongest signal, since designers usually name what they draw.
+const DEFAULT_KEYWORDS: &[(Role, &[&str])] = &[
+    (
+        Role::Modal,
+        &["modal", "dialog", "popup", "popover", "sheet"],
+    ),
+    (Role::Header, &["header", "topbar", "appbar", "masthead"]),
+    (
+        Role::Nav,
+        &[
+            "nav",
+            "navbar",
+            "navigation",
+            "menu",
+            "tabbar",
+            "sidebar",
+            "breadcrumbs",
+        ],
+    ),
+    (
+        Role::Input,
+        &[
+            "input",
+            "textfield",
+            "textbox",
+            "textarea",
+            "field",
+            "search",
+        ],
+    ),
+    (Role::Button, &["button", "btn", "cta"]),
+    (Role::Card, &["card", "tile"]),
+    (Role::List, &["list", "feed", "items"]),
+];
+
+/// Container node types roles are inferred for.
+const CONTAINER_TYPES: &[&str] = &["FRAME", "GROUP", "COMPONENT", "INSTANCE", "COMPONENT_SET"];
+
+const DEFAULT_MAX_CONTROL_HEIGHT: f64 = 64.0;
+const DEFAULT_MAX_HEADER_HEIGHT: f64 = 120.0;
+const DEFAULT_MIN_LIST_ITEMS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Header,
+    Nav,
+    Button,
+    Card,
+    List,
+    Input,
+    Modal,
+}
+
+/// The keywords and thresholds role inference uses.
+///
+/// Rules files are TOML. Keywords listed for a role replace its built-in
+/// ones; a keyword of several words, such as `"Text Field"`, matches layer
+/// names containing them in sequence:
+///
+/// ```toml
+/// [keywords]
+/// button = ["button", "btn", "chip"]
+/// input = ["Text Field", "search"]
+///
+/// [thresholds]
+/// max_control_height = 56
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleRules {
+    /// Keywords per role, in the order roles are checked, each split into
+    /// name words
+    keywords: Vec<(Role, Vec<Vec<String>>)>,
+    /// Buttons and inputs are at most this tall
+    pub max_control_height: f64,
+    /// Headers are at most this tall
+    pub max_header_height: f64,
+    /// Lists repeat at least this many similar children
+    pub min_list_items: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    keywords: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    thresholds: Thresholds,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Thresholds {
+    max_control_height: Option<f64>,
+    max_header_height: Option<f64>,
+    min_list_items: Option<usize>,
+}
+
+/// A role inferred for a node and what gave it away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Inference {
+    pub role: Role,
+    pub reason: &'static str,
+}
+
+/// A labelled node below the node roles were inferred for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoleLabel {
+    pub node_id: String,
+    pub name: String,
+    pub role: Role,
+    pub reason: &'static str,
+    /// Levels below the node the labels were inferred for, starting at 0
+    pub depth: usize,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "header" => Some(Self::Header),
+            "nav" | "navigation" => Some(Self::Nav),
+            "button" => Some(Self::Button),
+            "card" => Some(Self::Card),
+            "list" => Some(Self::List),
+            "input" => Some(Self::Input),
+            "modal" | "dialog" => Some(Self::Modal),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Header => "header",
+            Self::Nav => "nav",
+            Self::Button => "button",
+            Self::Card => "card",
+            Self::List => "list",
+            Self::Input => "input",
+            Self::Modal => "modal",
+        }
+    }
+
+    /// The HTML element for the role and the attributes it needs.
+    pub fn element(&self) -> (&'static str, &'static [(&'static str, &'static str)]) {
+        match self {
+            Self::Header => ("header", &[]),
+            Self::Nav => ("nav", &[]),
+            Self::Button => ("button", &[("type", "button")]),
+            Self::Card => ("article", &[]),
+            Self::List => ("ul", &[]),
+            // Inputs drawn as frames hold placeholder text and icons, which
+            // an <input> element cannot contain
+            Self::Input => ("div", &[("role", "textbox")]),
+            Self::Modal => ("div", &[("role", "dialog"), ("aria-modal", "true")]),
+        }
+    }
+}
+
+impl Default for RoleRules {
+    fn default() -> Self {
+        Self {
+            keywords: DEFAULT_KEYWORDS
+                .iter()
+                .map(|(role, keywords)| {
+                    let keywords = keywords.iter().map(|keyword| name_words(keyword)).collect();
+                    (*role, keywords)
+                })
+                .collect(),
+            max_control_height: DEFAULT_MAX_CONTROL_HEIGHT,
+            max_header_height: DEFAULT_MAX_HEADER_HEIGHT,
+            min_list_items: DEFAULT_MIN_LIST_ITEMS,
+        }
+    }
+}
+
+impl RoleRules {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        Self::parse(&content).map_err(|e| {
+            let message = match e {
+                Error::Config(message) => message,
+                e => e.to_string(),
+            };
+            Error::Config(format!("Invalid {}: {}", path.display(), message))
+        })
+    }
+
+    /// Parses a rules file on top of the built-in rules.
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: RulesFile = toml::from_str(content).map_err(|e| Error::Config(e.to_string()))?;
+        let mut rules = Self::default();
+
+        for (name, keywords) in file.keywords {
+            let role = Role::parse(&name).ok_or_else(|| {
+                Error::Config(format!(
+                    "unknown role '{}' in [keywords], expected {}",
+                    name, ROLE_NAMES
+                ))
+            })?;
+            let keywords: Vec<Vec<String>> = keywords
+                .iter()
+                .map(|keyword| name_words(keyword))
+                .filter(|words| !words.is_empty())
+                .collect();
+            if let Some((_, existing)) = rules.keywords.iter_mut().find(|(r, _)| *r == role) {
+                *existing = keywords;
+            }
+        }
+
+        let thresholds = file.thresholds;
+        rules.max_control_height = thresholds
+            .max_control_height
+            .unwrap_or(rules.max_control_height);
+        rules.max_header_height = thresholds
+            .max_header_height
+            .unwrap_or(rules.max_header_height);
+        rules.min_list_items = thresholds.min_list_items.unwrap_or(rules.min_list_items);
+
+        Ok(rules)
+    }
+
+    /// The first role with a keyword among the words of `name`.
+    fn role_named(&self, name: &str) -> Option<Role> {
+        let words = name_words(name);
+
+        self.keywords
+            .iter()
+            .find(|(_, keywords)| {
+                keywords.iter().any(|keyword| {
+                    words
+                        .windows(keyword.len())
+                        .any(|window| window == keyword.as_slice())
+                })
+            })
+            .map(|(role, _)| *role)
+    }
+}
+
+/// Infers the role of a container node from its name, then from its
+/// structure and styles. `parent` helps recognize headers.
+pub fn infer(node: &Value, parent: Option<&Value>, rules: &RoleRules) -> Option<Inference> {
+    if !CONTAINER_TYPES.contains(&node_type(node)) {
+        return None;
+    }
+
+    let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+    if let Some(role) = rules.role_named(name) {
+        return Some(Inference {
+            role,
+            reason: "name",
+        });
+    }
+
+    infer_from_structure(node, parent, rules)
+}
+
+/// Labels the visible nodes of a tree, `root` included, in tree order.
+pub fn label(root: &Value, rules: &RoleRules) -> Vec<RoleLabel> {
+    let mut labels = Vec::new();
+    collect(root, None, 0, rules, &mut labels);
+
+    labels
+}
+
+fn collect(
+    node: &Value,
+    parent: Option<&Value>,
+    depth: usize,
+    rules: &RoleRules,
+    labels: &mut Vec<RoleLabel>,
+) {
+    if is_hidden(node) {
+        return;
+    }
+
+    if let Some(inference) = infer(node, parent, rules) {
+        let field = |key: &str| {
+            node.get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        labels.push(RoleLabel {
+            node_id: field("id"),
+            name: field("name"),
+            role: inference.role,
+            reason: inference.reason,
+            depth,
+        });
+    }
+
+    for child in children(node) {
+        collect(child, Some(node), depth + 1, rules, labels);
+    }
+}
+
+fn infer_from_structure(
+    node: &Value,
+    parent: Option<&Value>,
+    rules: &RoleRules,
+) -> Option<Inference> {
+    let (width, height) = size(node)?;
+    let children = children(node);
+    let texts = children
+        .iter()
+        .filter(|child| node_type(child) == "TEXT")
+        .count();
+    let filled = has_visible_paint(node.get("fills"));
+    let stroked = has_visible_paint(node.get("strokes"));
+    let rounded = node
+        .get("cornerRadius")
+        .and_then(Value::as_f64)
+        .is_some_and(|radius| radius > 0.0);
+
+    if height <= rules.max_control_height && texts == 1 && children.len() <= 3 {
+        // A wide outlined box around one line of text, usually a placeholder
+        if stroked && !filled && width >= 3.0 * height {
+            return Some(Inference {
+                role: Role::Input,
+                reason: "outlined box around a single line of text",
+            });
+        }
+        if filled && (rounded || stroked) {
+            return Some(Inference {
+                role: Role::Button,
+                reason: "small filled box around a label",
+            });
+        }
+    }
+
+    if let Some(parent) = parent.filter(|_| height <= rules.max_header_height) {
+        let parent_bounds = parent.get("absoluteBoundingBox");
+        let bounds = node.get("absoluteBoundingBox");
+        let number = |bounds: Option<&Value>, key: &str| {
+            bounds.and_then(|b| b.get(key)).and_then(Value::as_f64)
+        };
+        let at_top = number(bounds, "y") == number(parent_bounds, "y");
+        let full_width = number(parent_bounds, "width") == Some(width);
+        let horizontal = node.get("layoutMode").and_then(Value::as_str) == Some("HORIZONTAL");
+        if at_top && full_width && horizontal && number(bounds, "y").is_some() {
+            return Some(Inference {
+                role: Role::Header,
+                reason: "full-width row at the top of its parent",
+            });
+        }
+    }
+
+    if repeats_children(&children, rules.min_list_items) {
+        return Some(Inference {
+            role: Role::List,
+            reason: "repeats similar children",
+        });
+    }
+
+    let shadowed = node
+        .get("effects")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .any(|effect| {
+            effect.get("type").and_then(Value::as_str) == Some("DROP_SHADOW") && !is_hidden(effect)
+        });
+    if rounded && (shadowed || stroked) && children.len() >= 2 && height > rules.max_control_height
+    {
+        return Some(Inference {
+            role: Role::Card,
+            reason: "rounded, raised container of several elements",
+        });
+    }
+
+    None
+}
+
+/// Whether enough children are instances of the same component, or share
+/// a type and name, to read as list items.
+fn repeats_children(children: &[&Value], min_items: usize) -> bool {
+    if children.is_empty() || children.len() < min_items {
+        return false;
+    }
+
+    let signature = |child: &Value| {
+        let identity = child
+            .get("componentId")
+            .or_else(|| child.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        (node_type(child).to_string(), identity)
+    };
+    let first = signature(children[0]);
+
+    children.iter().all(|child| signature(child) == first)
+}
+
+/// Splits a layer name into lowercase words, at separators and camel case
+/// humps, e.g. `Nav/PrimaryButton` into `nav`, `primary`, `button`.
+fn name_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for character in name.chars() {
+        if !character.is_alphanumeric() {
+            previous_lower = false;
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if character.is_uppercase() && previous_lower && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lower = character.is_lowercase();
+        word.extend(character.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn children(node: &Value) -> Vec<&Value> {
+    node.get("children")
+        .and_then(Value::as_array)
+        .map(|children| children.iter().filter(|child| !is_hidden(child)).collect())
+        .unwrap_or_default()
+}
+
+fn size(node: &Value) -> Option<(f64, f64)> {
+    let bounds = node.get("absoluteBoundingBox")?;
+    let width = bounds.get("width")?.as_f64()?;
+    let height = bounds.get("height")?.as_f64()?;
+
+    Some((width, height))
+}
+
+fn has_visible_paint(paints: Option<&Value>) -> bool {
+    paints
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .any(|paint| !is_hidden(paint))
+}
+
+fn node_type(node: &Value) -> &str {
+    node.get("type").and_then(Value::as_str).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn role(node: &Value, parent: Option<&Value>) -> Option<Role> {
+        infer(node, parent, &RoleRules::default()).map(|inference| inference.role)
+    }
+
+    #[test]
+    fn test_name_words() {
+        assert_eq!(
+            name_words("Nav/PrimaryButton - large"),
+            vec!["nav", "primary", "button", "large"]
+        );
+        assert_eq!(name_words("CTA"), vec!["cta"]);
+    }
+
+    #[test]
+    fn test_infer_from_name() {
+        let node = json!({ "type": "INSTANCE", "name": "Search Field" });
+        assert_eq!(role(&node, None), Some(Role::Input));
+
+        // Only whole words count
+        let node = json!({ "type": "FRAME", "name": "Cardinal" });
+        assert_eq!(role(&node, None), None);
+
+        let text = json!({ "type": "TEXT", "name": "Button" });
+        assert_eq!(role(&text, None), None);
+    }
+
+    #[test]
+    fn test_infer_from_structure() {
+        let label = json!({ "type": "TEXT", "name": "Label" });
+        let paint = json!([{ "type": "SOLID", "color": { "r": 0, "g": 0, "b": 0, "a": 1 } }]);
+
+        let button = json!({
+            "type": "FRAME",
+            "name": "Frame 12",
+            "cornerRadius": 8,
+            "fills": paint,
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 120, "height": 44 },
+            "children": [label]
+        });
+        assert_eq!(role(&button, None), Some(Role::Button));
+
+        let input = json!({
+            "type": "FRAME",
+            "name": "Frame 13",
+            "strokes": paint,
+            "fills": [],
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 320, "height": 44 },
+            "children": [label]
+        });
+        assert_eq!(role(&input, None), Some(Role::Input));
+
+        let row = json!({ "type": "INSTANCE", "name": "Row", "componentId": "1:1" });
+        let list = json!({
+            "type": "FRAME",
+            "name": "Frame 14",
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 320, "height": 400 },
+            "children": [row, row, row]
+        });
+        assert_eq!(role(&list, None), Some(Role::List));
+
+        let screen = json!({
+            "type": "FRAME",
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 390, "height": 844 }
+        });
+        let bar = json!({
+            "type": "FRAME",
+            "name": "Frame 15",
+            "layoutMode": "HORIZONTAL",
+            "absoluteBoundingBox": { "x": 0, "y": 0, "width": 390, "height": 56 },
+            "children": [label, label]
+        });
+        assert_eq!(role(&bar, Some(&screen)), Some(Role::Header));
+    }
+
+    #[test]
+    fn test_rules_file() {
+        let rules = RoleRules::parse(
+            r#"
+            [keywords]
+            button = ["Chip", "Icon Button"]
+
+            [thresholds]
+            min_list_items = 2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.role_named("Filter chip"), Some(Role::Button));
+        assert_eq!(rules.role_named("IconButton/Close"), Some(Role::Button));
+        // Listed roles replace their built-in keywords, others keep them
+        assert_eq!(rules.role_named("Primary btn"), None);
+        assert_eq!(rules.role_named("Product card"), Some(Role::Card));
+        assert_eq!(rules.min_list_items, 2);
+        assert_eq!(rules.max_control_height, DEFAULT_MAX_CONTROL_HEIGHT);
+
+        let error = RoleRules::parse("[keywords]\nbadge = [\"badge\"]").unwrap_err();
+        assert!(error.to_string().contains("unknown role 'badge'"));
+        assert!(RoleRules::parse("[colors]").is_err());
+    }
+
+    #[test]
+    fn test_label_reports_depth() {
+        let tree = json!({
+            "id": "1:1",
+            "type": "FRAME",
+            "name": "Checkout",
+            "children": [
+                { "id": "1:2", "type": "INSTANCE", "name": "Pay button" },
+                { "id": "1:3", "type": "FRAME", "name": "Old modal", "visible": false }
+            ]
+        });
+
+        let labels = label(&tree, &RoleRules::default());
+
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].node_id, "1:2");
+        assert_eq!(labels[0].role, Role::Button);
+        assert_eq!(labels[0].depth, 1);
+    }
+}
@@ -0,0 +1,392 @@
+//! Checks Figma API responses against the fields the readers in this crate
+//! expect, so changes to the API show up in the logs and the
+//! `schema_report` tool instead of as silently missing data.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Fields of the objects one endpoint returns.
+struct Shape {
+    /// Path below the API version, `*` matching any one segment
+    endpoint: &'static str,
+    /// Where the objects are: empty for the response itself, otherwise a
+    /// JSON pointer followed by `[]` for the items of an array or `{}` for
+    /// the values of an object
+    at: &'static str,
+    /// Fields the readers rely on
+    required: &'static [&'static str],
+    /// Other fields Figma documents
+    optional: &'static [&'static str],
+}
+
+const LIBRARY_ROOT: &[&str] = &["status", "error", "meta"];
+const PUBLISHED_COMPONENT: &[&str] = &[
+    "thumbnail_url",
+    "description",
+    "created_at",
+    "updated_at",
+    "user",
+    "containing_frame",
+];
+const PUBLISHED_STYLE: &[&str] = &[
+    "thumbnail_url",
+    "description",
+    "created_at",
+    "updated_at",
+    "user",
+    "sort_position",
+];
+
+const SHAPES: &[Shape] = &[
+    Shape {
+        endpoint: "/files/*",
+        at: "",
+        required: &["name", "document"],
+        optional: &[
+            "role",
+            "lastModified",
+            "editorType",
+            "thumbnailUrl",
+            "version",
+            "components",
+            "componentSets",
+            "schemaVersion",
+            "styles",
+            "mainFileKey",
+            "branches",
+            "linkAccess",
+        ],
+    },
+    Shape {
+        endpoint: "/files/*/nodes",
+        at: "",
+        required: &["nodes"],
+        optional: &[
+            "name",
+            "role",
+            "lastModified",
+            "editorType",
+            "thumbnailUrl",
+            "version",
+            "err",
+            "linkAccess",
+        ],
+    },
+    Shape {
+        endpoint: "/files/*/nodes",
+        at: "/nodes{}",
+        required: &["document"],
+        optional: &["components", "componentSets", "schemaVersion", "styles"],
+    },
+    Shape {
+        endpoint: "/images/*",
+        at: "",
+        required: &["images"],
+        optional: &["err", "status"],
+    },
+    Shape {
+        endpoint: "/files/*/comments",
+        at: "/comments[]",
+        required: &["id", "message", "created_at", "user"],
+        optional: &[
+            "uuid",
+            "file_key",
+            "parent_id",
+            "resolved_at",
+            "reactions",
+            "client_meta",
+            "order_id",
+        ],
+    },
+    Shape {
+        endpoint: "/files/*/versions",
+        at: "/versions[]",
+        required: &["id", "created_at"],
+        optional: &["label", "description", "user", "thumbnail_url"],
+    },
+    Shape {
+        endpoint: "/files/*/components",
+        at: "/meta/components[]",
+        required: &["key", "name", "file_key", "node_id"],
+        optional: PUBLISHED_COMPONENT,
+    },
+    Shape {
+        endpoint: "/teams/*/components",
+        at: "/meta/components[]",
+        required: &["key", "name", "file_key", "node_id"],
+        optional: PUBLISHED_COMPONENT,
+    },
+    Shape {
+        endpoint: "/files/*/component_sets",
+        at: "/meta/component_sets[]",
+        required: &["key", "name", "file_key", "node_id"],
+        optional: PUBLISHED_COMPONENT,
+    },
+    Shape {
+        endpoint: "/teams/*/component_sets",
+        at: "/meta/component_sets[]",
+        required: &["key", "name", "file_key", "node_id"],
+        optional: PUBLISHED_COMPONENT,
+    },
+    Shape {
+        endpoint: "/files/*/styles",
+        at: "/meta/styles[]",
+        required: &["key", "name", "style_type", "node_id"],
+        optional: PUBLISHED_STYLE,
+    },
+    Shape {
+        endpoint: "/teams/*/styles",
+        at: "/meta/styles[]",
+        required: &["key", "name", "style_type", "node_id"],
+        optional: PUBLISHED_STYLE,
+    },
+    Shape {
+        endpoint: "/files/*/variables/local",
+        at: "",
+        required: &["meta"],
+        optional: LIBRARY_ROOT,
+    },
+    Shape {
+        endpoint: "/files/*/variables/local",
+        at: "/meta/variableCollections{}",
+        required: &["name", "modes", "defaultModeId"],
+        optional: &[
+            "id",
+            "key",
+            "remote",
+            "hiddenFromPublishing",
+            "variableIds",
+            "deletedButReferenced",
+        ],
+    },
+    Shape {
+        endpoint: "/files/*/variables/local",
+        at: "/meta/variables{}",
+        required: &[
+            "name",
+            "resolvedType",
+            "variableCollectionId",
+            "valuesByMode",
+        ],
+        optional: &[
+            "id",
+            "key",
+            "remote",
+            "description",
+            "hiddenFromPublishing",
+            "scopes",
+            "codeSyntax",
+            "deletedButReferenced",
+        ],
+    },
+    Shape {
+        endpoint: "/files/*/variables/published",
+        at: "/meta/variableCollections{}",
+        required: &["name", "key", "subscribed_id"],
+        optional: &["id", "updatedAt"],
+    },
+    Shape {
+        endpoint: "/files/*/variables/published",
+        at: "/meta/variables{}",
+        required: &["name", "key", "subscribed_id", "variableCollectionId"],
+        optional: &["id", "resolvedDataType", "updatedAt"],
+    },
+    Shape {
+        endpoint: "/teams/*/projects",
+        at: "/projects[]",
+        required: &["id", "name"],
+        optional: &[],
+    },
+    Shape {
+        endpoint: "/projects/*/files",
+        at: "/files[]",
+        required: &["key", "name", "last_modified"],
+        optional: &["thumbnail_url", "branches"],
+    },
+    Shape {
+        endpoint: "/files/*/dev_resources",
+        at: "/dev_resources[]",
+        required: &["id", "name", "url", "node_id"],
+        optional: &["file_key"],
+    },
+    Shape {
+        endpoint: "/analytics/libraries/*/*/*",
+        at: "",
+        required: &["rows", "next_page"],
+        optional: &["cursor"],
+    },
+    Shape {
+        endpoint: "/me",
+        at: "",
+        required: &["id", "handle"],
+        optional: &["email", "img_url"],
+    },
+];
+
+/// What the responses of one endpoint looked like so far.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShapeReport {
+    pub endpoint: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub at: String,
+    /// Objects checked
+    pub checked: usize,
+    /// Expected fields that were absent, with the number of objects
+    /// lacking each
+    pub missing: BTreeMap<String, usize>,
+    /// Fields this crate does not know, with the number of objects
+    /// carrying each
+    pub unknown: BTreeMap<String, usize>,
+}
+
+impl ShapeReport {
+    pub fn has_drift(&self) -> bool {
+        !self.missing.is_empty() || !self.unknown.is_empty()
+    }
+}
+
+/// Fields seen in the responses of this server process. Clones share the
+/// same reports.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaMonitor {
+    reports: Arc<RwLock<BTreeMap<(&'static str, &'static str), ShapeReport>>>,
+}
+
+impl SchemaMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the response of a `GET` request to `path` (the URL path,
+    /// starting with the API version). Logs a warning the first time an
+    /// expected field is missing or an unknown one appears.
+    pub fn check(&self, path: &str, response: &Value) {
+        // Leave out the API version segment
+        let Some((_, endpoint)) = path.trim_start_matches('/').split_once('/') else {
+            return;
+        };
+
+        let shapes = SHAPES
+            .iter()
+            .filter(|shape| matches(shape.endpoint, endpoint));
+        for shape in shapes {
+            let objects = objects(response, shape.at);
+            if objects.is_empty() {
+                continue;
+            }
+
+            let Ok(mut reports) = self.reports.write() else {
+                return;
+            };
+            let report = reports
+                .entry((shape.endpoint, shape.at))
+                .or_insert_with(|| ShapeReport {
+                    endpoint: shape.endpoint.to_string(),
+                    at: shape.at.to_string(),
+                    ..Default::default()
+                });
+
+            for object in objects {
+                report.checked += 1;
+                for field in shape.required {
+                    if !object.contains_key(*field) {
+                        record(&mut report.missing, shape, "missing", field);
+                    }
+                }
+                for field in object.keys() {
+                    let field = field.as_str();
+                    if !shape.required.contains(&field) && !shape.optional.contains(&field) {
+                        record(&mut report.unknown, shape, "unknown", field);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports of the endpoints called so far, by endpoint.
+    pub fn report(&self) -> Vec<ShapeReport> {
+        match self.reports.read() {
+            Ok(reports) => reports.values().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+fn record(counts: &mut BTreeMap<String, usize>, shape: &Shape, kind: &str, field: &str) {
+    let count = counts.entry(field.to_string()).or_default();
+    if *count == 0 {
+        tracing::warn!(
+            endpoint = shape.endpoint,
+            at = shape.at,
+            field,
+            "Figma API response has {} field",
+            kind
+        );
+    }
+    *count += 1;
+}
+
+fn matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_start_matches('/').split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+
+    pattern.len() == path.len()
+        && pattern
+            .iter()
+            .zip(&path)
+            .all(|(pattern, segment)| *pattern == "*" || pattern == segment)
+}
+
+/// The objects at `at` in a response (see [`Shape::at`]).
+fn objects<'a>(response: &'a Value, at: &str) -> Vec<&'a serde_json::Map<String, Value>> {
+    let values: Vec<&Value> = if let Some(pointer) = at.strip_suffix("[]") {
+        let items = response.pointer(pointer).and_then(Value::as_array);
+        items.into_iter().flatten().collect()
+    } else if let Some(pointer) = at.strip_suffix("{}") {
+        let values = response.pointer(pointer).and_then(Value::as_object);
+        values
+            .into_iter()
+            .flat_map(|values| values.values())
+            .collect()
+    } else {
+        vec![response]
+    };
+
+    values.into_iter().filter_map(Value::as_object).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reports_missing_and_unknown_fields() {
+        let monitor = SchemaMonitor::new();
+        monitor.check(
+            "/v1/files/abc/versions",
+            &json!({
+                "versions": [
+                    { "id": "1", "created_at": "2024-01-01T00:00:00Z", "label": "Handoff" },
+                    { "id": "2", "createdAt": "2024-02-01T00:00:00Z" }
+                ],
+                "pagination": {}
+            }),
+        );
+        monitor.check("/v1/files/abc/nodes", &json!({ "nodes": { "1:2": null } }));
+
+        let report = monitor.report();
+        assert_eq!(report.len(), 2);
+        let versions = &report[1];
+        assert_eq!(versions.endpoint, "/files/*/versions");
+        assert_eq!(versions.checked, 2);
+        assert_eq!(versions.missing["created_at"], 1);
+        assert_eq!(versions.unknown["createdAt"], 1);
+        // Node entries that are null are not objects to check
+        assert!(!report[0].has_drift());
+        assert_eq!(report[0].at, "");
+    }
+}
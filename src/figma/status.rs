@@ -0,0 +1,197 @@
+//! Status conventions designers put in page and frame names, such as `✅`,
+//! `WIP`, `[deprecated]` or `v2`, read into structured fields so agents can
+//! tell finished screens from drafts and ones they should not build.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Markers per state, checked against the lowercased name. Emoji match
+/// anywhere; words and phrases only as whole words. Plain words such as
+/// "done" or "review" are left out, as they are common in screen names.
+const STATE_MARKERS: &[(State, &[&str])] = &[
+    (
+        State::Deprecated,
+        &[
+            "⛔",
+            "🚫",
+            "🗑",
+            "deprecated",
+            "obsolete",
+            "legacy",
+            "do not use",
+            "don't use",
+        ],
+    ),
+    (
+        State::InProgress,
+        &["🚧", "⏳", "🟡", "wip", "draft", "in progress"],
+    ),
+    (
+        State::Review,
+        &["👀", "in review", "needs review", "for review"],
+    ),
+    (
+        State::Ready,
+        &["✅", "✔", "☑", "🟢", "ready for dev", "approved"],
+    ),
+];
+
+/// How far along a page or frame is. When a name carries markers of
+/// several states, the one listed first wins, so a `✅ Login [deprecated]`
+/// frame counts as deprecated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Deprecated,
+    InProgress,
+    Review,
+    Ready,
+}
+
+/// The status read from a name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NameStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<State>,
+    /// A design version such as `2` or `2.1`, from `v2` or `V2.1`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The markers found, as they appear in the marker lists
+    pub markers: Vec<String>,
+}
+
+/// Reads the status markers of a name. Returns `None` if there are none.
+pub fn parse(name: &str) -> Option<NameStatus> {
+    let name = name.to_lowercase();
+    let words: Vec<&str> = name
+        .split(|c: char| !(c.is_alphanumeric() || c == '.' || c == '\''))
+        .map(|word| word.trim_matches('.'))
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut status = NameStatus::default();
+    for (state, markers) in STATE_MARKERS {
+        for marker in *markers {
+            let found = if marker.is_ascii() {
+                contains_words(&words, marker)
+            } else {
+                name.contains(marker)
+            };
+            if found {
+                status.state.get_or_insert(*state);
+                status.markers.push(marker.to_string());
+            }
+        }
+    }
+
+    status.version = words
+        .iter()
+        .filter_map(|word| word.strip_prefix('v'))
+        .find(|version| {
+            version
+                .split('.')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(String::from);
+    if let Some(version) = &status.version {
+        status.markers.push(format!("v{}", version));
+    }
+
+    (!status.markers.is_empty()).then_some(status)
+}
+
+/// Adds a `status` field to the pages and top-level frames of a node tree
+/// or a Figma response, and to the nodes of a nodes response, whose names
+/// carry status markers.
+pub fn annotate(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let node_type = map.get("type").and_then(Value::as_str);
+            if matches!(node_type, Some("DOCUMENT" | "CANVAS")) {
+                if let Some(Value::Array(children)) = map.get_mut("children") {
+                    children.iter_mut().for_each(add_status);
+                }
+            }
+            for (key, value) in map.iter_mut() {
+                if key == "document" {
+                    add_status(value);
+                }
+                annotate(value);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(annotate),
+        _ => {}
+    }
+}
+
+fn add_status(node: &mut Value) {
+    let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+    let Some(status) = parse(name) else {
+        return;
+    };
+    if let Ok(status) = serde_json::to_value(status) {
+        node["status"] = status;
+    }
+}
+
+/// Whether `phrase` occurs as a run of whole words.
+fn contains_words(words: &[&str], phrase: &str) -> bool {
+    let phrase: Vec<&str> = phrase.split(' ').collect();
+
+    words
+        .windows(phrase.len())
+        .any(|window| window == phrase.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_markers() {
+        let status = parse("✅ Checkout v2.1").unwrap();
+        assert_eq!(status.state, Some(State::Ready));
+        assert_eq!(status.version.as_deref(), Some("2.1"));
+
+        let status = parse("Login [Deprecated] ✅").unwrap();
+        assert_eq!(status.state, Some(State::Deprecated));
+        assert_eq!(status.markers, vec!["deprecated", "✅"]);
+
+        assert_eq!(
+            parse("WIP - Onboarding").unwrap().state,
+            Some(State::InProgress)
+        );
+        assert_eq!(parse("Wipe animation"), None);
+        assert_eq!(parse("👀 Cart").unwrap().state, Some(State::Review));
+        assert_eq!(parse("Review queue"), None);
+        assert_eq!(parse("Navigation"), None);
+    }
+
+    #[test]
+    fn test_annotate_pages_and_frames() {
+        let mut file = json!({
+            "document": {
+                "type": "DOCUMENT",
+                "children": [{
+                    "type": "CANVAS",
+                    "name": "🚧 Settings",
+                    "children": [
+                        {
+                            "type": "FRAME",
+                            "name": "Profile v3",
+                            "children": [{ "type": "TEXT", "name": "Done" }]
+                        }
+                    ]
+                }]
+            }
+        });
+
+        annotate(&mut file);
+
+        let page = &file["document"]["children"][0];
+        assert_eq!(page["status"]["state"], "in_progress");
+        assert_eq!(page["children"][0]["status"]["version"], "3");
+        assert!(page["children"][0]["children"][0].get("status").is_none());
+    }
+}
@@ -0,0 +1,355 @@
+//! Detection of tables drawn as grids of aligned text layers, such as
+//! pricing tables and data specs, and their extraction as CSV or Markdown.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::bounds::{Bounds, BoundsKind};
+use super::layers::is_hidden;
+
+/// Table output formats accepted by [`TableFormat::parse`], for error
+/// messages.
+pub const TABLE_FORMAT_NAMES: &str = "markdown, csv or json";
+
+/// Share of the grid's cells that must hold text.
+const MIN_FILLED_RATIO: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Markdown,
+    Csv,
+    Json,
+}
+
+/// A table found in a frame, with its first row as header.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Table {
+    /// The smallest container holding the grid
+    pub node_id: String,
+    pub name: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A text layer and its layout box.
+#[derive(Debug, Clone)]
+struct Cell {
+    text: String,
+    bounds: Bounds,
+}
+
+impl TableFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+impl Table {
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in &self.rows {
+            let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        for (index, row) in self.rows.iter().enumerate() {
+            let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+            markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if index == 0 {
+                let separator = vec!["---"; row.len()];
+                markdown.push_str(&format!("| {} |\n", separator.join(" | ")));
+            }
+        }
+
+        markdown
+    }
+}
+
+/// Finds tables below `root`. Each table is reported for the smallest
+/// container whose text layers line up in at least two rows and two
+/// columns; single-cell rows above or below the grid, such as a title, are
+/// left out.
+pub fn detect(root: &Value) -> Vec<Table> {
+    let mut tables = Vec::new();
+    find(root, &mut tables);
+
+    tables
+}
+
+/// Looks for tables in the descendants of `node` first, then in `node`
+/// itself. Returns whether a table was found.
+fn find(node: &Value, tables: &mut Vec<Table>) -> bool {
+    if is_hidden(node) || node.get("type").and_then(Value::as_str) == Some("TEXT") {
+        return false;
+    }
+
+    let mut found = false;
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        found |= find(child, tables);
+    }
+    if found {
+        return true;
+    }
+
+    let mut cells = Vec::new();
+    collect_cells(node, &mut cells);
+    let Some(rows) = grid(cells) else {
+        return false;
+    };
+
+    let field = |key: &str| {
+        node.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    tables.push(Table {
+        node_id: field("id"),
+        name: field("name"),
+        rows,
+    });
+
+    true
+}
+
+fn collect_cells(node: &Value, cells: &mut Vec<Cell>) {
+    if is_hidden(node) {
+        return;
+    }
+
+    if node.get("type").and_then(Value::as_str) == Some("TEXT") {
+        let text = node
+            .get("characters")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(bounds) = Bounds::of(node, BoundsKind::Layout.key()) {
+            if !text.is_empty() {
+                cells.push(Cell { text, bounds });
+            }
+        }
+        return;
+    }
+
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        collect_cells(child, cells);
+    }
+}
+
+/// Arranges cells into a grid of rows and columns, if they form one.
+fn grid(cells: Vec<Cell>) -> Option<Vec<Vec<String>>> {
+    let mut rows = rows(cells);
+
+    // Titles and footnotes sit alone on their row
+    while rows.first().is_some_and(|row| row.len() < 2) {
+        rows.remove(0);
+    }
+    while rows.last().is_some_and(|row| row.len() < 2) {
+        rows.pop();
+    }
+    if rows.len() < 2 {
+        return None;
+    }
+
+    let columns = columns(rows.iter().flatten());
+    if columns.len() < 2 {
+        return None;
+    }
+
+    let mut filled = 0;
+    let mut grid = Vec::new();
+    for row in &rows {
+        let mut values = vec![String::new(); columns.len()];
+        for cell in row {
+            let center = cell.bounds.x + cell.bounds.width / 2.0;
+            let column = columns
+                .iter()
+                .position(|(start, end)| center >= *start && center <= *end)
+                .unwrap_or_default();
+            let value = &mut values[column];
+            if !value.is_empty() {
+                value.push(' ');
+            }
+            value.push_str(&cell.text);
+        }
+        filled += values.iter().filter(|value| !value.is_empty()).count();
+        grid.push(values);
+    }
+
+    let ratio = filled as f64 / (rows.len() * columns.len()) as f64;
+    (ratio >= MIN_FILLED_RATIO).then_some(grid)
+}
+
+/// Groups cells into rows by their vertical centers, top to bottom, each
+/// row ordered left to right.
+fn rows(mut cells: Vec<Cell>) -> Vec<Vec<Cell>> {
+    let center = |cell: &Cell| cell.bounds.y + cell.bounds.height / 2.0;
+    cells.sort_by(|a, b| center(a).total_cmp(&center(b)));
+
+    let mut rows: Vec<Vec<Cell>> = Vec::new();
+    for cell in cells {
+        let same_row = rows
+            .last()
+            .and_then(|row| row.first())
+            .is_some_and(|first| {
+                let tolerance = first.bounds.height.min(cell.bounds.height) / 2.0;
+                (center(&cell) - center(first)).abs() <= tolerance
+            });
+        match rows.last_mut() {
+            Some(row) if same_row => row.push(cell),
+            _ => rows.push(vec![cell]),
+        }
+    }
+
+    for row in &mut rows {
+        row.sort_by(|a, b| a.bounds.x.total_cmp(&b.bounds.x));
+    }
+
+    rows
+}
+
+/// Merges the horizontal extents of cells into column ranges. Cells of a
+/// column overlap horizontally whether they are aligned left, center or
+/// right.
+fn columns<'a>(cells: impl Iterator<Item = &'a Cell>) -> Vec<(f64, f64)> {
+    let mut extents: Vec<(f64, f64)> = cells
+        .map(|cell| (cell.bounds.x, cell.bounds.x + cell.bounds.width))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in extents {
+        match columns.last_mut() {
+            Some(column) if start < column.1 => column.1 = column.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+
+    columns
+}
+
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(characters: &str, x: f64, y: f64, width: f64) -> Value {
+        json!({
+            "type": "TEXT",
+            "characters": characters,
+            "absoluteBoundingBox": { "x": x, "y": y, "width": width, "height": 20 }
+        })
+    }
+
+    fn pricing() -> Value {
+        json!({
+            "id": "1:1",
+            "type": "FRAME",
+            "name": "Pricing",
+            "children": [
+                text("Compare plans", 0.0, 0.0, 400.0),
+                {
+                    "id": "1:2",
+                    "type": "FRAME",
+                    "name": "Table",
+                    "children": [
+                        { "type": "FRAME", "children": [
+                            text("Plan", 0.0, 40.0, 60.0),
+                            text("Price", 150.0, 40.0, 50.0),
+                            text("Seats", 300.0, 40.0, 50.0)
+                        ] },
+                        { "type": "FRAME", "children": [
+                            text("Starter", 0.0, 70.0, 70.0),
+                            // Right-aligned numbers still overlap the header
+                            text("$9", 180.0, 71.0, 20.0),
+                            text("1", 340.0, 70.0, 10.0)
+                        ] },
+                        { "type": "FRAME", "children": [
+                            text("Team, annual", 0.0, 100.0, 110.0),
+                            text("$29", 170.0, 100.0, 30.0)
+                        ] }
+                    ]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_detect_finds_smallest_container() {
+        let tables = detect(&pricing());
+
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].node_id, "1:2");
+        assert_eq!(
+            tables[0].rows,
+            vec![
+                vec!["Plan", "Price", "Seats"],
+                vec!["Starter", "$9", "1"],
+                vec!["Team, annual", "$29", ""]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_title_row_is_left_out() {
+        let mut frame = pricing();
+        let table = frame["children"][1]["children"].take();
+        frame["children"] = json!([frame["children"][0].clone()]);
+        frame["children"]
+            .as_array_mut()
+            .unwrap()
+            .extend(table.as_array().unwrap().iter().cloned());
+
+        let tables = detect(&frame);
+
+        assert_eq!(tables[0].node_id, "1:1");
+        assert_eq!(tables[0].rows.len(), 3);
+    }
+
+    #[test]
+    fn test_paragraphs_are_not_tables() {
+        let frame = json!({
+            "type": "FRAME",
+            "children": [
+                text("Heading", 0.0, 0.0, 200.0),
+                text("First paragraph", 0.0, 40.0, 300.0),
+                text("Second paragraph", 0.0, 80.0, 300.0)
+            ]
+        });
+
+        assert!(detect(&frame).is_empty());
+    }
+
+    #[test]
+    fn test_csv_and_markdown() {
+        let table = &detect(&pricing())[0];
+
+        assert_eq!(
+            table.to_csv(),
+            "Plan,Price,Seats\nStarter,$9,1\n\"Team, annual\",$29,\n"
+        );
+        assert!(table
+            .to_markdown()
+            .starts_with("| Plan | Price | Seats |\n| --- | --- | --- |\n"));
+    }
+}
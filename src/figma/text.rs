@@ -0,0 +1,415 @@
+//! Renders the text of a node tree as Markdown, so copy keeps its headings,
+//! emphasis and lists instead of reading as a flat list of strings.
+
+use serde_json::Value;
+
+use super::layers::is_hidden;
+
+/// Bold starts at this font weight.
+const BOLD_WEIGHT: f64 = 600.0;
+
+/// Style name words that mark body copy, which never becomes a heading.
+const BODY_STYLE_WORDS: &[&str] = &["body", "paragraph", "caption", "label", "small"];
+
+/// Markdown headings go down to this level.
+const MAX_HEADING_LEVEL: usize = 6;
+
+/// A text layer in reading order.
+struct Block<'a> {
+    node: &'a Value,
+    characters: &'a str,
+    font_size: f64,
+    /// Heading level given by the text style's name, with 0 for body styles
+    style_level: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Emphasis {
+    bold: bool,
+    italic: bool,
+}
+
+/// Renders the visible text below `root` as Markdown. `styles` is the
+/// `styles` map of a `get_file_nodes` entry, whose names can mark headings
+/// (`H1`, `Heading 2`, `Display`). Otherwise the font sizes above the most
+/// used one become headings, largest first.
+pub fn to_markdown(root: &Value, styles: Option<&Value>) -> String {
+    let mut blocks = Vec::new();
+    collect(root, styles, &mut blocks);
+
+    let body_size = body_size(&blocks);
+    let mut heading_sizes: Vec<f64> = blocks
+        .iter()
+        .map(|block| block.font_size)
+        .filter(|&size| size > body_size)
+        .collect();
+    heading_sizes.sort_by(|a, b| b.total_cmp(a));
+    heading_sizes.dedup();
+
+    let mut sections = Vec::new();
+    for block in &blocks {
+        let level = match block.style_level {
+            Some(level) => level,
+            None => heading_sizes
+                .iter()
+                .position(|&size| size == block.font_size)
+                .map_or(0, |rank| (rank + 1).min(MAX_HEADING_LEVEL)),
+        };
+
+        let section = if level > 0 {
+            let text = block.characters.split_whitespace().collect::<Vec<_>>();
+            format!("{} {}", "#".repeat(level), escape(&text.join(" ")))
+        } else {
+            paragraphs(block)
+        };
+        if !section.is_empty() {
+            sections.push(section);
+        }
+    }
+
+    let mut markdown = sections.join("\n\n");
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+
+    markdown
+}
+
+/// Collects visible text layers. Children of frames without auto layout
+/// are read top to bottom, then left to right, since their layer order is
+/// stacking order.
+fn collect<'a>(node: &'a Value, styles: Option<&Value>, blocks: &mut Vec<Block<'a>>) {
+    if is_hidden(node) {
+        return;
+    }
+
+    if node.get("type").and_then(Value::as_str) == Some("TEXT") {
+        let characters = node
+            .get("characters")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if !characters.trim().is_empty() {
+            blocks.push(Block {
+                node,
+                characters,
+                font_size: style_number(node, "fontSize").unwrap_or_default(),
+                style_level: style_name(node, styles).and_then(heading_level),
+            });
+        }
+        return;
+    }
+
+    let mut children: Vec<&Value> = node
+        .get("children")
+        .and_then(Value::as_array)
+        .map(|children| children.iter().collect())
+        .unwrap_or_default();
+    let auto_layout = matches!(
+        node.get("layoutMode").and_then(Value::as_str),
+        Some("HORIZONTAL" | "VERTICAL")
+    );
+    if !auto_layout {
+        let position = |child: &Value, axis: &str| {
+            child
+                .pointer(&format!("/absoluteBoundingBox/{}", axis))
+                .and_then(Value::as_f64)
+                .unwrap_or_default()
+        };
+        children.sort_by(|a, b| {
+            position(a, "y")
+                .total_cmp(&position(b, "y"))
+                .then(position(a, "x").total_cmp(&position(b, "x")))
+        });
+    }
+
+    for child in children {
+        collect(child, styles, blocks);
+    }
+}
+
+/// The font size covering the most characters, taken as body copy.
+fn body_size(blocks: &[Block]) -> f64 {
+    let mut counts: Vec<(f64, usize)> = Vec::new();
+    for block in blocks {
+        let length = block.characters.chars().count();
+        match counts.iter_mut().find(|(size, _)| *size == block.font_size) {
+            Some((_, count)) => *count += length,
+            None => counts.push((block.font_size, length)),
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(size, _)| size)
+        .unwrap_or_default()
+}
+
+/// Renders a body text layer: lines Figma marks as list items become
+/// Markdown lists, other lines paragraphs, with bold and italic runs.
+fn paragraphs(block: &Block) -> String {
+    let line_types: Vec<&str> = block
+        .node
+        .get("lineTypes")
+        .and_then(Value::as_array)
+        .map(|types| types.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut out = String::new();
+    let mut previous_list = false;
+    for (index, line) in styled_lines(block).iter().enumerate() {
+        let text = render_runs(line);
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let marker = match line_types.get(index) {
+            Some(&"UNORDERED") => Some("- "),
+            Some(&"ORDERED") => Some("1. "),
+            _ => None,
+        };
+        let list = marker.is_some();
+        if !out.is_empty() {
+            // List items stay together, everything else is a paragraph
+            out.push_str(if list && previous_list { "\n" } else { "\n\n" });
+        }
+        out.push_str(marker.unwrap_or_default());
+        out.push_str(text.trim());
+        previous_list = list;
+    }
+
+    out
+}
+
+/// Splits a text layer into lines of characters with their emphasis.
+/// Figma indexes style overrides by UTF-16 code unit.
+fn styled_lines(block: &Block) -> Vec<Vec<(char, Emphasis)>> {
+    let overrides: Vec<u64> = block
+        .node
+        .get("characterStyleOverrides")
+        .and_then(Value::as_array)
+        .map(|overrides| overrides.iter().filter_map(Value::as_u64).collect())
+        .unwrap_or_default();
+    let table = block
+        .node
+        .get("styleOverrideTable")
+        .and_then(Value::as_object);
+    let base = emphasis(block.node.get("style"), None);
+
+    let mut lines = vec![Vec::new()];
+    let mut unit = 0;
+    for character in block.characters.chars() {
+        let style_id = overrides.get(unit).copied().unwrap_or_default();
+        unit += character.len_utf16();
+
+        if character == '\n' {
+            lines.push(Vec::new());
+            continue;
+        }
+        let emphasis = match table.and_then(|table| table.get(&style_id.to_string())) {
+            Some(style) if style_id != 0 => self::emphasis(Some(style), Some(base)),
+            _ => base,
+        };
+        if let Some(line) = lines.last_mut() {
+            line.push((character, emphasis));
+        }
+    }
+
+    lines
+}
+
+fn emphasis(style: Option<&Value>, base: Option<Emphasis>) -> Emphasis {
+    let base = base.unwrap_or_default();
+    let Some(style) = style else {
+        return base;
+    };
+
+    Emphasis {
+        bold: style
+            .get("fontWeight")
+            .and_then(Value::as_f64)
+            .map_or(base.bold, |weight| weight >= BOLD_WEIGHT),
+        italic: style
+            .get("italic")
+            .and_then(Value::as_bool)
+            .unwrap_or(base.italic),
+    }
+}
+
+/// Wraps runs of equal emphasis in Markdown markers, keeping surrounding
+/// whitespace outside them as Markdown requires.
+fn render_runs(line: &[(char, Emphasis)]) -> String {
+    let mut out = String::new();
+    let mut start = 0;
+    while start < line.len() {
+        let emphasis = line[start].1;
+        let end = line[start..]
+            .iter()
+            .position(|(_, other)| *other != emphasis)
+            .map_or(line.len(), |offset| start + offset);
+        let run: String = line[start..end]
+            .iter()
+            .map(|(character, _)| character)
+            .collect();
+
+        let marker = match (emphasis.bold, emphasis.italic) {
+            (true, true) => "***",
+            (true, false) => "**",
+            (false, true) => "*",
+            (false, false) => "",
+        };
+        let content = run.trim();
+        if marker.is_empty() || content.is_empty() {
+            out.push_str(&escape(&run));
+        } else {
+            let leading = &run[..run.len() - run.trim_start().len()];
+            let trailing = &run[run.trim_end().len()..];
+            out.push_str(&format!(
+                "{}{}{}{}{}",
+                leading,
+                marker,
+                escape(content),
+                marker,
+                trailing
+            ));
+        }
+        start = end;
+    }
+
+    out
+}
+
+/// Reads the heading level a text style's name implies: `H2` or
+/// `Heading 2` give 2, `Display` and `Title` 1, body styles 0.
+fn heading_level(name: &str) -> Option<usize> {
+    let words: Vec<String> = name
+        .split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let explicit = words.iter().find_map(|word| {
+        let level = word.strip_prefix('h')?.parse().ok()?;
+        (1..=MAX_HEADING_LEVEL).contains(&level).then_some(level)
+    });
+    if explicit.is_some() {
+        return explicit;
+    }
+
+    for (index, word) in words.iter().enumerate() {
+        if word == "heading" || word == "headline" {
+            let level = words.get(index + 1).and_then(|next| next.parse().ok());
+            return Some(level.unwrap_or(1).clamp(1, MAX_HEADING_LEVEL));
+        }
+        if word == "display" || word == "title" {
+            return Some(1);
+        }
+        if BODY_STYLE_WORDS.contains(&word.as_str()) {
+            return Some(0);
+        }
+    }
+
+    None
+}
+
+fn style_name<'a>(node: &Value, styles: Option<&'a Value>) -> Option<&'a str> {
+    let style_id = node.pointer("/styles/text").and_then(Value::as_str)?;
+
+    styles?
+        .get(style_id)
+        .and_then(|style| style.get("name"))
+        .and_then(Value::as_str)
+}
+
+fn style_number(node: &Value, key: &str) -> Option<f64> {
+    node.get("style")?.get(key)?.as_f64()
+}
+
+/// Escapes characters Markdown would read as formatting.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if matches!(character, '\\' | '*' | '_' | '`' | '#' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(id: &str, characters: &str, size: f64, y: f64) -> Value {
+        json!({
+            "id": id,
+            "type": "TEXT",
+            "characters": characters,
+            "style": { "fontSize": size, "fontWeight": 400 },
+            "absoluteBoundingBox": { "x": 0, "y": y, "width": 300, "height": 20 }
+        })
+    }
+
+    #[test]
+    fn test_sizes_become_headings_in_reading_order() {
+        let frame = json!({
+            "type": "FRAME",
+            "children": [
+                text("1:3", "Body copy that is long enough to be the body size.", 16.0, 80.0),
+                text("1:2", "Shipping", 24.0, 40.0),
+                text("1:1", "Checkout", 32.0, 0.0),
+                text("1:4", "More body copy below the first paragraph.", 16.0, 120.0)
+            ]
+        });
+
+        assert_eq!(
+            to_markdown(&frame, None),
+            "# Checkout\n\n## Shipping\n\nBody copy that is long enough to be the body size.\n\nMore body copy below the first paragraph.\n"
+        );
+    }
+
+    #[test]
+    fn test_style_names_set_heading_levels() {
+        let mut heading = text("1:1", "Plans", 20.0, 0.0);
+        heading["styles"] = json!({ "text": "S:1" });
+        let mut lead = text("1:2", "Pick the plan that fits.", 20.0, 40.0);
+        lead["styles"] = json!({ "text": "S:2" });
+        let frame = json!({ "type": "FRAME", "children": [heading, lead] });
+        let styles = json!({
+            "S:1": { "name": "Heading/H3", "styleType": "TEXT" },
+            "S:2": { "name": "Body Large", "styleType": "TEXT" }
+        });
+
+        assert_eq!(
+            to_markdown(&frame, Some(&styles)),
+            "### Plans\n\nPick the plan that fits.\n"
+        );
+    }
+
+    #[test]
+    fn test_emphasis_and_lists() {
+        let mut node = text("1:1", "Includes a_b:\nFree shipping\nReturns", 16.0, 0.0);
+        // "a_b" is bold, "Returns" italic
+        let overrides = [vec![0; 9], vec![1; 3], vec![0; 16], vec![2; 7]].concat();
+        node["characterStyleOverrides"] = json!(overrides);
+        node["styleOverrideTable"] = json!({ "1": { "fontWeight": 700 }, "2": { "italic": true } });
+        node["lineTypes"] = json!(["NONE", "UNORDERED", "UNORDERED"]);
+
+        assert_eq!(
+            to_markdown(&node, None),
+            "Includes **a\\_b**:\n\n- Free shipping\n- *Returns*\n"
+        );
+    }
+
+    #[test]
+    fn test_heading_level() {
+        assert_eq!(heading_level("Heading 2"), Some(2));
+        assert_eq!(heading_level("Mobile/H4 Bold"), Some(4));
+        assert_eq!(heading_level("Display XL"), Some(1));
+        assert_eq!(heading_level("Body/Regular"), Some(0));
+        assert_eq!(heading_level("Brand Blue"), None);
+    }
+}
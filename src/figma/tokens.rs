@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use super::codegen::{box_shadow, css_color, solid_fill};
+
+/// Variable scopes whose numbers are pixel lengths in CSS.
+const PIXEL_SCOPES: &[&str] = &[
+    "CORNER_RADIUS",
+    "WIDTH_HEIGHT",
+    "GAP",
+    "STROKE_FLOAT",
+    "EFFECT_FLOAT",
+    "FONT_SIZE",
+    "LINE_HEIGHT",
+    "LETTER_SPACING",
+    "PARAGRAPH_SPACING",
+    "PARAGRAPH_INDENT",
+];
+
+/// A design token: a named CSS value derived from a style or variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// Kebab-case name, e.g. `color-primary-500`
+    pub name: String,
+    pub value: TokenValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenValue {
+    Literal(String),
+    /// Reference to another token by name
+    Alias(String),
+}
+
+/// Output format of generated token files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenFormat {
+    /// CSS custom properties on `:root`
+    Css,
+    /// A flat JSON object of token names to values
+    Json,
+}
+
+impl TokenFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "css" => Some(Self::Css),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Creates tokens from a `GET /v1/files/:key/variables/local` response,
+/// using each collection's default mode. Aliases stay references to the
+/// aliased token.
+pub fn from_variables(response: &Value) -> Vec<Token> {
+    let Some(variables) = response
+        .pointer("/meta/variables")
+        .and_then(Value::as_object)
+    else {
+        return Vec::new();
+    };
+    let collections = response.pointer("/meta/variableCollections");
+
+    let names: HashMap<&str, String> = variables
+        .iter()
+        .filter_map(|(id, variable)| {
+            let name = variable.get("name")?.as_str()?;
+            Some((id.as_str(), token_name(name)))
+        })
+        .collect();
+
+    let mut tokens = Vec::new();
+    for (id, variable) in variables {
+        if variable.get("remote").and_then(Value::as_bool) == Some(true) {
+            continue;
+        }
+        let Some(name) = names.get(id.as_str()) else {
+            continue;
+        };
+
+        let default_mode = variable
+            .get("variableCollectionId")
+            .and_then(Value::as_str)
+            .and_then(|collection| collections?.get(collection)?.get("defaultModeId"))
+            .and_then(Value::as_str);
+        let values = variable.get("valuesByMode").and_then(Value::as_object);
+        let value = match (values, default_mode) {
+            (Some(values), Some(mode)) => values.get(mode),
+            (Some(values), None) => values.values().next(),
+            _ => None,
+        };
+        let Some(value) = value else {
+            continue;
+        };
+
+        if let Some(value) = variable_value(variable, value, &names) {
+            tokens.push(Token {
+                name: name.clone(),
+                value,
+            });
+        }
+    }
+
+    tokens
+}
+
+/// Creates tokens from a file's local styles. `style_nodes` is the
+/// `GET /v1/files/:key/nodes` response for the style node IDs, which holds
+/// the style values.
+pub fn from_styles(file: &Value, style_nodes: &Value) -> Vec<Token> {
+    let Some(styles) = file.get("styles").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut tokens = Vec::new();
+    for (node_id, style) in styles {
+        if style.get("remote").and_then(Value::as_bool) == Some(true) {
+            continue;
+        }
+        let Some(name) = style.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let Some(node) = style_nodes.pointer(&format!("/nodes/{}/document", node_id)) else {
+            continue;
+        };
+
+        let name = token_name(name);
+        match style.get("styleType").and_then(Value::as_str) {
+            Some("FILL") => {
+                if let Some(color) = solid_fill(node.get("fills")) {
+                    tokens.push(Token {
+                        name,
+                        value: TokenValue::Literal(color),
+                    });
+                }
+            }
+            Some("EFFECT") => {
+                let shadows: Vec<String> = node
+                    .get("effects")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(box_shadow)
+                    .collect();
+                if !shadows.is_empty() {
+                    tokens.push(Token {
+                        name,
+                        value: TokenValue::Literal(shadows.join(", ")),
+                    });
+                }
+            }
+            Some("TEXT") => tokens.extend(text_tokens(&name, node.get("style"))),
+            _ => {}
+        }
+    }
+
+    tokens
+}
+
+/// Renders tokens sorted by name, so regenerated files only change when the
+/// tokens do.
+pub fn render(tokens: &[Token], format: TokenFormat, prefix: Option<&str>) -> String {
+    let mut tokens = tokens.to_vec();
+    tokens.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let name = |name: &str| match prefix {
+        Some(prefix) => format!("{}-{}", token_name(prefix), name),
+        None => name.to_string(),
+    };
+
+    match format {
+        TokenFormat::Css => {
+            let declarations: String = tokens
+                .iter()
+                .map(|token| {
+                    let value = match &token.value {
+                        TokenValue::Literal(value) => value.clone(),
+                        TokenValue::Alias(target) => format!("var(--{})", name(target)),
+                    };
+                    format!("  --{}: {};\n", name(&token.name), value)
+                })
+                .collect();
+            format!(":root {{\n{}}}\n", declarations)
+        }
+        TokenFormat::Json => {
+            let map: serde_json::Map<String, Value> = tokens
+                .iter()
+                .map(|token| {
+                    let value = match &token.value {
+                        TokenValue::Literal(value) => value.clone(),
+                        TokenValue::Alias(target) => format!("{{{}}}", name(target)),
+                    };
+                    (name(&token.name), Value::String(value))
+                })
+                .collect();
+            let mut json = serde_json::to_string_pretty(&map).unwrap_or_default();
+            json.push('\n');
+            json
+        }
+    }
+}
+
+fn variable_value(
+    variable: &Value,
+    value: &Value,
+    names: &HashMap<&str, String>,
+) -> Option<TokenValue> {
+    if value.get("type").and_then(Value::as_str) == Some("VARIABLE_ALIAS") {
+        let target = names.get(value.get("id")?.as_str()?)?;
+        return Some(TokenValue::Alias(target.clone()));
+    }
+
+    let value = match variable.get("resolvedType").and_then(Value::as_str)? {
+        "COLOR" => css_color(value, 1.0),
+        "FLOAT" => {
+            let number = value.as_f64()?;
+            let scopes = variable.get("scopes").and_then(Value::as_array);
+            let is_pixel = scopes
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .any(|scope| PIXEL_SCOPES.contains(&scope));
+            let number = format_number(number);
+            Some(if is_pixel {
+                format!("{}px", number)
+            } else {
+                number
+            })
+        }
+        "STRING" => Some(json!(value.as_str()?).to_string()),
+        _ => None,
+    }?;
+
+    Some(TokenValue::Literal(value))
+}
+
+fn text_tokens(name: &str, style: Option<&Value>) -> Vec<Token> {
+    let Some(style) = style else {
+        return Vec::new();
+    };
+
+    let mut tokens = Vec::new();
+    let mut push = |suffix: &str, value: String| {
+        tokens.push(Token {
+            name: format!("{}-{}", name, suffix),
+            value: TokenValue::Literal(value),
+        });
+    };
+
+    if let Some(family) = style.get("fontFamily").and_then(Value::as_str) {
+        push("font-family", format!("'{}'", family));
+    }
+    if let Some(size) = style.get("fontSize").and_then(Value::as_f64) {
+        push("font-size", format!("{}px", format_number(size)));
+    }
+    if let Some(weight) = style.get("fontWeight").and_then(Value::as_f64) {
+        push("font-weight", format_number(weight));
+    }
+    if let Some(line_height) = style.get("lineHeightPx").and_then(Value::as_f64) {
+        push("line-height", format!("{}px", format_number(line_height)));
+    }
+
+    tokens
+}
+
+/// Converts a Figma style or variable name like `Color/Primary 500` into
+/// `color-primary-500`.
+pub fn token_name(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn format_number(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+
+    format!("{}", rounded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_name() {
+        assert_eq!(token_name("Color/Primary 500"), "color-primary-500");
+        assert_eq!(token_name(" Spacing / md "), "spacing-md");
+    }
+
+    #[test]
+    fn test_tokens_from_variables() {
+        let response = json!({
+            "meta": {
+                "variableCollections": { "C:1": { "defaultModeId": "1:0" } },
+                "variables": {
+                    "V:1": {
+                        "name": "color/blue",
+                        "resolvedType": "COLOR",
+                        "variableCollectionId": "C:1",
+                        "valuesByMode": {
+                            "1:0": { "r": 0, "g": 0, "b": 1, "a": 1 },
+                            "1:1": { "r": 0, "g": 0, "b": 0.5, "a": 1 }
+                        }
+                    },
+                    "V:2": {
+                        "name": "color/link",
+                        "resolvedType": "COLOR",
+                        "variableCollectionId": "C:1",
+                        "valuesByMode": { "1:0": { "type": "VARIABLE_ALIAS", "id": "V:1" } }
+                    },
+                    "V:3": {
+                        "name": "space/md",
+                        "resolvedType": "FLOAT",
+                        "scopes": ["GAP"],
+                        "variableCollectionId": "C:1",
+                        "valuesByMode": { "1:0": 16 }
+                    }
+                }
+            }
+        });
+
+        let css = render(&from_variables(&response), TokenFormat::Css, Some("ds"));
+        assert_eq!(
+            css,
+            ":root {\n  --ds-color-blue: #0000ff;\n  --ds-color-link: var(--ds-color-blue);\n  --ds-space-md: 16px;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_tokens_from_styles() {
+        let file = json!({
+            "styles": {
+                "1:2": { "name": "Brand/Primary", "styleType": "FILL" },
+                "1:3": { "name": "Heading", "styleType": "TEXT" }
+            }
+        });
+        let nodes = json!({
+            "nodes": {
+                "1:2": { "document": { "fills": [{ "type": "SOLID", "color": { "r": 1, "g": 0, "b": 0, "a": 1 } }] } },
+                "1:3": { "document": { "style": { "fontFamily": "Inter", "fontSize": 32, "fontWeight": 700 } } }
+            }
+        });
+
+        let json = render(&from_styles(&file, &nodes), TokenFormat::Json, None);
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["brand-primary"], "#ff0000");
+        assert_eq!(json["heading-font-size"], "32px");
+        assert_eq!(json["heading-font-weight"], "700");
+    }
+}
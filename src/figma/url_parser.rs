@@ -7,25 +7,34 @@ use crate::{Error, Result};
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FigmaUrlType {
     File { file_id: String, node_id: Option<String> },
+    Team { team_id: String },
+    Project { project_id: String },
     Unknown,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FigmaUrlInfo {
     pub url_type: FigmaUrlType,
+    /// Key of the branch a file URL points into; it works as a file key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_key: Option<String>,
     pub original_url: String,
 }
 
 #[derive(Debug, Clone)]
 pub struct FigmaUrlParser {
     file_regex: Regex,
+    files_regex: Regex,
 }
 
 impl FigmaUrlParser {
     pub fn new() -> Self {
         Self {
-            file_regex: Regex::new(r"^https?://(?:www\.)?figma\.com/(?:file|design)/([A-Za-z0-9]+)(?:/[^?]*)?(?:\?.*node-id=([^&]+))?")
+            file_regex: Regex::new(r"^https?://(?:www\.)?figma\.com/(?:file|design)/([A-Za-z0-9]+)(?:/branch/([A-Za-z0-9]+))?(?:/[^?]*)?(?:\?.*node-id=([^&]+))?")
                 .expect("Invalid file regex"),
+            // Team and project pages, optionally below an organization ID
+            files_regex: Regex::new(r"^https?://(?:www\.)?figma\.com/files/(?:[^/?]+/)?(team|project)/(\d+)")
+                .expect("Invalid files regex"),
         }
     }
 
@@ -36,16 +45,25 @@ impl FigmaUrlParser {
             return Err(Error::InvalidUrl(format!("Not a Figma URL: {}", url_str)));
         }
 
+        let mut branch_key = None;
         let url_type = if let Some(captures) = self.file_regex.captures(url_str) {
             let file_id = captures.get(1).unwrap().as_str().to_string();
-            let node_id = captures.get(2).map(|m| m.as_str().to_string());
+            branch_key = captures.get(2).map(|m| m.as_str().to_string());
+            let node_id = captures.get(3).map(|m| m.as_str().to_string());
             FigmaUrlType::File { file_id, node_id }
+        } else if let Some(captures) = self.files_regex.captures(url_str) {
+            let id = captures.get(2).unwrap().as_str().to_string();
+            match captures.get(1).unwrap().as_str() {
+                "team" => FigmaUrlType::Team { team_id: id },
+                _ => FigmaUrlType::Project { project_id: id },
+            }
         } else {
             FigmaUrlType::Unknown
         };
 
         Ok(FigmaUrlInfo {
             url_type,
+            branch_key,
             original_url: url_str.to_string(),
         })
     }
@@ -105,11 +123,23 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_non_file_figma_url() {
+    fn test_parse_project_url() {
         let parser = FigmaUrlParser::new();
         
         let result = parser.parse("https://www.figma.com/files/project/123456").unwrap();
-        assert_eq!(result.url_type, FigmaUrlType::Unknown);
+        assert_eq!(result.url_type, FigmaUrlType::Project {
+            project_id: "123456".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_parse_team_url_below_organization() {
+        let parser = FigmaUrlParser::new();
+        
+        let result = parser.parse("https://www.figma.com/files/1234567890/team/789012/Design-Team").unwrap();
+        assert_eq!(result.url_type, FigmaUrlType::Team {
+            team_id: "789012".to_string(),
+        });
     }
 
     #[test]
@@ -142,6 +172,21 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_parse_branch_url() {
+        let parser = FigmaUrlParser::new();
+        
+        let result = parser.parse("https://www.figma.com/design/ABC123/branch/XYZ789/my-design?node-id=1-2").unwrap();
+        assert_eq!(result.url_type, FigmaUrlType::File {
+            file_id: "ABC123".to_string(),
+            node_id: Some("1-2".to_string()),
+        });
+        assert_eq!(result.branch_key, Some("XYZ789".to_string()));
+
+        let result = parser.parse("https://www.figma.com/file/ABC123/my-design").unwrap();
+        assert_eq!(result.branch_key, None);
+    }
+
     #[test]
     fn test_extract_file_id_from_design_url() {
         let parser = FigmaUrlParser::new();
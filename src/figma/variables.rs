@@ -0,0 +1,340 @@
+//! Variable collections of a file with their modes and the value of each
+//! variable per mode, from Figma's local variables endpoint.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::codegen::css_color;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariableCollection {
+    pub id: String,
+    pub name: String,
+    /// Mode names in the order Figma lists them
+    pub modes: Vec<String>,
+    pub default_mode: String,
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Variable {
+    pub id: String,
+    /// Slash-separated name, e.g. `color/primary/500`
+    pub name: String,
+    /// `COLOR`, `FLOAT`, `STRING` or `BOOLEAN`
+    #[serde(rename = "type")]
+    pub resolved_type: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+    /// Code names set in Figma per platform (`WEB`, `ANDROID`, `iOS`)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub code_syntax: BTreeMap<String, String>,
+    /// Value per mode name: colors as CSS colors, aliases as
+    /// `{"alias": "<variable name>"}`
+    pub values: BTreeMap<String, Value>,
+}
+
+/// A variable collection a library publishes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublishedCollection {
+    pub id: String,
+    /// The ID files using the library know the collection by
+    pub subscribed_id: String,
+    pub key: String,
+    pub name: String,
+    pub updated_at: String,
+    pub variables: Vec<PublishedVariable>,
+}
+
+/// A published variable, without values: files using the library resolve
+/// those from their own subscription.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublishedVariable {
+    pub id: String,
+    /// The ID in `VARIABLE_ALIAS` values of files using the library
+    pub subscribed_id: String,
+    pub key: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub resolved_type: String,
+    pub updated_at: String,
+}
+
+/// Reads a `GET /files/:key/variables/local` response into its local
+/// collections, sorted by name, with their variables sorted by name.
+/// Variables from libraries are only referenced, by their aliases.
+pub fn collections(response: &Value) -> Vec<VariableCollection> {
+    let object = |pointer: &str| response.pointer(pointer).and_then(Value::as_object);
+    let (Some(collections), variables) = (
+        object("/meta/variableCollections"),
+        object("/meta/variables"),
+    ) else {
+        return Vec::new();
+    };
+    let variables = variables.into_iter().flatten();
+
+    let names: HashMap<&str, &str> = variables
+        .clone()
+        .filter_map(|(id, variable)| Some((id.as_str(), variable.get("name")?.as_str()?)))
+        .collect();
+
+    let mut result: Vec<VariableCollection> = collections
+        .iter()
+        .filter(|(_, collection)| !is_remote(collection))
+        .map(|(id, collection)| {
+            let modes: Vec<(&str, &str)> = collection
+                .get("modes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|mode| {
+                    Some((mode.get("modeId")?.as_str()?, mode.get("name")?.as_str()?))
+                })
+                .collect();
+            let mode_name = |mode_id: &str| {
+                modes
+                    .iter()
+                    .find(|(id, _)| *id == mode_id)
+                    .map_or(mode_id, |(_, name)| name)
+                    .to_string()
+            };
+
+            let mut members: Vec<Variable> = variables
+                .clone()
+                .filter(|(_, variable)| {
+                    !is_remote(variable)
+                        && variable.get("variableCollectionId").and_then(Value::as_str)
+                            == Some(id.as_str())
+                })
+                .map(|(id, variable)| {
+                    let values = variable.get("valuesByMode").and_then(Value::as_object);
+                    let code_syntax = variable.get("codeSyntax").and_then(Value::as_object);
+                    Variable {
+                        id: id.clone(),
+                        name: field(variable, "name"),
+                        resolved_type: field(variable, "resolvedType"),
+                        description: field(variable, "description"),
+                        scopes: strings(variable.get("scopes")),
+                        code_syntax: code_syntax
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|(platform, name)| {
+                                Some((platform.clone(), name.as_str()?.to_string()))
+                            })
+                            .collect(),
+                        values: values
+                            .into_iter()
+                            .flatten()
+                            .map(|(mode, value)| (mode_name(mode), display_value(value, &names)))
+                            .collect(),
+                    }
+                })
+                .collect();
+            members.sort_by(|a, b| a.name.cmp(&b.name));
+
+            VariableCollection {
+                id: id.clone(),
+                name: field(collection, "name"),
+                modes: modes.iter().map(|(_, name)| name.to_string()).collect(),
+                default_mode: mode_name(
+                    collection
+                        .get("defaultModeId")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default(),
+                ),
+                variables: members,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+
+    result
+}
+
+/// Reads a `GET /files/:key/variables/published` response into its
+/// collections, sorted by name, with their variables sorted by name.
+pub fn published(response: &Value) -> Vec<PublishedCollection> {
+    let object = |pointer: &str| response.pointer(pointer).and_then(Value::as_object);
+    let Some(collections) = object("/meta/variableCollections") else {
+        return Vec::new();
+    };
+    let variables = object("/meta/variables");
+
+    let mut result: Vec<PublishedCollection> = collections
+        .iter()
+        .map(|(id, collection)| {
+            let mut members: Vec<PublishedVariable> = variables
+                .into_iter()
+                .flatten()
+                .filter(|(_, variable)| {
+                    variable.get("variableCollectionId").and_then(Value::as_str)
+                        == Some(id.as_str())
+                })
+                .map(|(id, variable)| PublishedVariable {
+                    id: id.clone(),
+                    subscribed_id: field(variable, "subscribed_id"),
+                    key: field(variable, "key"),
+                    name: field(variable, "name"),
+                    resolved_type: field(variable, "resolvedDataType"),
+                    updated_at: field(variable, "updatedAt"),
+                })
+                .collect();
+            members.sort_by(|a, b| a.name.cmp(&b.name));
+
+            PublishedCollection {
+                id: id.clone(),
+                subscribed_id: field(collection, "subscribed_id"),
+                key: field(collection, "key"),
+                name: field(collection, "name"),
+                updated_at: field(collection, "updatedAt"),
+                variables: members,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+
+    result
+}
+
+/// A value as agents read it: colors in CSS notation, aliases by the name
+/// of the variable they point to (or its ID for library variables).
+fn display_value(value: &Value, names: &HashMap<&str, &str>) -> Value {
+    if value.get("type").and_then(Value::as_str) == Some("VARIABLE_ALIAS") {
+        let id = value.get("id").and_then(Value::as_str).unwrap_or_default();
+        return json!({ "alias": names.get(id).copied().unwrap_or(id) });
+    }
+    if let Some(color) = css_color(value, 1.0) {
+        return Value::String(color);
+    }
+
+    value.clone()
+}
+
+fn is_remote(value: &Value) -> bool {
+    value.get("remote").and_then(Value::as_bool) == Some(true)
+}
+
+fn field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collections_with_modes_and_aliases() {
+        let response = json!({
+            "meta": {
+                "variableCollections": {
+                    "C:1": {
+                        "name": "Theme",
+                        "defaultModeId": "1:0",
+                        "modes": [
+                            { "modeId": "1:0", "name": "Light" },
+                            { "modeId": "1:1", "name": "Dark" }
+                        ]
+                    },
+                    "C:2": { "name": "Library", "remote": true, "modes": [] }
+                },
+                "variables": {
+                    "V:1": {
+                        "name": "color/brand",
+                        "resolvedType": "COLOR",
+                        "variableCollectionId": "C:1",
+                        "scopes": ["ALL_FILLS"],
+                        "codeSyntax": { "WEB": "var(--color-brand)" },
+                        "valuesByMode": {
+                            "1:0": { "r": 0, "g": 0.4, "b": 1, "a": 1 },
+                            "1:1": { "r": 0.4, "g": 0.6, "b": 1, "a": 1 }
+                        }
+                    },
+                    "V:2": {
+                        "name": "color/link",
+                        "resolvedType": "COLOR",
+                        "variableCollectionId": "C:1",
+                        "valuesByMode": {
+                            "1:0": { "type": "VARIABLE_ALIAS", "id": "V:1" },
+                            "1:1": { "type": "VARIABLE_ALIAS", "id": "VariableID:abc/1:2" }
+                        }
+                    }
+                }
+            }
+        });
+
+        let collections = collections(&response);
+
+        assert_eq!(collections.len(), 1);
+        let theme = &collections[0];
+        assert_eq!(theme.modes, vec!["Light", "Dark"]);
+        assert_eq!(theme.default_mode, "Light");
+        assert_eq!(theme.variables[0].values["Dark"], json!("#6699ff"));
+        assert_eq!(theme.variables[0].code_syntax["WEB"], "var(--color-brand)");
+        assert_eq!(
+            theme.variables[1].values["Light"],
+            json!({ "alias": "color/brand" })
+        );
+        assert_eq!(
+            theme.variables[1].values["Dark"],
+            json!({ "alias": "VariableID:abc/1:2" })
+        );
+    }
+
+    #[test]
+    fn test_published_collections() {
+        let response = json!({
+            "meta": {
+                "variableCollections": {
+                    "C:1": {
+                        "name": "Tokens",
+                        "key": "ck1",
+                        "subscribed_id": "VariableCollectionId:ck1/9:1",
+                        "updatedAt": "2024-05-01T10:00:00Z"
+                    }
+                },
+                "variables": {
+                    "V:2": {
+                        "name": "space/m",
+                        "key": "vk2",
+                        "subscribed_id": "VariableID:vk2/9:3",
+                        "variableCollectionId": "C:1",
+                        "resolvedDataType": "FLOAT"
+                    },
+                    "V:1": {
+                        "name": "color/brand",
+                        "key": "vk1",
+                        "subscribed_id": "VariableID:vk1/9:2",
+                        "variableCollectionId": "C:1",
+                        "resolvedDataType": "COLOR"
+                    }
+                }
+            }
+        });
+
+        let collections = published(&response);
+
+        assert_eq!(collections[0].key, "ck1");
+        let variables = &collections[0].variables;
+        assert_eq!(variables[0].name, "color/brand");
+        assert_eq!(variables[0].subscribed_id, "VariableID:vk1/9:2");
+        assert_eq!(variables[1].resolved_type, "FLOAT");
+    }
+}
@@ -0,0 +1,109 @@
+//! The version history of a file, from Figma's versions endpoint.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Most versions Figma returns per page.
+pub const MAX_PAGE_SIZE: usize = 50;
+
+/// A saved version of a file, either named by a user or created by
+/// autosave.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Version {
+    pub id: String,
+    pub created_at: String,
+    /// The name given when the version was saved; empty for autosaves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub author: String,
+}
+
+/// Reads one page of a `GET /files/:key/versions` response, newest first.
+pub fn versions(response: &Value) -> Vec<Version> {
+    let versions = response.get("versions").and_then(Value::as_array);
+
+    versions
+        .into_iter()
+        .flatten()
+        .map(|version| {
+            let field = |key: &str| {
+                version
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+            };
+            Version {
+                id: field("id").unwrap_or_default(),
+                created_at: field("created_at").unwrap_or_default(),
+                label: field("label"),
+                description: field("description"),
+                author: version
+                    .pointer("/user/handle")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Whether the response links to a page of older versions.
+pub fn has_next_page(response: &Value) -> bool {
+    response
+        .pointer("/pagination/next_page")
+        .and_then(Value::as_str)
+        .is_some_and(|url| !url.is_empty())
+}
+
+/// The version ID to pass as `before` for the next page, if the response
+/// links to one.
+pub fn next_before(response: &Value) -> Option<String> {
+    if !has_next_page(response) {
+        return None;
+    }
+
+    versions(response).pop().map(|version| version.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_versions_page() {
+        let response = json!({
+            "versions": [
+                {
+                    "id": "2",
+                    "created_at": "2024-05-02T09:00:00Z",
+                    "label": "Handoff",
+                    "description": "",
+                    "user": { "handle": "Ana" }
+                },
+                {
+                    "id": "1",
+                    "created_at": "2024-05-01T09:00:00Z",
+                    "label": null,
+                    "description": null,
+                    "user": { "handle": "Sam" }
+                }
+            ],
+            "pagination": {
+                "next_page": "https://api.figma.com/v1/files/ABC/versions?before=1"
+            }
+        });
+
+        let versions = versions(&response);
+
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].label.as_deref(), Some("Handoff"));
+        assert_eq!(versions[0].description, None);
+        assert_eq!(versions[1].author, "Sam");
+        assert!(has_next_page(&response));
+        assert!(!has_next_page(&json!({ "versions": [] })));
+    }
+}
@@ -0,0 +1,580 @@
+//! Usage guides returned by the `help` tool. Guides to single tools are
+//! generated from the tool definitions (description and input schema), so
+//! they always match what the tools accept; guides to workflows spanning
+//! several tools are written here as [`TOPICS`].
+
+use rmcp::model::Tool;
+use serde_json::{json, Map, Value};
+
+/// A guide to a workflow or concept spanning several tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Topic {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub guide: &'static str,
+}
+
+pub const TOPICS: &[Topic] = &[
+    Topic {
+        name: "workflow",
+        summary: "From a Figma URL to file data, step by step",
+        guide: r#"# Workflow
+
+1. Extract the file key (and node ID) from the URL the user gave:
+   `{"name": "parse_figma_url", "arguments": {"url": "https://www.figma.com/design/aBcD1234EfGh/App?node-id=1-2"}}`
+2. Look at the pages of the file:
+   `{"name": "get_file", "arguments": {"file_key": "aBcD1234EfGh"}}`
+3. Explore the frames of a page or the node from the URL (`1-2` in URLs is `1:2` in the API):
+   `{"name": "get_file_nodes", "arguments": {"file_key": "aBcD1234EfGh", "node_ids": "1:2", "depth": 2}}`
+4. Export what you need to see or ship:
+   `{"name": "export_images", "arguments": {"file_key": "aBcD1234EfGh", "node_ids": "1:2"}}`
+
+In a repository with a `figma-mcp.toml`, call `load_workspace` first: it names the files that belong to the project.
+
+Tool responses are compact JSON by default to save tokens. Pass `pretty: true` to get indented JSON."#,
+    },
+    Topic {
+        name: "navigation",
+        summary: "Exploring large files with the depth parameter without exceeding token limits",
+        guide: r#"# Navigating Large Files
+
+`get_file` and `get_file_nodes` take a `depth` to limit the response size:
+
+- **depth=1** (default): For files: pages only. For nodes: direct children only
+- **depth=2**: For files: pages + top-level objects. For nodes: children + grandchildren
+- **depth=3+**: Deeper traversal (use carefully to avoid large responses)
+
+Strategy:
+
+1. Start with the page structure:
+   `{"name": "get_file", "arguments": {"file_key": "aBcD1234EfGh"}}`
+2. Explore a page's contents by its ID:
+   `{"name": "get_file_nodes", "arguments": {"file_key": "aBcD1234EfGh", "node_ids": "0:1"}}`
+3. Inspect components and frames deeper:
+   `{"name": "get_file_nodes", "arguments": {"file_key": "aBcD1234EfGh", "node_ids": "1:2", "depth": 3}}`
+
+Pass `pages` (names or globs like `Checkout*`) to `get_file` to fetch only those pages.
+
+For depth=3 or more, the server projects the response size from the node counts of earlier, shallower responses. If it exceeds the node limit, the request is refused with an explanation; pass `force: true` to fetch anyway. If a deep `get_file` request fails with a Figma server error or times out, the server retries at depth=1 and returns the page outline with a warning.
+
+Pages and top-level frames whose names carry status markers (`✅`, `WIP`, `🚧`, `[deprecated]`, `v2`, ...) get a `status` field with `state` (`ready`, `review`, `in_progress` or `deprecated`), `version` and the `markers` found; avoid implementing deprecated screens."#,
+    },
+    Topic {
+        name: "resources",
+        summary: "Exported images and large results as MCP resources",
+        guide: r#"# Resources
+
+After exporting images with `export_images`, they are available as MCP resources:
+- List all exported images using the resources API
+- Access image data as base64-encoded blobs
+- Resources are identified by URIs like: `figma://file/{file_key}/node/{node_id}.{format}`
+
+Results of `get_file`, `get_file_nodes`, `get_branch`, `find_hidden_layers`, `suggest_layer_names` and `get_board_graph` larger than `FIGMA_MCP_INLINE_LIMIT` (default: 50000 bytes) come back as a `resource_link` with the URI, size and a short summary instead of the content. Read the `figma://result/{id}` resource when you need the full result, or pass `inline: true` to get it directly. Only the most recent results are kept.
+
+When `FIGMA_MCP_RESOURCE_CHUNK_SIZE` is set, reading an image larger than it returns a JSON manifest with `chunks`: read each `...?chunk=N` URI in order and concatenate the decoded bytes.
+
+Clients that cannot read resources can pass `data_uri: true` to `export_images` to get the images inline:
+`{"name": "export_images", "arguments": {"file_key": "aBcD1234EfGh", "node_ids": "1:2", "data_uri": true}}`
+
+For accessibility reviews, pass `filter` to `export_images` to get PNG exports back as processed images: `protanopia`, `deuteranopia` or `tritanopia` simulate color blindness, `grayscale` checks contrast without hue, and `blur` is a squint test of the visual hierarchy."#,
+    },
+    Topic {
+        name: "urls",
+        summary: "The Figma URL formats parse_figma_url understands",
+        guide: r#"# Supported URL Formats
+
+- File: https://www.figma.com/file/FILE_ID/filename
+- File with node: https://www.figma.com/file/FILE_ID/filename?node-id=1%3A2
+- Design URL: https://www.figma.com/design/FILE_ID/filename?node-id=1-2
+- Branch: https://www.figma.com/design/FILE_ID/branch/BRANCH_KEY/filename (the branch key works as a file key)
+- Team: https://www.figma.com/files/team/TEAM_ID/Team-Name
+- Project: https://www.figma.com/files/project/PROJECT_ID
+
+`{"name": "parse_figma_url", "arguments": {"url": "https://www.figma.com/files/team/1234567890/Design"}}`"#,
+    },
+    Topic {
+        name: "authentication",
+        summary: "Setting up the Figma access token",
+        guide: r#"# Authentication
+
+Set your Figma personal access token as an environment variable:
+export FIGMA_TOKEN="your_figma_token_here"
+
+Get your token from: https://www.figma.com/developers/api#access-tokens
+
+To keep it out of the environment, put `token = "..."` in ~/.config/figma-mcp/config.toml instead.
+
+Where personal access tokens are not allowed, configure a Figma OAuth app with FIGMA_MCP_OAUTH_CLIENT_ID and FIGMA_MCP_OAUTH_CLIENT_SECRET and run `figma-mcp --oauth-login` once; the server then refreshes its access tokens itself.
+
+To work with several accounts, set a token per account as FIGMA_TOKEN_<NAME> (e.g. FIGMA_TOKEN_WORK) and pick one with:
+`{"name": "switch_account", "arguments": {"account": "work"}}`
+
+Check it works with:
+`{"name": "get_me", "arguments": {}}`
+
+Some tools need extra scopes on the token (such as `file_comments:write` or `file_variables:read`) or an Enterprise plan; their guides say so."#,
+    },
+];
+
+/// Tool categories of the overview, in order. Tools not listed are shown
+/// under "Other tools", so a forgotten entry is never hidden.
+const TOOL_GROUPS: &[(&str, &[&str])] = &[
+    (
+        "Files",
+        &[
+            "parse_figma_url",
+            "get_file",
+            "get_branch",
+            "get_file_nodes",
+            "get_file_versions",
+            "export_images",
+            "export_animation",
+            "make_contact_sheet",
+            "generate_alt_text",
+        ],
+    ),
+    (
+        "Background jobs",
+        &["enqueue_export", "list_jobs", "job_result", "cancel_job"],
+    ),
+    (
+        "Libraries",
+        &[
+            "get_file_components",
+            "get_file_component_sets",
+            "get_file_styles",
+            "get_local_variables",
+            "get_published_variables",
+            "get_library_actions",
+            "get_library_usages",
+            "get_team_projects",
+            "get_project_files",
+            "get_team_components",
+            "get_team_component_sets",
+            "get_team_styles",
+        ],
+    ),
+    (
+        "Comments and dev resources",
+        &[
+            "get_comments",
+            "post_comment",
+            "reply_to_comment",
+            "delete_comment",
+            "get_dev_resources",
+            "create_dev_resource",
+            "update_dev_resource",
+            "delete_dev_resource",
+        ],
+    ),
+    (
+        "Analysis",
+        &[
+            "extract_text",
+            "extract_tables",
+            "extract_chart_data",
+            "compare_locales",
+            "get_board_graph",
+            "tally_votes",
+            "infer_roles",
+            "nodes_in_region",
+            "measure_nodes",
+            "find_hidden_layers",
+            "suggest_layer_names",
+            "annotate_contrast",
+            "create_pr_attachment",
+        ],
+    ),
+    (
+        "Workspace and design system",
+        &[
+            "load_workspace",
+            "sync_workspace",
+            "index_files",
+            "query_design_system",
+        ],
+    ),
+    (
+        "Live editor (requires the companion plugin)",
+        &[
+            "get_current_selection",
+            "get_open_file",
+            "selection_to_code",
+            "create_node",
+            "update_node",
+            "rename_layers",
+        ],
+    ),
+    (
+        "Server",
+        &[
+            "get_me",
+            "switch_account",
+            "schema_report",
+            "purge_all",
+            "help",
+        ],
+    ),
+];
+
+/// Parameters left out of examples, since they only change the formatting.
+const QUIET_PARAMETERS: &[&str] = &["pretty", "inline"];
+
+/// The instructions sent to clients on initialization. They name only the
+/// tools this deployment registered, so clients are not pointed at tools
+/// that are disabled, such as the live editor tools without the plugin
+/// bridge.
+pub fn instructions(tools: &[Tool], webhooks: bool) -> String {
+    let has = |name: &str| tools.iter().any(|tool| tool.name == name);
+    let mut text = String::from(
+        "A Figma MCP server that provides tools to access Figma files and export images.",
+    );
+
+    let groups: Vec<String> = TOOL_GROUPS
+        .iter()
+        .filter(|(_, names)| names.iter().any(|name| has(name)))
+        .map(|(title, _)| title.to_lowercase())
+        .collect();
+    if !groups.is_empty() {
+        text.push_str(&format!(" Tools cover: {}.", groups.join("; ")));
+    }
+
+    let writing: Vec<&str> = tools
+        .iter()
+        .filter(|tool| {
+            let annotations = tool.annotations.as_ref();
+            annotations.and_then(|annotations| annotations.read_only_hint) == Some(false)
+        })
+        .map(|tool| tool.name.as_ref())
+        .collect();
+    if writing.is_empty() {
+        text.push_str(" All tools only read data.");
+    } else {
+        text.push_str(&format!(
+            " These tools change data in Figma or on disk, so check with the user before \
+             calling them: {}.",
+            writing.join(", ")
+        ));
+    }
+
+    if webhooks {
+        text.push_str(
+            " File updates and comments arrive as log messages from the figma_webhook logger.",
+        );
+    }
+    if has("help") {
+        text.push_str(" Use 'help' tool for usage instructions.");
+    }
+
+    text
+}
+
+/// The guide for a topic: a workflow from [`TOPICS`] or the name of a tool.
+pub fn guide(topic: &str, tools: &[Tool]) -> Option<String> {
+    let topic = topic.trim().trim_matches('`').to_lowercase();
+
+    if let Some(found) = TOPICS.iter().find(|found| found.name == topic) {
+        return Some(found.guide.to_string());
+    }
+
+    tools
+        .iter()
+        .find(|tool| tool.name == topic.as_str())
+        .map(tool_guide)
+}
+
+/// The overview: the topics and the tools by category.
+pub fn overview(tools: &[Tool]) -> String {
+    let mut text = String::from(
+        "# Figma MCP Server Help\n\n\
+         This MCP server provides tools to access and work with Figma files using file keys, \
+         with depth control to manage response size.\n\n\
+         Call `help` with a `topic` for a focused guide: a workflow below, or a tool name for \
+         its parameters and example calls, e.g. `{\"name\": \"help\", \"arguments\": \
+         {\"topic\": \"get_file\"}}`.\n\n## Topics\n\n",
+    );
+    for topic in TOPICS {
+        text.push_str(&format!("- `{}`: {}\n", topic.name, topic.summary));
+    }
+
+    let mut listed = Vec::new();
+    let mut section = |title: &str, names: &mut dyn Iterator<Item = &Tool>| {
+        let lines: Vec<String> = names
+            .map(|tool| format!("- `{}`: {}\n", tool.name, first_sentence(tool)))
+            .collect();
+        if !lines.is_empty() {
+            text.push_str(&format!("\n## {}\n\n{}", title, lines.concat()));
+        }
+    };
+
+    for (title, names) in TOOL_GROUPS {
+        listed.extend_from_slice(names);
+        let mut group = names
+            .iter()
+            .filter_map(|name| tools.iter().find(|tool| tool.name == *name));
+        section(title, &mut group);
+    }
+    let mut others = tools
+        .iter()
+        .filter(|tool| !listed.contains(&tool.name.as_ref()));
+    section("Other tools", &mut others);
+
+    text
+}
+
+/// A tool's description, parameters and example calls.
+fn tool_guide(tool: &Tool) -> String {
+    let mut text = format!("# `{}`\n\n", tool.name);
+    if let Some(description) = &tool.description {
+        text.push_str(&format!("{}\n", description));
+    }
+
+    let parameters = parameters(tool);
+    if !parameters.is_empty() {
+        text.push_str("\n## Parameters\n\n");
+        for parameter in &parameters {
+            text.push_str(&format!(
+                "- `{}` ({}{}){}\n",
+                parameter.name,
+                parameter.kind,
+                if parameter.required { ", required" } else { "" },
+                parameter
+                    .description
+                    .map(|description| format!(": {}", description))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+
+    text.push_str("\n## Examples\n\n");
+    let required: Vec<&Parameter> = parameters.iter().filter(|p| p.required).collect();
+    text.push_str(&example(tool, &required));
+
+    let optional: Vec<&Parameter> = parameters
+        .iter()
+        .filter(|p| p.required || p.example.is_some())
+        .filter(|p| !QUIET_PARAMETERS.contains(&p.name))
+        .collect();
+    if optional.len() > required.len() {
+        text.push_str("\nWith options:\n\n");
+        text.push_str(&example(tool, &optional));
+    }
+
+    text
+}
+
+/// A parameter read from a tool's input schema.
+struct Parameter<'a> {
+    name: &'a str,
+    kind: String,
+    required: bool,
+    description: Option<&'a str>,
+    example: Option<Value>,
+}
+
+/// The parameters of a tool, required ones first.
+fn parameters(tool: &Tool) -> Vec<Parameter<'_>> {
+    let schema = &tool.input_schema;
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    let properties = schema.get("properties").and_then(Value::as_object);
+
+    let mut parameters: Vec<Parameter> = properties
+        .into_iter()
+        .flatten()
+        .map(|(name, property)| Parameter {
+            name,
+            kind: kind(property),
+            required: required.contains(&name.as_str()),
+            description: property.get("description").and_then(Value::as_str),
+            example: example_value(&tool.name, name),
+        })
+        .collect();
+    parameters.sort_by_key(|parameter| !parameter.required);
+
+    parameters
+}
+
+/// An example call with the given parameters, as the client would send it.
+fn example(tool: &Tool, parameters: &[&Parameter]) -> String {
+    let arguments: Map<String, Value> = parameters
+        .iter()
+        .map(|parameter| {
+            let value = parameter
+                .example
+                .clone()
+                .unwrap_or_else(|| placeholder(&parameter.kind));
+            (parameter.name.to_string(), value)
+        })
+        .collect();
+    // Built by hand so the name comes before the arguments
+    let call = format!(
+        "{{\"name\":{},\"arguments\":{}}}",
+        json!(tool.name),
+        Value::Object(arguments)
+    );
+
+    format!("```json\n{}\n```\n", call)
+}
+
+/// The JSON type of a schema property, without `null` for optional ones.
+fn kind(property: &Value) -> String {
+    let kinds: Vec<&str> = match property.get("type") {
+        Some(Value::String(kind)) => vec![kind],
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|kind| *kind != "null")
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if kinds.is_empty() {
+        "any".to_string()
+    } else {
+        kinds.join(" or ")
+    }
+}
+
+/// A value for parameters without an example, by type.
+fn placeholder(kind: &str) -> Value {
+    match kind.split(' ').next() {
+        Some("integer") => json!(1),
+        Some("number") => json!(1.0),
+        Some("boolean") => json!(true),
+        _ => json!("..."),
+    }
+}
+
+/// Realistic values for common parameters.
+fn example_value(tool: &str, parameter: &str) -> Option<Value> {
+    let value = match parameter {
+        "url" if tool == "parse_figma_url" => {
+            json!("https://www.figma.com/design/aBcD1234EfGh/App?node-id=1-2")
+        }
+        "url" => json!("https://github.com/acme/app/pull/42"),
+        "file_key" => json!("aBcD1234EfGh"),
+        "file_keys" => json!("aBcD1234EfGh,XyZ9876WvUt"),
+        "branch_key" => json!("XyZ9876WvUt"),
+        "node_ids" => json!("1:2,1:3"),
+        "node_id" | "parent_id" => json!("1:2"),
+        "team_id" => json!("1234567890"),
+        "project_id" => json!("987654"),
+        "comment_id" => json!("1234567"),
+        "dev_resource_id" => json!("5678"),
+        "job_id" => json!("export-1"),
+        "message" => json!("The spacing here differs from the spec"),
+        "name" if tool.ends_with("dev_resource") => json!("PR #42"),
+        "asset" => json!("component"),
+        "query" => json!("Button"),
+        "output" => json!("design/handoff"),
+        "node_type" => json!("FRAME"),
+        "start_date" => json!("2024-05-06"),
+        "end_date" => json!("2024-05-12"),
+        "depth" => json!(2),
+        "pages" => json!("Checkout*"),
+        "limit" => json!(10),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+fn first_sentence(tool: &Tool) -> &str {
+    let description = tool.description.as_deref().unwrap_or_default();
+
+    match description.find(". ") {
+        Some(end) => &description[..=end],
+        None => description,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ToolAnnotations;
+    use std::sync::Arc;
+
+    fn tool(name: &str, description: &str, schema: Value) -> Tool {
+        Tool {
+            name: name.to_string().into(),
+            description: Some(description.to_string().into()),
+            input_schema: Arc::new(schema.as_object().unwrap().clone()),
+            annotations: None,
+        }
+    }
+
+    fn tools() -> Vec<Tool> {
+        vec![
+            tool(
+                "get_file",
+                "Get file contents. Pages only by default.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "depth": { "type": "integer", "nullable": true, "description": "Depth to traverse" },
+                        "file_key": { "type": "string", "description": "The Figma file key" },
+                        "pretty": { "type": "boolean", "nullable": true }
+                    },
+                    "required": ["file_key"]
+                }),
+            ),
+            tool(
+                "new_tool",
+                "Does something new",
+                json!({ "type": "object" }),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_tool_guide_from_schema() {
+        let guide = guide("get_file", &tools()).unwrap();
+
+        assert!(guide.contains("- `file_key` (string, required): The Figma file key\n"));
+        assert!(guide.contains("- `depth` (integer): Depth to traverse\n"));
+        assert!(guide.contains(r#"{"name":"get_file","arguments":{"file_key":"aBcD1234EfGh"}}"#));
+        assert!(guide
+            .contains(r#"{"name":"get_file","arguments":{"depth":2,"file_key":"aBcD1234EfGh"}}"#));
+        assert!(!guide.contains("\"pretty\""));
+    }
+
+    #[test]
+    fn test_overview_and_topics() {
+        let overview = overview(&tools());
+        assert!(overview.contains("## Files\n\n- `get_file`: Get file contents.\n"));
+        assert!(overview.contains("## Other tools\n\n- `new_tool`: Does something new\n"));
+        assert!(overview.contains("- `navigation`: "));
+
+        assert!(guide("Navigation", &tools())
+            .unwrap()
+            .starts_with("# Navigating"));
+        assert_eq!(guide("no_such_tool", &tools()), None);
+    }
+
+    #[test]
+    fn test_instructions_name_registered_tools_only() {
+        let mut tools = tools();
+        let text = instructions(&tools, false);
+        assert!(text.contains("Tools cover: files."));
+        assert!(text.contains("All tools only read data."));
+        assert!(!text.contains("live editor"));
+        assert!(!text.contains("'help'"));
+
+        let mut update_node = tool("update_node", "Change a node", json!({ "type": "object" }));
+        update_node.annotations = Some(ToolAnnotations {
+            read_only_hint: Some(false),
+            ..Default::default()
+        });
+        tools.push(update_node);
+        let text = instructions(&tools, true);
+        assert!(text.contains("files; live editor (requires the companion plugin)."));
+        assert!(text.contains("check with the user before calling them: update_node."));
+        assert!(text.contains("figma_webhook"));
+    }
+}
@@ -0,0 +1,280 @@
+//! Streamable HTTP transport, so one server can be deployed remotely and
+//! shared by a team instead of being spawned per client over stdio.
+//!
+//! Clients POST JSON-RPC messages to `/mcp` and receive responses and
+//! notifications as server-sent events. Each client gets its own session,
+//! which ends when the client DELETEs it or after an idle timeout. With a
+//! token configured, requests must carry it as a bearer token.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use hyper_util::server::graceful::GracefulShutdown;
+use rmcp::service::{RoleServer, Service};
+use rmcp::transport::streamable_http_server::session::local::{LocalSessionManager, SessionConfig};
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use tokio::net::TcpListener;
+
+use crate::webhooks::constant_time_eq;
+use crate::{Error, Result};
+
+/// Path of the MCP endpoint.
+pub const MCP_PATH: &str = "/mcp";
+
+/// Sessions are closed after this long without requests, so clients that
+/// went away without ending theirs don't pile up.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Time a client gets to send a request's headers, so slow clients cannot
+/// hold connections open.
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most headers accepted on a request.
+const MAX_HEADERS: usize = 100;
+
+/// Time open connections get to finish when the server shuts down.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+type HttpResponse = Response<BoxBody<Bytes, Infallible>>;
+
+/// Listens on the given address, e.g. `0.0.0.0:3000`, and serves MCP until
+/// the process is interrupted or terminated.
+pub async fn run<S>(
+    addr: &str,
+    new_session: impl Fn() -> Result<S> + Send + Sync + 'static,
+    token: Option<String>,
+) -> Result<()>
+where
+    S: Service<RoleServer> + Send + 'static,
+{
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| Error::Config(format!("Failed to listen on {}: {}", addr, e)))?;
+    if token.is_none() {
+        tracing::warn!(
+            "FIGMA_MCP_HTTP_TOKEN is not set; anyone who can reach {} can use the Figma token",
+            addr
+        );
+    }
+
+    serve(listener, new_session, token, shutdown_signal()).await
+}
+
+/// Serves MCP on the listener until `shutdown` completes, with a service
+/// from `new_session` per session; sessions it fails for are refused. Open
+/// sessions are then closed and connections get a moment to finish.
+pub async fn serve<S>(
+    listener: TcpListener,
+    new_session: impl Fn() -> Result<S> + Send + Sync + 'static,
+    token: Option<String>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()>
+where
+    S: Service<RoleServer> + Send + 'static,
+{
+    let sessions = Arc::new(LocalSessionManager {
+        sessions: Default::default(),
+        session_config: SessionConfig {
+            keep_alive: Some(SESSION_IDLE_TIMEOUT),
+            ..SessionConfig::default()
+        },
+    });
+    let service = StreamableHttpService::new(
+        move || new_session().map_err(|e| std::io::Error::other(e.to_string())),
+        Arc::clone(&sessions),
+        StreamableHttpServerConfig::default(),
+    );
+    let token: Option<Arc<str>> = token.map(Into::into);
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    if let Ok(local_addr) = listener.local_addr() {
+        tracing::info!("MCP server listening on http://{}{}", local_addr, MCP_PATH);
+    }
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept HTTP connection: {}", e);
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let service = service.clone();
+        let token = token.clone();
+        let handler = service_fn(move |request| {
+            let service = service.clone();
+            let token = token.clone();
+            async move { Ok::<_, Infallible>(route(&service, token.as_deref(), request).await) }
+        });
+        let connection = http1::Builder::new()
+            .timer(TokioTimer::new())
+            .header_read_timeout(HEADER_READ_TIMEOUT)
+            .max_headers(MAX_HEADERS)
+            .serve_connection(TokioIo::new(stream), handler);
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!("HTTP connection failed: {}", e);
+            }
+        });
+    }
+
+    // Closing the sessions ends their event streams, which would otherwise
+    // keep the connections open
+    let open: Vec<_> = sessions.sessions.write().await.drain().collect();
+    tracing::info!("Shutting down, closing {} session(s)", open.len());
+    for (_, session) in open {
+        let _ = session.close().await;
+    }
+    if tokio::time::timeout(SHUTDOWN_GRACE, graceful.shutdown())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Dropped HTTP connections that did not finish in time");
+    }
+
+    Ok(())
+}
+
+async fn route<S>(
+    service: &StreamableHttpService<S>,
+    token: Option<&str>,
+    request: Request<Incoming>,
+) -> HttpResponse
+where
+    S: Service<RoleServer> + Send + 'static,
+{
+    if request.uri().path() != MCP_PATH {
+        return plain(StatusCode::NOT_FOUND, "Not found");
+    }
+    if let Some(token) = token {
+        let bearer = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !bearer.is_some_and(|bearer| constant_time_eq(bearer, token)) {
+            let mut response = plain(StatusCode::UNAUTHORIZED, "Unauthorized");
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+            return response;
+        }
+    }
+
+    service.handle(request).await
+}
+
+fn plain(status: StatusCode, message: &'static str) -> HttpResponse {
+    let mut response = Response::new(Full::new(Bytes::from_static(message.as_bytes())).boxed());
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, "text/plain".parse().unwrap());
+    response
+}
+
+/// Completes on Ctrl-C, or on unix when the process is terminated.
+pub(crate) async fn shutdown_signal() {
+    let interrupt = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = interrupt => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => interrupt.await,
+        }
+    }
+    #[cfg(not(unix))]
+    interrupt.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::FigmaServer;
+    use serde_json::{json, Value};
+
+    const ACCEPT: &str = "application/json, text/event-stream";
+
+    #[tokio::test]
+    async fn test_session_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}{}", listener.local_addr().unwrap(), MCP_PATH);
+        let server = FigmaServer::new("test-token".to_string()).unwrap();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(serve(
+            listener,
+            move || Ok(server.clone()),
+            Some("secret".to_string()),
+            async {
+                let _ = stopped.await;
+            },
+        ));
+        let client = reqwest::Client::new();
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" }
+            }
+        });
+
+        let response = client
+            .post(&url)
+            .header("Accept", ACCEPT)
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+
+        let response = client
+            .post(&url)
+            .bearer_auth("secret")
+            .header("Accept", ACCEPT)
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.headers().contains_key("mcp-session-id"));
+        let body = response.text().await.unwrap();
+        let data = body
+            .lines()
+            .find_map(|line| line.strip_prefix("data:"))
+            .unwrap();
+        let message: Value = serde_json::from_str(data.trim()).unwrap();
+        assert_eq!(message["id"], 1);
+        assert!(message["result"]["serverInfo"].is_object());
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,297 @@
+//! Long-running operations (large exports, syncs, indexing of whole files)
+//! run in the background as jobs, so the tool call that starts one returns
+//! immediately and the agent polls for the result.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::{Error, Result};
+
+/// Nodes exported per request by export jobs, so progress can be reported
+/// as batches complete.
+pub const EXPORT_BATCH_SIZE: usize = 50;
+
+/// Job results are readable as resources below this URI.
+pub const JOB_URI_PREFIX: &str = "figma://job/";
+
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Export,
+    Sync,
+    Index,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// How many of a job's items are done, for jobs that know their size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// A background operation and, once it finished, its result.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+    /// The final result, or the partial result while running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip)]
+    pub cancellation: CancellationToken,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+impl Job {
+    pub fn resource_uri(&self) -> String {
+        format!("{}{}", JOB_URI_PREFIX, self.job_id)
+    }
+
+    /// The job without its result, for listings.
+    pub fn summary(&self) -> Value {
+        let mut summary = serde_json::json!(self);
+        if let Some(summary) = summary.as_object_mut() {
+            summary.remove("result");
+            summary.insert(
+                "resource_uri".to_string(),
+                Value::String(self.resource_uri()),
+            );
+        }
+
+        summary
+    }
+}
+
+/// Jobs of this server process, by job ID.
+#[derive(Clone, Default)]
+pub struct Jobs {
+    jobs: Arc<RwLock<HashMap<String, Job>>>,
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a queued job, with progress tracking when `total` is known.
+    pub fn create(&self, kind: JobKind, total: Option<usize>) -> Result<Job> {
+        let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+        let kind_name = serde_json::json!(kind);
+        let job = Job {
+            job_id: format!("{}-{}", kind_name.as_str().unwrap_or("job"), id),
+            kind,
+            status: JobStatus::Queued,
+            progress: total.map(|total| Progress {
+                processed: 0,
+                total,
+            }),
+            result: None,
+            error: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        self.write()?.insert(job.job_id.clone(), job.clone());
+
+        Ok(job)
+    }
+
+    pub fn get(&self, job_id: &str) -> Result<Option<Job>> {
+        Ok(self.read()?.get(job_id).cloned())
+    }
+
+    /// All jobs, oldest first.
+    pub fn list(&self) -> Result<Vec<Job>> {
+        let mut jobs: Vec<Job> = self.read()?.values().cloned().collect();
+        jobs.sort_by_key(|job| job_number(&job.job_id));
+
+        Ok(jobs)
+    }
+
+    /// Applies `change` to a job, returning the updated job.
+    pub fn update(&self, job_id: &str, change: impl FnOnce(&mut Job)) -> Result<Option<Job>> {
+        Ok(self.write()?.get_mut(job_id).map(|job| {
+            change(job);
+            job.clone()
+        }))
+    }
+
+    /// Cancels a job that has not finished yet, aborting its requests.
+    pub fn cancel(&self, job_id: &str) -> Result<Option<Job>> {
+        self.update(job_id, |job| {
+            if !job.status.is_finished() {
+                job.cancellation.cancel();
+                job.status = JobStatus::Cancelled;
+            }
+        })
+    }
+
+    /// Records a job's outcome, unless it was cancelled in the meantime.
+    pub fn finish(&self, job_id: &str, outcome: Result<Value>) -> Result<Option<Job>> {
+        self.update(job_id, |job| {
+            if job.status == JobStatus::Cancelled {
+                return;
+            }
+            match outcome {
+                Ok(result) => {
+                    job.status = JobStatus::Completed;
+                    job.result = Some(result);
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+        })
+    }
+
+    /// Cancels the given jobs if still running and forgets them with their
+    /// results. Returns the number of jobs forgotten.
+    pub fn remove(&self, job_ids: &[&str]) -> Result<usize> {
+        let mut jobs = self.write()?;
+        let mut count = 0;
+        for job_id in job_ids {
+            if let Some(job) = jobs.remove(*job_id) {
+                if !job.status.is_finished() {
+                    job.cancellation.cancel();
+                }
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Cancels the jobs still running and forgets all jobs with their
+    /// results. Returns the number of jobs forgotten.
+    pub fn clear(&self) -> Result<usize> {
+        let mut jobs = self.write()?;
+        for job in jobs.values().filter(|job| !job.status.is_finished()) {
+            job.cancellation.cancel();
+        }
+        let count = jobs.len();
+        jobs.clear();
+
+        Ok(count)
+    }
+
+    fn read(&self) -> Result<std::sync::RwLockReadGuard<'_, HashMap<String, Job>>> {
+        self.jobs
+            .read()
+            .map_err(|_| Error::Internal("Job registry lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, HashMap<String, Job>>> {
+        self.jobs
+            .write()
+            .map_err(|_| Error::Internal("Job registry lock poisoned".to_string()))
+    }
+}
+
+/// Extracts the job ID from a job resource URI.
+pub fn job_id_from_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(JOB_URI_PREFIX)
+}
+
+fn job_number(job_id: &str) -> u64 {
+    job_id
+        .rsplit('-')
+        .next()
+        .and_then(|number| number.parse().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let jobs = Jobs::new();
+        let job = jobs.create(JobKind::Export, Some(2)).unwrap();
+        assert!(job.job_id.starts_with("export-"));
+        assert_eq!(job.status, JobStatus::Queued);
+
+        jobs.finish(&job.job_id, Ok(json!({ "images": {} })))
+            .unwrap();
+
+        let job = jobs.get(&job.job_id).unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.result, Some(json!({ "images": {} })));
+        assert!(job.summary().get("result").is_none());
+        assert_eq!(
+            job_id_from_uri(&job.resource_uri()),
+            Some(job.job_id.as_str())
+        );
+        assert!(jobs.get("export-0").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cancel_keeps_cancelled_status() {
+        let jobs = Jobs::new();
+        let job = jobs.create(JobKind::Sync, None).unwrap();
+
+        jobs.cancel(&job.job_id).unwrap();
+        jobs.finish(&job.job_id, Err(Error::Cancelled)).unwrap();
+
+        let cancelled = jobs.get(&job.job_id).unwrap().unwrap();
+        assert!(job.cancellation.is_cancelled());
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert_eq!(cancelled.error, None);
+
+        // Finished jobs stay as they are
+        let done = jobs.create(JobKind::Index, None).unwrap();
+        jobs.finish(&done.job_id, Ok(Value::Null)).unwrap();
+        jobs.cancel(&done.job_id).unwrap();
+        assert_eq!(
+            jobs.get(&done.job_id).unwrap().unwrap().status,
+            JobStatus::Completed
+        );
+    }
+
+    #[test]
+    fn test_clear_cancels_running_jobs() {
+        let jobs = Jobs::new();
+        let running = jobs.create(JobKind::Export, None).unwrap();
+        let done = jobs.create(JobKind::Index, None).unwrap();
+        jobs.finish(&done.job_id, Ok(Value::Null)).unwrap();
+
+        assert_eq!(jobs.clear().unwrap(), 2);
+        assert!(running.cancellation.is_cancelled());
+        assert!(!done.cancellation.is_cancelled());
+        assert!(jobs.list().unwrap().is_empty());
+
+        // A job finishing after the purge is not stored again
+        assert!(jobs
+            .finish(&running.job_id, Ok(Value::Null))
+            .unwrap()
+            .is_none());
+    }
+}
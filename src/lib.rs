@@ -1,5 +1,30 @@
 pub mod figma;
 pub mod server;
 pub mod error;
+pub mod config;
+pub mod output;
+pub mod bridge;
+pub mod webhooks;
+pub mod workspace;
+pub mod sync;
+pub mod archive;
+pub mod attachment;
+pub mod jobs;
+pub mod results;
+pub mod sessions;
+pub mod protocol;
+pub mod aliases;
+pub mod descriptions;
+pub mod help;
+pub mod raster;
+pub mod accounts;
+pub mod credentials;
+pub mod encryption;
+pub mod http;
+#[cfg(unix)]
+pub mod socket;
+#[cfg(feature = "otel")]
+pub mod telemetry;
 
-pub use error::{Error, Result};
\ No newline at end of file
+pub use config::Config;
+pub use error::{ApiError, Error, Result};
\ No newline at end of file
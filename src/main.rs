@@ -1,21 +1,349 @@
-use figma_mcp::{server::FigmaServer, Result};
+#[cfg(feature = "keychain")]
+use figma_mcp::credentials;
+use figma_mcp::{
+    bridge::PluginBridge,
+    config,
+    figma::oauth::{Authorization, OAuthConfig, OAuthSession},
+    server::FigmaServer,
+    webhooks::WebhookReceiver,
+    Config, Error, Result,
+};
 use std::env;
-use tracing_subscriber::{fmt, EnvFilter};
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    fmt().with_env_filter(EnvFilter::from_default_env()).init();
+    // Initialize tracing on stderr, stdout carries the MCP protocol
+    let registry = tracing_subscriber::registry().with(
+        fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(EnvFilter::from_default_env()),
+    );
 
-    // Get Figma token from environment
-    let figma_token = env::var("FIGMA_TOKEN")
-        .map_err(|_| figma_mcp::Error::Auth(
-            "FIGMA_TOKEN environment variable not set. Get your token from: https://www.figma.com/developers/api#access-tokens".to_string()
-        ))?;
+    // Export this crate's spans over OTLP when an endpoint is configured
+    #[cfg(feature = "otel")]
+    let _otel_guard = {
+        use tracing_subscriber::filter::Targets;
+
+        let guard = figma_mcp::telemetry::OtelGuard::from_env()?;
+        let layer = guard.as_ref().map(|guard| {
+            let targets = Targets::new().with_target("figma_mcp", tracing::Level::INFO);
+            guard.layer().with_filter(targets)
+        });
+        registry.with(layer).init();
+        guard
+    };
+    #[cfg(not(feature = "otel"))]
+    registry.init();
+
+    let mut config = Config::from_env()?;
+    let bridge_addr = config.bridge_addr.clone();
+
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let oauth_login = match args.iter().position(|arg| arg == "--oauth-login") {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    };
+    #[cfg(feature = "keychain")]
+    if args.first().map(String::as_str) == Some("store-token") {
+        return store_token(&args[1..]).await;
+    }
+    if args.first().map(String::as_str) == Some("purge") {
+        return purge(&config, &args[1..]).await;
+    }
+    #[cfg(feature = "keychain")]
+    if let (true, Some(oauth)) = (config.encryption_keychain, &mut config.oauth) {
+        let lookup = tokio::task::spawn_blocking(credentials::keychain_encryption_key);
+        oauth.encryption = Some(
+            lookup
+                .await
+                .map_err(|e| Error::Internal(format!("Keychain lookup failed: {}", e)))??,
+        );
+    }
+    // With OAuth configured, FIGMA_TOKEN is not needed
+    let (figma_token, oauth) = match &config.oauth {
+        Some(oauth) => (
+            String::new(),
+            Some(oauth_session(oauth, oauth_login).await?),
+        ),
+        None if oauth_login => {
+            return Err(Error::Config(
+                "--oauth-login needs FIGMA_MCP_OAUTH_CLIENT_ID and FIGMA_MCP_OAUTH_CLIENT_SECRET"
+                    .to_string(),
+            ))
+        }
+        None => match default_token(&config).await? {
+            Some(figma_token) => (figma_token, None),
+            // Named accounts alone will do as well
+            None if !config.account_tokens.is_empty() => (String::new(), None),
+            None => return Err(Error::Auth(
+                "No Figma token found. Set FIGMA_TOKEN or `token` in ~/.config/figma-mcp/config.toml (get one from: https://www.figma.com/developers/api#access-tokens), or configure OAuth with FIGMA_MCP_OAUTH_CLIENT_ID and FIGMA_MCP_OAUTH_CLIENT_SECRET".to_string()
+            )),
+        },
+    };
+    if oauth_login {
+        return Ok(());
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--deterministic") {
+        args.remove(index);
+        config.deterministic = true;
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--webhook-port") {
+        let Some(port) = args.get(index + 1) else {
+            return Err(Error::Config(
+                "--webhook-port needs a port number".to_string(),
+            ));
+        };
+        config.webhook_port = Some(config::parse_port("--webhook-port", port)?);
+        args.drain(index..=index + 1);
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--http") {
+        let Some(addr) = args.get(index + 1) else {
+            return Err(Error::Config(
+                "--http needs an address, e.g. 0.0.0.0:3000".to_string(),
+            ));
+        };
+        config.http_addr = Some(addr.clone());
+        args.drain(index..=index + 1);
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--socket") {
+        let Some(path) = args.get(index + 1) else {
+            return Err(Error::Config("--socket needs a file path".to_string()));
+        };
+        config.socket_path = Some(path.into());
+        args.drain(index..=index + 1);
+    }
+    let http_addr = config.http_addr.clone();
+    let socket_path = config.socket_path.clone();
+    let webhook_port = config.webhook_port;
+    let webhook_passcode = config.webhook_passcode.clone();
+
+    match args.first().map(String::as_str) {
+        None => {}
+        Some("sync") => {
+            let server = create_server(figma_token, config, oauth)?;
+            return sync(&server, &args[1..]).await;
+        }
+        Some(command) => {
+            return Err(Error::Config(format!(
+                "Unknown command '{}'. Run without arguments to start the MCP server, or use `sync [--manifest PATH] [--deterministic]`, `purge`, `--http ADDR`, `--socket PATH` or `--oauth-login`",
+                command
+            )));
+        }
+    }
 
     // Create and start the server
-    let server = FigmaServer::new(figma_token)?;
-    server.run_stdio().await?;
+    let mut server = create_server(figma_token, config, oauth)?;
+    if let Some(addr) = bridge_addr {
+        server = server.with_bridge(PluginBridge::bind(&addr).await?);
+    }
+    if let Some(port) = webhook_port {
+        let Some(passcode) = webhook_passcode else {
+            return Err(Error::Config(
+                "FIGMA_MCP_WEBHOOK_PASSCODE must be set to receive webhooks".to_string(),
+            ));
+        };
+        let addr = format!("127.0.0.1:{}", port);
+        server = server.with_webhooks(WebhookReceiver::bind(&addr, passcode).await?);
+    }
+    match (http_addr, socket_path) {
+        (Some(_), Some(_)) => {
+            return Err(Error::Config(
+                "Serve either over HTTP or on a socket, not both".to_string(),
+            ))
+        }
+        (Some(addr), None) => server.run_http(&addr).await?,
+        #[cfg(unix)]
+        (None, Some(path)) => server.run_unix_socket(&path).await?,
+        #[cfg(not(unix))]
+        (None, Some(_)) => {
+            return Err(Error::Config(
+                "Unix sockets are not supported on this platform".to_string(),
+            ))
+        }
+        (None, None) => server.run_stdio().await?,
+    }
+
+    Ok(())
+}
+
+fn create_server(
+    figma_token: String,
+    config: Config,
+    oauth: Option<OAuthSession>,
+) -> Result<FigmaServer> {
+    let server = FigmaServer::with_config(figma_token, config)?;
+
+    match oauth {
+        Some(oauth) => server.with_oauth(oauth),
+        None => Ok(server),
+    }
+}
+
+/// The default account's token: `FIGMA_TOKEN`, the credentials file's
+/// `token`, or with the `keychain` feature the OS keychain's.
+async fn default_token(config: &Config) -> Result<Option<String>> {
+    if let Ok(figma_token) = env::var("FIGMA_TOKEN") {
+        return Ok(Some(figma_token));
+    }
+    if let Some(figma_token) = &config.credentials.token {
+        return Ok(Some(figma_token.clone()));
+    }
+
+    #[cfg(feature = "keychain")]
+    {
+        let account = figma_mcp::accounts::DEFAULT_ACCOUNT;
+        let lookup = tokio::task::spawn_blocking(move || credentials::keychain_token(account));
+        lookup
+            .await
+            .map_err(|e| Error::Internal(format!("Keychain lookup failed: {}", e)))?
+    }
+    #[cfg(not(feature = "keychain"))]
+    Ok(None)
+}
+
+/// Stores a token read from stdin in the OS keychain, for the default
+/// account or the one named.
+#[cfg(feature = "keychain")]
+async fn store_token(args: &[String]) -> Result<()> {
+    let account = match args {
+        [] => figma_mcp::accounts::DEFAULT_ACCOUNT.to_string(),
+        [account] => account.to_lowercase(),
+        _ => {
+            return Err(Error::Config(
+                "Usage: figma-mcp store-token [ACCOUNT] < token.txt".to_string(),
+            ))
+        }
+    };
+
+    eprintln!("Paste the Figma token for account '{}':", account);
+    let mut token = String::new();
+    std::io::stdin()
+        .read_line(&mut token)
+        .map_err(|e| Error::Config(format!("Failed to read the token: {}", e)))?;
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        return Err(Error::Config("No token given".to_string()));
+    }
+
+    let stored = account.clone();
+    tokio::task::spawn_blocking(move || credentials::store_keychain_token(&stored, &token))
+        .await
+        .map_err(|e| Error::Internal(format!("Keychain update failed: {}", e)))??;
+    eprintln!(
+        "Stored the token for account '{}' in the OS keychain",
+        account
+    );
+
+    Ok(())
+}
+
+/// Deletes the credentials kept on disk: the OAuth token file and, with
+/// the `keychain` feature, the tokens of the configured accounts and the
+/// encryption key in the OS keychain. The credentials file is the user's
+/// own and stays.
+async fn purge(config: &Config, args: &[String]) -> Result<()> {
+    if !args.is_empty() {
+        return Err(Error::Config("Usage: figma-mcp purge".to_string()));
+    }
+
+    let token_file = match &config.oauth {
+        Some(oauth) => Some(oauth.token_file.clone()),
+        None => OAuthConfig::default_token_file(),
+    };
+    if let Some(path) = token_file {
+        match std::fs::remove_file(&path) {
+            Ok(()) => eprintln!("Removed the OAuth tokens in {}", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(Error::Auth(format!(
+                    "Failed to remove {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        }
+    }
+
+    #[cfg(feature = "keychain")]
+    {
+        let default = figma_mcp::accounts::DEFAULT_ACCOUNT.to_string();
+        let mut entries = vec![(credentials::KEYCHAIN_SERVICE, default.clone())];
+        entries.extend(
+            config
+                .account_tokens
+                .keys()
+                .map(|account| (credentials::KEYCHAIN_SERVICE, account.clone())),
+        );
+        entries.push((credentials::ENCRYPTION_KEYCHAIN_SERVICE, default));
+
+        for (service, account) in entries {
+            let name = account.clone();
+            let deleted = tokio::task::spawn_blocking(move || {
+                credentials::delete_keychain_entry(service, &name)
+            })
+            .await
+            .map_err(|e| Error::Internal(format!("Keychain update failed: {}", e)))??;
+            if deleted {
+                eprintln!("Removed '{}' from the OS keychain ({})", account, service);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resumes the stored OAuth session, or authorizes the app when there is
+/// none or `login` asks to. The authorization URL goes to stderr, stdout
+/// carries the MCP protocol.
+async fn oauth_session(config: &OAuthConfig, login: bool) -> Result<OAuthSession> {
+    if !login {
+        if let Some(session) = OAuthSession::load(config)? {
+            return Ok(session);
+        }
+    }
+
+    let authorization = Authorization::start(config).await?;
+    eprintln!(
+        "Open this URL to give figma-mcp access to Figma:\n\n  {}\n",
+        authorization.url
+    );
+    let session = authorization.finish().await?;
+    eprintln!(
+        "Figma access was granted, tokens are stored in {}",
+        config.token_file.display()
+    );
+
+    Ok(session)
+}
+
+/// Runs the workspace manifest's targets, for use in build scripts and
+/// pre-commit hooks.
+async fn sync(server: &FigmaServer, args: &[String]) -> Result<()> {
+    let manifest = match args {
+        [] => None,
+        [flag, path] if flag == "--manifest" => Some(path.as_str()),
+        _ => {
+            return Err(Error::Config(
+                "Usage: figma-mcp sync [--manifest PATH] [--deterministic]".to_string(),
+            ))
+        }
+    };
+
+    let report = server.run_sync(manifest, CancellationToken::new()).await?;
+
+    for path in &report.written {
+        println!("wrote {}", path.display());
+    }
+    println!(
+        "{} file(s) written, {} unchanged",
+        report.written.len(),
+        report.unchanged.len()
+    );
 
     Ok(())
 }
@@ -0,0 +1,43 @@
+use serde::Serialize;
+
+/// Serializes a tool result, compact unless `pretty` is requested. Compact
+/// output avoids spending a large share of the client's token budget on
+/// indentation.
+pub fn to_json<T: Serialize>(value: &T, pretty: bool) -> String {
+    if pretty {
+        to_pretty_json(value)
+    } else {
+        to_compact_json(value)
+    }
+}
+
+/// Serializes a tool result as pretty-printed JSON.
+pub fn to_pretty_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|e| format!("Serialization error: {}", e))
+}
+
+/// Serializes a tool result as compact JSON.
+pub fn to_compact_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|e| format!("Serialization error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pretty_and_compact_output_match() {
+        let value = json!({ "name": "Page 1", "children": [{ "id": "1:2" }] });
+
+        let pretty = to_pretty_json(&value);
+        let compact = to_compact_json(&value);
+
+        assert!(pretty.contains('\n'));
+        assert!(!compact.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&compact).unwrap()
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! The MCP protocol revisions the server speaks and the features that
+//! differ between them, so older clients get responses they understand.
+
+use rmcp::model::{ProtocolVersion, Tool};
+
+/// Revisions the server speaks, oldest first.
+pub const SUPPORTED_VERSIONS: &[ProtocolVersion] =
+    &[ProtocolVersion::V_2024_11_05, ProtocolVersion::V_2025_03_26];
+
+/// The revision to use with a client asking for `requested`: that revision
+/// if the server speaks it, otherwise the newest older one it speaks, and
+/// the oldest for clients older than all of them.
+pub fn negotiate(requested: &ProtocolVersion) -> ProtocolVersion {
+    SUPPORTED_VERSIONS
+        .iter()
+        .rev()
+        .find(|version| *version <= requested)
+        .unwrap_or(&SUPPORTED_VERSIONS[0])
+        .clone()
+}
+
+/// What a protocol revision supports, where the server's output depends
+/// on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    /// Tool annotations such as `readOnlyHint` (2025-03-26)
+    pub tool_annotations: bool,
+}
+
+impl Features {
+    /// The features of a negotiated revision.
+    pub fn of(version: &ProtocolVersion) -> Self {
+        Self {
+            tool_annotations: *version >= ProtocolVersion::V_2025_03_26,
+        }
+    }
+
+    /// Leaves out the parts of tool definitions the revision lacks.
+    pub fn adapt_tools(&self, mut tools: Vec<Tool>) -> Vec<Tool> {
+        if !self.tool_annotations {
+            tools.iter_mut().for_each(|tool| tool.annotations = None);
+        }
+
+        tools
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ToolAnnotations;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_negotiate() {
+        let version = |version: &str| serde_json::from_str(&format!("\"{}\"", version)).unwrap();
+
+        assert_eq!(
+            negotiate(&ProtocolVersion::V_2024_11_05),
+            ProtocolVersion::V_2024_11_05
+        );
+        assert_eq!(
+            negotiate(&version("2025-06-18")),
+            ProtocolVersion::V_2025_03_26
+        );
+        assert_eq!(
+            negotiate(&version("2024-10-07")),
+            ProtocolVersion::V_2024_11_05
+        );
+    }
+
+    #[test]
+    fn test_annotations_only_for_newer_clients() {
+        let tool = Tool {
+            name: "delete_comment".into(),
+            description: None,
+            input_schema: Arc::new(Default::default()),
+            annotations: Some(ToolAnnotations::default()),
+        };
+
+        let features = Features::of(&ProtocolVersion::V_2024_11_05);
+        let tools = features.adapt_tools(vec![tool.clone()]);
+        assert!(tools[0].annotations.is_none());
+
+        let features = Features::of(&ProtocolVersion::V_2025_03_26);
+        let tools = features.adapt_tools(vec![tool]);
+        assert!(tools[0].annotations.is_some());
+    }
+}
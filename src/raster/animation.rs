@@ -0,0 +1,425 @@
+//! Animated GIF and APNG encoding, for previewing a sequence of frames.
+//!
+//! Frames of different sizes are centered on a canvas as large as the
+//! largest of them. GIF frames share a palette of the most common colors,
+//! which suits flat UI renders; APNG keeps every pixel as is.
+
+use std::collections::HashMap;
+
+use ::png::{BitDepth, ColorType, Encoder};
+
+use super::{png, Image};
+use crate::Result;
+
+pub const ANIMATION_FORMATS: &str = "gif or apng";
+
+/// Colors of the GIF palette, besides the transparent one.
+const MAX_COLORS: usize = 255;
+
+/// Index of the transparent color in the GIF palette.
+const TRANSPARENT: u8 = 0;
+
+/// Largest LZW code in a GIF, 12 bits.
+const MAX_CODE: u16 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "gif" => Some(Self::Gif),
+            "apng" => Some(Self::Apng),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "apng",
+        }
+    }
+
+    /// File extension, which for APNG is that of PNG, so viewers without
+    /// animation support still show the first frame.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "png",
+        }
+    }
+}
+
+/// Encodes the frames as an endlessly looping animation, showing each
+/// for `delay_ms` milliseconds.
+pub fn encode(frames: &[Image], delay_ms: u32, format: AnimationFormat) -> Result<Vec<u8>> {
+    let frames = fit(frames);
+
+    match format {
+        AnimationFormat::Gif => Ok(encode_gif(&frames, delay_ms)),
+        AnimationFormat::Apng => encode_apng(&frames, delay_ms),
+    }
+}
+
+/// Centers the frames on transparent canvases of the largest size.
+fn fit(frames: &[Image]) -> Vec<Image> {
+    let width = frames.iter().map(|frame| frame.width).max().unwrap_or(1);
+    let height = frames.iter().map(|frame| frame.height).max().unwrap_or(1);
+
+    frames
+        .iter()
+        .map(|frame| {
+            if frame.width == width && frame.height == height {
+                return frame.clone();
+            }
+            let mut canvas = Image::new(width, height, [0, 0, 0, 0]);
+            let x = (width - frame.width) as usize / 2;
+            let y = (height - frame.height) as usize / 2;
+            canvas.blit(frame, x, y);
+            canvas
+        })
+        .collect()
+}
+
+fn encode_apng(frames: &[Image], delay_ms: u32) -> Result<Vec<u8>> {
+    let Some(first) = frames.first() else {
+        return Ok(Vec::new());
+    };
+
+    let mut apng = Vec::new();
+    let mut encoder = Encoder::new(&mut apng, first.width, first.height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    // Looping forever, each frame replacing the previous one's pixels
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .and_then(|_| encoder.set_frame_delay(delay_ms.min(u16::MAX as u32) as u16, 1000))
+        .map_err(png::encoding_failed)?;
+
+    // The first frame is the image shown without animation support
+    let mut writer = encoder.write_header().map_err(png::encoding_failed)?;
+    for frame in frames {
+        writer
+            .write_image_data(&frame.pixels)
+            .map_err(png::encoding_failed)?;
+    }
+    writer.finish().map_err(png::encoding_failed)?;
+
+    Ok(apng)
+}
+
+fn encode_gif(frames: &[Image], delay_ms: u32) -> Vec<u8> {
+    let Some(first) = frames.first() else {
+        return Vec::new();
+    };
+    let palette = Palette::new(frames);
+    let width = first.width.min(u16::MAX as u32) as u16;
+    let height = first.height.min(u16::MAX as u32) as u16;
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    // A global table of 256 colors, 8 bits per channel
+    gif.extend_from_slice(&[0xf7, TRANSPARENT, 0]);
+    for index in 0..=255 {
+        gif.extend_from_slice(&palette.color(index));
+    }
+    // Loop forever
+    gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    let delay = (delay_ms.div_ceil(10)).min(u16::MAX as u32) as u16;
+    for frame in frames {
+        // Cleared to transparent before the next frame, with a delay in
+        // hundredths of a second
+        gif.extend_from_slice(&[0x21, 0xf9, 0x04, 0x09]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[TRANSPARENT, 0]);
+
+        gif.push(0x2c);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.push(0);
+
+        let indices = palette.indices(frame);
+        gif.push(8);
+        for block in lzw_compress(&indices, 8).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3b);
+
+    gif
+}
+
+/// The most common colors of the frames, by 15-bit color, with the
+/// nearest of them for every other color.
+struct Palette {
+    colors: Vec<[u8; 3]>,
+    nearest: Vec<u8>,
+}
+
+impl Palette {
+    fn new(frames: &[Image]) -> Self {
+        let mut bins = vec![(0u64, [0u64; 3]); 1 << 15];
+        for pixel in frames.iter().flat_map(|frame| frame.pixels.chunks_exact(4)) {
+            if pixel[3] < 128 {
+                continue;
+            }
+            let (count, sums) = &mut bins[bin(pixel)];
+            *count += 1;
+            for channel in 0..3 {
+                sums[channel] += pixel[channel] as u64;
+            }
+        }
+
+        let mut common: Vec<usize> = (0..bins.len()).filter(|&key| bins[key].0 > 0).collect();
+        common.sort_by_key(|&key| std::cmp::Reverse(bins[key].0));
+        common.truncate(MAX_COLORS);
+
+        let colors: Vec<[u8; 3]> = common
+            .iter()
+            .map(|&key| {
+                let (count, sums) = bins[key];
+                sums.map(|sum| (sum / count) as u8)
+            })
+            .collect();
+
+        // Colors in the palette map to themselves, the others to the
+        // closest entry
+        let mut nearest = vec![TRANSPARENT; 1 << 15];
+        let mut mapped = vec![false; 1 << 15];
+        for (index, &key) in common.iter().enumerate() {
+            nearest[key] = index as u8 + 1;
+            mapped[key] = true;
+        }
+        for key in (0..nearest.len()).filter(|&key| !mapped[key] && bins[key].0 > 0) {
+            let center =
+                [(key >> 10) & 31, (key >> 5) & 31, key & 31].map(|v| ((v << 3) | 4) as i32);
+            let closest = colors.iter().enumerate().min_by_key(|(_, color)| {
+                (0..3)
+                    .map(|channel| (color[channel] as i32 - center[channel]).pow(2))
+                    .sum::<i32>()
+            });
+            if let Some((index, _)) = closest {
+                nearest[key] = index as u8 + 1;
+            }
+        }
+
+        Self { colors, nearest }
+    }
+
+    fn color(&self, index: u8) -> [u8; 3] {
+        match index {
+            TRANSPARENT => [0, 0, 0],
+            index => self
+                .colors
+                .get(index as usize - 1)
+                .copied()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn indices(&self, frame: &Image) -> Vec<u8> {
+        frame
+            .pixels
+            .chunks_exact(4)
+            .map(|pixel| match pixel[3] {
+                0..=127 => TRANSPARENT,
+                _ => self.nearest[bin(pixel)],
+            })
+            .collect()
+    }
+}
+
+fn bin(pixel: &[u8]) -> usize {
+    ((pixel[0] as usize >> 3) << 10) | ((pixel[1] as usize >> 3) << 5) | (pixel[2] as usize >> 3)
+}
+
+/// Compresses palette indices with GIF's variable-width LZW, starting
+/// over once the code table is full.
+fn lzw_compress(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_code_size + 1;
+    let mut writer = BitWriter::default();
+    writer.bits(clear, width);
+
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(current) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = codes.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        writer.bits(current, width);
+        if next < MAX_CODE {
+            codes.insert((current, index), next);
+            // Decoders widen codes once the table outgrows them
+            if next == 1 << width && width < 12 {
+                width += 1;
+            }
+            next += 1;
+        } else {
+            writer.bits(clear, width);
+            codes.clear();
+            next = end + 1;
+            width = min_code_size + 1;
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(current) = prefix {
+        writer.bits(current, width);
+    }
+    writer.bits(end, width);
+
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Writes the low `n` bits of `value`, least significant first.
+    fn bits(&mut self, value: u16, n: u32) {
+        self.buffer |= (value as u32) << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes a GIF LZW stream, as a viewer would.
+    fn lzw_decompress(data: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut width = min_code_size + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        let (mut buffer, mut count, mut bytes) = (0u32, 0u32, data.iter());
+
+        loop {
+            while count < width {
+                buffer |= (*bytes.next().unwrap() as u32) << count;
+                count += 8;
+            }
+            let code = (buffer & ((1 << width) - 1)) as usize;
+            buffer >>= width;
+            count -= width;
+
+            if code == clear {
+                table = (0..clear).map(|value| vec![value as u8]).collect();
+                table.extend([Vec::new(), Vec::new()]);
+                width = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return out;
+            }
+
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [previous.clone(), vec![previous[0]]].concat(),
+                (None, None) => panic!("invalid code"),
+            };
+            if let Some(previous) = previous {
+                if table.len() < MAX_CODE as usize {
+                    table.push([previous, vec![entry[0]]].concat());
+                }
+            }
+            if table.len() == 1 << width && width < 12 {
+                width += 1;
+            }
+            out.extend_from_slice(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    fn frame(width: u32, height: u32, color: [u8; 4]) -> Image {
+        Image::new(width, height, color)
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        // Long enough to widen the codes and restart the table
+        let mut indices: Vec<u8> = (0..40_000u32).map(|i| (i * i / 7 % 251) as u8).collect();
+        indices.extend([3; 5000]);
+
+        let compressed = lzw_compress(&indices, 8);
+
+        assert_eq!(lzw_decompress(&compressed, 8), indices);
+    }
+
+    #[test]
+    fn test_frames_are_centered_on_largest() {
+        let frames = fit(&[frame(4, 2, [255, 0, 0, 255]), frame(2, 2, [0, 0, 255, 255])]);
+
+        assert_eq!((frames[1].width, frames[1].height), (4, 2));
+        // Transparent either side of the smaller frame
+        assert_eq!(&frames[1].pixels[..8], &[0, 0, 0, 0, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_apng_first_frame_is_default_image() {
+        let frames = [frame(3, 2, [255, 0, 0, 255]), frame(3, 2, [0, 255, 0, 255])];
+
+        let apng = encode(&frames, 500, AnimationFormat::Apng).unwrap();
+
+        assert_eq!(png::decode(&apng).unwrap(), frames[0]);
+        let chunks = |kind: &[u8]| apng.windows(4).filter(|window| *window == kind).count();
+        assert_eq!(chunks(b"acTL"), 1);
+        assert_eq!(chunks(b"fcTL"), 2);
+        assert_eq!(chunks(b"fdAT"), 1);
+    }
+
+    #[test]
+    fn test_gif_palette_keeps_common_colors() {
+        let mut first = frame(2, 1, [200, 30, 60, 255]);
+        first.pixels[4..].copy_from_slice(&[0, 0, 0, 0]);
+        let frames = [first, frame(2, 1, [202, 31, 61, 255])];
+
+        let palette = Palette::new(&frames);
+
+        assert_eq!(palette.indices(&frames[0]), vec![1, TRANSPARENT]);
+        assert_eq!(palette.indices(&frames[1]), vec![1, 1]);
+        // Near-identical colors are averaged into one entry
+        assert_eq!(palette.color(1), [201, 30, 60]);
+
+        let gif = encode(&frames, 250, AnimationFormat::Gif).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(gif.last(), Some(&0x3b));
+    }
+}
@@ -0,0 +1,261 @@
+//! Drawing onto RGBA images: rectangles and a tiny bitmap font for labels.
+
+use super::Image;
+
+const RED: [u8; 4] = [229, 57, 53, 255];
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+const BLACK: [u8; 4] = [33, 33, 33, 255];
+
+/// 3x5 glyphs, one row per entry with the leftmost pixel in the high bit.
+const GLYPHS: &[(char, [u8; 5])] = &[
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('<', [0b001, 0b010, 0b100, 0b010, 0b001]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('(', [0b010, 0b100, 0b100, 0b100, 0b010]),
+    (')', [0b010, 0b001, 0b001, 0b001, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+];
+
+/// A numbered box outlined on an image, in pixel coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    /// Legend text, limited to digits and `.:<`
+    pub legend: String,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, color: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat(width as usize * height as usize),
+        }
+    }
+
+    /// Fills a rectangle, clipped to the image.
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, color: [u8; 4]) {
+        let x0 = x.clamp(0, self.width as i64) as usize;
+        let x1 = (x + width).clamp(0, self.width as i64) as usize;
+        let y0 = y.clamp(0, self.height as i64) as usize;
+        let y1 = (y + height).clamp(0, self.height as i64) as usize;
+
+        for row in y0..y1 {
+            let start = (row * self.width as usize + x0) * 4;
+            let end = (row * self.width as usize + x1) * 4;
+            for pixel in self.pixels[start..end].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Draws a translucent rectangle over the image, clipped to it, using
+    /// the alpha of `color`.
+    pub fn blend_rect(&mut self, x: i64, y: i64, width: i64, height: i64, color: [u8; 4]) {
+        let x0 = x.clamp(0, self.width as i64) as usize;
+        let x1 = (x + width).clamp(0, self.width as i64) as usize;
+        let y0 = y.clamp(0, self.height as i64) as usize;
+        let y1 = (y + height).clamp(0, self.height as i64) as usize;
+        let alpha = color[3] as f64 / 255.0;
+
+        for row in y0..y1 {
+            let start = (row * self.width as usize + x0) * 4;
+            let end = (row * self.width as usize + x1) * 4;
+            for pixel in self.pixels[start..end].chunks_exact_mut(4) {
+                let below = pixel[3] as f64 / 255.0 * (1.0 - alpha);
+                let out = alpha + below;
+                if out == 0.0 {
+                    continue;
+                }
+                for channel in 0..3 {
+                    let value =
+                        (color[channel] as f64 * alpha + pixel[channel] as f64 * below) / out;
+                    pixel[channel] = value.round() as u8;
+                }
+                pixel[3] = (out * 255.0).round() as u8;
+            }
+        }
+    }
+
+    /// Outlines a rectangle with lines `thickness` wide, drawn inside it.
+    pub fn stroke_rect(
+        &mut self,
+        x: i64,
+        y: i64,
+        width: i64,
+        height: i64,
+        thickness: i64,
+        color: [u8; 4],
+    ) {
+        self.fill_rect(x, y, width, thickness, color);
+        self.fill_rect(x, y + height - thickness, width, thickness, color);
+        self.fill_rect(x, y, thickness, height, color);
+        self.fill_rect(x + width - thickness, y, thickness, height, color);
+    }
+
+    /// Draws text with the built-in 3x5 font, each font pixel `unit`
+    /// pixels wide. Letters are drawn as capitals, unsupported characters
+    /// as spaces.
+    pub fn draw_text(&mut self, x: i64, y: i64, text: &str, unit: i64, color: [u8; 4]) {
+        for (index, character) in text.chars().enumerate() {
+            let character = match character {
+                '\u{2013}' | '\u{2014}' => '-',
+                character => character.to_ascii_uppercase(),
+            };
+            let Some((_, rows)) = GLYPHS.iter().find(|(glyph, _)| *glyph == character) else {
+                continue;
+            };
+            let left = x + index as i64 * 4 * unit;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        let px = left + column * unit;
+                        let py = y + row as i64 * unit;
+                        self.fill_rect(px, py, unit, unit, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies `other` onto this image with its top-left corner at `(x, y)`.
+    pub(super) fn blit(&mut self, other: &Image, x: usize, y: usize) {
+        let stride = other.width as usize * 4;
+        for (row, pixels) in other.pixels.chunks_exact(stride.max(1)).enumerate() {
+            let start = ((y + row) * self.width as usize + x) * 4;
+            self.pixels[start..start + stride].copy_from_slice(pixels);
+        }
+    }
+}
+
+/// Outlines each annotation with a numbered badge and appends a legend
+/// below the image listing each number with its legend text.
+pub fn annotate(image: &Image, annotations: &[Annotation]) -> Image {
+    let unit = (image.width.max(image.height) as i64 / 400).max(2);
+    let line_height = 9 * unit;
+    let legend_height = if annotations.is_empty() {
+        0
+    } else {
+        annotations.len() as i64 * line_height + 2 * unit
+    };
+
+    let mut canvas = Image::new(image.width, image.height + legend_height as u32, WHITE);
+    canvas.blit(image, 0, 0);
+
+    for (index, annotation) in annotations.iter().enumerate() {
+        let number = (index + 1).to_string();
+        canvas.stroke_rect(
+            annotation.x,
+            annotation.y,
+            annotation.width,
+            annotation.height,
+            unit,
+            RED,
+        );
+        badge(
+            &mut canvas,
+            annotation.x,
+            annotation.y - 7 * unit,
+            &number,
+            unit,
+        );
+
+        let y = image.height as i64 + 2 * unit + index as i64 * line_height;
+        badge(&mut canvas, 2 * unit, y, &number, unit);
+        canvas.draw_text(
+            (4 * number.len() as i64 + 5) * unit,
+            y + unit,
+            &annotation.legend,
+            unit,
+            BLACK,
+        );
+    }
+
+    canvas
+}
+
+/// Draws a label in white on a red box, kept inside the image.
+fn badge(image: &mut Image, x: i64, y: i64, label: &str, unit: i64) {
+    let width = (4 * label.len() as i64 + 1) * unit;
+    let height = 7 * unit;
+    let x = x.clamp(0, (image.width as i64 - width).max(0));
+    let y = y.clamp(0, (image.height as i64 - height).max(0));
+
+    image.fill_rect(x, y, width, height, RED);
+    image.draw_text(x + unit, y + unit, label, unit, WHITE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_outlines_and_adds_legend() {
+        let image = Image::new(100, 100, WHITE);
+        let annotations = vec![Annotation {
+            x: 20,
+            y: 40,
+            width: 30,
+            height: 20,
+            legend: "2.5:1 < 4.5:1".to_string(),
+        }];
+
+        let annotated = annotate(&image, &annotations);
+
+        let pixel = |x: u32, y: u32| {
+            let offset = ((y * annotated.width + x) * 4) as usize;
+            [
+                annotated.pixels[offset],
+                annotated.pixels[offset + 1],
+                annotated.pixels[offset + 2],
+                annotated.pixels[offset + 3],
+            ]
+        };
+        assert_eq!(annotated.height, 100 + 18 + 4);
+        assert_eq!(pixel(20, 50), RED);
+        assert_eq!(pixel(35, 50), WHITE);
+        assert_eq!(pixel(5, 106), RED);
+    }
+}
@@ -0,0 +1,287 @@
+use super::{png, Image};
+use crate::Result;
+
+/// Linear RGB matrices simulating dichromacy at full severity, from Machado,
+/// Oliveira and Fernandes (2009).
+const PROTANOPIA: [[f64; 3]; 3] = [
+    [0.152286, 1.052583, -0.204868],
+    [0.114503, 0.786281, 0.099216],
+    [-0.003882, -0.048116, 1.051998],
+];
+const DEUTERANOPIA: [[f64; 3]; 3] = [
+    [0.367322, 0.860646, -0.227968],
+    [0.280085, 0.672501, 0.047413],
+    [-0.011820, 0.042940, 0.968881],
+];
+const TRITANOPIA: [[f64; 3]; 3] = [
+    [1.255528, -0.076749, -0.178779],
+    [-0.078411, 0.930809, 0.147602],
+    [0.004733, 0.691367, 0.303900],
+];
+/// Rec. 709 luminance weights, applied to every channel.
+const GRAYSCALE: [[f64; 3]; 3] = [
+    [0.2126, 0.7152, 0.0722],
+    [0.2126, 0.7152, 0.0722],
+    [0.2126, 0.7152, 0.0722],
+];
+
+/// Blur radius as a fraction of the image's longer side, strong enough that
+/// only the visual hierarchy survives, as when squinting at a screen.
+const SQUINT_RADIUS: f64 = 0.01;
+
+/// Filter names accepted by [`Filter::parse`], for error messages.
+pub const FILTER_NAMES: &str = "protanopia, deuteranopia, tritanopia, grayscale or blur";
+
+/// Post-processing applied to exported images for accessibility reviews.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// Red-blind color vision
+    Protanopia,
+    /// Green-blind color vision
+    Deuteranopia,
+    /// Blue-blind color vision
+    Tritanopia,
+    /// Luminance only, to check contrast without relying on hue
+    Grayscale,
+    /// Heavy blur for a "squint test" of the visual hierarchy
+    Blur,
+}
+
+impl Filter {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "protanopia" => Some(Self::Protanopia),
+            "deuteranopia" => Some(Self::Deuteranopia),
+            "tritanopia" => Some(Self::Tritanopia),
+            "grayscale" | "greyscale" => Some(Self::Grayscale),
+            "blur" | "squint" => Some(Self::Blur),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Protanopia => "protanopia",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Tritanopia => "tritanopia",
+            Self::Grayscale => "grayscale",
+            Self::Blur => "blur",
+        }
+    }
+
+    pub fn apply(&self, image: &mut Image) {
+        match self {
+            Self::Protanopia => color_matrix(image, &PROTANOPIA),
+            Self::Deuteranopia => color_matrix(image, &DEUTERANOPIA),
+            Self::Tritanopia => color_matrix(image, &TRITANOPIA),
+            Self::Grayscale => color_matrix(image, &GRAYSCALE),
+            Self::Blur => {
+                let longer_side = image.width.max(image.height) as f64;
+                let radius = (longer_side * SQUINT_RADIUS).round().max(2.0) as usize;
+                blur(image, radius);
+            }
+        }
+    }
+}
+
+/// Decodes a PNG, applies `filter` and encodes the result as PNG.
+pub fn apply_to_png(data: &[u8], filter: Filter) -> Result<Vec<u8>> {
+    let mut image = png::decode(data)?;
+    filter.apply(&mut image);
+
+    png::encode(&image)
+}
+
+/// Transforms each pixel's color in linear RGB, keeping alpha.
+fn color_matrix(image: &mut Image, matrix: &[[f64; 3]; 3]) {
+    let to_linear: Vec<f64> = (0..=255)
+        .map(|v| srgb_to_linear(v as f64 / 255.0))
+        .collect();
+
+    for pixel in image.pixels.chunks_exact_mut(4) {
+        let rgb = [
+            to_linear[pixel[0] as usize],
+            to_linear[pixel[1] as usize],
+            to_linear[pixel[2] as usize],
+        ];
+        for (channel, row) in pixel.iter_mut().zip(matrix) {
+            let linear = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+            *channel = (linear_to_srgb(linear.clamp(0.0, 1.0)) * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Approximates a Gaussian blur with three box blur passes per axis. Colors
+/// are premultiplied by alpha while blurring so transparent pixels don't
+/// darken edges.
+fn blur(image: &mut Image, radius: usize) {
+    let width = image.width as usize;
+    let height = image.height as usize;
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let mut samples: Vec<f64> = image
+        .pixels
+        .chunks_exact(4)
+        .flat_map(|pixel| {
+            let alpha = pixel[3] as f64 / 255.0;
+            [
+                pixel[0] as f64 * alpha,
+                pixel[1] as f64 * alpha,
+                pixel[2] as f64 * alpha,
+                pixel[3] as f64,
+            ]
+        })
+        .collect();
+
+    for _ in 0..3 {
+        // Rows, then columns
+        for y in 0..height {
+            box_blur(&mut samples, y * width * 4, 4, width, radius);
+        }
+        for x in 0..width {
+            box_blur(&mut samples, x * 4, width * 4, height, radius);
+        }
+    }
+
+    for (pixel, sample) in image
+        .pixels
+        .chunks_exact_mut(4)
+        .zip(samples.chunks_exact(4))
+    {
+        let alpha = sample[3] / 255.0;
+        for channel in 0..3 {
+            let value = if alpha > 0.0 {
+                sample[channel] / alpha
+            } else {
+                0.0
+            };
+            pixel[channel] = value.round().clamp(0.0, 255.0) as u8;
+        }
+        pixel[3] = sample[3].round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// Blurs `count` RGBA samples starting at `start`, `step` apart, with a
+/// moving average over `2 * radius + 1` samples. Edges are extended.
+fn box_blur(samples: &mut [f64], start: usize, step: usize, count: usize, radius: usize) {
+    let line: Vec<[f64; 4]> = (0..count)
+        .map(|i| {
+            let offset = start + i * step;
+            [
+                samples[offset],
+                samples[offset + 1],
+                samples[offset + 2],
+                samples[offset + 3],
+            ]
+        })
+        .collect();
+    let at = |i: isize| line[i.clamp(0, count as isize - 1) as usize];
+    let window = (2 * radius + 1) as f64;
+
+    let mut sum = [0.0; 4];
+    for i in -(radius as isize)..=radius as isize {
+        let sample = at(i);
+        for channel in 0..4 {
+            sum[channel] += sample[channel];
+        }
+    }
+
+    for i in 0..count {
+        let offset = start + i * step;
+        for channel in 0..4 {
+            samples[offset + channel] = sum[channel] / window;
+        }
+
+        let leaving = at(i as isize - radius as isize);
+        let entering = at(i as isize + radius as isize + 1);
+        for channel in 0..4 {
+            sum[channel] += entering[channel] - leaving[channel];
+        }
+    }
+}
+
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(rgba: [u8; 4]) -> Image {
+        Image {
+            width: 1,
+            height: 1,
+            pixels: rgba.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_dichromacy_keeps_gray_and_alpha() {
+        for filter in [Filter::Protanopia, Filter::Deuteranopia, Filter::Tritanopia] {
+            let mut image = pixel([128, 128, 128, 100]);
+            filter.apply(&mut image);
+
+            for (actual, expected) in image.pixels.iter().zip([128, 128, 128, 100]) {
+                assert!(actual.abs_diff(expected) <= 1, "{}", filter.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_grayscale() {
+        let mut image = pixel([255, 0, 0, 255]);
+        Filter::Grayscale.apply(&mut image);
+
+        let [r, g, b, a] = image.pixels[..] else {
+            panic!("expected one pixel");
+        };
+        assert!(r == g && g == b);
+        assert!(r > 100 && r < 150);
+        assert_eq!(a, 255);
+    }
+
+    #[test]
+    fn test_blur_spreads_and_keeps_flat_areas() {
+        // Three passes of radius 2 spread a pixel by at most 6 in each axis
+        let mut image = Image {
+            width: 21,
+            height: 21,
+            pixels: [255, 255, 255, 255].repeat(21 * 21),
+        };
+        let center = (10 * 21 + 10) * 4;
+        image.pixels[center..center + 3].copy_from_slice(&[0, 0, 0]);
+        blur(&mut image, 2);
+
+        assert!(image.pixels[center] > 200);
+        assert!(image.pixels[center - 4] < 255);
+        assert_eq!(&image.pixels[..4], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_protanopia_confuses_red_and_green() {
+        let mut red = pixel([255, 0, 0, 255]);
+        let mut green = pixel([0, 128, 0, 255]);
+        Filter::Protanopia.apply(&mut red);
+        Filter::Protanopia.apply(&mut green);
+
+        // Both end up as olive tones with no meaningful blue
+        assert!(red.pixels[0].abs_diff(red.pixels[1]) < 60);
+        assert!(green.pixels[0].abs_diff(green.pixels[1]) < 60);
+        assert!(red.pixels[2] < 40 && green.pixels[2] < 40);
+    }
+}
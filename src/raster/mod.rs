@@ -0,0 +1,19 @@
+pub mod animation;
+pub mod draw;
+pub mod filters;
+pub mod png;
+pub mod sheet;
+pub mod watermark;
+
+pub use animation::AnimationFormat;
+pub use draw::Annotation;
+pub use filters::Filter;
+
+/// An 8-bit RGBA image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major RGBA samples
+    pub pixels: Vec<u8>,
+}
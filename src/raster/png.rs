@@ -0,0 +1,196 @@
+//! PNG decoding to RGBA and encoding from RGBA, on top of the `png` crate.
+//!
+//! Decoding expands any PNG to 8-bit RGBA, refusing images whose pixels
+//! would take more than `MAX_DECODED_BYTES`. Encoding always writes 8-bit
+//! RGBA.
+
+use ::png::{BitDepth, ColorType, DecodeOptions, Decoder, Encoder, Limits, Transformations};
+
+use super::Image;
+use crate::{Error, Result};
+
+pub const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Largest RGBA pixel buffer a decoded image may take, 64 megapixels:
+/// beyond any export, but refusing headers that claim gigapixel images
+/// before anything is allocated for them.
+pub const MAX_DECODED_BYTES: usize = 64 * 1024 * 1024 * 4;
+
+pub fn decode(data: &[u8]) -> Result<Image> {
+    let mut options = DecodeOptions::default();
+    options.set_ignore_adler32(false);
+    let mut decoder = Decoder::new_with_options(data, options);
+    decoder.set_limits(Limits {
+        bytes: MAX_DECODED_BYTES,
+    });
+    // 8 bits per sample, palettes and transparency expanded to alpha
+    decoder.set_transformations(Transformations::normalize_to_color8() | Transformations::ALPHA);
+
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let (width, height) = reader.info().size();
+    let size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|size| *size <= MAX_DECODED_BYTES)
+        .ok_or_else(|| {
+            Error::Internal(format!(
+                "Invalid PNG: {}x{} pixels is too large to decode",
+                width, height
+            ))
+        })?;
+    if reader.output_buffer_size() > size {
+        return Err(invalid("unexpected output size"));
+    }
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let output = reader.next_frame(&mut buffer).map_err(invalid)?;
+    buffer.truncate(output.buffer_size());
+
+    let pixels = match output.color_type {
+        ColorType::Rgba => buffer,
+        ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|sample| [sample[0], sample[1], sample[2], 255])
+            .collect(),
+        ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|sample| [sample[0], sample[0], sample[0], sample[1]])
+            .collect(),
+        ColorType::Grayscale => buffer
+            .iter()
+            .flat_map(|&gray| [gray, gray, gray, 255])
+            .collect(),
+        ColorType::Indexed => return Err(invalid("palette was not expanded")),
+    };
+
+    Ok(Image {
+        width: output.width,
+        height: output.height,
+        pixels,
+    })
+}
+
+pub fn encode(image: &Image) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = Encoder::new(&mut png, image.width, image.height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(encoding_failed)?;
+    writer
+        .write_image_data(&image.pixels)
+        .map_err(encoding_failed)?;
+    writer.finish().map_err(encoding_failed)?;
+
+    Ok(png)
+}
+
+pub(super) fn encoding_failed(e: ::png::EncodingError) -> Error {
+    Error::Internal(format!("Failed to encode PNG: {}", e))
+}
+
+fn invalid(e: impl std::fmt::Display) -> Error {
+    Error::Internal(format!("Invalid PNG: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An 8-bit PNG with `data` as its compressed image data, whatever
+    /// the header claims.
+    fn png_with(width: u32, height: u32, color: ColorType, data: &[u8]) -> Vec<u8> {
+        let mut png = Vec::new();
+        let mut encoder = Encoder::new(&mut png, width, height);
+        encoder.set_color(color);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_chunk(::png::chunk::IDAT, data).unwrap();
+        // Writes IEND
+        drop(writer);
+
+        png
+    }
+
+    /// The zlib stream of `data` in stored blocks, with its Adler-32.
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        let mut stream = vec![0x78, 0x01, 1];
+        stream.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        stream.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+        stream.extend_from_slice(data);
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in data {
+            a = (a + byte as u32) % 65521;
+            b = (b + a) % 65521;
+        }
+        stream.extend_from_slice(&((b << 16) | a).to_be_bytes());
+        stream
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let image = Image {
+            width: 3,
+            height: 2,
+            pixels: vec![
+                255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, //
+                0, 0, 0, 0, 128, 128, 128, 128, 255, 255, 255, 255,
+            ],
+        };
+
+        let decoded = decode(&encode(&image).unwrap()).unwrap();
+
+        assert_eq!(decoded, image);
+    }
+
+    #[test]
+    fn test_decode_filtered_rgb() {
+        // 2x2 RGB image with a Sub and a Paeth filtered row
+        let raw = [
+            1, 10, 20, 30, 5, 5, 5, // Sub: (10,20,30), (15,25,35)
+            4, 1, 1, 1, 1, 1, 1, // Paeth: above + 1
+        ];
+
+        let image = decode(&png_with(2, 2, ColorType::Rgb, &zlib_stored(&raw))).unwrap();
+
+        assert_eq!(
+            image.pixels,
+            vec![10, 20, 30, 255, 15, 25, 35, 255, 11, 21, 31, 255, 16, 26, 36, 255]
+        );
+    }
+
+    #[test]
+    fn test_oversized_header_is_refused() {
+        // Claims 4 billion by 4 billion pixels over a few bytes of data
+        let png = png_with(
+            u32::MAX >> 1,
+            u32::MAX >> 1,
+            ColorType::Rgba,
+            &zlib_stored(&[0; 5]),
+        );
+
+        let error = decode(&png).unwrap_err();
+
+        assert!(error.to_string().contains("Invalid PNG"));
+    }
+
+    #[test]
+    fn test_malformed_data_is_refused() {
+        let raw = [0, 1, 2, 3, 255];
+        let mut stream = zlib_stored(&raw);
+
+        // Wrong checksum
+        let end = stream.len() - 1;
+        stream[end] ^= 1;
+        assert!(decode(&png_with(1, 1, ColorType::Rgba, &stream)).is_err());
+
+        // Fewer rows than the header claims
+        let short = zlib_stored(&raw);
+        assert!(decode(&png_with(1, 2, ColorType::Rgba, &short)).is_err());
+
+        // Cut short
+        let png = png_with(1, 1, ColorType::Rgba, &zlib_stored(&raw));
+        assert!(decode(&png).is_ok());
+        assert!(decode(&png[..png.len() - 20]).is_err());
+        assert!(decode(b"\x89PNG\r\n").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Contact sheets: frames scaled down into a labeled grid, to overview a
+//! flow or a page of screens in one image.
+
+use super::Image;
+
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+const GRAY: [u8; 4] = [97, 97, 97, 255];
+const BORDER: [u8; 4] = [224, 224, 224, 255];
+
+/// Size of a font pixel in the labels.
+const UNIT: i64 = 2;
+
+/// Space around and between cells.
+const PADDING: i64 = 8 * UNIT;
+
+/// Height of the label line below each thumbnail.
+const LABEL_HEIGHT: i64 = 9 * UNIT;
+
+/// A frame on the sheet, with the label shown below it.
+#[derive(Debug, Clone)]
+pub struct Tile {
+    pub label: String,
+    pub image: Image,
+}
+
+/// Lays the tiles out left to right in `columns` columns of cells
+/// `cell_width` pixels wide. Each frame is scaled down to fit its cell,
+/// at most twice as tall as wide, over white; rows are as tall as their
+/// tallest thumbnail plus the labels.
+pub fn compose(tiles: &[Tile], columns: usize, cell_width: u32) -> Image {
+    let columns = columns.clamp(1, tiles.len().max(1));
+    let cell = cell_width.max(1) as i64;
+    let thumbnails: Vec<Image> = tiles
+        .iter()
+        .map(|tile| thumbnail(&tile.image, cell_width, cell_width * 2))
+        .collect();
+    let row_heights: Vec<i64> = thumbnails
+        .chunks(columns)
+        .map(|row| {
+            let tallest = row.iter().map(|image| image.height).max().unwrap_or(0);
+            tallest as i64 + LABEL_HEIGHT
+        })
+        .collect();
+
+    let width = columns as i64 * (cell + PADDING) + PADDING;
+    let height = row_heights
+        .iter()
+        .map(|height| height + PADDING)
+        .sum::<i64>()
+        + PADDING;
+    let mut sheet = Image::new(width as u32, height as u32, WHITE);
+
+    // Labels get as many characters as fit the cell
+    let max_chars = (cell / (4 * UNIT)).max(1) as usize;
+    let mut top = PADDING;
+    for (row, row_height) in row_heights.iter().enumerate() {
+        for column in 0..columns {
+            let index = row * columns + column;
+            let (Some(tile), Some(image)) = (tiles.get(index), thumbnails.get(index)) else {
+                break;
+            };
+            let left = PADDING + column as i64 * (cell + PADDING);
+            let x = left + (cell - image.width as i64) / 2;
+
+            sheet.fill_rect(
+                x - 1,
+                top - 1,
+                image.width as i64 + 2,
+                image.height as i64 + 2,
+                BORDER,
+            );
+            sheet.blit(image, x as usize, top as usize);
+            let label = truncate(&tile.label, max_chars);
+            let label_top = top + row_height - LABEL_HEIGHT + 2 * UNIT;
+            sheet.draw_text(left, label_top, &label, UNIT, GRAY);
+        }
+        top += row_height + PADDING;
+    }
+
+    sheet
+}
+
+/// Scales the image down to fit `max_width` by `max_height`, averaging the
+/// pixels each thumbnail pixel covers, and flattens it over white.
+fn thumbnail(image: &Image, max_width: u32, max_height: u32) -> Image {
+    let factor = (max_width as f64 / image.width.max(1) as f64)
+        .min(max_height as f64 / image.height.max(1) as f64)
+        .min(1.0);
+    let width = ((image.width as f64 * factor).round() as u32).max(1);
+    let height = ((image.height as f64 * factor).round() as u32).max(1);
+
+    let span = |index: u32, size: u32, source: u32| {
+        let start = (index as u64 * source as u64 / size as u64) as usize;
+        let end = ((index as u64 + 1) * source as u64 / size as u64) as usize;
+        start..end.max(start + 1).min(source.max(1) as usize)
+    };
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        let rows = span(y, height, image.height);
+        for x in 0..width {
+            let columns = span(x, width, image.width);
+            let mut sums = [0u64; 3];
+            let mut count = 0u64;
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    let offset = (row * image.width as usize + column) * 4;
+                    let Some(pixel) = image.pixels.get(offset..offset + 4) else {
+                        continue;
+                    };
+                    let alpha = pixel[3] as u64;
+                    for channel in 0..3 {
+                        sums[channel] +=
+                            (pixel[channel] as u64 * alpha + 255 * (255 - alpha)) / 255;
+                    }
+                    count += 1;
+                }
+            }
+            let count = count.max(1);
+            pixels.extend(sums.map(|sum| (sum / count) as u8));
+            pixels.push(255);
+        }
+    }
+
+    Image {
+        width,
+        height,
+        pixels,
+    }
+}
+
+fn truncate(label: &str, max_chars: usize) -> String {
+    if label.chars().count() <= max_chars {
+        return label.to_string();
+    }
+    let kept: String = label.chars().take(max_chars.saturating_sub(2)).collect();
+
+    format!("{}..", kept.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * image.width + x) * 4) as usize;
+        image.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_thumbnail_averages_and_flattens() {
+        // Black and transparent columns average to gray over white
+        let mut image = Image::new(4, 2, [0, 0, 0, 255]);
+        for y in 0..2 {
+            for x in [1, 3] {
+                let offset = ((y * 4 + x) * 4) as usize;
+                image.pixels[offset..offset + 4].copy_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+
+        let small = thumbnail(&image, 2, 4);
+
+        assert_eq!((small.width, small.height), (2, 1));
+        assert_eq!(pixel(&small, 0, 0), [127, 127, 127, 255]);
+        // Never scaled up
+        assert_eq!(thumbnail(&image, 40, 40).width, 4);
+    }
+
+    #[test]
+    fn test_compose_grid() {
+        let tiles: Vec<Tile> = ["Home", "Cart", "Checkout"]
+            .iter()
+            .map(|label| Tile {
+                label: label.to_string(),
+                image: Image::new(200, 100, [0, 0, 255, 255]),
+            })
+            .collect();
+
+        let sheet = compose(&tiles, 2, 100);
+
+        // Two columns, two rows of 50 pixel thumbnails
+        assert_eq!(sheet.width as i64, 2 * (100 + PADDING) + PADDING);
+        assert_eq!(
+            sheet.height as i64,
+            2 * (50 + LABEL_HEIGHT + PADDING) + PADDING
+        );
+        let (x, y) = (PADDING as u32, PADDING as u32);
+        assert_eq!(pixel(&sheet, x + 50, y + 25), [0, 0, 255, 255]);
+        assert_eq!(pixel(&sheet, x - 1, y), BORDER);
+        // The third cell is on the second row, the fourth left empty
+        let second_row = y + 50 + LABEL_HEIGHT as u32 + PADDING as u32;
+        assert_eq!(pixel(&sheet, x + 50, second_row + 25), [0, 0, 255, 255]);
+        assert_eq!(
+            pixel(&sheet, x + 100 + PADDING as u32 + 50, second_row + 25),
+            WHITE
+        );
+    }
+
+    #[test]
+    fn test_long_labels_are_truncated() {
+        assert_eq!(truncate("Checkout", 12), "Checkout");
+        assert_eq!(truncate("Checkout confirmation", 12), "Checkout c..");
+    }
+}
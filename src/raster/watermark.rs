@@ -0,0 +1,136 @@
+//! Watermarks stamped on exported images, so frames that leave through
+//! agent transcripts stay marked as confidential.
+
+use regex::Regex;
+
+use super::{png, Image};
+use crate::{Error, Result};
+
+/// Opacity of the band behind the text, over the image.
+const BAND_ALPHA: u8 = 160;
+const BAND: [u8; 3] = [33, 33, 33];
+const TEXT: [u8; 4] = [255, 255, 255, 255];
+
+/// Formats [`stamp`] can watermark, for error messages.
+pub const WATERMARK_FORMATS: &str = "png or svg";
+
+/// Whether [`stamp`] can watermark exports in this format.
+pub fn supports(format: &str) -> bool {
+    matches!(format, "png" | "svg")
+}
+
+/// Watermarks an exported PNG or SVG image.
+pub fn stamp(data: &[u8], format: &str, text: &str) -> Result<Vec<u8>> {
+    match format {
+        "png" => {
+            let mut image = png::decode(data)?;
+            watermark(&mut image, text);
+            png::encode(&image)
+        }
+        "svg" => watermark_svg(data, text),
+        _ => Err(Error::Internal(format!(
+            "Cannot watermark {} images, only {}",
+            format, WATERMARK_FORMATS
+        ))),
+    }
+}
+
+/// Draws `text` in white on a translucent band along the bottom edge,
+/// sized to the image. Characters the built-in font lacks are left out.
+pub fn watermark(image: &mut Image, text: &str) {
+    let columns = (4 * text.chars().count() as i64 - 1).max(1);
+    // The text spans up to half the width, on a band up to a fifth of the
+    // height
+    let unit = (image.width as i64 / 2 / columns)
+        .min(image.height as i64 / 35)
+        .max(1);
+    let band_height = 7 * unit;
+    let top = image.height as i64 - band_height;
+    let [r, g, b] = BAND;
+
+    image.blend_rect(
+        0,
+        top,
+        image.width as i64,
+        band_height,
+        [r, g, b, BAND_ALPHA],
+    );
+    let left = (image.width as i64 - columns * unit) / 2;
+    image.draw_text(left.max(unit), top + unit, text, unit, TEXT);
+}
+
+/// Appends the band and text to an SVG, positioned in its viewBox.
+fn watermark_svg(data: &[u8], text: &str) -> Result<Vec<u8>> {
+    let svg = std::str::from_utf8(data).map_err(|_| invalid("not UTF-8"))?;
+    let view_box = Regex::new(r#"<svg[^>]*\sviewBox="([^"]+)""#).unwrap();
+    let numbers: Vec<f64> = view_box
+        .captures(svg)
+        .map(|captures| {
+            captures[1]
+                .split([' ', ','])
+                .filter_map(|number| number.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let [x, y, width, height] = numbers[..] else {
+        return Err(invalid("no viewBox"));
+    };
+    let Some(end) = svg.rfind("</svg>") else {
+        return Err(invalid("no closing tag"));
+    };
+
+    let font_size = (height / 12.0).min(width / 1.2 / text.chars().count().max(1) as f64);
+    let band_height = font_size * 1.6;
+    let stamp = format!(
+        r##"<g class="watermark"><rect x="{x}" y="{band_y}" width="{width}" height="{band_height}" fill="#212121" fill-opacity="{opacity:.2}"/><text x="{text_x}" y="{text_y}" font-family="sans-serif" font-size="{font_size}" font-weight="bold" fill="#ffffff" text-anchor="middle">{text}</text></g>"##,
+        band_y = y + height - band_height,
+        opacity = BAND_ALPHA as f64 / 255.0,
+        text_x = x + width / 2.0,
+        text_y = y + height - band_height / 2.0 + font_size * 0.35,
+        text = escape_xml(text),
+    );
+
+    Ok(format!("{}{}{}", &svg[..end], stamp, &svg[end..]).into_bytes())
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn invalid(message: &str) -> Error {
+    Error::Internal(format!("Invalid SVG: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watermark_png_band() {
+        let mut image = Image::new(200, 100, [255, 255, 255, 255]);
+        watermark(&mut image, "CONFIDENTIAL");
+
+        let pixel = |x: u32, y: u32| {
+            let offset = ((y * image.width + x) * 4) as usize;
+            image.pixels[offset..offset + 4].to_vec()
+        };
+        // Above the band nothing changed, the band darkens the image
+        assert_eq!(pixel(0, 50), [255, 255, 255, 255]);
+        assert!(pixel(0, 99)[0] < 150);
+        assert!(image.pixels.chunks(4).any(|pixel| pixel == TEXT));
+    }
+
+    #[test]
+    fn test_watermark_svg() {
+        let svg = br#"<svg width="100" height="50" viewBox="0 0 100 50" fill="none"><rect width="100" height="50"/></svg>"#;
+        let stamped = String::from_utf8(stamp(svg, "svg", "Internal <draft>").unwrap()).unwrap();
+
+        assert!(stamped.ends_with("</g></svg>"));
+        assert!(stamped.contains(">Internal &lt;draft&gt;</text>"));
+        assert!(stamp(b"<svg></svg>", "svg", "x").is_err());
+        assert!(stamp(svg, "pdf", "x").is_err());
+    }
+}
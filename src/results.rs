@@ -0,0 +1,201 @@
+//! Large tool results kept in memory and returned as resource links, so a
+//! tool call answers with a short reference and the client reads the
+//! content only when it needs it.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Stored results are readable as resources below this URI.
+pub const RESULT_URI_PREFIX: &str = "figma://result/";
+
+/// Results kept at a time; storing another drops the oldest.
+pub const MAX_STORED_RESULTS: usize = 32;
+
+static NEXT_RESULT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A tool result held for reading as a resource.
+#[derive(Debug, Clone)]
+pub struct StoredResult {
+    pub result_id: String,
+    pub tool: String,
+    pub description: String,
+    pub text: String,
+}
+
+/// What a tool returns in place of a large result, shaped like an MCP
+/// `resource_link` content block with a summary of the content added.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLink {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    pub mime_type: &'static str,
+    /// Size of the content in bytes
+    pub size: usize,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub summary: Value,
+}
+
+impl StoredResult {
+    pub fn resource_uri(&self) -> String {
+        format!("{}{}", RESULT_URI_PREFIX, self.result_id)
+    }
+
+    /// File name for saving the result: `get_file-3.json`.
+    pub fn filename(&self) -> String {
+        format!("{}.json", self.result_id)
+    }
+}
+
+/// Large results of this server process, oldest first.
+#[derive(Clone, Default)]
+pub struct Results {
+    results: Arc<RwLock<VecDeque<StoredResult>>>,
+}
+
+impl Results {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the JSON output of a tool and returns a link to it.
+    pub fn store(
+        &self,
+        tool: &str,
+        description: String,
+        text: String,
+        summary: Value,
+    ) -> Result<ResourceLink> {
+        let id = NEXT_RESULT_ID.fetch_add(1, Ordering::Relaxed);
+        let result = StoredResult {
+            result_id: format!("{}-{}", tool, id),
+            tool: tool.to_string(),
+            description,
+            text,
+        };
+        let link = ResourceLink {
+            kind: "resource_link",
+            uri: result.resource_uri(),
+            name: result.filename(),
+            description: result.description.clone(),
+            mime_type: "application/json",
+            size: result.text.len(),
+            summary,
+        };
+
+        let mut results = self.write()?;
+        if results.len() >= MAX_STORED_RESULTS {
+            results.pop_front();
+        }
+        results.push_back(result);
+
+        Ok(link)
+    }
+
+    pub fn get(&self, result_id: &str) -> Result<Option<StoredResult>> {
+        Ok(self
+            .read()?
+            .iter()
+            .find(|result| result.result_id == result_id)
+            .cloned())
+    }
+
+    /// All stored results, oldest first.
+    pub fn list(&self) -> Result<Vec<StoredResult>> {
+        Ok(self.read()?.iter().cloned().collect())
+    }
+
+    /// Drops the given stored results. Returns the number dropped.
+    pub fn remove(&self, result_ids: &[&str]) -> Result<usize> {
+        let mut results = self.write()?;
+        let before = results.len();
+        results.retain(|result| !result_ids.contains(&result.result_id.as_str()));
+
+        Ok(before - results.len())
+    }
+
+    /// Drops all stored results. Returns the number dropped.
+    pub fn clear(&self) -> Result<usize> {
+        let mut results = self.write()?;
+        let count = results.len();
+        results.clear();
+
+        Ok(count)
+    }
+
+    fn read(&self) -> Result<std::sync::RwLockReadGuard<'_, VecDeque<StoredResult>>> {
+        self.results
+            .read()
+            .map_err(|_| Error::Internal("Result store lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, VecDeque<StoredResult>>> {
+        self.results
+            .write()
+            .map_err(|_| Error::Internal("Result store lock poisoned".to_string()))
+    }
+}
+
+/// Extracts the result ID from a result resource URI.
+pub fn result_id_from_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(RESULT_URI_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_store_and_read_back() {
+        let results = Results::new();
+        let link = results
+            .store(
+                "get_file",
+                "File abc".to_string(),
+                "{\"name\":\"App\"}".to_string(),
+                json!({ "name": "App" }),
+            )
+            .unwrap();
+
+        assert_eq!(link.kind, "resource_link");
+        assert_eq!(link.size, 14);
+        assert_eq!(
+            link.name,
+            format!("{}.json", result_id_from_uri(&link.uri).unwrap())
+        );
+        let result_id = result_id_from_uri(&link.uri).unwrap();
+        let stored = results.get(result_id).unwrap().unwrap();
+        assert_eq!(stored.text, "{\"name\":\"App\"}");
+        assert_eq!(stored.tool, "get_file");
+    }
+
+    #[test]
+    fn test_oldest_results_are_dropped() {
+        let results = Results::new();
+        let links: Vec<ResourceLink> = (0..=MAX_STORED_RESULTS)
+            .map(|i| {
+                let text = i.to_string();
+                results
+                    .store("get_file_nodes", String::new(), text, Value::Null)
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(results.list().unwrap().len(), MAX_STORED_RESULTS);
+        let first = result_id_from_uri(&links[0].uri).unwrap();
+        assert!(results.get(first).unwrap().is_none());
+
+        assert_eq!(results.clear().unwrap(), MAX_STORED_RESULTS);
+        assert!(results.list().unwrap().is_empty());
+    }
+}
@@ -1,43 +1,268 @@
 use base64::{engine::general_purpose, Engine as _};
 use rmcp::{
-    handler::server::{router::tool::ToolRouter, tool::Parameters},
+    handler::server::{
+        router::tool::ToolRouter,
+        tool::{Parameters, ToolCallContext},
+    },
     model::*,
     schemars,
-    service::{RequestContext, RoleServer},
-    tool, tool_handler, tool_router,
+    service::{NotificationContext, Peer, RequestContext, RoleServer},
+    tool, tool_router,
     transport::stdio,
     Error as McpError, ServerHandler, ServiceExt,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
 use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{field, Instrument};
 
+#[cfg(unix)]
+use crate::socket;
 use crate::{
-    figma::{FigmaClient, FigmaUrlParser, ImageCache},
-    Error,
+    accounts::Accounts,
+    aliases::{self, TOOL_ALIASES},
+    attachment::Attachment,
+    bridge::PluginBridge,
+    figma::{
+        analytics::{self, AnalyticsFormat, Asset, Metric, ANALYTICS_FORMAT_NAMES, ASSET_NAMES},
+        bounds::{self, Bounds, BoundsKind, NodeBounds, BOUNDS_NAMES},
+        branches,
+        charts::{self, Chart},
+        codegen::{self, CodeFormat},
+        comments::{self, CommentAnchor, CommentThread},
+        contrast::{self, ContrastIssue},
+        dev_resources::{self, DevResource},
+        export::{self, ExportFailure, ExportFormat, ExportOptions},
+        figjam::{self, BoardGraph, VoteTally},
+        freshness::{FileMeta, FileMetaCache, Freshness},
+        index::{AssetKind, FileSummary},
+        layers::{self, LayerReport},
+        library::{self, PublishedComponent, PublishedComponentSet, PublishedStyle},
+        locales::{self, LocaleGroup, DEFAULT_MAX_LENGTH_RATIO},
+        naming::{self, NamingIssue},
+        node_stats::DOCUMENT_NODE_ID,
+        normalize,
+        oauth::OAuthSession,
+        pages::{self, NameFilter},
+        projects, response_cache,
+        retry::RetryPolicy,
+        roles::{self, RoleLabel},
+        schema::ShapeReport,
+        status,
+        tables::{self, Table, TableFormat, TABLE_FORMAT_NAMES},
+        text,
+        variables::{self, PublishedCollection, VariableCollection},
+        versions, DesignIndex, FigmaClient, FigmaUrlParser, FileIndex, ImageCache, ImageEntry,
+        NodeStats,
+    },
+    help, http,
+    jobs::{self, Job, JobKind, JobStatus, Jobs},
+    output,
+    protocol::{self, Features},
+    raster::{
+        animation::{self, AnimationFormat, ANIMATION_FORMATS},
+        draw,
+        filters::{self, FILTER_NAMES},
+        png,
+        sheet::{self, Tile},
+        watermark::{self, WATERMARK_FORMATS},
+        Annotation, Filter,
+    },
+    results::{self, Results},
+    sessions::{ResourceOwners, Session, Visibility},
+    sync::{self, SyncReport},
+    webhooks::{WebhookEvent, WebhookReceiver},
+    workspace::{self, Workspace},
+    Config, Error,
 };
 
+/// Logger name of the log messages that carry webhook events.
+const WEBHOOK_LOGGER: &str = "figma_webhook";
+
+const ALT_TEXT_SYSTEM_PROMPT: &str = "You write alt text for user interface designs. \
+Describe the purpose and content of the image in one or two sentences (at most 150 characters). \
+Do not start with \"Image of\" and reply with the alt text only.";
+
+// Questions relayed to the user when a required parameter is missing. MCP
+// elicitation would let us ask the user directly, but the rmcp version in use
+// does not support it yet, so the question is returned as a tool error the
+// client's model can forward instead.
+const FILE_KEY_QUESTION: &str =
+    "Which Figma file should be used? Ask the user for a file URL and extract its key with parse_figma_url.";
+const NODE_IDS_QUESTION: &str =
+    "Which nodes should be used? Ask the user for a URL with a node-id, \
+or list the pages and frames with get_file and let the user pick.";
+const TEAM_ID_QUESTION: &str =
+    "Which team should be used? Ask the user for the URL of the team's page in Figma and \
+extract the team ID with parse_figma_url.";
+const PROJECT_ID_QUESTION: &str =
+    "Which project should be used? List the team's projects with get_team_projects, or ask the \
+user for a project URL and extract its ID with parse_figma_url.";
+const COMMENT_ID_QUESTION: &str =
+    "Which comment should be used? List the threads with get_comments and pick one.";
+const BRANCH_KEY_QUESTION: &str =
+    "Which branch should be used? Ask the user for the branch URL and extract its key with \
+parse_figma_url, or list the file's branches with get_file and branch_data: true.";
+const DEV_RESOURCE_ID_QUESTION: &str =
+    "Which dev resource should be used? List them with get_dev_resources and pick one.";
+const RENAMES_QUESTION: &str =
+    "Which layers should be renamed, and to what? List poorly named layers with \
+suggest_layer_names and confirm the new names with the user.";
+
+// Share of the get_file timeout given to a deep fetch before falling back to
+// depth 1, so the fallback still has time to complete.
+const DEEP_FETCH_TIMEOUT_SHARE: f64 = 0.67;
+
+// Fetches at this depth or deeper are checked against the projected node count
+const GUARDED_DEPTH: u32 = 3;
+
+// Frames export_animation stitches at most, as every frame is kept decoded
+const MAX_ANIMATION_FRAMES: usize = 60;
+
+// How long export_animation shows each frame by default
+const DEFAULT_FRAME_DELAY_MS: u32 = 1000;
+
+// Frames make_contact_sheet lays out at most
+const MAX_CONTACT_SHEET_FRAMES: usize = 100;
+
+// Width of the contact sheet cells, and the range it may be set to
+const DEFAULT_THUMBNAIL_WIDTH: u32 = 320;
+const THUMBNAIL_WIDTHS: std::ops::RangeInclusive<u32> = 32..=1024;
+
+// Node types create_node can add through the companion plugin
+const CREATABLE_NODE_TYPES: &[&str] = &["FRAME", "TEXT", "RECTANGLE"];
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
 #[derive(Clone)]
 pub struct FigmaServer {
-    client: FigmaClient,
+    accounts: Accounts,
     url_parser: FigmaUrlParser,
     image_cache: ImageCache,
+    node_stats: NodeStats,
+    design_index: DesignIndex,
+    jobs: Jobs,
+    results: Results,
+    /// Latest known version of each file, to flag stale cached data
+    file_meta: FileMetaCache,
+    /// Sessions that created each image, result and job
+    owners: ResourceOwners,
+    /// The HTTP or socket session this server answers; unset for stdio,
+    /// whose only client sees everything
+    session: Option<Arc<Session>>,
+    bridge: Option<PluginBridge>,
+    webhooks: Option<WebhookReceiver>,
+    /// Lowest level of log messages the client asked for, as a
+    /// `LoggingLevel` discriminant
+    log_level: Arc<AtomicU8>,
+    config: Config,
     tool_router: ToolRouter<FigmaServer>,
 }
 
 #[tool_router]
 impl FigmaServer {
     pub fn new(figma_token: String) -> std::result::Result<Self, Error> {
-        let client = FigmaClient::new(figma_token)?;
+        Self::with_config(figma_token, Config::default())
+    }
+
+    pub fn with_config(figma_token: String, config: Config) -> std::result::Result<Self, Error> {
+        let mut client = FigmaClient::builder(figma_token).max_pages(config.max_pages);
+        if let Some(user_agent) = &config.user_agent {
+            client = client.user_agent(user_agent);
+        }
+        if let Some(api_version) = &config.api_version {
+            client = client.api_version(api_version);
+        }
+        if let Some(timeout) = config.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(timeout) = config.request_timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(retries) = config.retries {
+            client = client.retry(RetryPolicy {
+                max_retries: retries,
+                ..RetryPolicy::default()
+            });
+        }
+        if let Some(proxy) = &config.proxy {
+            client = client.proxy(proxy);
+        }
+        if let Some(requests_per_minute) = config.rate_limit {
+            client = client.rate_limit(Some(requests_per_minute));
+        }
+        if let Some(max_bytes) = config.etag_cache_size {
+            client = client.etag_cache(max_bytes);
+        }
+        let response_ttl = config
+            .response_cache_ttl
+            .unwrap_or(response_cache::DEFAULT_TTL);
+        client = client.response_cache(response_ttl);
+        let client = client.gateway(config.gateway.clone()).build()?;
+        let accounts = Accounts::new(client, &config.account_tokens, &config.account_gateways)?;
         let url_parser = FigmaUrlParser::new();
+        let mut tool_router = Self::tool_router();
+        if !config.account_tokens.is_empty() {
+            tool_router += Self::account_tool_router();
+        }
+        config.descriptions.check(&tool_router.list_all())?;
 
         Ok(Self {
-            client,
+            accounts,
             url_parser,
             image_cache: ImageCache::new(),
-            tool_router: Self::tool_router(),
+            node_stats: NodeStats::new(),
+            design_index: DesignIndex::new(),
+            jobs: Jobs::new(),
+            results: Results::new(),
+            file_meta: FileMetaCache::new(),
+            owners: ResourceOwners::new(),
+            session: None,
+            bridge: None,
+            webhooks: None,
+            log_level: Arc::new(AtomicU8::new(LoggingLevel::Debug as u8)),
+            config,
+            tool_router,
         })
     }
 
+    /// Enables the tools that read the live editor state through the
+    /// companion Figma plugin.
+    pub fn with_bridge(mut self, bridge: PluginBridge) -> Self {
+        self.bridge = Some(bridge);
+        self.tool_router += Self::bridge_tool_router();
+        self
+    }
+
+    /// Forwards the file updates and comments the receiver gets to the
+    /// client as log messages.
+    pub fn with_webhooks(mut self, webhooks: WebhookReceiver) -> Self {
+        tokio::spawn(self.clone().forget_updated_files(webhooks.subscribe()));
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Authenticates Figma requests of the default account with the
+    /// session's OAuth access tokens instead of a personal access token.
+    pub fn with_oauth(mut self, oauth: OAuthSession) -> crate::Result<Self> {
+        self.accounts = self.accounts.with_oauth(oauth)?;
+        Ok(self)
+    }
+
+    /// The client of the account in use, for tool handlers.
+    fn client(&self) -> Result<FigmaClient, McpError> {
+        self.accounts
+            .client()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))
+    }
+
     pub async fn run_stdio(self) -> std::result::Result<(), Error> {
         tracing::info!("Starting Figma MCP server");
 
@@ -55,10 +280,89 @@ impl FigmaServer {
         Ok(())
     }
 
+    /// Serves MCP over streamable HTTP on the given address until the
+    /// process is interrupted, with a session per client. Sessions share
+    /// the caches and jobs but switch accounts on their own; which of each
+    /// other's resources they see is set by `FIGMA_MCP_RESOURCE_VISIBILITY`.
+    pub async fn run_http(self, addr: &str) -> std::result::Result<(), Error> {
+        tracing::info!("Starting Figma MCP server");
+
+        let token = self.config.http_token.clone();
+        http::run(addr, move || self.for_session(), token).await
+    }
+
+    /// Serves MCP on a Unix socket file until the process is interrupted,
+    /// with a session per connection. The file is removed on shutdown.
+    #[cfg(unix)]
+    pub async fn run_unix_socket(self, path: &std::path::Path) -> std::result::Result<(), Error> {
+        tracing::info!("Starting Figma MCP server");
+
+        let listener = socket::bind(path, self.config.socket_mode).await?;
+        tracing::info!("MCP server listening on {}", path.display());
+        tokio::select! {
+            _ = self.accept_connections(listener) => {}
+            _ = http::shutdown_signal() => {}
+        }
+        let _ = std::fs::remove_file(path);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    async fn accept_connections(&self, listener: tokio::net::UnixListener) {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept socket connection: {}", e);
+                    continue;
+                }
+            };
+
+            let server = match self.for_session() {
+                Ok(server) => server,
+                Err(e) => {
+                    tracing::warn!("Failed to start socket session: {}", e);
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                match server.serve(stream).await {
+                    Ok(service) => {
+                        let _ = service.waiting().await;
+                    }
+                    Err(e) => tracing::debug!("Socket session failed to start: {:?}", e),
+                }
+            });
+        }
+    }
+
+    /// A server for another HTTP or socket session, with its own log level,
+    /// session ID and account in use. Caches are shared with the other
+    /// sessions. Once the session has ended and its last clone is dropped,
+    /// what it created and no other session did is released.
+    fn for_session(&self) -> crate::Result<Self> {
+        let server = Self {
+            session: None,
+            ..self.clone()
+        };
+        let session = Session::new(move |id| match server.purge_session(id) {
+            Ok(purged) => tracing::debug!("Session {} ended, released {}", id, purged),
+            Err(e) => tracing::warn!("Failed to release data of session {}: {}", id, e),
+        });
+
+        Ok(Self {
+            accounts: self.accounts.for_session()?,
+            session: Some(Arc::new(session)),
+            log_level: Arc::new(AtomicU8::new(LoggingLevel::Debug as u8)),
+            ..self.clone()
+        })
+    }
+
     #[tool(description = "Parse a Figma URL to extract IDs and determine the URL type")]
     async fn parse_figma_url(
         &self,
-        Parameters(ParseUrlRequest { url }): Parameters<ParseUrlRequest>,
+        Parameters(ParseUrlRequest { url, pretty }): Parameters<ParseUrlRequest>,
     ) -> Result<CallToolResult, McpError> {
         let url_info = match self.url_parser.parse(&url) {
             Ok(parsed) => parsed,
@@ -68,8 +372,7 @@ impl FigmaServer {
             }
         };
 
-        let result = serde_json::to_string_pretty(&url_info)
-            .unwrap_or_else(|e| format!("Serialization error: {}", e));
+        let result = output::to_json(&url_info, pretty.unwrap_or(false));
 
         tool_success(result)
     }
@@ -77,21 +380,219 @@ impl FigmaServer {
     #[tool(description = "Get file contents from a Figma file using file key")]
     async fn get_file(
         &self,
-        Parameters(GetFileRequest { file_key, depth }): Parameters<GetFileRequest>,
+        Parameters(GetFileRequest {
+            file_key,
+            depth,
+            force,
+            exclude_hidden,
+            exclude_archived,
+            pages,
+            version,
+            branch_data,
+            inline,
+            pretty,
+        }): Parameters<GetFileRequest>,
+        ct: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
         let depth = depth.unwrap_or(1);
-        let result = match self.client.get_file(&file_key, Some(depth)).await {
+        let pages = pages.as_deref().and_then(NameFilter::parse);
+        if !force.unwrap_or(false) {
+            if let Some(error_msg) =
+                self.check_projected_size(&file_key, &[DOCUMENT_NODE_ID], depth)
+            {
+                return tool_error(error_msg);
+            }
+        }
+
+        let version = version
+            .map(|version| version.trim().to_string())
+            .filter(|version| !version.is_empty());
+        let historical = version.is_some();
+        let client = self
+            .client()?
+            .with_cancellation(ct)
+            .with_version(version)
+            .with_branch_data(branch_data.unwrap_or(false));
+        let timeout = self.config.tool_timeouts.for_tool("get_file");
+
+        // Keep part of the time budget for a shallow retry when a deep fetch fails
+        let attempt_timeout = if depth > 1 {
+            timeout.mul_f64(DEEP_FETCH_TIMEOUT_SHARE)
+        } else {
+            timeout
+        };
+
+        let mut warning = None;
+        let mut fetched_depth = depth;
+        let request = fetch_file(&client, &file_key, pages.as_ref(), Some(depth));
+        let mut result = match with_timeout(attempt_timeout, request).await {
             Ok(file) => file,
+            Err(e) if depth > 1 && is_overload_error(&e) => {
+                let fallback_timeout = timeout.saturating_sub(attempt_timeout);
+                let request = fetch_file(&client, &file_key, pages.as_ref(), Some(1));
+                match with_timeout(fallback_timeout, request).await {
+                    Ok(file) => {
+                        warning = Some(format!(
+                            "Warning: fetching the file at depth {} failed ({}), so only the \
+                             page outline (depth 1) is returned. Use get_file_nodes with page \
+                             or frame IDs to explore deeper.",
+                            depth, e
+                        ));
+                        fetched_depth = 1;
+                        file
+                    }
+                    Err(e) => {
+                        let error_msg = format!("Error fetching file: {}", e);
+                        return tool_error(error_msg);
+                    }
+                }
+            }
             Err(e) => {
                 let error_msg = format!("Error fetching file: {}", e);
                 return tool_error(error_msg);
             }
         };
 
-        let result = serde_json::to_string_pretty(&result)
-            .unwrap_or_else(|e| format!("Serialization error: {}", e));
+        // Before the new statistics, which a change of version would drop
+        if !historical {
+            self.record_version(&file_key, FileMeta::from_file(&result));
+        }
+        let _ = self
+            .node_stats
+            .record_file(&file_key, &result, fetched_depth);
+        if exclude_hidden.unwrap_or(self.config.exclude_hidden) {
+            layers::strip_hidden(&mut result);
+        }
+        if let Some(exclude) = self.archive_filter(exclude_archived) {
+            pages::strip_excluded(&mut result, &exclude);
+        }
+        status::annotate(&mut result);
+        normalize::canonicalize(&mut result);
+        if self.config.deterministic {
+            normalize::strip_volatile(&mut result);
+        }
+
+        let summary = || {
+            let pages = result
+                .pointer("/document/children")
+                .and_then(Value::as_array);
+            let pages: Vec<&Value> = pages
+                .into_iter()
+                .flatten()
+                .filter_map(|page| page.get("name"))
+                .collect();
+            json!({ "name": result.get("name"), "pages": pages })
+        };
+        let text = output::to_json(&result, pretty.unwrap_or(false));
+        let description = format!("File {} at depth {}", file_key, fetched_depth);
+        let mut content = self.large_result("get_file", description, text, summary, inline);
+        if let Some(warning) = warning {
+            content.insert(0, Content::text(warning));
+        }
+
+        Ok(CallToolResult::success(content))
+    }
 
-        tool_success(result)
+    #[tool(
+        description = "Get the contents of a branch of a Figma file by its branch key, from a branch URL (via parse_figma_url) or from get_file with branch_data. The branch key also works as file_key with the other tools."
+    )]
+    async fn get_branch(
+        &self,
+        Parameters(GetBranchRequest {
+            branch_key,
+            file_key,
+            depth,
+            force,
+            exclude_archived,
+            inline,
+            pretty,
+        }): Parameters<GetBranchRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let branch_key = branch_key.trim();
+        if branch_key.is_empty() {
+            return missing_parameter("branch_key", BRANCH_KEY_QUESTION);
+        }
+
+        let depth = depth.unwrap_or(1);
+        if !force.unwrap_or(false) {
+            if let Some(error_msg) =
+                self.check_projected_size(branch_key, &[DOCUMENT_NODE_ID], depth)
+            {
+                return tool_error(error_msg);
+            }
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_branch");
+
+        let file_key = file_key
+            .map(|file_key| file_key.trim().to_string())
+            .filter(|file_key| !file_key.is_empty());
+        let request = async {
+            // Check the branch belongs to the file before fetching it
+            if let Some(file_key) = &file_key {
+                let file = client
+                    .with_branch_data(true)
+                    .get_file(file_key, Some(1))
+                    .await?;
+                let found = branches::branches(&file);
+                if !found.iter().any(|branch| branch.key == branch_key) {
+                    let names: Vec<String> = found
+                        .iter()
+                        .map(|branch| format!("{} ({})", branch.name, branch.key))
+                        .collect();
+                    return Err(Error::NotFound(format!(
+                        "file {} has no branch {}; its branches are: {}",
+                        file_key,
+                        branch_key,
+                        if names.is_empty() {
+                            "none".to_string()
+                        } else {
+                            names.join(", ")
+                        }
+                    )));
+                }
+            }
+
+            client.get_file(branch_key, Some(depth)).await
+        };
+        let mut result = match with_timeout(timeout, request).await {
+            Ok(branch) => branch,
+            Err(e) => {
+                let error_msg = format!("Error fetching branch: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let _ = self.node_stats.record_file(branch_key, &result, depth);
+        if self.config.exclude_hidden {
+            layers::strip_hidden(&mut result);
+        }
+        if let Some(exclude) = self.archive_filter(exclude_archived) {
+            pages::strip_excluded(&mut result, &exclude);
+        }
+        status::annotate(&mut result);
+        normalize::canonicalize(&mut result);
+        if self.config.deterministic {
+            normalize::strip_volatile(&mut result);
+        }
+
+        let summary = || {
+            json!({
+                "name": result.get("name"),
+                "main_file_key": branches::main_file_key(&result),
+            })
+        };
+        let text = output::to_json(&result, pretty.unwrap_or(false));
+        let description = format!("Branch {} at depth {}", branch_key, depth);
+        let content = self.large_result("get_branch", description, text, summary, inline);
+
+        Ok(CallToolResult::success(content))
     }
 
     #[tool(description = "Get specific nodes from a file using file key")]
@@ -101,16 +602,40 @@ impl FigmaServer {
             file_key,
             node_ids,
             depth,
+            force,
+            exclude_hidden,
+            exclude_archived,
+            version,
+            inline,
+            pretty,
         }): Parameters<GetFileNodesRequest>,
+        ct: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
-        let node_ids: Vec<String> = node_ids.split(',').map(|s| s.trim().to_string()).collect();
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+
         let depth = depth.unwrap_or(1);
+        if !force.unwrap_or(false) {
+            let ids: Vec<&str> = node_ids.iter().map(String::as_str).collect();
+            if let Some(error_msg) = self.check_projected_size(&file_key, &ids, depth) {
+                return tool_error(error_msg);
+            }
+        }
 
-        let result = match self
-            .client
-            .get_file_nodes(&file_key, &node_ids, Some(depth))
-            .await
-        {
+        let version = version
+            .map(|version| version.trim().to_string())
+            .filter(|version| !version.is_empty());
+        let historical = version.is_some();
+        let client = self.client()?.with_cancellation(ct).with_version(version);
+        let timeout = self.config.tool_timeouts.for_tool("get_file_nodes");
+        let request = client.get_file_nodes(&file_key, &node_ids, Some(depth));
+        let mut result = match with_timeout(timeout, request).await {
             Ok(nodes) => nodes,
             Err(e) => {
                 let error_msg = format!("Error fetching file nodes: {}", e);
@@ -118,10 +643,39 @@ impl FigmaServer {
             }
         };
 
-        let result = serde_json::to_string_pretty(&result)
-            .unwrap_or_else(|e| format!("Serialization error: {}", e));
+        if !historical {
+            self.record_version(&file_key, FileMeta::from_file(&result));
+        }
+        let _ = self.node_stats.record_nodes(&file_key, &result, depth);
+        if exclude_hidden.unwrap_or(self.config.exclude_hidden) {
+            layers::strip_hidden(&mut result);
+        }
+        if let Some(exclude) = self.archive_filter(exclude_archived) {
+            pages::strip_excluded(&mut result, &exclude);
+        }
+        status::annotate(&mut result);
+        normalize::canonicalize(&mut result);
+        if self.config.deterministic {
+            normalize::strip_volatile(&mut result);
+        }
+
+        let summary = || {
+            let nodes = result.get("nodes").and_then(Value::as_object);
+            let names: serde_json::Map<String, Value> = nodes
+                .into_iter()
+                .flatten()
+                .map(|(id, node)| {
+                    let name = node.pointer("/document/name").cloned();
+                    (id.clone(), name.unwrap_or_default())
+                })
+                .collect();
+            json!({ "nodes": names })
+        };
+        let text = output::to_json(&result, pretty.unwrap_or(false));
+        let description = format!("Nodes {} of file {}", node_ids.join(", "), file_key);
+        let content = self.large_result("get_file_nodes", description, text, summary, inline);
 
-        tool_success(result)
+        Ok(CallToolResult::success(content))
     }
 
     #[tool(description = "Export images from a Figma file using file key")]
@@ -131,278 +685,5206 @@ impl FigmaServer {
             file_key,
             node_ids,
             format,
+            formats,
             scale,
+            data_uri,
+            filter,
+            bounds,
+            use_absolute_bounds,
+            contents_only,
+            pretty,
         }): Parameters<ExportImageRequest>,
+        ct: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
-        let node_ids_to_export: Vec<String> =
-            node_ids.split(',').map(|s| s.trim().to_string()).collect();
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
 
-        let format = format.as_deref().unwrap_or("png");
-        let scale_value = scale.unwrap_or(1.0);
+        let node_ids_to_export = parse_node_ids(&node_ids);
+        if node_ids_to_export.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
 
-        let result = match self
-            .client
-            .export_images(&file_key, &node_ids_to_export, format, scale)
-            .await
+        let formats = match export::validate_formats(format.as_deref(), formats.as_deref(), scale) {
+            Ok(formats) => formats,
+            Err(message) => return tool_error(format!("Error: {}", message)),
+        };
+        if let Some(error_msg) = formats
+            .iter()
+            .find_map(|format| self.unwatermarkable(format.name()))
         {
-            Ok(export_result) => export_result,
-            Err(e) => {
-                let error_msg = format!("Error exporting images: {}", e);
+            return tool_error(error_msg);
+        }
+
+        let filter = match filter.as_deref().map(|name| (name, Filter::parse(name))) {
+            None => None,
+            Some((_, Some(filter))) => Some(filter),
+            Some((name, None)) => {
+                let error_msg = format!(
+                    "Error: unknown filter '{}', expected {}",
+                    name, FILTER_NAMES
+                );
+                return tool_error(error_msg);
+            }
+        };
+        if filter.is_some() && formats.iter().any(|format| *format != ExportFormat::Png) {
+            let error_msg = "Error: filters can only be applied to png exports".to_string();
+            return tool_error(error_msg);
+        }
+        let use_absolute_bounds = match (parse_bounds(bounds.as_deref()), use_absolute_bounds) {
+            (Err(error_msg), _) => return tool_error(error_msg),
+            (Ok(kind), Some(flag)) if bounds.is_some() && flag != kind.use_absolute_bounds() => {
+                let error_msg = format!(
+                    "Error: use_absolute_bounds {} contradicts bounds '{}'",
+                    flag,
+                    kind.name()
+                );
                 return tool_error(error_msg);
             }
+            (Ok(kind), flag) => flag.unwrap_or(kind.use_absolute_bounds()),
         };
 
-        // Register exported images in cache
-        if let Some(images) = result.get("images").and_then(|v| v.as_object()) {
-            for (node_id, url) in images {
-                if let Some(url_str) = url.as_str() {
-                    let _ = self.image_cache.register_export(
-                        file_key.clone(),
-                        node_id.clone(),
-                        format.to_string(),
-                        scale_value,
-                        url_str.to_string(),
-                    );
-                }
+        let client = self.client()?.with_cancellation(ct.clone());
+        let timeout = self.config.tool_timeouts.for_tool("export_images");
+        let deadline = Instant::now() + timeout;
+        // Each format is a request of its own; the node lookups they share
+        // are coalesced by the client
+        let requests = formats.iter().map(|format| {
+            let options = ExportOptions {
+                // Vector formats only accept the default scale
+                scale: if format.is_vector() { None } else { scale },
+                use_absolute_bounds,
+                contents_only: contents_only.unwrap_or(true),
+            };
+            self.export_format(
+                &client,
+                &file_key,
+                &node_ids_to_export,
+                *format,
+                options,
+                deadline,
+            )
+        });
+        let mut exported = Vec::new();
+        let mut errors = Vec::new();
+        for (format, export) in formats
+            .iter()
+            .zip(futures_util::future::join_all(requests).await)
+        {
+            match export {
+                Ok(export) => exported.push((format.name(), export)),
+                Err(error_msg) => errors.push((format.name(), error_msg)),
             }
         }
+        if exported.is_empty() {
+            let error_msg = match errors.as_slice() {
+                [(_, error_msg)] => error_msg.clone(),
+                _ => errors
+                    .iter()
+                    .map(|(format, error_msg)| format!("{}: {}", format, error_msg))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            };
+            return tool_error(error_msg);
+        }
 
-        let result = serde_json::to_string_pretty(&result)
-            .unwrap_or_else(|e| format!("Serialization error: {}", e));
+        let exports: Vec<(String, String)> = exported
+            .iter()
+            .flat_map(|(_, export)| export.exports.iter().cloned())
+            .collect();
+        let failures: Vec<ExportFailure> = exported
+            .iter()
+            .flat_map(|(_, export)| export.failures.iter().cloned())
+            .collect();
 
-        tool_success(result)
-    }
+        // Formats that failed altogether, after the nodes that failed in
+        // the others
+        let mut notes: Vec<String> = Vec::new();
+        if !failures.is_empty() {
+            notes.push(export::describe_failures(&failures));
+        }
+        notes.extend(
+            errors
+                .iter()
+                .map(|(format, error_msg)| format!("{}: {}", format, error_msg)),
+        );
 
-    #[tool(description = "Get current user information (useful for testing authentication)")]
-    async fn get_me(&self) -> Result<CallToolResult, McpError> {
-        let result = match self.client.get_me().await {
-            Ok(user) => user,
-            Err(e) => {
-                let error_msg = format!("Error fetching user info: {}", e);
+        if let Some(filter) = filter {
+            let request = self.filtered_images(&exports, filter, &ct);
+            let Ok(mut content) = tokio::time::timeout_at(deadline, request).await else {
+                let error_msg = format!("Error exporting images: {}", Error::Timeout(timeout));
                 return tool_error(error_msg);
-            }
-        };
+            };
+            content.extend(notes.into_iter().map(Content::text));
 
-        let result = serde_json::to_string_pretty(&result)
-            .unwrap_or_else(|e| format!("Serialization error: {}", e));
+            return Ok(CallToolResult::success(content));
+        }
 
-        tool_success(result)
-    }
+        if !data_uri.unwrap_or(false) {
+            let result = if let [(_, export)] = exported.as_slice() {
+                self.export_result(export)
+            } else {
+                // Images by format, with all of them among the resources
+                let mut images = Map::new();
+                let mut failed = Map::new();
+                for (format, export) in &exported {
+                    let mut format_result = self.export_result(export);
+                    images.insert(format.to_string(), format_result["images"].take());
+                    if !export.failures.is_empty() {
+                        failed.insert(format.to_string(), json!(export.failures));
+                    }
+                }
+                let mut result = json!({
+                    "images": images,
+                    "resources": self.export_resources(&exports),
+                });
+                if !failed.is_empty() {
+                    result["failed"] = Value::Object(failed);
+                }
+                if !errors.is_empty() {
+                    let errors: Map<String, Value> = errors
+                        .into_iter()
+                        .map(|(format, error_msg)| (format.to_string(), Value::String(error_msg)))
+                        .collect();
+                    result["errors"] = Value::Object(errors);
+                }
+                result
+            };
 
-    #[tool(description = "Help: How to use this Figma file MCP server")]
-    async fn help(&self) -> Result<CallToolResult, McpError> {
-        let help_text = r#"
-# Figma MCP Server Help
+            let result = output::to_json(&result, pretty.unwrap_or(false));
+            return tool_success(result);
+        }
 
-This MCP server provides tools to access and work with Figma files using file keys with depth control to manage response size.
+        let request = self.data_uri_markdown(&exports, &ct);
+        let Ok(mut markdown) = tokio::time::timeout_at(deadline, request).await else {
+            let error_msg = format!("Error exporting images: {}", Error::Timeout(timeout));
+            return tool_error(error_msg);
+        };
+        for note in notes {
+            markdown.push_str(&format!("\n\n{}\n", note));
+        }
 
-## Workflow
+        tool_success(markdown)
+    }
 
-1. First, use `parse_figma_url` to extract the file key from a Figma URL
-2. Then use the file key with other tools to access file data
-3. Use the depth parameter to control how much data is returned and avoid token limits
-4. Navigate deeper into the file structure using recursive calls with specific node IDs
+    #[tool(
+        description = "Generate alt text suggestions for nodes by exporting them and asking the client's model to describe them (requires a client with sampling support)"
+    )]
+    async fn generate_alt_text(
+        &self,
+        Parameters(GenerateAltTextRequest {
+            file_key,
+            node_ids,
+            context,
+            pretty,
+        }): Parameters<GenerateAltTextRequest>,
+        peer: Peer<RoleServer>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let supports_sampling = peer
+            .peer_info()
+            .and_then(|info| info.capabilities.sampling.as_ref())
+            .is_some();
+        if !supports_sampling {
+            let error_msg =
+                "The connected client does not support sampling, which is required to generate alt text"
+                    .to_string();
+            return tool_error(error_msg);
+        }
 
-## Available Tools
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
 
-### URL Parsing
-- `parse_figma_url`: Parse any Figma URL to extract file key and node information
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
 
-### File Operations (require file key from parse_figma_url)
-- `get_file`: Get file structure using file key with depth control (default: 1)
-- `get_file_nodes`: Get specific nodes using file key with depth control (default: 1)
-- `export_images`: Export images from file using file key
-- `get_me`: Test authentication and get user info
+        let timeout = self.config.tool_timeouts.for_tool("generate_alt_text");
+        let deadline = Instant::now() + timeout;
 
-## Resources
+        let client = self.client()?.with_cancellation(ct.clone());
+        let request = client.export_images(&file_key, &node_ids, "png", ExportOptions::default());
+        let result = match with_timeout(timeout, request).await {
+            Ok(export_result) => export_result,
+            Err(e) => {
+                let error_msg = format!("Error exporting images: {}", e);
+                return tool_error(error_msg);
+            }
+        };
 
-After exporting images using the `export_images` tool, they are available as MCP resources.
-You can:
-- List all exported images using the resources API
-- Access image data as base64-encoded blobs
-- Resources are identified by URIs like: `figma://file/{file_key}/node/{node_id}.{format}`
+        let (nodes, failures) =
+            check_exports(&client, &file_key, &result, &node_ids, 1.0, deadline).await;
+        let exports = self.register_exports(&file_key, &result, "png", 1.0, nodes.as_ref());
 
-## Depth Parameter
+        let mut suggestions = serde_json::Map::new();
+        for failure in failures {
+            suggestions.insert(failure.node_id, json!({ "error": failure.reason }));
+        }
+        let mut remaining = exports.into_iter();
+        while let Some((node_id, uri)) = remaining.next() {
+            if ct.is_cancelled() {
+                return tool_error("Alt text generation cancelled".to_string());
+            }
 
-Both `get_file` and `get_file_nodes` support a depth parameter to limit response size:
+            let request = self.describe_image(&peer, &uri, context.as_deref(), &ct);
+            let Ok(described) = tokio::time::timeout_at(deadline, request).await else {
+                let pending: Vec<String> = std::iter::once(node_id)
+                    .chain(remaining.map(|(node_id, _)| node_id))
+                    .collect();
+                let result = json!({
+                    "file_key": file_key,
+                    "alt_text": suggestions,
+                    "complete": false,
+                    "warning": format!(
+                        "Timed out after {} seconds; {} node(s) were not described",
+                        timeout.as_secs(),
+                        pending.len()
+                    ),
+                    "continuation": {
+                        "tool": "generate_alt_text",
+                        "file_key": file_key,
+                        "node_ids": pending.join(","),
+                    },
+                });
+                let result = output::to_json(&result, pretty.unwrap_or(false));
 
-- **depth=1** (default): For files: pages only. For nodes: direct children only
-- **depth=2**: For files: pages + top-level objects. For nodes: children + grandchildren
-- **depth=3+**: Deeper traversal (use carefully to avoid large responses)
+                return tool_success(result);
+            };
 
-## Recursive Navigation Strategy
+            let suggestion = match described {
+                Ok(alt_text) => json!({ "alt_text": alt_text, "resource_uri": uri }),
+                Err(e) => json!({ "error": e.to_string(), "resource_uri": uri }),
+            };
+            suggestions.insert(node_id, suggestion);
+        }
 
-To navigate large files without exceeding token limits:
+        let result = json!({ "file_key": file_key, "alt_text": suggestions, "complete": true });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
 
-1. Start with `get_file` at depth=1 to see page structure
-2. Use `get_file_nodes` with specific page IDs at depth=1 to explore page contents
-3. Use `get_file_nodes` with specific component/frame IDs for deeper inspection
-4. Adjust depth as needed based on response size
+        tool_success(result)
+    }
 
-## Supported URL Formats
-- File: https://www.figma.com/file/FILE_ID/filename
-- File with node: https://www.figma.com/file/FILE_ID/filename?node-id=1%3A2
-- Design URL: https://www.figma.com/design/FILE_ID/filename
+    #[tool(
+        description = "Queue a large image export to run in the background and return a job ID immediately. Poll job_result with the job ID; a log notification is also sent when the job finishes."
+    )]
+    async fn enqueue_export(
+        &self,
+        Parameters(EnqueueExportRequest {
+            file_key,
+            node_ids,
+            format,
+            scale,
+            use_absolute_bounds,
+            contents_only,
+            pretty,
+        }): Parameters<EnqueueExportRequest>,
+        peer: Peer<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
 
-## Authentication
-Set your Figma personal access token as an environment variable:
-export FIGMA_TOKEN="your_figma_token_here"
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
 
-Get your token from: https://www.figma.com/developers/api#access-tokens
-"#;
+        let format = match export::validate(format.as_deref().unwrap_or("png"), scale) {
+            Ok(format) => format.name(),
+            Err(message) => return tool_error(format!("Error: {}", message)),
+        };
+        if let Some(error_msg) = self.unwatermarkable(format) {
+            return tool_error(error_msg);
+        }
+        let options = ExportOptions {
+            scale,
+            use_absolute_bounds: use_absolute_bounds.unwrap_or(false),
+            contents_only: contents_only.unwrap_or(true),
+        };
 
-        Ok(CallToolResult::success(vec![Content::text(
-            help_text.to_string(),
-        )]))
-    }
-}
+        let total = node_ids.len();
+        let server = self.clone();
+        let started = self.spawn_job(JobKind::Export, Some(total), peer, move |job| async move {
+            server
+                .run_export_job(&job, &file_key, &node_ids, format, options)
+                .await
+        });
 
-#[tool_handler]
-impl ServerHandler for FigmaServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            server_info: Implementation::from_build_env(),
-            capabilities: ServerCapabilities::builder()
-                .enable_tools()
-                .enable_resources()
-                .build(),
-            instructions: Some("A Figma MCP server that provides tools to access Figma files and export images. Use 'help' tool for usage instructions.".into()),
-        }
+        job_started(started, pretty)
     }
 
-    async fn list_resources(
+    #[tool(
+        description = "List background jobs (exports, syncs, indexing) with their status and progress"
+    )]
+    async fn list_jobs(
         &self,
-        _request: Option<PaginatedRequestParam>,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ListResourcesResult, McpError> {
-        let entries = self.image_cache.list_all().map_err(|e| {
-            McpError::internal_error(format!("Failed to list resources: {}", e), None)
-        })?;
+        Parameters(ListJobsRequest { pretty }): Parameters<ListJobsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let jobs = match self.jobs.list() {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                let error_msg = format!("Error listing jobs: {}", e);
+                return tool_error(error_msg);
+            }
+        };
 
-        let resources: Vec<Resource> = entries
+        let jobs: Vec<Value> = jobs
             .iter()
-            .map(|(uri, entry)| {
-                let name = format!("Node {} Export", entry.node_id);
-                let description = format!(
-                    "Exported from Figma file {} as {} ({}x scale)",
-                    entry.file_key, entry.format, entry.scale
-                );
-                let mime_type = crate::figma::ImageCache::get_mime_type(&entry.format);
-
-                Resource::new(
-                    RawResource {
-                        uri: uri.clone(),
-                        name,
-                        description: Some(description),
-                        mime_type: Some(mime_type.to_string()),
-                        size: entry.cached_data.as_ref().map(|data| data.len() as u32),
-                    },
-                    None,
-                )
-            })
+            .filter(|job| self.can_see(&job.resource_uri()))
+            .map(Job::summary)
             .collect();
+        let result = output::to_json(&json!({ "jobs": jobs }), pretty.unwrap_or(false));
 
-        Ok(ListResourcesResult {
-            resources,
-            next_cursor: None,
-        })
+        tool_success(result)
     }
 
-    async fn read_resource(
+    #[tool(
+        description = "Get a background job's status and progress, and its result once finished (the partial result while running). Results are also readable as figma://job/{job_id} resources."
+    )]
+    async fn job_result(
         &self,
-        request: ReadResourceRequestParam,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ReadResourceResult, McpError> {
-        let uri = request.uri;
-
-        let entry = self
-            .image_cache
-            .get_entry(&uri)
-            .map_err(|e| McpError::internal_error(format!("Failed to get resource: {}", e), None))?
-            .ok_or_else(|| {
-                McpError::resource_not_found(format!("Resource not found: {}", uri), None)
-            })?;
+        Parameters(JobRequest { job_id, pretty }): Parameters<JobRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if !self.can_see_job(job_id.trim()) {
+            return unknown_job(&job_id);
+        }
+        let job = match self.jobs.get(job_id.trim()) {
+            Ok(Some(job)) => job,
+            Ok(None) => return unknown_job(&job_id),
+            Err(e) => {
+                let error_msg = format!("Error reading job: {}", e);
+                return tool_error(error_msg);
+            }
+        };
 
-        // Check if we need to download the image
-        let image_data = if let Some(cached_data) = entry.cached_data {
-            cached_data
-        } else {
-            // Check if URL is expired
-            if self.image_cache.is_expired(&entry) {
-                return Err(McpError::internal_error(
-                    "Figma URL has expired. Please re-export the image.",
-                    None,
-                ));
+        let result = output::to_json(&job, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Cancel a background job that has not finished yet, aborting its pending requests",
+        annotations(read_only_hint = false)
+    )]
+    async fn cancel_job(
+        &self,
+        Parameters(JobRequest { job_id, pretty }): Parameters<JobRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        // Other sessions' jobs are reported as unknown, not as forbidden
+        if !self.can_see_job(job_id.trim()) {
+            return unknown_job(&job_id);
+        }
+        let job = match self.jobs.cancel(job_id.trim()) {
+            Ok(Some(job)) => job,
+            Ok(None) => return unknown_job(&job_id),
+            Err(e) => {
+                let error_msg = format!("Error cancelling job: {}", e);
+                return tool_error(error_msg);
             }
+        };
 
-            // Download image from Figma URL
-            let response = reqwest::get(&entry.figma_url).await.map_err(|e| {
-                McpError::internal_error(format!("Failed to download image: {}", e), None)
-            })?;
+        let result = output::to_json(&job.summary(), pretty.unwrap_or(false));
 
-            if !response.status().is_success() {
-                return Err(McpError::internal_error(
-                    format!("Failed to download image: HTTP {}", response.status()),
-                    None,
-                ));
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Measure nodes, returning both their layout box (absoluteBoundingBox) and render bounds (absoluteRenderBounds, including shadows, blurs and outside strokes) with how far the rendering extends past the layout box on each side"
+    )]
+    async fn measure_nodes(
+        &self,
+        Parameters(MeasureNodesRequest {
+            file_key,
+            node_ids,
+            pretty,
+        }): Parameters<MeasureNodesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("measure_nodes");
+        let request = client.get_file_nodes(&file_key, &node_ids, Some(1));
+        let nodes = match with_timeout(timeout, request).await {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                let error_msg = format!("Error measuring nodes: {}", e);
+                return tool_error(error_msg);
             }
+        };
 
-            let data = response
-                .bytes()
-                .await
-                .map_err(|e| {
-                    McpError::internal_error(format!("Failed to read image data: {}", e), None)
-                })?
-                .to_vec();
+        let measurements: Vec<NodeBounds> = node_ids
+            .iter()
+            .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)))
+            .map(bounds::measure)
+            .collect();
+        let result = output::to_json(&measurements, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List a file's version history, newest first: who saved each version, when, and its label and description. Follows Figma's pages to return the whole history, or up to `limit`; pass the returned `next_before` as `before` to continue further back."
+    )]
+    async fn get_file_versions(
+        &self,
+        Parameters(GetFileVersionsRequest {
+            file_key,
+            limit,
+            before,
+            pretty,
+        }): Parameters<GetFileVersionsRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_file_versions");
+        let request = client.list_file_versions(&file_key, limit, before);
+        let (found, next_before) = match with_timeout(timeout, request).await {
+            Ok((response, next_before)) => (versions::versions(&response), next_before),
+            Err(e) => {
+                let error_msg = format!("Error getting file versions: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = json!({ "versions": found, "next_before": next_before });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List the components a file publishes to its team library, with their global keys, names, descriptions, node IDs and component sets. Use the keys to map design components to code components."
+    )]
+    async fn get_file_components(
+        &self,
+        Parameters(GetFileComponentsRequest { file_key, pretty }): Parameters<
+            GetFileComponentsRequest,
+        >,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_file_components");
+        let response = match with_timeout(timeout, client.get_file_components(&file_key)).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Error getting file components: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let mut components: Vec<PublishedComponent> = library::components(&response);
+        // Documentation links are only in file data, so take them from the
+        // index if the file is in it
+        if let Ok(Some(definitions)) = self.design_index.definitions(&file_key) {
+            for component in &mut components {
+                let definition = definitions
+                    .iter()
+                    .find(|definition| definition.key == component.key);
+                if let Some(definition) = definition {
+                    component.documentation_links = definition.documentation_links.clone();
+                }
+            }
+        }
+        let result = output::to_json(&components, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List the component sets (variant groups) a file publishes, with their keys, descriptions, property definitions (variant options, boolean, text and instance swap properties with defaults) and variant names. Much smaller than walking the document tree to discover variants."
+    )]
+    async fn get_file_component_sets(
+        &self,
+        Parameters(GetFileComponentSetsRequest { file_key, pretty }): Parameters<
+            GetFileComponentSetsRequest,
+        >,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self
+            .config
+            .tool_timeouts
+            .for_tool("get_file_component_sets");
+        let request = async {
+            let response = client.get_file_component_sets(&file_key).await?;
+            let mut sets = library::component_sets(&response);
+            if sets.is_empty() {
+                return Ok(sets);
+            }
+
+            // Property definitions are only on the sets' nodes
+            let node_ids: Vec<String> = sets.iter().map(|set| set.node_id.clone()).collect();
+            let nodes = client.get_file_nodes(&file_key, &node_ids, Some(1)).await?;
+            library::add_properties(&mut sets, &nodes);
+            Ok(sets)
+        };
+        let sets: Vec<PublishedComponentSet> = match with_timeout(timeout, request).await {
+            Ok(sets) => sets,
+            Err(e) => {
+                let error_msg = format!("Error getting file component sets: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&sets, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Get a file's local variable collections (design tokens in Figma Variables) with their modes and each variable's type, scopes, code syntax and value per mode. Colors are CSS colors and aliases name the variable they point to. The endpoint requires an Enterprise plan and a token with the file_variables:read scope."
+    )]
+    async fn get_local_variables(
+        &self,
+        Parameters(GetLocalVariablesRequest {
+            file_key,
+            collections,
+            inline,
+            pretty,
+        }): Parameters<GetLocalVariablesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let filter = collections.as_deref().and_then(NameFilter::parse);
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_local_variables");
+        let request = client.get_local_variables(&file_key);
+        let mut found: Vec<VariableCollection> = match with_timeout(timeout, request).await {
+            Ok(response) => variables::collections(&response),
+            Err(Error::Api(e)) if e.status == 403 => {
+                let error_msg = format!(
+                    "Error getting local variables: {}. Figma only serves variables to members \
+                     of Enterprise organizations, with a token that has the \
+                     file_variables:read scope.",
+                    e
+                );
+                return tool_error(error_msg);
+            }
+            Err(e) => {
+                let error_msg = format!("Error getting local variables: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+        if let Some(filter) = &filter {
+            found.retain(|collection| filter.matches(&collection.name));
+        }
+
+        let summary = || {
+            let counts: serde_json::Map<String, Value> = found
+                .iter()
+                .map(|collection| (collection.name.clone(), json!(collection.variables.len())))
+                .collect();
+            json!({ "variables": counts })
+        };
+        let text = output::to_json(&json!({ "collections": found }), pretty.unwrap_or(false));
+        let description = format!("Local variables of file {}", file_key);
+        let content = self.large_result("get_local_variables", description, text, summary, inline);
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "List the variables a library file publishes, per collection, with their keys and subscribed IDs. Files using the library reference these variables by subscribed ID in their aliases (see get_local_variables), so this resolves tokens across files. Requires an Enterprise plan and the file_variables:read scope."
+    )]
+    async fn get_published_variables(
+        &self,
+        Parameters(GetPublishedVariablesRequest {
+            file_key,
+            collections,
+            pretty,
+        }): Parameters<GetPublishedVariablesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let filter = collections.as_deref().and_then(NameFilter::parse);
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self
+            .config
+            .tool_timeouts
+            .for_tool("get_published_variables");
+        let request = client.get_published_variables(&file_key);
+        let mut found: Vec<PublishedCollection> = match with_timeout(timeout, request).await {
+            Ok(response) => variables::published(&response),
+            Err(Error::Api(e)) if e.status == 403 => {
+                let error_msg = format!(
+                    "Error getting published variables: {}. Figma only serves variables to \
+                     members of Enterprise organizations, with a token that has the \
+                     file_variables:read scope.",
+                    e
+                );
+                return tool_error(error_msg);
+            }
+            Err(e) => {
+                let error_msg = format!("Error getting published variables: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+        if let Some(filter) = &filter {
+            found.retain(|collection| filter.matches(&collection.name));
+        }
+
+        tool_success(output::to_json(
+            &json!({ "collections": found }),
+            pretty.unwrap_or(false),
+        ))
+    }
+
+    #[tool(
+        description = "Weekly insertions and detachments of a library file's components, styles or variables across the organization, per asset or per team, as JSON or CSV. For questions like which components were detached most last week, set start_date and end_date and sort_by detachments. Requires an Enterprise plan and the library_analytics:read scope."
+    )]
+    async fn get_library_actions(
+        &self,
+        Parameters(GetLibraryActionsRequest {
+            file_key,
+            asset,
+            group_by,
+            start_date,
+            end_date,
+            sort_by,
+            limit,
+            format,
+            pretty,
+        }): Parameters<GetLibraryActionsRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let query = AnalyticsQuery {
+            file_key,
+            asset,
+            group_by,
+            start_date,
+            end_date,
+            sort_by,
+            limit,
+            format,
+            pretty,
+        };
+
+        self.library_analytics("get_library_actions", Metric::Actions, query, ct)
+            .await
+    }
+
+    #[tool(
+        description = "Current usages of a library file's components, styles or variables across the organization, per asset (with the number of teams and files using it) or per file, as JSON or CSV. sort_by usages finds the most used assets. Requires an Enterprise plan and the library_analytics:read scope."
+    )]
+    async fn get_library_usages(
+        &self,
+        Parameters(GetLibraryUsagesRequest {
+            file_key,
+            asset,
+            group_by,
+            sort_by,
+            limit,
+            format,
+            pretty,
+        }): Parameters<GetLibraryUsagesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let query = AnalyticsQuery {
+            file_key,
+            asset,
+            group_by,
+            start_date: None,
+            end_date: None,
+            sort_by,
+            limit,
+            format,
+            pretty,
+        };
+
+        self.library_analytics("get_library_usages", Metric::Usages, query, ct)
+            .await
+    }
+
+    #[tool(
+        description = "List the styles a file publishes (color fills, text, effects and layout grids) with their keys, types, names and descriptions, without fetching the document."
+    )]
+    async fn get_file_styles(
+        &self,
+        Parameters(GetFileStylesRequest { file_key, pretty }): Parameters<GetFileStylesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_file_styles");
+        let request = client.get_file_styles(&file_key);
+        let styles: Vec<PublishedStyle> = match with_timeout(timeout, request).await {
+            Ok(response) => library::styles(&response),
+            Err(e) => {
+                let error_msg = format!("Error getting file styles: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&styles, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List the projects of a Figma team by team ID (from a team URL via parse_figma_url). Use get_project_files on a project to find file keys without asking the user for file URLs."
+    )]
+    async fn get_team_projects(
+        &self,
+        Parameters(GetTeamProjectsRequest { team_id, pretty }): Parameters<GetTeamProjectsRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let team_id = team_id.trim();
+        if team_id.is_empty() {
+            return missing_parameter("team_id", TEAM_ID_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_team_projects");
+        let response = match with_timeout(timeout, client.get_team_projects(team_id)).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Error getting team projects: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = json!({
+            "team": response.get("name"),
+            "projects": projects::projects(&response),
+        });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List the files in a Figma project, most recently modified first, with their file keys for the other tools. Get project IDs from get_team_projects or a project URL via parse_figma_url."
+    )]
+    async fn get_project_files(
+        &self,
+        Parameters(GetProjectFilesRequest { project_id, pretty }): Parameters<
+            GetProjectFilesRequest,
+        >,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let project_id = project_id.trim();
+        if project_id.is_empty() {
+            return missing_parameter("project_id", PROJECT_ID_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_project_files");
+        let response = match with_timeout(timeout, client.get_project_files(project_id)).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Error getting project files: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = json!({
+            "project": response.get("name"),
+            "files": projects::files(&response),
+        });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List the components published to a team library across all of its files, with their keys, names, descriptions, file keys and component sets. Follows Figma's pages to return all of them, or up to `limit`; pass next_after from the response as after to continue."
+    )]
+    async fn get_team_components(
+        &self,
+        Parameters(GetTeamLibraryRequest {
+            team_id,
+            limit,
+            after,
+            pretty,
+        }): Parameters<GetTeamLibraryRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let team_id = team_id.trim();
+        if team_id.is_empty() {
+            return missing_parameter("team_id", TEAM_ID_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_team_components");
+        let request = client.list_team_components(team_id, limit, after);
+        let (components, next_after) = match with_timeout(timeout, request).await {
+            Ok((response, next_after)) => (library::components(&response), next_after),
+            Err(e) => {
+                let error_msg = format!("Error getting team components: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = json!({ "components": components, "next_after": next_after });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List the component sets (variant groups) published to a team library across all of its files, with their keys, names, descriptions and file keys. Use get_file_component_sets on a set's file for its property definitions. Follows Figma's pages to return all of them, or up to `limit`; pass next_after from the response as after to continue."
+    )]
+    async fn get_team_component_sets(
+        &self,
+        Parameters(GetTeamLibraryRequest {
+            team_id,
+            limit,
+            after,
+            pretty,
+        }): Parameters<GetTeamLibraryRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let team_id = team_id.trim();
+        if team_id.is_empty() {
+            return missing_parameter("team_id", TEAM_ID_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self
+            .config
+            .tool_timeouts
+            .for_tool("get_team_component_sets");
+        let request = client.list_team_component_sets(team_id, limit, after);
+        let (sets, next_after) = match with_timeout(timeout, request).await {
+            Ok((response, next_after)) => (library::component_sets(&response), next_after),
+            Err(e) => {
+                let error_msg = format!("Error getting team component sets: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = json!({ "component_sets": sets, "next_after": next_after });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List the styles published to a team library across all of its files (color fills, text, effects and layout grids) with their keys, types, names, descriptions and file keys, e.g. to feed a design token pipeline. Follows Figma's pages to return all of them, or up to `limit`; pass next_after from the response as after to continue."
+    )]
+    async fn get_team_styles(
+        &self,
+        Parameters(GetTeamLibraryRequest {
+            team_id,
+            limit,
+            after,
+            pretty,
+        }): Parameters<GetTeamLibraryRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let team_id = team_id.trim();
+        if team_id.is_empty() {
+            return missing_parameter("team_id", TEAM_ID_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_team_styles");
+        let request = client.list_team_styles(team_id, limit, after);
+        let (styles, next_after) = match with_timeout(timeout, request).await {
+            Ok((response, next_after)) => (library::styles(&response), next_after),
+            Err(e) => {
+                let error_msg = format!("Error getting team styles: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = json!({ "styles": styles, "next_after": next_after });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Get the comment threads of a file, with replies and whether each thread is resolved. Filter by the nodes comments are pinned to or by resolved state to read designer feedback on specific frames."
+    )]
+    async fn get_comments(
+        &self,
+        Parameters(GetCommentsRequest {
+            file_key,
+            node_ids,
+            resolved,
+            pretty,
+        }): Parameters<GetCommentsRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = node_ids.as_deref().map(parse_node_ids).unwrap_or_default();
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_comments");
+        let response = match with_timeout(timeout, client.get_comments(&file_key)).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Error getting comments: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let threads: Vec<CommentThread> = comments::threads(&response, &node_ids, resolved);
+        let result = output::to_json(&threads, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Leave a comment on a file, pinned to a node (optionally at an offset from its top-left corner) or to a canvas position. Use it to record review findings where designers will see them. Needs a token with the file_comments:write scope.",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    async fn post_comment(
+        &self,
+        Parameters(PostCommentRequest {
+            file_key,
+            message,
+            node_id,
+            x,
+            y,
+            pretty,
+        }): Parameters<PostCommentRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+        if message.trim().is_empty() {
+            return tool_error("Error posting comment: the message is empty".to_string());
+        }
+
+        let node_id = node_id
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+        let anchor = match (node_id, x, y) {
+            (Some(node_id), x, y) => Some(CommentAnchor::Node {
+                node_id,
+                x: x.unwrap_or_default(),
+                y: y.unwrap_or_default(),
+            }),
+            (None, Some(x), Some(y)) => Some(CommentAnchor::Canvas { x, y }),
+            (None, None, None) => None,
+            (None, _, _) => {
+                return tool_error(
+                    "Error posting comment: a canvas position needs both x and y".to_string(),
+                );
+            }
+        };
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("post_comment");
+        let body = comments::new_comment(&message, anchor.as_ref());
+        let comment = match with_timeout(timeout, client.post_comment(&file_key, &body)).await {
+            Ok(comment) => comment,
+            Err(e) => {
+                let error_msg = format!("Error posting comment: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let thread = comments::thread(&comment);
+        let result = output::to_json(&thread, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Reply to a comment thread, given the ID of its top-level comment (from get_comments). Needs a token with the file_comments:write scope.",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    async fn reply_to_comment(
+        &self,
+        Parameters(ReplyToCommentRequest {
+            file_key,
+            comment_id,
+            message,
+            pretty,
+        }): Parameters<ReplyToCommentRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+        if comment_id.trim().is_empty() {
+            return missing_parameter("comment_id", COMMENT_ID_QUESTION);
+        }
+        if message.trim().is_empty() {
+            return tool_error("Error replying to comment: the message is empty".to_string());
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("reply_to_comment");
+        let body = comments::new_reply(comment_id.trim(), &message);
+        let reply = match with_timeout(timeout, client.post_comment(&file_key, &body)).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                let error_msg = format!("Error replying to comment: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let reply = comments::reply(&reply);
+        let result = output::to_json(&reply, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Delete a comment written with the current token; deleting a top-level comment deletes its replies too. Figma's API cannot resolve threads, so this is the way to clear feedback that has been addressed.",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn delete_comment(
+        &self,
+        Parameters(DeleteCommentRequest {
+            file_key,
+            comment_id,
+        }): Parameters<DeleteCommentRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+        if comment_id.trim().is_empty() {
+            return missing_parameter("comment_id", COMMENT_ID_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("delete_comment");
+        let request = client.delete_comment(&file_key, comment_id.trim());
+        if let Err(e) = with_timeout(timeout, request).await {
+            let error_msg = format!("Error deleting comment: {}", e);
+            return tool_error(error_msg);
+        }
+
+        tool_success(format!("Deleted comment {}", comment_id.trim()))
+    }
+
+    #[tool(
+        description = "List the dev resources of a file: links from nodes to code such as pull requests, Storybook stories or docs, shown in Dev Mode. Filter by the nodes they are attached to. Needs a token with the file_dev_resources:read scope."
+    )]
+    async fn get_dev_resources(
+        &self,
+        Parameters(GetDevResourcesRequest {
+            file_key,
+            node_ids,
+            pretty,
+        }): Parameters<GetDevResourcesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = node_ids.as_deref().map(parse_node_ids).unwrap_or_default();
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_dev_resources");
+        let request = client.get_dev_resources(&file_key, &node_ids);
+        let response = match with_timeout(timeout, request).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Error getting dev resources: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let resources: Vec<DevResource> = dev_resources::resources(&response);
+        let result = output::to_json(&resources, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Attach a dev resource to a node: a named link to a pull request, Storybook story or other code, shown in Dev Mode. Needs a token with the file_dev_resources:write scope.",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    async fn create_dev_resource(
+        &self,
+        Parameters(CreateDevResourceRequest {
+            file_key,
+            node_id,
+            name,
+            url,
+            pretty,
+        }): Parameters<CreateDevResourceRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+        if node_id.trim().is_empty() {
+            return missing_parameter("node_id", NODE_IDS_QUESTION);
+        }
+        if name.trim().is_empty() {
+            return tool_error("Error creating dev resource: the name is empty".to_string());
+        }
+        if let Err(e) = url::Url::parse(url.trim()) {
+            let error_msg = format!("Error creating dev resource: invalid URL: {}", e);
+            return tool_error(error_msg);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("create_dev_resource");
+        let body = dev_resources::new_resource(&file_key, node_id.trim(), name.trim(), url.trim());
+        let response = match with_timeout(timeout, client.create_dev_resources(&body)).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Error creating dev resource: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        match dev_resources::created(&response) {
+            Ok(resource) => tool_success(output::to_json(&resource, pretty.unwrap_or(false))),
+            Err(e) => tool_error(format!("Error creating dev resource: {}", e)),
+        }
+    }
+
+    #[tool(
+        description = "Rename a dev resource or point it at another URL. Needs a token with the file_dev_resources:write scope.",
+        annotations(read_only_hint = false, idempotent_hint = true)
+    )]
+    async fn update_dev_resource(
+        &self,
+        Parameters(UpdateDevResourceRequest {
+            dev_resource_id,
+            name,
+            url,
+        }): Parameters<UpdateDevResourceRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if dev_resource_id.trim().is_empty() {
+            return missing_parameter("dev_resource_id", DEV_RESOURCE_ID_QUESTION);
+        }
+
+        let name = name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty());
+        let url = url.as_deref().map(str::trim).filter(|url| !url.is_empty());
+        if name.is_none() && url.is_none() {
+            return tool_error(
+                "Error updating dev resource: give a new name, URL or both".to_string(),
+            );
+        }
+        if let Some(Err(e)) = url.map(url::Url::parse) {
+            let error_msg = format!("Error updating dev resource: invalid URL: {}", e);
+            return tool_error(error_msg);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("update_dev_resource");
+        let body = dev_resources::resource_update(dev_resource_id.trim(), name, url);
+        let response = match with_timeout(timeout, client.update_dev_resources(&body)).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Error updating dev resource: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+        if let Some(e) = dev_resources::update_error(&response) {
+            return tool_error(format!("Error updating dev resource: {}", e));
+        }
+
+        tool_success(format!("Updated dev resource {}", dev_resource_id.trim()))
+    }
+
+    #[tool(
+        description = "Remove a dev resource from its node. Needs a token with the file_dev_resources:write scope.",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn delete_dev_resource(
+        &self,
+        Parameters(DeleteDevResourceRequest {
+            file_key,
+            dev_resource_id,
+        }): Parameters<DeleteDevResourceRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+        if dev_resource_id.trim().is_empty() {
+            return missing_parameter("dev_resource_id", DEV_RESOURCE_ID_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("delete_dev_resource");
+        let request = client.delete_dev_resource(&file_key, dev_resource_id.trim());
+        if let Err(e) = with_timeout(timeout, request).await {
+            let error_msg = format!("Error deleting dev resource: {}", e);
+            return tool_error(error_msg);
+        }
+
+        tool_success(format!("Deleted dev resource {}", dev_resource_id.trim()))
+    }
+
+    #[tool(
+        description = "Extract the text of nodes as Markdown: text styles and font sizes map to headings, bold and italic runs to emphasis, and Figma list lines to Markdown lists. Useful for specs and content migration."
+    )]
+    async fn extract_text(
+        &self,
+        Parameters(ExtractTextRequest { file_key, node_ids }): Parameters<ExtractTextRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("extract_text");
+        let request = client.get_file_nodes(&file_key, &node_ids, None);
+        let nodes = match with_timeout(timeout, request).await {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                let error_msg = format!("Error extracting text: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let sections: Vec<String> = node_ids
+            .iter()
+            .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}", node_id)))
+            .map(|entry| {
+                let document = entry.get("document").unwrap_or(&Value::Null);
+                text::to_markdown(document, entry.get("styles"))
+            })
+            .filter(|markdown| !markdown.is_empty())
+            .collect();
+        if sections.is_empty() {
+            return tool_error(format!(
+                "Error: no visible text found in {}",
+                node_ids.join(", ")
+            ));
+        }
+
+        tool_success(sections.join("\n"))
+    }
+
+    #[tool(
+        description = "Detect tables drawn as grids of aligned text layers (pricing tables, data specs) below nodes and extract them as Markdown tables, CSV or JSON rows. The first row is the header."
+    )]
+    async fn extract_tables(
+        &self,
+        Parameters(ExtractTablesRequest {
+            file_key,
+            node_ids,
+            format,
+            pretty,
+        }): Parameters<ExtractTablesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+
+        let format_name = format.as_deref().unwrap_or("markdown");
+        let Some(format) = TableFormat::parse(format_name) else {
+            return tool_error(format!(
+                "Error: unsupported table format '{}', expected {}",
+                format_name, TABLE_FORMAT_NAMES
+            ));
+        };
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("extract_tables");
+        let request = client.get_file_nodes(&file_key, &node_ids, None);
+        let nodes = match with_timeout(timeout, request).await {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                let error_msg = format!("Error extracting tables: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let found: Vec<Table> = node_ids
+            .iter()
+            .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)))
+            .flat_map(tables::detect)
+            .collect();
+        if found.is_empty() {
+            return tool_error(format!(
+                "Error: no table found in {}; tables need text layers aligned in at least two rows and two columns",
+                node_ids.join(", ")
+            ));
+        }
+
+        let content = match format {
+            TableFormat::Json => vec![Content::text(output::to_json(
+                &found,
+                pretty.unwrap_or(false),
+            ))],
+            TableFormat::Markdown => found
+                .iter()
+                .map(|table| {
+                    Content::text(format!(
+                        "### {} ({})\n\n{}",
+                        table.name,
+                        table.node_id,
+                        table.to_markdown()
+                    ))
+                })
+                .collect(),
+            TableFormat::Csv => found
+                .iter()
+                .map(|table| Content::text(table.to_csv()))
+                .collect(),
+        };
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "Read back approximate series values from simple bar and line chart mockups below nodes: bars drawn as rectangles sharing a baseline, lines drawn as vectors or point markers. Values are mapped through numeric axis labels when present, otherwise given in pixels."
+    )]
+    async fn extract_chart_data(
+        &self,
+        Parameters(ExtractChartDataRequest {
+            file_key,
+            node_ids,
+            pretty,
+        }): Parameters<ExtractChartDataRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("extract_chart_data");
+        // Line charts are read from the vector paths
+        let request = client.get_file_nodes_with_geometry(&file_key, &node_ids, None);
+        let nodes = match with_timeout(timeout, request).await {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                let error_msg = format!("Error extracting chart data: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let found: Vec<Chart> = node_ids
+            .iter()
+            .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)))
+            .flat_map(charts::detect)
+            .collect();
+        if found.is_empty() {
+            return tool_error(format!(
+                "Error: no chart found in {}; charts need at least three bars sharing a baseline, or a line with three points",
+                node_ids.join(", ")
+            ));
+        }
+
+        tool_success(output::to_json(&found, pretty.unwrap_or(false)))
+    }
+
+    #[tool(
+        description = "Label the frames below nodes with the UI role they appear to play (header, nav, button, card, list, input, modal), inferred from layer names, structure and styles. Generated code (selection_to_code, workspace codegen) uses the same labels to pick elements."
+    )]
+    async fn infer_roles(
+        &self,
+        Parameters(InferRolesRequest {
+            file_key,
+            node_ids,
+            pretty,
+        }): Parameters<InferRolesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("infer_roles");
+        let request = client.get_file_nodes(&file_key, &node_ids, None);
+        let nodes = match with_timeout(timeout, request).await {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                let error_msg = format!("Error inferring roles: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let labels: Vec<RoleLabel> = node_ids
+            .iter()
+            .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)))
+            .flat_map(|node| roles::label(node, &self.config.rules))
+            .collect();
+        let result = output::to_json(&labels, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Find the nodes in a region of a page or frame, given as a rectangle or as the box of a reference node, e.g. for 'the stuff in the top-right of the screen'"
+    )]
+    async fn nodes_in_region(
+        &self,
+        Parameters(NodesInRegionRequest {
+            file_key,
+            node_id,
+            x,
+            y,
+            width,
+            height,
+            reference_node_id,
+            contained,
+            pretty,
+        }): Parameters<NodesInRegionRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+        if node_id.trim().is_empty() {
+            return missing_parameter("node_id", NODE_IDS_QUESTION);
+        }
+
+        let node_id = node_id.trim().to_string();
+        let reference_node_id = reference_node_id
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+        let rectangle = match (x, y, width, height) {
+            (Some(x), Some(y), Some(width), Some(height)) => Some(Bounds {
+                x,
+                y,
+                width,
+                height,
+            }),
+            (None, None, None, None) => None,
+            _ => {
+                return tool_error(
+                    "Error: give all of x, y, width and height, or none of them".to_string(),
+                )
+            }
+        };
+        if rectangle.is_some() == reference_node_id.is_some() {
+            return tool_error(
+                "Error: give either a rectangle (x, y, width, height) or reference_node_id"
+                    .to_string(),
+            );
+        }
+
+        let mut node_ids = vec![node_id.clone()];
+        node_ids.extend(reference_node_id.clone());
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("nodes_in_region");
+        let request = client.get_file_nodes(&file_key, &node_ids, None);
+        let nodes = match with_timeout(timeout, request).await {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                let error_msg = format!("Error finding nodes in region: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let document = |id: &str| nodes.pointer(&format!("/nodes/{}/document", id));
+        let Some(root) = document(&node_id) else {
+            return tool_error(format!("Error: node {} not found", node_id));
+        };
+        // Rectangles are relative to the frame searched in; pages have no
+        // box, so they use canvas coordinates
+        let region = match rectangle {
+            Some(rectangle) => {
+                let origin = Bounds::of(root, BoundsKind::Layout.key()).unwrap_or_default();
+                Bounds {
+                    x: origin.x + rectangle.x,
+                    y: origin.y + rectangle.y,
+                    ..rectangle
+                }
+            }
+            None => {
+                let reference = reference_node_id.as_deref().unwrap_or_default();
+                match document(reference)
+                    .and_then(|node| Bounds::of(node, BoundsKind::Layout.key()))
+                {
+                    Some(bounds) => bounds,
+                    None => {
+                        return tool_error(format!(
+                            "Error: reference node {} not found or has no bounding box",
+                            reference
+                        ))
+                    }
+                }
+            }
+        };
+
+        let found = bounds::nodes_in_region(
+            root,
+            &region,
+            contained.unwrap_or(false),
+            reference_node_id.as_deref(),
+        );
+        let result = json!({ "region": region, "nodes": found });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "List hidden and locked layers with their paths, in a whole file or below specific nodes. Hidden layers are often abandoned explorations."
+    )]
+    async fn find_hidden_layers(
+        &self,
+        Parameters(FindHiddenLayersRequest {
+            file_key,
+            node_ids,
+            pages,
+            inline,
+            pretty,
+        }): Parameters<FindHiddenLayersRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = node_ids.as_deref().map(parse_node_ids).unwrap_or_default();
+        let pages = pages.as_deref().and_then(NameFilter::parse);
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("find_hidden_layers");
+        let exclude = self.archive_filter(None);
+        let request = async {
+            if node_ids.is_empty() {
+                let mut file = fetch_file(&client, &file_key, pages.as_ref(), None).await?;
+                if let Some(exclude) = &exclude {
+                    pages::strip_excluded(&mut file, exclude);
+                }
+                let pages = file.pointer("/document/children").and_then(Value::as_array);
+                let reports: Vec<LayerReport> = pages
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|page| layers::hidden_and_locked(page, None))
+                    .collect();
+                return Ok(reports);
+            }
+
+            let nodes = client.get_file_nodes(&file_key, &node_ids, None).await?;
+            let reports = node_ids
+                .iter()
+                .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)))
+                .flat_map(|node| layers::hidden_and_locked(node, None))
+                .collect();
+            Ok(reports)
+        };
+        let reports: Vec<LayerReport> = match with_timeout(timeout, request).await {
+            Ok(reports) => reports,
+            Err(e) => {
+                let error_msg = format!("Error finding hidden layers: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let hidden = reports.iter().filter(|report| report.hidden).count();
+        let locked = reports.iter().filter(|report| report.locked).count();
+        let result = json!({ "hidden": hidden, "locked": locked, "layers": reports });
+        let summary = || json!({ "hidden": hidden, "locked": locked });
+        let text = output::to_json(&result, pretty.unwrap_or(false));
+        let description = format!("Hidden and locked layers of file {}", file_key);
+        let content = self.large_result("find_hidden_layers", description, text, summary, inline);
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "List layers still named the way Figma names new layers (\"Frame 4821\", \"Group 12\") with suggested names inferred from their content and role, as a rename worksheet in JSON or CSV. With the companion plugin, pass the renames to rename_layers to apply them."
+    )]
+    async fn suggest_layer_names(
+        &self,
+        Parameters(SuggestLayerNamesRequest {
+            file_key,
+            node_ids,
+            pages,
+            format,
+            inline,
+            pretty,
+        }): Parameters<SuggestLayerNamesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let format_name = format.as_deref().unwrap_or("json");
+        let Some(format) = AnalyticsFormat::parse(format_name) else {
+            let error_msg = format!(
+                "Error suggesting layer names: unknown format '{}', expected {}",
+                format_name, ANALYTICS_FORMAT_NAMES
+            );
+            return tool_error(error_msg);
+        };
+        let node_ids = node_ids.as_deref().map(parse_node_ids).unwrap_or_default();
+        let pages = pages.as_deref().and_then(NameFilter::parse);
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("suggest_layer_names");
+        let exclude = self.archive_filter(None);
+        let rules = &self.config.rules;
+        let request = async {
+            if node_ids.is_empty() {
+                let mut file = fetch_file(&client, &file_key, pages.as_ref(), None).await?;
+                if let Some(exclude) = &exclude {
+                    pages::strip_excluded(&mut file, exclude);
+                }
+                let pages = file.pointer("/document/children").and_then(Value::as_array);
+                let issues: Vec<NamingIssue> = pages
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|page| naming::report(page, None, rules))
+                    .collect();
+                return Ok(issues);
+            }
+
+            let nodes = client.get_file_nodes(&file_key, &node_ids, None).await?;
+            let issues = node_ids
+                .iter()
+                .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)))
+                .flat_map(|node| naming::report(node, None, rules))
+                .collect();
+            Ok(issues)
+        };
+        let issues: Vec<NamingIssue> = match with_timeout(timeout, request).await {
+            Ok(issues) => issues,
+            Err(e) => {
+                let error_msg = format!("Error suggesting layer names: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        if format == AnalyticsFormat::Csv {
+            return tool_success(naming::to_csv(&issues));
+        }
+
+        let suggested = issues
+            .iter()
+            .filter(|issue| issue.suggestion.is_some())
+            .count();
+        let unnamed = issues.len();
+        let result = json!({ "unnamed": unnamed, "suggested": suggested, "layers": issues });
+        let summary = || json!({ "unnamed": unnamed, "suggested": suggested });
+        let text = output::to_json(&result, pretty.unwrap_or(false));
+        let description = format!("Layer name suggestions for file {}", file_key);
+        let content = self.large_result("suggest_layer_names", description, text, summary, inline);
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "Pair frames that are translations of each other, named with a locale suffix (e.g. `Checkout / en`, `Checkout / de`, `Checkout (pt-BR)`), and compare their text layers with the reference locale. Flags texts that extend past their container, truncate, sit in fixed-size boxes or got much longer, and texts missing from a translation, for localization QA."
+    )]
+    async fn compare_locales(
+        &self,
+        Parameters(CompareLocalesRequest {
+            file_key,
+            node_ids,
+            pages,
+            reference_locale,
+            max_length_ratio,
+            pretty,
+        }): Parameters<CompareLocalesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = node_ids.as_deref().map(parse_node_ids).unwrap_or_default();
+        let pages = pages.as_deref().and_then(NameFilter::parse);
+        let reference_locale = reference_locale
+            .as_deref()
+            .map(str::trim)
+            .filter(|locale| !locale.is_empty());
+        let max_length_ratio = max_length_ratio.unwrap_or(DEFAULT_MAX_LENGTH_RATIO);
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("compare_locales");
+        let exclude = self.archive_filter(None);
+        let request = async {
+            if node_ids.is_empty() {
+                let mut file = fetch_file(&client, &file_key, pages.as_ref(), None).await?;
+                if let Some(exclude) = &exclude {
+                    pages::strip_excluded(&mut file, exclude);
+                }
+                let pages = file.pointer("/document/children").and_then(Value::as_array);
+                let groups = locales::compare(
+                    pages.into_iter().flatten(),
+                    reference_locale,
+                    max_length_ratio,
+                );
+                return Ok(groups);
+            }
+
+            let nodes = client.get_file_nodes(&file_key, &node_ids, None).await?;
+            let roots = node_ids
+                .iter()
+                .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)));
+            Ok(locales::compare(roots, reference_locale, max_length_ratio))
+        };
+        let groups: Vec<LocaleGroup> = match with_timeout(timeout, request).await {
+            Ok(groups) => groups,
+            Err(e) => {
+                let error_msg = format!("Error comparing locales: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&groups, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Get the stickies and shapes with text of a FigJam board, with their colors and sections, and the connectors between them as a graph of nodes and edges. Useful for turning workshop boards into documents."
+    )]
+    async fn get_board_graph(
+        &self,
+        Parameters(GetBoardGraphRequest {
+            file_key,
+            node_ids,
+            pages,
+            inline,
+            pretty,
+        }): Parameters<GetBoardGraphRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = node_ids.as_deref().map(parse_node_ids).unwrap_or_default();
+        let pages = pages.as_deref().and_then(NameFilter::parse);
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("get_board_graph");
+        let exclude = self.archive_filter(None);
+        let request = async {
+            if node_ids.is_empty() {
+                let mut file = fetch_file(&client, &file_key, pages.as_ref(), None).await?;
+                if let Some(exclude) = &exclude {
+                    pages::strip_excluded(&mut file, exclude);
+                }
+                let pages = file.pointer("/document/children").and_then(Value::as_array);
+                return Ok(figjam::graph(pages.into_iter().flatten()));
+            }
+
+            let nodes = client.get_file_nodes(&file_key, &node_ids, None).await?;
+            let roots = node_ids
+                .iter()
+                .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)));
+            Ok(figjam::graph(roots))
+        };
+        let graph: BoardGraph = match with_timeout(timeout, request).await {
+            Ok(graph) => graph,
+            Err(e) => {
+                let error_msg = format!("Error getting board graph: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let summary = || json!({ "nodes": graph.nodes.len(), "edges": graph.edges.len() });
+        let text = output::to_json(&graph, pretty.unwrap_or(false));
+        let description = format!("Board graph of file {}", file_key);
+        let content = self.large_result("get_board_graph", description, text, summary, inline);
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "Count the stamps (+1, heart, ...) placed on each sticky and shape of a FigJam board and rank the items by votes, to summarize dot voting. Each stamp counts for the smallest item under its center."
+    )]
+    async fn tally_votes(
+        &self,
+        Parameters(TallyVotesRequest {
+            file_key,
+            node_ids,
+            pages,
+            pretty,
+        }): Parameters<TallyVotesRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = node_ids.as_deref().map(parse_node_ids).unwrap_or_default();
+        let pages = pages.as_deref().and_then(NameFilter::parse);
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("tally_votes");
+        let exclude = self.archive_filter(None);
+        let request = async {
+            if node_ids.is_empty() {
+                let mut file = fetch_file(&client, &file_key, pages.as_ref(), None).await?;
+                if let Some(exclude) = &exclude {
+                    pages::strip_excluded(&mut file, exclude);
+                }
+                let pages = file.pointer("/document/children").and_then(Value::as_array);
+                return Ok(figjam::tally(pages.into_iter().flatten()));
+            }
+
+            let nodes = client.get_file_nodes(&file_key, &node_ids, None).await?;
+            let roots = node_ids
+                .iter()
+                .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)));
+            Ok(figjam::tally(roots))
+        };
+        let tally: VoteTally = match with_timeout(timeout, request).await {
+            Ok(tally) => tally,
+            Err(e) => {
+                let error_msg = format!("Error tallying votes: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&tally, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Audit the text contrast of a frame against WCAG AA and return its export with failing text nodes outlined in red and numbered, plus a legend of their contrast ratios"
+    )]
+    async fn annotate_contrast(
+        &self,
+        Parameters(AnnotateContrastRequest {
+            file_key,
+            node_id,
+            scale,
+            bounds,
+            pretty,
+        }): Parameters<AnnotateContrastRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_id = node_id.trim();
+        if node_id.is_empty() {
+            return missing_parameter("node_id", NODE_IDS_QUESTION);
+        }
+
+        let bounds = match parse_bounds(bounds.as_deref()) {
+            Ok(bounds) => bounds,
+            Err(error_msg) => return tool_error(error_msg),
+        };
+        if let Err(message) = export::validate("png", scale) {
+            return tool_error(format!("Error: {}", message));
+        }
+
+        let timeout = self.config.tool_timeouts.for_tool("annotate_contrast");
+        let request = self.contrast_overlay(&file_key, node_id, scale.unwrap_or(1.0), bounds, &ct);
+        let (uri, image, issues) = match with_timeout(timeout, request).await {
+            Ok(overlay) => overlay,
+            Err(e) => {
+                let error_msg = format!("Error annotating contrast: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let issues: Vec<Value> = issues
+            .iter()
+            .enumerate()
+            .map(|(index, issue)| {
+                let mut issue = json!(issue);
+                issue["label"] = json!(index + 1);
+                issue
+            })
+            .collect();
+        let legend = json!({ "resource_uri": uri, "issues": issues });
+
+        let content = vec![
+            Content::image(general_purpose::STANDARD.encode(&image), "image/png"),
+            Content::text(output::to_json(&legend, pretty.unwrap_or(false))),
+        ];
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "Export frames in order and stitch them into a looping animated GIF or APNG, for previewing a prototype's steps in chat"
+    )]
+    async fn export_animation(
+        &self,
+        Parameters(ExportAnimationRequest {
+            file_key,
+            node_ids,
+            format,
+            delay_ms,
+            scale,
+            bounds,
+            pretty,
+        }): Parameters<ExportAnimationRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+        if node_ids.len() > MAX_ANIMATION_FRAMES {
+            let error_msg = format!(
+                "Error: {} frames requested, an animation has at most {}",
+                node_ids.len(),
+                MAX_ANIMATION_FRAMES
+            );
+            return tool_error(error_msg);
+        }
+
+        let format = match format
+            .as_deref()
+            .map(|name| (name, AnimationFormat::parse(name)))
+        {
+            None => AnimationFormat::Gif,
+            Some((_, Some(format))) => format,
+            Some((name, None)) => {
+                let error_msg = format!(
+                    "Error: unknown animation format '{}', expected {}",
+                    name, ANIMATION_FORMATS
+                );
+                return tool_error(error_msg);
+            }
+        };
+        let bounds = match parse_bounds(bounds.as_deref()) {
+            Ok(bounds) => bounds,
+            Err(error_msg) => return tool_error(error_msg),
+        };
+        if let Err(message) = export::validate("png", scale) {
+            return tool_error(format!("Error: {}", message));
+        }
+        let delay_ms = delay_ms.unwrap_or(DEFAULT_FRAME_DELAY_MS);
+
+        let timeout = self.config.tool_timeouts.for_tool("export_animation");
+        let deadline = Instant::now() + timeout;
+        let request = self.render_animation(
+            &file_key,
+            &node_ids,
+            format,
+            delay_ms,
+            scale.unwrap_or(1.0),
+            bounds,
+            deadline,
+            &ct,
+        );
+        let (uri, data) = match with_timeout(timeout, request).await {
+            Ok(animation) => animation,
+            Err(e) => {
+                let error_msg = format!("Error exporting animation: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let mime_type = ImageCache::get_mime_type(format.extension());
+        let filename = self.export_filename(&node_ids[0], &uri);
+        let result = json!({
+            "resource_uri": uri,
+            "filename": filename,
+            "mime_type": mime_type,
+            "frames": node_ids,
+            "delay_ms": delay_ms,
+            "size": data.len(),
+        });
+
+        let content = vec![
+            Content::image(general_purpose::STANDARD.encode(&data), mime_type),
+            Content::text(output::to_json(&result, pretty.unwrap_or(false))),
+        ];
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "Export frames as thumbnails and compose them into a single grid image labeled with the frame names, for an overview of a flow or a page of screens"
+    )]
+    async fn make_contact_sheet(
+        &self,
+        Parameters(MakeContactSheetRequest {
+            file_key,
+            node_ids,
+            columns,
+            thumbnail_width,
+            pretty,
+        }): Parameters<MakeContactSheetRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let mut frames: Vec<String> = Vec::new();
+        for node_id in parse_node_ids(&node_ids) {
+            if !frames.contains(&node_id) {
+                frames.push(node_id);
+            }
+        }
+        if frames.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+        if frames.len() > MAX_CONTACT_SHEET_FRAMES {
+            let error_msg = format!(
+                "Error: {} frames requested, a contact sheet has at most {}",
+                frames.len(),
+                MAX_CONTACT_SHEET_FRAMES
+            );
+            return tool_error(error_msg);
+        }
+
+        let thumbnail_width = thumbnail_width.unwrap_or(DEFAULT_THUMBNAIL_WIDTH);
+        if !THUMBNAIL_WIDTHS.contains(&thumbnail_width) {
+            let error_msg = format!(
+                "Error: thumbnail_width {} is out of range, expected {} to {}",
+                thumbnail_width,
+                THUMBNAIL_WIDTHS.start(),
+                THUMBNAIL_WIDTHS.end()
+            );
+            return tool_error(error_msg);
+        }
+        // As square as the frames allow
+        let columns = match columns {
+            Some(0) => return tool_error("Error: columns must be at least 1".to_string()),
+            Some(columns) => columns as usize,
+            None => (frames.len() as f64).sqrt().ceil() as usize,
+        };
+
+        let timeout = self.config.tool_timeouts.for_tool("make_contact_sheet");
+        let deadline = Instant::now() + timeout;
+        let request =
+            self.render_contact_sheet(&file_key, &frames, columns, thumbnail_width, deadline, &ct);
+        let (uri, data, tiles, failures) = match with_timeout(timeout, request).await {
+            Ok(contact_sheet) => contact_sheet,
+            Err(e) => {
+                let error_msg = format!("Error making contact sheet: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let mut result = json!({
+            "resource_uri": uri,
+            "filename": self.export_filename(&frames[0], &uri),
+            "columns": columns.min(tiles.len()),
+            "frames": tiles,
+        });
+        if !failures.is_empty() {
+            result["failed"] = json!(failures);
+        }
+
+        let content = vec![
+            Content::image(general_purpose::STANDARD.encode(&data), "image/png"),
+            Content::text(output::to_json(&result, pretty.unwrap_or(false))),
+        ];
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "Bundle frame exports and a markdown spec referencing them into a directory or ZIP archive, for attaching to a pull request as a design reference",
+        annotations(read_only_hint = false)
+    )]
+    async fn create_pr_attachment(
+        &self,
+        Parameters(CreatePrAttachmentRequest {
+            file_key,
+            node_ids,
+            output,
+            zip,
+            overwrite,
+            scale,
+            bounds,
+            title,
+            pretty,
+        }): Parameters<CreatePrAttachmentRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if file_key.trim().is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let node_ids = parse_node_ids(&node_ids);
+        if node_ids.is_empty() {
+            return missing_parameter("node_ids", NODE_IDS_QUESTION);
+        }
+
+        let bounds = match parse_bounds(bounds.as_deref()) {
+            Ok(bounds) => bounds,
+            Err(error_msg) => return tool_error(error_msg),
+        };
+        if let Err(message) = export::validate("png", scale) {
+            return tool_error(format!("Error: {}", message));
+        }
+        let output = match self.tool_path(&output) {
+            Ok(output) => output,
+            Err(message) => return tool_error(format!("Error: output {}", message)),
+        };
+        let zip = zip.unwrap_or(false);
+        let overwrite = overwrite.unwrap_or(false);
+        if zip && !overwrite && output.exists() {
+            return already_exists(&[output]);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("create_pr_attachment");
+        let request = Attachment::build(
+            &client,
+            &file_key,
+            &node_ids,
+            scale,
+            bounds,
+            title.as_deref(),
+        );
+        let mut attachment = match with_timeout(timeout, request).await {
+            Ok(attachment) => attachment,
+            Err(e) => {
+                let error_msg = format!("Error creating attachment: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+        if let Some(text) = &self.config.watermark {
+            let images = attachment.entries.iter_mut();
+            for (_, image) in images.filter(|(path, _)| path.ends_with(".png")) {
+                match stamp_watermark(std::mem::take(image), "png", text).await {
+                    Ok(stamped) => *image = stamped,
+                    Err(e) => return tool_error(format!("Error creating attachment: {}", e)),
+                }
+            }
+        }
+
+        if !zip && !overwrite {
+            let existing = attachment.existing_files(&output);
+            if !existing.is_empty() {
+                return already_exists(&existing);
+            }
+        }
+
+        let written = if zip {
+            attachment
+                .write_zip(&output)
+                .map(|_| json!({ "zip": output }))
+        } else {
+            attachment
+                .write_directory(&output)
+                .map(|files| json!({ "directory": output, "files": files }))
+        };
+        let result = match written {
+            Ok(result) => result,
+            Err(e) => {
+                let error_msg = format!("Error writing attachment: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Run the exports, token generation and codegen defined in the workspace manifest and write the results into the repository (unchanged files are left alone)",
+        annotations(read_only_hint = false, idempotent_hint = true)
+    )]
+    async fn sync_workspace(
+        &self,
+        Parameters(SyncWorkspaceRequest {
+            path,
+            background,
+            pretty,
+        }): Parameters<SyncWorkspaceRequest>,
+        peer: Peer<RoleServer>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let path = match path.as_deref().map(|path| self.tool_path(path)).transpose() {
+            Ok(path) => path.map(|path| path.to_string_lossy().into_owned()),
+            Err(message) => return tool_error(format!("Error: path {}", message)),
+        };
+
+        if background.unwrap_or(false) {
+            let server = self.clone();
+            let started = self.spawn_job(JobKind::Sync, None, peer, move |job| async move {
+                let report = server
+                    .run_sync(path.as_deref(), job.cancellation.clone())
+                    .await?;
+                Ok(json!(report))
+            });
+
+            return job_started(started, pretty);
+        }
+
+        let timeout = self.config.tool_timeouts.for_tool("sync_workspace");
+        let request = self.run_sync(path.as_deref(), ct);
+        let report = match with_timeout(timeout, request).await {
+            Ok(report) => report,
+            Err(e) => {
+                let error_msg = format!("Error syncing workspace: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&report, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Index files to map the components, styles and variables they define and use across files (needed by query_design_system)"
+    )]
+    async fn index_files(
+        &self,
+        Parameters(IndexFilesRequest {
+            file_keys,
+            background,
+            pretty,
+        }): Parameters<IndexFilesRequest>,
+        peer: Peer<RoleServer>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let file_keys = parse_node_ids(&file_keys);
+        if file_keys.is_empty() {
+            return missing_parameter("file_keys", FILE_KEY_QUESTION);
+        }
+
+        if background.unwrap_or(false) {
+            let total = file_keys.len();
+            let server = self.clone();
+            // The job keeps the account in use when it was started
+            let client = self.client()?;
+            let started =
+                self.spawn_job(JobKind::Index, Some(total), peer, move |job| async move {
+                    let client = client.with_cancellation(job.cancellation.clone());
+                    let indexed = server.index(&client, &file_keys, Some(&job)).await?;
+                    Ok(json!({ "indexed": indexed }))
+                });
+
+            return job_started(started, pretty);
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("index_files");
+        let request = self.index(&client, &file_keys, None);
+        let indexed = match with_timeout(timeout, request).await {
+            Ok(indexed) => indexed,
+            Err(e) => {
+                let error_msg = format!("Error indexing files: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = json!({ "indexed": indexed });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Find components, styles and variables by name or key across indexed files, with where they are defined and used, and which indexed files changed on Figma since they were indexed (stale)"
+    )]
+    async fn query_design_system(
+        &self,
+        Parameters(QueryDesignSystemRequest {
+            query,
+            kind,
+            pretty,
+        }): Parameters<QueryDesignSystemRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let kind = match kind.as_deref() {
+            None => None,
+            Some(value) => match AssetKind::parse(value) {
+                Some(kind) => Some(kind),
+                None => {
+                    let error_msg = format!(
+                        "Error querying design system: unknown kind '{}', expected component, style or variable",
+                        value
+                    );
+                    return tool_error(error_msg);
+                }
+            },
+        };
+
+        let indexed_files = match self.design_index.summaries() {
+            Ok(summaries) => summaries,
+            Err(e) => return tool_error(format!("Error querying design system: {}", e)),
+        };
+        if indexed_files.is_empty() {
+            return tool_error(
+                "Error querying design system: no files are indexed yet, call index_files first"
+                    .to_string(),
+            );
+        }
+
+        // The index is a snapshot; tell the agent which files changed since,
+        // or index them again first if configured to
+        let client = &self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool("query_design_system");
+        let request = async {
+            let mut sources = self.index_freshness(client).await?;
+            let stale = stale_files(&sources);
+            if self.config.refresh_stale && !stale.is_empty() {
+                self.index(client, &stale, None).await?;
+                sources = self.index_freshness(client).await?;
+            }
+            Ok(sources)
+        };
+        let sources = match with_timeout(timeout, request).await {
+            Ok(sources) => sources,
+            Err(e) => return tool_error(format!("Error querying design system: {}", e)),
+        };
+        let stale = stale_files(&sources);
+
+        let assets = match self.design_index.query(&query, kind) {
+            Ok(assets) => assets,
+            Err(e) => return tool_error(format!("Error querying design system: {}", e)),
+        };
+
+        let indexed_keys: Vec<&str> = indexed_files.iter().map(|f| f.file_key.as_str()).collect();
+        let mut result =
+            json!({ "indexed_files": indexed_keys, "sources": sources, "assets": assets });
+        if !stale.is_empty() {
+            result["warning"] = json!(format!(
+                "Changed since indexed: {}. Call index_files on them again for current data.",
+                stale.join(", ")
+            ));
+        }
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Load the repository's figma-mcp.toml workspace manifest: the Figma files related to this project, export/token/codegen targets and naming conventions"
+    )]
+    async fn load_workspace(
+        &self,
+        Parameters(LoadWorkspaceRequest { path, pretty }): Parameters<LoadWorkspaceRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = match path.as_deref().map(|path| self.tool_path(path)).transpose() {
+            Ok(path) => path.map(|path| path.to_string_lossy().into_owned()),
+            Err(message) => return tool_error(format!("Error: path {}", message)),
+        };
+        let workspace = match self.workspace(path.as_deref()) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                let error_msg = format!("Error loading workspace: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let files: Vec<Value> = workspace
+            .manifest
+            .files
+            .iter()
+            .map(|entry| {
+                json!({
+                    "name": entry.name,
+                    "url": entry.url,
+                    "file_key": workspace.file_key(&entry.name).ok(),
+                    "description": entry.description,
+                })
+            })
+            .collect();
+
+        let result = json!({
+            "root": workspace.root,
+            "manifest": workspace.manifest_path,
+            "files": files,
+            "exports": workspace.manifest.exports,
+            "tokens": workspace.manifest.tokens,
+            "codegen": workspace.manifest.codegen,
+            "naming": workspace.manifest.naming,
+        });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(description = "Get current user information (useful for testing authentication)")]
+    async fn get_me(&self) -> Result<CallToolResult, McpError> {
+        let timeout = self.config.tool_timeouts.for_tool("get_me");
+        let result = match with_timeout(timeout, self.client()?.get_me()).await {
+            Ok(user) => user,
+            Err(e) => {
+                let error_msg = format!("Error fetching user info: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_compact_json(&result);
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Remove all design data this server holds in memory: exported images, stored large results, background jobs with their results, the design index, node statistics and cached API responses. Running jobs are cancelled. Design data is never written to disk, so afterwards none of this session's is left on the host; stored credentials such as the OAuth token file are removed with the `figma-mcp purge` command instead. On a server shared over HTTP or a socket, only the images, results and jobs this session created are removed; the caches other sessions use are kept.",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn purge_all(&self) -> Result<CallToolResult, McpError> {
+        let purged = match &self.session {
+            Some(session) => self.purge_session(session.id()),
+            None => self.purge_server(),
+        };
+        let purged = match purged {
+            Ok(purged) => purged,
+            Err(e) => return tool_error(format!("Error purging cached data: {}", e)),
+        };
+        tracing::info!("Purged cached design data: {}", purged);
+
+        tool_success(output::to_compact_json(&json!({ "purged": purged })))
+    }
+
+    #[tool(
+        description = "Diagnostics: compare the Figma API responses seen so far with the fields this server reads. Lists per endpoint how many objects were checked and which expected fields were missing or unknown fields appeared, a sign the API changed."
+    )]
+    async fn schema_report(
+        &self,
+        Parameters(SchemaReportRequest { pretty }): Parameters<SchemaReportRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let endpoints: Vec<ShapeReport> = self.client()?.schema().report();
+        let drift = endpoints.iter().any(ShapeReport::has_drift);
+
+        let result = json!({ "drift": drift, "endpoints": endpoints });
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Help: How to use this Figma file MCP server. Without a topic, lists the workflow guides and all tools; with a tool name as topic, returns its parameters and example calls"
+    )]
+    async fn help(
+        &self,
+        Parameters(HelpRequest { topic }): Parameters<HelpRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let tools = self.config.descriptions.apply(self.tool_router.list_all());
+
+        let Some(topic) = topic.filter(|topic| !topic.trim().is_empty()) else {
+            return tool_success(help::overview(&tools));
+        };
+
+        // Old tool names lead to the guide of the renamed tool
+        let name =
+            aliases::resolve(TOOL_ALIASES, topic.trim()).map_or(topic.as_str(), |alias| alias.tool);
+        match help::guide(name, &tools) {
+            Some(guide) => tool_success(guide),
+            None => tool_error(format!(
+                "Error: unknown help topic '{}'; call help without a topic for the list of topics and tools",
+                topic
+            )),
+        }
+    }
+}
+
+// Registered when named accounts are configured
+#[tool_router(router = account_tool_router)]
+impl FigmaServer {
+    #[tool(
+        description = "Switch the Figma account later tool calls use, between the default account (FIGMA_TOKEN) and the named ones configured as FIGMA_TOKEN_<NAME>. Without an account, lists the accounts and the one in use."
+    )]
+    async fn switch_account(
+        &self,
+        Parameters(SwitchAccountRequest { account }): Parameters<SwitchAccountRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if let Some(account) = account {
+            match self.accounts.switch(&account) {
+                Ok(true) => {
+                    tracing::info!(
+                        "Switched to Figma account '{}'",
+                        account.trim().to_lowercase()
+                    )
+                }
+                Ok(false) => {
+                    return tool_error(format!(
+                        "Error: unknown account '{}'; the accounts are {}",
+                        account,
+                        self.accounts.names().join(", ")
+                    ))
+                }
+                Err(e) => return tool_error(format!("Error switching accounts: {}", e)),
+            }
+        }
+        let active = match self.accounts.active() {
+            Ok(active) => active,
+            Err(e) => return tool_error(format!("Error reading the account in use: {}", e)),
+        };
+
+        let result = json!({
+            "active": active,
+            "accounts": self.accounts.names(),
+        });
+
+        tool_success(output::to_compact_json(&result))
+    }
+}
+
+// Tools backed by the companion Figma plugin, registered by `with_bridge`
+#[tool_router(router = bridge_tool_router)]
+impl FigmaServer {
+    #[tool(
+        description = "Get the nodes currently selected in the Figma editor (requires the companion plugin)"
+    )]
+    async fn get_current_selection(
+        &self,
+        Parameters(PluginStateRequest { pretty }): Parameters<PluginStateRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let timeout = self.config.tool_timeouts.for_tool("get_current_selection");
+        let request = self.plugin_request("get_current_selection", json!({}), &ct);
+        let result = match with_timeout(timeout, request).await {
+            Ok(selection) => selection,
+            Err(e) => {
+                let error_msg = format!("Error fetching selection: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Get the file and page open in the Figma editor (requires the companion plugin)"
+    )]
+    async fn get_open_file(
+        &self,
+        Parameters(PluginStateRequest { pretty }): Parameters<PluginStateRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let timeout = self.config.tool_timeouts.for_tool("get_open_file");
+        let request = self.plugin_request("get_open_file", json!({}), &ct);
+        let result = match with_timeout(timeout, request).await {
+            Ok(file) => file,
+            Err(e) => {
+                let error_msg = format!("Error fetching open file: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Generate HTML or React code for the nodes selected in the Figma editor (requires the companion plugin)"
+    )]
+    async fn selection_to_code(
+        &self,
+        Parameters(SelectionToCodeRequest { format }): Parameters<SelectionToCodeRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let format_name = format.as_deref().unwrap_or("react");
+        let Some(format) = CodeFormat::parse(format_name) else {
+            let error_msg = format!(
+                "Error generating code: unsupported format '{}', expected html or react",
+                format_name
+            );
+            return tool_error(error_msg);
+        };
+
+        let timeout = self.config.tool_timeouts.for_tool("selection_to_code");
+        let request = self.plugin_request("export_selection", json!({}), &ct);
+        let selection = match with_timeout(timeout, request).await {
+            Ok(selection) => selection,
+            Err(e) => {
+                let error_msg = format!("Error exporting selection: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let nodes = selection
+            .get("nodes")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        if nodes.is_empty() {
+            return tool_error(
+                "Error generating code: nothing is selected in the Figma editor".to_string(),
+            );
+        }
+
+        let content = nodes
+            .iter()
+            .map(|node| Content::text(codegen::generate(node, format, &self.config.rules)))
+            .collect();
+
+        Ok(CallToolResult::success(content))
+    }
+
+    #[tool(
+        description = "Create a frame, text or rectangle in the file open in the Figma editor (requires the companion plugin)",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    async fn create_node(
+        &self,
+        Parameters(CreateNodeRequest {
+            node_type,
+            parent_id,
+            properties,
+            pretty,
+        }): Parameters<CreateNodeRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let node_type = node_type.trim().to_uppercase();
+        if !CREATABLE_NODE_TYPES.contains(&node_type.as_str()) {
+            let error_msg = format!(
+                "Error creating node: unsupported node type '{}', expected one of {}",
+                node_type,
+                CREATABLE_NODE_TYPES.join(", ")
+            );
+            return tool_error(error_msg);
+        }
+
+        let params = json!({
+            "type": node_type,
+            "parentId": parent_id,
+            "properties": properties,
+        });
+
+        let timeout = self.config.tool_timeouts.for_tool("create_node");
+        let request = self.plugin_request("create_node", params, &ct);
+        let result = match with_timeout(timeout, request).await {
+            Ok(node) => node,
+            Err(e) => {
+                let error_msg = format!("Error creating node: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Update properties of a node in the file open in the Figma editor (requires the companion plugin)",
+        annotations(read_only_hint = false, idempotent_hint = true)
+    )]
+    async fn update_node(
+        &self,
+        Parameters(UpdateNodeRequest {
+            node_id,
+            properties,
+            pretty,
+        }): Parameters<UpdateNodeRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if node_id.trim().is_empty() {
+            return missing_parameter("node_id", NODE_IDS_QUESTION);
+        }
+
+        let params = json!({
+            "nodeId": node_id.trim(),
+            "properties": properties,
+        });
+
+        let timeout = self.config.tool_timeouts.for_tool("update_node");
+        let request = self.plugin_request("update_node", params, &ct);
+        let result = match with_timeout(timeout, request).await {
+            Ok(node) => node,
+            Err(e) => {
+                let error_msg = format!("Error updating node: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+
+    #[tool(
+        description = "Rename layers in the file open in the Figma editor, e.g. to apply the suggestions of suggest_layer_names (requires the companion plugin). Returns the renamed layers with their previous names and the ones that failed.",
+        annotations(read_only_hint = false, idempotent_hint = true)
+    )]
+    async fn rename_layers(
+        &self,
+        Parameters(RenameLayersRequest { renames, pretty }): Parameters<RenameLayersRequest>,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        if renames.is_empty() {
+            return missing_parameter("renames", RENAMES_QUESTION);
+        }
+        if let Some(rename) = renames.iter().find(|rename| rename.name.trim().is_empty()) {
+            let error_msg = format!(
+                "Error renaming layers: empty name for node {}",
+                rename.node_id
+            );
+            return tool_error(error_msg);
+        }
+
+        let timeout = self.config.tool_timeouts.for_tool("rename_layers");
+        let request = self.plugin_request("rename_nodes", json!({ "renames": renames }), &ct);
+        let result = match with_timeout(timeout, request).await {
+            Ok(result) => result,
+            Err(e) => {
+                let error_msg = format!("Error renaming layers: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        let result = output::to_json(&result, pretty.unwrap_or(false));
+
+        tool_success(result)
+    }
+}
+
+impl FigmaServer {
+    /// Returns an error message when a deep fetch is projected, from the node
+    /// counts of earlier responses, to exceed the configured node limit.
+    fn check_projected_size(
+        &self,
+        file_key: &str,
+        node_ids: &[&str],
+        depth: u32,
+    ) -> Option<String> {
+        if depth < GUARDED_DEPTH {
+            return None;
+        }
+
+        let projected: usize = node_ids
+            .iter()
+            .filter_map(|node_id| {
+                self.node_stats
+                    .estimate(file_key, node_id, depth)
+                    .ok()
+                    .flatten()
+            })
+            .sum();
+
+        let limit = self.config.max_projected_nodes;
+        if projected <= limit {
+            return None;
+        }
+
+        Some(format!(
+            "Error: fetching at depth {} is projected to return about {} nodes, above the limit of {}. \
+             Use a lower depth, fetch specific frames with get_file_nodes, or set force to true to fetch anyway.",
+            depth, projected, limit
+        ))
+    }
+
+    /// How each indexed file compares with the file on Figma.
+    async fn index_freshness(&self, client: &FigmaClient) -> crate::Result<Vec<Freshness>> {
+        let indexed_files = self.design_index.summaries()?;
+        let checks = indexed_files.iter().map(|file| {
+            let cached = FileMeta {
+                version: file.version.clone(),
+                last_modified: file.last_modified.clone(),
+            };
+            async move { self.freshness(client, &file.file_key, &cached).await }
+        });
+
+        Ok(futures_util::future::join_all(checks).await)
+    }
+
+    /// How the cached data of a file compares with the file on Figma,
+    /// asking Figma unless its version was seen within `META_TTL`. A failed
+    /// check leaves `stale` unset rather than failing the tool.
+    async fn freshness(
+        &self,
+        client: &FigmaClient,
+        file_key: &str,
+        cached: &FileMeta,
+    ) -> Freshness {
+        let current = match self.file_meta.get(file_key) {
+            Some(meta) => Some(meta),
+            None => match client.get_file_meta(file_key).await {
+                Ok(response) => {
+                    let meta = FileMeta::from_meta(&response);
+                    self.record_version(file_key, meta.clone());
+                    Some(meta)
+                }
+                Err(e) => {
+                    tracing::debug!("Failed to check whether {} changed: {}", file_key, e);
+                    None
+                }
+            },
+        };
+
+        Freshness::new(file_key, cached, current.as_ref())
+    }
+
+    /// Records the version of a file as just read from Figma. When it
+    /// changed since the last one seen, data derived from the file is
+    /// invalidated.
+    fn record_version(&self, file_key: &str, meta: FileMeta) {
+        if let Ok(true) = self.file_meta.insert(file_key, meta) {
+            self.invalidate_file(file_key);
+        }
+    }
+
+    /// Drops the node statistics and reusable responses of a changed file
+    /// and marks its exports stale. The design index keeps the file, reported stale by
+    /// `query_design_system` until it is indexed again.
+    fn invalidate_file(&self, file_key: &str) {
+        let _ = self.node_stats.forget(file_key);
+        for client in self.accounts.clients() {
+            let _ = client.forget_file(file_key);
+        }
+        if let Ok(images) = self.image_cache.invalidate_file(file_key) {
+            tracing::debug!("File {} changed, invalidated {} image(s)", file_key, images);
+        }
+    }
+
+    /// Invalidates the data of files webhooks report as updated, without
+    /// waiting for a request to notice the new version.
+    async fn forget_updated_files(self, mut events: broadcast::Receiver<WebhookEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) if event.event_type == "FILE_UPDATE" => {
+                    let _ = self.file_meta.invalidate(&event.file_key);
+                    self.invalidate_file(&event.file_key);
+                }
+                Ok(_) => {}
+                // Missed updates may be of any file, so check them all again
+                Err(RecvError::Lagged(_)) => {
+                    let _ = self.file_meta.clear();
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Records that this session created the resource, so it can see it
+    /// with session visibility.
+    fn claim(&self, uri: &str) {
+        if let Some(session) = &self.session {
+            let _ = self.owners.add(uri, session.id());
+        }
+    }
+
+    /// Whether this session may list and read the resource.
+    fn can_see(&self, uri: &str) -> bool {
+        match (&self.session, self.config.resource_visibility) {
+            (None, _) | (_, Visibility::Shared) => true,
+            (Some(session), Visibility::Session) => self.owners.is_owner(uri, session.id()),
+        }
+    }
+
+    /// Drops everything the server holds, for the only client of a stdio
+    /// server.
+    fn purge_server(&self) -> crate::Result<Value> {
+        self.node_stats.clear()?;
+        self.owners.clear()?;
+        self.file_meta.clear()?;
+        let mut responses = 0;
+        for client in self.accounts.clients() {
+            responses += client.clear_cached_responses()?;
+        }
+
+        Ok(json!({
+            "images": self.image_cache.clear()?,
+            "results": self.results.clear()?,
+            "jobs": self.jobs.clear()?,
+            "indexed_files": self.design_index.clear()?,
+            "responses": responses,
+        }))
+    }
+
+    /// Drops the images, results and jobs no other session created; the
+    /// caches all sessions share are kept.
+    fn purge_session(&self, session: &str) -> crate::Result<Value> {
+        let uris = self.owners.forget_session(session)?;
+        let result_ids: Vec<&str> = uris
+            .iter()
+            .filter_map(|uri| results::result_id_from_uri(uri))
+            .collect();
+        let job_ids: Vec<&str> = uris
+            .iter()
+            .filter_map(|uri| jobs::job_id_from_uri(uri))
+            .collect();
+
+        Ok(json!({
+            "images": self.image_cache.remove(&uris)?,
+            "results": self.results.remove(&result_ids)?,
+            "jobs": self.jobs.remove(&job_ids)?,
+        }))
+    }
+
+    /// Whether this session may read and cancel the job.
+    fn can_see_job(&self, job_id: &str) -> bool {
+        self.can_see(&format!("{}{}", jobs::JOB_URI_PREFIX, job_id))
+    }
+
+    /// Registers every exported image URL in the cache and returns the
+    /// resource URI for each exported node ID.
+    fn register_exports(
+        &self,
+        file_key: &str,
+        export_result: &Value,
+        format: &str,
+        scale: f64,
+        nodes: Option<&Value>,
+    ) -> Vec<(String, String)> {
+        let Some(images) = export_result.get("images").and_then(|v| v.as_object()) else {
+            return Vec::new();
+        };
+
+        let mut exports = Vec::new();
+        for (node_id, url) in images {
+            let Some(url_str) = url.as_str() else {
+                continue;
+            };
+
+            let registered = self.image_cache.register_export(
+                file_key.to_string(),
+                node_id.clone(),
+                format.to_string(),
+                scale,
+                url_str.to_string(),
+            );
+            if let Ok(uri) = registered {
+                self.claim(&uri);
+                let name = nodes
+                    .and_then(|nodes| nodes.pointer(&format!("/nodes/{}/document/name", node_id)))
+                    .and_then(Value::as_str);
+                if let Some(name) = name {
+                    let _ = self.image_cache.set_name(&uri, name);
+                }
+                exports.push((node_id.clone(), uri));
+            }
+        }
+
+        exports
+    }
+
+    /// Exports the nodes in one format and registers the images. Fails with
+    /// the message to return when no image was exported.
+    async fn export_format(
+        &self,
+        client: &FigmaClient,
+        file_key: &str,
+        node_ids: &[String],
+        format: ExportFormat,
+        options: ExportOptions,
+        deadline: Instant,
+    ) -> Result<FormatExport, String> {
+        let timeout = self.config.tool_timeouts.for_tool("export_images");
+        let request = client.export_images(file_key, node_ids, format.name(), options);
+        let result = match tokio::time::timeout_at(deadline, request).await {
+            Ok(Ok(export_result)) => export_result,
+            Ok(Err(e)) => {
+                let request = explain_export_error(client, file_key, node_ids, e);
+                return Err(match tokio::time::timeout_at(deadline, request).await {
+                    Ok(error_msg) => error_msg,
+                    Err(_) => format!("Error exporting images: {}", Error::Timeout(timeout)),
+                });
+            }
+            Err(_) => {
+                return Err(format!(
+                    "Error exporting images: {}",
+                    Error::Timeout(timeout)
+                ))
+            }
+        };
+
+        let scale = options.scale.unwrap_or(1.0);
+        let (nodes, failures) =
+            check_exports(client, file_key, &result, node_ids, scale, deadline).await;
+        if failures.len() == node_ids.len() {
+            return Err(format!(
+                "Error exporting images: {}",
+                export::describe_failures(&failures)
+            ));
+        }
+
+        let exports =
+            self.register_exports(file_key, &result, format.name(), scale, nodes.as_ref());
+
+        Ok(FormatExport {
+            result,
+            exports,
+            failures,
+        })
+    }
+
+    /// The JSON result of an export in one format.
+    fn export_result(&self, export: &FormatExport) -> Value {
+        let mut result = export.result.clone();
+        // Signed export URLs change on every call, the resource URIs don't;
+        // with a watermark, the URLs would also bypass it
+        if self.config.deterministic || self.config.watermark.is_some() {
+            let images: Map<String, Value> = export
+                .exports
+                .iter()
+                .map(|(node_id, uri)| (node_id.clone(), Value::String(uri.clone())))
+                .collect();
+            result["images"] = Value::Object(images);
+        }
+        result["resources"] = json!(self.export_resources(&export.exports));
+        if !export.failures.is_empty() {
+            result["failed"] = json!(export.failures);
+        }
+
+        result
+    }
+
+    /// Where each export can be read, with the file name and type to save
+    /// it under.
+    fn export_resources(&self, exports: &[(String, String)]) -> Vec<Value> {
+        exports
+            .iter()
+            .filter_map(|(node_id, uri)| {
+                let entry = self.image_cache.get_entry(uri).ok()??;
+                Some(json!({
+                    "node_id": node_id,
+                    "uri": uri,
+                    "filename": entry.filename(uri),
+                    "mime_type": ImageCache::get_mime_type(&entry.format),
+                }))
+            })
+            .collect()
+    }
+
+    /// The file name to save an export under, falling back to the node ID.
+    fn export_filename(&self, node_id: &str, uri: &str) -> String {
+        match self.image_cache.get_entry(uri) {
+            Ok(Some(entry)) => entry.filename(uri),
+            _ => node_id.to_string(),
+        }
+    }
+
+    /// Renders exported images as markdown images with `data:` URIs, for
+    /// clients that show inline images but cannot read resources.
+    async fn data_uri_markdown(
+        &self,
+        exports: &[(String, String)],
+        cancellation: &CancellationToken,
+    ) -> String {
+        let mut images = Vec::new();
+        for (node_id, uri) in exports {
+            let image = match self.image_data_uri(uri, cancellation).await {
+                Ok(data_uri) => {
+                    format!("![{}]({})", self.export_filename(node_id, uri), data_uri)
+                }
+                Err(e) => format!("Error loading image {}: {}", node_id, e),
+            };
+            images.push(image);
+        }
+
+        images.join("\n\n")
+    }
+
+    /// Exports a frame covering its `bounds`, audits its text contrast and
+    /// draws the failing text nodes onto the export. The annotated image is
+    /// registered as a resource; returns its URI, the PNG and the issues in
+    /// label order.
+    async fn contrast_overlay(
+        &self,
+        file_key: &str,
+        node_id: &str,
+        scale: f64,
+        bounds: BoundsKind,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<(String, Vec<u8>, Vec<ContrastIssue>)> {
+        let client = self
+            .accounts
+            .client()?
+            .with_cancellation(cancellation.clone());
+        let node_ids = vec![node_id.to_string()];
+
+        let nodes = client.get_file_nodes(file_key, &node_ids, None).await?;
+        let frame = nodes
+            .pointer(&format!("/nodes/{}/document", node_id))
+            .ok_or_else(|| Error::NotFound(format!("Node {} not found", node_id)))?;
+        let issues = contrast::audit(frame);
+
+        let export = client
+            .export_images(
+                file_key,
+                &node_ids,
+                "png",
+                ExportOptions {
+                    scale: Some(scale),
+                    use_absolute_bounds: bounds.use_absolute_bounds(),
+                    ..ExportOptions::default()
+                },
+            )
+            .await?;
+        let exports = self.register_exports(file_key, &export, "png", scale, Some(&nodes));
+        let Some((_, uri)) = exports.first() else {
+            return Err(Error::NotFound(format!(
+                "Figma did not render node {}",
+                node_id
+            )));
+        };
+        let entry = self
+            .image_cache
+            .get_entry(uri)?
+            .ok_or_else(|| Error::NotFound(format!("Resource not found: {}", uri)))?;
+        let image_data = self.load_image_data(uri, &entry, cancellation).await?;
+
+        let origin = Bounds::of_kind(frame, bounds).unwrap_or_default();
+        let annotations = contrast_annotations(&issues, origin, scale);
+
+        // Decoding and re-encoding is CPU bound
+        let annotated = tokio::task::spawn_blocking(move || {
+            let image = png::decode(&image_data)?;
+            png::encode(&draw::annotate(&image, &annotations))
+        })
+        .await
+        .map_err(|e| Error::Internal(format!("Annotating image failed: {}", e)))??;
+
+        let uri = self.image_cache.register_generated(
+            file_key.to_string(),
+            node_id.to_string(),
+            "contrast",
+            annotated.clone(),
+        )?;
+        self.claim(&uri);
+
+        Ok((uri, annotated, issues))
+    }
+
+    /// Exports the frames as PNG and encodes them, in the order given, as
+    /// an animation registered under the first frame. Returns its URI and
+    /// data.
+    #[allow(clippy::too_many_arguments)]
+    async fn render_animation(
+        &self,
+        file_key: &str,
+        node_ids: &[String],
+        format: AnimationFormat,
+        delay_ms: u32,
+        scale: f64,
+        bounds: BoundsKind,
+        deadline: Instant,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<(String, Vec<u8>)> {
+        let client = self
+            .accounts
+            .client()?
+            .with_cancellation(cancellation.clone());
+        // Frames may be shown more than once, but are exported once
+        let mut distinct: Vec<String> = Vec::new();
+        for node_id in node_ids {
+            if !distinct.contains(node_id) {
+                distinct.push(node_id.clone());
+            }
+        }
+
+        let options = ExportOptions {
+            scale: Some(scale),
+            use_absolute_bounds: bounds.use_absolute_bounds(),
+            ..ExportOptions::default()
+        };
+        let export = client
+            .export_images(file_key, &distinct, "png", options)
+            .await?;
+        let (nodes, failures) =
+            check_exports(&client, file_key, &export, &distinct, scale, deadline).await;
+        if !failures.is_empty() {
+            return Err(Error::NotFound(export::describe_failures(&failures)));
+        }
+        let exports: BTreeMap<String, String> = self
+            .register_exports(file_key, &export, "png", scale, nodes.as_ref())
+            .into_iter()
+            .collect();
+
+        let mut images = Vec::new();
+        for node_id in &distinct {
+            let uri = exports
+                .get(node_id)
+                .ok_or_else(|| Error::NotFound(format!("Figma did not render node {}", node_id)))?;
+            let entry = self
+                .image_cache
+                .get_entry(uri)?
+                .ok_or_else(|| Error::NotFound(format!("Resource not found: {}", uri)))?;
+            images.push(self.load_image_data(uri, &entry, cancellation).await?);
+        }
+
+        let order: Vec<usize> = node_ids
+            .iter()
+            .filter_map(|node_id| distinct.iter().position(|id| id == node_id))
+            .collect();
+        // Decoding and encoding is CPU bound
+        let data = tokio::task::spawn_blocking(move || {
+            let decoded = images
+                .iter()
+                .map(|image| png::decode(image))
+                .collect::<crate::Result<Vec<_>>>()?;
+            let frames: Vec<_> = order.iter().map(|&index| decoded[index].clone()).collect();
+            animation::encode(&frames, delay_ms, format)
+        })
+        .await
+        .map_err(|e| Error::Internal(format!("Encoding animation failed: {}", e)))??;
+
+        let uri = self.image_cache.register_generated_as(
+            file_key.to_string(),
+            node_ids[0].clone(),
+            "animation",
+            format.extension(),
+            data.clone(),
+        )?;
+        self.claim(&uri);
+
+        Ok((uri, data))
+    }
+
+    /// Exports the frames at about the size of their thumbnails and lays
+    /// them out in a grid labeled with their names, registered under the
+    /// first frame. Frames Figma fails to render are left out and returned
+    /// with the URI, the PNG data and the frames on the sheet.
+    async fn render_contact_sheet(
+        &self,
+        file_key: &str,
+        node_ids: &[String],
+        columns: usize,
+        thumbnail_width: u32,
+        deadline: Instant,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<(String, Vec<u8>, Vec<Value>, Vec<ExportFailure>)> {
+        let client = self
+            .accounts
+            .client()?
+            .with_cancellation(cancellation.clone());
+
+        // Scaled for the narrowest frame to fill its cell; wider ones are
+        // scaled down further locally
+        let nodes = client.get_file_nodes(file_key, node_ids, Some(1)).await?;
+        let narrowest = node_ids
+            .iter()
+            .filter_map(|node_id| nodes.pointer(&format!("/nodes/{}/document", node_id)))
+            .filter_map(|node| Bounds::of_kind(node, BoundsKind::Render))
+            .map(|bounds| bounds.width)
+            .filter(|width| *width > 0.0)
+            .fold(f64::INFINITY, f64::min);
+        let scale = (thumbnail_width as f64 / narrowest).clamp(export::MIN_SCALE, 1.0);
+
+        let options = ExportOptions {
+            scale: Some(scale),
+            ..ExportOptions::default()
+        };
+        let export = client
+            .export_images(file_key, node_ids, "png", options)
+            .await?;
+        let (_, failures) =
+            check_exports(&client, file_key, &export, node_ids, scale, deadline).await;
+        if failures.len() == node_ids.len() {
+            return Err(Error::NotFound(export::describe_failures(&failures)));
+        }
+        let exports = self.register_exports(file_key, &export, "png", scale, Some(&nodes));
+
+        let mut tiles = Vec::new();
+        let mut images = Vec::new();
+        for node_id in node_ids {
+            let Some((_, uri)) = exports.iter().find(|(id, _)| id == node_id) else {
+                continue;
+            };
+            let Some(entry) = self.image_cache.get_entry(uri)? else {
+                continue;
+            };
+            let name = nodes
+                .pointer(&format!("/nodes/{}/document/name", node_id))
+                .and_then(Value::as_str)
+                .unwrap_or(node_id);
+            tiles.push(json!({ "node_id": node_id, "name": name, "uri": uri }));
+            images.push((
+                name.to_string(),
+                self.load_image_data(uri, &entry, cancellation).await?,
+            ));
+        }
+
+        // Decoding, scaling and encoding is CPU bound
+        let watermark = self.config.watermark.clone();
+        let data = tokio::task::spawn_blocking(move || {
+            let tiles = images
+                .iter()
+                .map(|(label, image)| {
+                    let image = png::decode(image)?;
+                    Ok(Tile {
+                        label: label.clone(),
+                        image,
+                    })
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+            let mut sheet = sheet::compose(&tiles, columns, thumbnail_width);
+            // Too small to read on the thumbnails
+            if let Some(text) = &watermark {
+                watermark::watermark(&mut sheet, text);
+            }
+            png::encode(&sheet)
+        })
+        .await
+        .map_err(|e| Error::Internal(format!("Composing contact sheet failed: {}", e)))??;
+
+        let uri = self.image_cache.register_generated(
+            file_key.to_string(),
+            node_ids[0].clone(),
+            "contact-sheet",
+            data.clone(),
+        )?;
+        self.claim(&uri);
+
+        Ok((uri, data, tiles, failures))
+    }
+
+    /// Creates a queued job owned by this session.
+    fn create_job(&self, kind: JobKind, total: Option<usize>) -> crate::Result<Job> {
+        let job = self.jobs.create(kind, total)?;
+        self.claim(&job.resource_uri());
+
+        Ok(job)
+    }
+
+    /// Runs `work` as a background job and returns the queued job. The
+    /// outcome is recorded when `work` finishes or the job is cancelled,
+    /// and the client is notified.
+    fn spawn_job<F, Fut>(
+        &self,
+        kind: JobKind,
+        total: Option<usize>,
+        peer: Peer<RoleServer>,
+        work: F,
+    ) -> crate::Result<Job>
+    where
+        F: FnOnce(Job) -> Fut + Send + 'static,
+        Fut: Future<Output = crate::Result<Value>> + Send + 'static,
+    {
+        let job = self.create_job(kind, total)?;
+
+        let jobs = self.jobs.clone();
+        let started = job.clone();
+        tokio::spawn(async move {
+            let job_id = job.job_id.clone();
+            let cancellation = job.cancellation.clone();
+            let _ = jobs.update(&job_id, |job| {
+                if job.status == JobStatus::Queued {
+                    job.status = JobStatus::Running;
+                }
+            });
+
+            let outcome = tokio::select! {
+                outcome = work(job) => outcome,
+                _ = cancellation.cancelled() => Err(Error::Cancelled),
+            };
+            if let Ok(Some(job)) = jobs.finish(&job_id, outcome) {
+                notify_job_finished(&peer, &job).await;
+            }
+        });
+
+        Ok(started)
+    }
+
+    /// Exports the nodes of an export job batch by batch, recording
+    /// progress and the partial result as batches complete.
+    async fn run_export_job(
+        &self,
+        job: &Job,
+        file_key: &str,
+        node_ids: &[String],
+        format: &str,
+        options: ExportOptions,
+    ) -> crate::Result<Value> {
+        let client = self
+            .accounts
+            .client()?
+            .with_cancellation(job.cancellation.clone());
+        let timeout = self.config.tool_timeouts.for_tool("export_images");
+        let scale = options.scale.unwrap_or(1.0);
+
+        let mut images = BTreeMap::new();
+        let mut failed = Vec::new();
+        for batch in node_ids.chunks(jobs::EXPORT_BATCH_SIZE) {
+            let deadline = Instant::now() + timeout;
+            let request = client.export_images(file_key, batch, format, options);
+            match with_timeout(timeout, request).await {
+                Ok(result) => {
+                    let (nodes, failures) =
+                        check_exports(&client, file_key, &result, batch, scale, deadline).await;
+                    images.extend(self.register_exports(
+                        file_key,
+                        &result,
+                        format,
+                        scale,
+                        nodes.as_ref(),
+                    ));
+                    failed.extend(failures);
+                }
+                Err(e) => failed.extend(batch.iter().map(|node_id| ExportFailure {
+                    node_id: node_id.clone(),
+                    reason: e.to_string(),
+                })),
+            }
+
+            let partial = json!({ "images": images, "failed": failed });
+            let _ = self.jobs.update(&job.job_id, |job| {
+                if let Some(progress) = job.progress.as_mut() {
+                    progress.processed += batch.len();
+                }
+                job.result = Some(partial);
+            });
+        }
+
+        if images.is_empty() && !failed.is_empty() {
+            return Err(Error::NotFound(export::describe_failures(&failed)));
+        }
+
+        let exports: Vec<(String, String)> = images.clone().into_iter().collect();
+        let resources = self.export_resources(&exports);
+
+        Ok(json!({ "images": images, "resources": resources, "failed": failed }))
+    }
+
+    /// Fetches, sorts and renders library analytics for
+    /// `get_library_actions` and `get_library_usages`.
+    async fn library_analytics(
+        &self,
+        tool: &str,
+        metric: Metric,
+        query: AnalyticsQuery,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let file_key = query.file_key.trim();
+        if file_key.is_empty() {
+            return missing_parameter("file_key", FILE_KEY_QUESTION);
+        }
+
+        let Some(asset) = Asset::parse(&query.asset) else {
+            return tool_error(format!(
+                "Error: unsupported asset '{}', expected {}",
+                query.asset, ASSET_NAMES
+            ));
+        };
+
+        let Some(group_by) = metric.group_by(asset, query.group_by.as_deref()) else {
+            return tool_error(format!(
+                "Error: unsupported group_by '{}', expected {} or {}",
+                query.group_by.unwrap_or_default(),
+                asset.name(),
+                metric.other_grouping()
+            ));
+        };
+
+        let format_name = query.format.as_deref().unwrap_or("json");
+        let Some(format) = AnalyticsFormat::parse(format_name) else {
+            return tool_error(format!(
+                "Error: unsupported format '{}', expected {}",
+                format_name, ANALYTICS_FORMAT_NAMES
+            ));
+        };
+
+        let mut params = vec![("group_by", group_by.to_string())];
+        for (name, date) in [
+            ("start_date", query.start_date),
+            ("end_date", query.end_date),
+        ] {
+            let Some(date) = date.map(|date| date.trim().to_string()) else {
+                continue;
+            };
+            if date.is_empty() {
+                continue;
+            }
+            if !analytics::is_date(&date) {
+                return tool_error(format!(
+                    "Error: invalid {} '{}', expected a date like 2024-05-06",
+                    name, date
+                ));
+            }
+            params.push((name, date));
+        }
+
+        let client = self.client()?.with_cancellation(ct);
+        let timeout = self.config.tool_timeouts.for_tool(tool);
+        let request = analytics_pages(&client, file_key, asset, metric, &params);
+        let (mut rows, complete) = match with_timeout(timeout, request).await {
+            Ok(result) => result,
+            Err(Error::Api(e)) if e.status == 403 => {
+                let error_msg = format!(
+                    "Error getting library analytics: {}. Figma only serves library \
+                     analytics to members of Enterprise organizations, for library files, \
+                     with a token that has the library_analytics:read scope.",
+                    e
+                );
+                return tool_error(error_msg);
+            }
+            Err(e) => {
+                let error_msg = format!("Error getting library analytics: {}", e);
+                return tool_error(error_msg);
+            }
+        };
+
+        if let Some(column) = query.sort_by.as_deref().map(str::trim) {
+            if !column.is_empty() && !analytics::sort_rows(&mut rows, column) && !rows.is_empty() {
+                return tool_error(format!(
+                    "Error: no row has a numeric '{}' column to sort by",
+                    column
+                ));
+            }
+        }
+
+        let total = rows.len();
+        rows.truncate(query.limit.unwrap_or(analytics::DEFAULT_ROW_LIMIT).max(1));
+
+        let mut notes = Vec::new();
+        if rows.len() < total {
+            notes.push(format!(
+                "Showing {} of {} rows; raise limit for more.",
+                rows.len(),
+                total
+            ));
+        }
+        if !complete {
+            notes.push(format!(
+                "Stopped after {} pages of analytics; narrow the date range for complete results.",
+                analytics::MAX_PAGES
+            ));
+        }
+
+        let mut content: Vec<Content> = notes.into_iter().map(Content::text).collect();
+        content.push(Content::text(match format {
+            AnalyticsFormat::Csv => analytics::to_csv(&rows),
+            AnalyticsFormat::Json => output::to_json(
+                &json!({
+                    "asset": asset.name(),
+                    "group_by": group_by,
+                    "rows": rows,
+                    "total_rows": total,
+                }),
+                query.pretty.unwrap_or(false),
+            ),
+        }));
+
+        Ok(CallToolResult::success(content))
+    }
+
+    /// The contents of a tool result: the JSON inline, or a link to it as
+    /// a `figma://result/` resource with `summary` if it is larger than
+    /// `FIGMA_MCP_INLINE_LIMIT` and the call did not ask for it inline.
+    fn large_result(
+        &self,
+        tool: &str,
+        description: String,
+        result: String,
+        summary: impl FnOnce() -> Value,
+        inline: Option<bool>,
+    ) -> Vec<Content> {
+        let limit = self.config.inline_limit;
+        if inline.unwrap_or(false) || limit == 0 || result.len() <= limit {
+            return vec![Content::text(result)];
+        }
+
+        match self
+            .results
+            .store(tool, description, result.clone(), summary())
+        {
+            Ok(link) => {
+                self.claim(&link.uri);
+                let note = format!(
+                    "The result is {} bytes, so it is returned as a resource link. Read {} \
+                     for the full result, or call {} again with inline: true.",
+                    link.size, link.uri, tool
+                );
+                vec![
+                    Content::text(note),
+                    Content::text(output::to_json(&link, false)),
+                ]
+            }
+            Err(_) => vec![Content::text(result)],
+        }
+    }
+
+    /// The archived and scratch names to leave out of a response: the
+    /// configured patterns by default, the built-in heuristics if a call
+    /// asks for exclusion without any configured.
+    fn archive_filter(&self, exclude_archived: Option<bool>) -> Option<NameFilter> {
+        match exclude_archived {
+            Some(false) => None,
+            Some(true) => Some(
+                self.config
+                    .exclude_names
+                    .clone()
+                    .unwrap_or_else(NameFilter::archive),
+            ),
+            None => self.config.exclude_names.clone(),
+        }
+    }
+
+    /// Indexes files one by one, recording progress on `job` if given.
+    async fn index(
+        &self,
+        client: &FigmaClient,
+        file_keys: &[String],
+        job: Option<&Job>,
+    ) -> crate::Result<Vec<FileSummary>> {
+        let mut indexed = Vec::new();
+        for file_key in file_keys {
+            let mut file = client.get_file(file_key, None).await?;
+            if self.config.exclude_hidden {
+                layers::strip_hidden(&mut file);
+            }
+            if let Some(exclude) = &self.config.exclude_names {
+                pages::strip_excluded(&mut file, exclude);
+            }
+
+            // Variables are only available on Enterprise plans
+            let variables = client.get_local_variables(file_key).await.ok();
+
+            let file_index = FileIndex::from_file(file_key, &file, variables.as_ref());
+            self.record_version(file_key, file_index.meta.clone());
+            indexed.push(self.design_index.insert(file_index)?);
+
+            if let Some(job) = job {
+                let _ = self.jobs.update(&job.job_id, |job| {
+                    if let Some(progress) = job.progress.as_mut() {
+                        progress.processed += 1;
+                    }
+                });
+            }
+        }
+
+        Ok(indexed)
+    }
+
+    /// Applies `filter` to exported PNG images, returning each as image
+    /// content preceded by its node ID.
+    async fn filtered_images(
+        &self,
+        exports: &[(String, String)],
+        filter: Filter,
+        cancellation: &CancellationToken,
+    ) -> Vec<Content> {
+        let mut content = Vec::new();
+        for (node_id, uri) in exports {
+            match self.filtered_image(uri, filter, cancellation).await {
+                Ok(image) => {
+                    let base64_data = general_purpose::STANDARD.encode(&image);
+                    let filename = self.export_filename(node_id, uri);
+                    content.push(Content::text(format!("{} ({})", filename, filter.name())));
+                    content.push(Content::image(base64_data, "image/png"));
+                }
+                Err(e) => {
+                    content.push(Content::text(format!(
+                        "Error filtering image {}: {}",
+                        node_id, e
+                    )));
+                }
+            }
+        }
+
+        content
+    }
+
+    async fn filtered_image(
+        &self,
+        uri: &str,
+        filter: Filter,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<Vec<u8>> {
+        let entry = self
+            .image_cache
+            .get_entry(uri)?
+            .ok_or_else(|| Error::NotFound(format!("Resource not found: {}", uri)))?;
+        let image_data = self.load_image_data(uri, &entry, cancellation).await?;
+
+        // Decoding and re-encoding is CPU bound
+        let filtered =
+            tokio::task::spawn_blocking(move || filters::apply_to_png(&image_data, filter))
+                .await
+                .map_err(|e| Error::Internal(format!("Image filter failed: {}", e)))??;
+
+        // Blurring leaves the watermark unreadable, so stamp it again
+        match &self.config.watermark {
+            Some(text) if filter == Filter::Blur => stamp_watermark(filtered, "png", text).await,
+            _ => Ok(filtered),
+        }
+    }
+
+    /// An error message when exports in `format` cannot carry the
+    /// configured watermark.
+    fn unwatermarkable(&self, format: &str) -> Option<String> {
+        if self.config.watermark.is_none() || watermark::supports(format) {
+            return None;
+        }
+
+        Some(format!(
+            "Error: exports are watermarked (FIGMA_MCP_WATERMARK), which works for {} only",
+            WATERMARK_FORMATS
+        ))
+    }
+
+    async fn image_data_uri(
+        &self,
+        uri: &str,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<String> {
+        let entry = self
+            .image_cache
+            .get_entry(uri)?
+            .ok_or_else(|| Error::NotFound(format!("Resource not found: {}", uri)))?;
+        let image_data = self.load_image_data(uri, &entry, cancellation).await?;
+
+        let base64_data = self
+            .image_cache
+            .encode(uri, entry.export_time, &image_data)?;
+        let mime_type = ImageCache::get_mime_type(&entry.format);
+
+        Ok(format!("data:{};base64,{}", mime_type, base64_data))
+    }
+
+    /// Runs the targets of the workspace manifest, as `figma-mcp sync` and
+    /// the `sync_workspace` tool do.
+    pub async fn run_sync(
+        &self,
+        path: Option<&str>,
+        cancellation: CancellationToken,
+    ) -> crate::Result<SyncReport> {
+        let workspace = self.workspace(path)?;
+        let client = self.accounts.client()?.with_cancellation(cancellation);
+
+        sync::sync_workspace(
+            &client,
+            &workspace,
+            self.config.deterministic,
+            &self.config.rules,
+            self.config.exclude_names.as_ref(),
+        )
+        .await
+    }
+
+    /// Loads the workspace manifest from `path`, the configured location, or
+    /// the nearest `figma-mcp.toml` above the working directory.
+    fn workspace(&self, path: Option<&str>) -> crate::Result<Workspace> {
+        if let Some(path) = path
+            .map(std::path::Path::new)
+            .or(self.config.workspace.as_deref())
+        {
+            return Workspace::load(path);
+        }
+
+        let current_dir = std::env::current_dir()
+            .map_err(|e| Error::Internal(format!("Failed to get working directory: {}", e)))?;
+
+        Workspace::find(&current_dir)
+    }
+
+    /// Resolves a path from tool input within the workspace root: the
+    /// directory of the configured manifest, or else the working directory.
+    fn tool_path(&self, path: &str) -> std::result::Result<std::path::PathBuf, String> {
+        let configured = self
+            .config
+            .workspace
+            .as_deref()
+            .and_then(std::path::Path::parent)
+            .filter(|root| !root.as_os_str().is_empty());
+        let root = match configured {
+            Some(root) => root.to_path_buf(),
+            None => std::env::current_dir()
+                .map_err(|e| format!("cannot be resolved: no working directory ({})", e))?,
+        };
+
+        workspace::resolve_within(&root, std::path::Path::new(path))
+    }
+
+    /// Sends a request to the companion plugin, if the bridge is enabled.
+    async fn plugin_request(
+        &self,
+        method: &str,
+        params: Value,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<Value> {
+        let Some(bridge) = &self.bridge else {
+            return Err(Error::Bridge(
+                "The plugin bridge is not enabled. Set FIGMA_MCP_BRIDGE_ADDR to enable it."
+                    .to_string(),
+            ));
+        };
+
+        tokio::select! {
+            biased;
+            _ = cancellation.cancelled() => Err(Error::Cancelled),
+            result = bridge.request(method, params) => result,
+        }
+    }
+
+    /// Returns the image bytes for a cached export, downloading them from
+    /// Figma on first access.
+    async fn load_image_data(
+        &self,
+        uri: &str,
+        entry: &ImageEntry,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<Vec<u8>> {
+        if let Some(cached_data) = &entry.cached_data {
+            return Ok(cached_data.clone());
+        }
+
+        let reexported;
+        let entry = if entry.stale || self.image_cache.is_expired(entry) {
+            if !self.config.refresh_stale {
+                let reason = if entry.stale {
+                    "The file changed since this image was exported."
+                } else {
+                    "Figma URL has expired."
+                };
+                return Err(Error::Internal(format!(
+                    "{} Please re-export the image.",
+                    reason
+                )));
+            }
+            reexported = self.reexport(uri, entry, cancellation).await?;
+            &reexported
+        } else {
+            entry
+        };
+
+        let mut data = self
+            .accounts
+            .client()?
+            .with_cancellation(cancellation.clone())
+            .download_image(&entry.figma_url, &entry.format)
+            .await?;
+        if let Some(text) = &self.config.watermark {
+            data = stamp_watermark(data, &entry.format, text).await?;
+        }
+
+        // Cache the downloaded data, unless the entry was re-exported meanwhile
+        let _ = self
+            .image_cache
+            .update_cached_data(uri, &entry.figma_url, data.clone());
+
+        Ok(data)
+    }
+
+    /// Exports the node of a stale or expired entry again and returns the
+    /// updated entry. Only the scale of the original export is kept; other
+    /// export options are Figma's defaults.
+    async fn reexport(
+        &self,
+        uri: &str,
+        entry: &ImageEntry,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<ImageEntry> {
+        let client = self
+            .accounts
+            .client()?
+            .with_cancellation(cancellation.clone());
+        let options = ExportOptions {
+            scale: Some(entry.scale),
+            ..ExportOptions::default()
+        };
+        let node_ids = [entry.node_id.clone()];
+        let result = client
+            .export_images(&entry.file_key, &node_ids, &entry.format, options)
+            .await?;
+        let figma_url = result
+            .pointer("/images")
+            .and_then(|images| images.get(&entry.node_id))
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                Error::NotFound(format!("Figma did not render node {}", entry.node_id))
+            })?;
+
+        self.image_cache.register_export(
+            entry.file_key.clone(),
+            entry.node_id.clone(),
+            entry.format.clone(),
+            entry.scale,
+            figma_url.to_string(),
+        )?;
+        self.image_cache
+            .get_entry(uri)?
+            .ok_or_else(|| Error::NotFound(format!("Resource not found: {}", uri)))
+    }
+
+    /// Asks the client's model to describe an exported image via sampling.
+    async fn describe_image(
+        &self,
+        peer: &Peer<RoleServer>,
+        uri: &str,
+        context: Option<&str>,
+        cancellation: &CancellationToken,
+    ) -> crate::Result<String> {
+        let entry = self
+            .image_cache
+            .get_entry(uri)?
+            .ok_or_else(|| Error::NotFound(format!("Resource not found: {}", uri)))?;
+        let image_data = self.load_image_data(uri, &entry, cancellation).await?;
+
+        let base64_data = self
+            .image_cache
+            .encode(uri, entry.export_time, &image_data)?;
+        let mime_type = ImageCache::get_mime_type(&entry.format);
+
+        let mut messages = vec![SamplingMessage {
+            role: Role::User,
+            content: Content::image(base64_data, mime_type),
+        }];
+        if let Some(context) = context {
+            let context = format!("Context for this design: {}", context);
+            messages.push(SamplingMessage {
+                role: Role::User,
+                content: Content::text(context),
+            });
+        }
+
+        let request = CreateMessageRequestParam {
+            messages,
+            model_preferences: None,
+            system_prompt: Some(ALT_TEXT_SYSTEM_PROMPT.to_string()),
+            include_context: None,
+            temperature: None,
+            max_tokens: 200,
+            stop_sequences: None,
+            metadata: None,
+        };
+
+        let response = tokio::select! {
+            biased;
+            _ = cancellation.cancelled() => return Err(Error::Cancelled),
+            response = peer.create_message(request) => response,
+        };
+        let response =
+            response.map_err(|e| Error::Internal(format!("Sampling request failed: {}", e)))?;
+
+        let Some(text) = response.message.content.as_text() else {
+            return Err(Error::Internal(
+                "Client model returned non-text content".to_string(),
+            ));
+        };
+
+        Ok(text.text.trim().to_string())
+    }
+}
+
+impl ServerHandler for FigmaServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::LATEST,
+            server_info: Implementation::from_build_env(),
+            capabilities: ServerCapabilities::builder()
+                .enable_logging()
+                .enable_tools()
+                .enable_resources()
+                .build(),
+            instructions: Some(
+                self.config
+                    .descriptions
+                    .instructions
+                    .clone()
+                    .unwrap_or_else(|| {
+                        help::instructions(&self.tool_router.list_all(), self.webhooks.is_some())
+                    }),
+            ),
+        }
+    }
+
+    async fn initialize(
+        &self,
+        request: InitializeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<InitializeResult, McpError> {
+        // Clients asking for a revision the server doesn't speak get the
+        // closest older one
+        let protocol_version = protocol::negotiate(&request.protocol_version);
+        tracing::info!(
+            client = %request.client_info.name,
+            requested = %request.protocol_version,
+            negotiated = %protocol_version,
+            "Client initialized"
+        );
+
+        if context.peer.peer_info().is_none() {
+            context.peer.set_peer_info(request);
+        }
+
+        Ok(InitializeResult {
+            protocol_version,
+            ..self.get_info()
+        })
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        if let Some(webhooks) = &self.webhooks {
+            tokio::spawn(forward_events(
+                webhooks.subscribe(),
+                context.peer,
+                Arc::clone(&self.log_level),
+            ));
+        }
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.log_level.store(request.level as u8, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn call_tool(
+        &self,
+        mut request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let request_id = format!("req-{}", NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed));
+        let alias = aliases::resolve(TOOL_ALIASES, &request.name);
+        if let Some(alias) = alias {
+            tracing::warn!(
+                alias = alias.name,
+                tool = alias.tool,
+                "Tool called by its old name"
+            );
+            request.name = alias.tool.into();
+        }
+        let span = tracing::info_span!(
+            "tool_call",
+            request_id = %request_id,
+            tool = %request.name,
+            jsonrpc_id = ?context.id,
+            duration_ms = field::Empty,
+            is_error = field::Empty,
+        );
+
+        let start = Instant::now();
+        let tcc = ToolCallContext::new(self, request, context);
+        let result = self.tool_router.call(tcc).instrument(span.clone()).await;
+
+        let is_error = match &result {
+            Ok(result) => result.is_error == Some(true),
+            Err(_) => true,
+        };
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        span.record("is_error", is_error);
+        span.in_scope(|| tracing::info!("Tool call completed"));
+
+        // Include the request ID in errors so users can find the matching logs
+        let mut result = match result {
+            Ok(mut result) if is_error => {
+                for content in &mut result.content {
+                    if let RawContent::Text(text) = &mut content.raw {
+                        text.text
+                            .push_str(&format!(" (request ID: {})", request_id));
+                    }
+                }
+                Ok(result)
+            }
+            Err(mut error) => {
+                error.message = format!("{} (request ID: {})", error.message, request_id).into();
+                Err(error)
+            }
+            result => result,
+        };
+
+        if let (Some(alias), Ok(result)) = (alias, &mut result) {
+            result.content.insert(0, Content::text(alias.notice()));
+        }
+
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let features = Features::of(&session_protocol_version(&context));
+        let tools = self.config.descriptions.apply(self.tool_router.list_all());
+        let tools = features.adapt_tools(tools);
+
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let entries = self.image_cache.list_all().map_err(|e| {
+            McpError::internal_error(format!("Failed to list resources: {}", e), None)
+        })?;
+
+        let jobs = self.jobs.list().map_err(|e| {
+            McpError::internal_error(format!("Failed to list resources: {}", e), None)
+        })?;
+
+        let mut resources: Vec<Resource> = entries
+            .iter()
+            .filter(|(uri, _)| self.can_see(uri))
+            .map(|(uri, entry)| {
+                let node = match &entry.name {
+                    Some(name) => format!("\"{}\" (node {})", name, entry.node_id),
+                    None => format!("Node {}", entry.node_id),
+                };
+                let description = format!(
+                    "{} exported from Figma file {} as {} ({}x scale)",
+                    node, entry.file_key, entry.format, entry.scale
+                );
+                let mime_type = crate::figma::ImageCache::get_mime_type(&entry.format);
+
+                Resource::new(
+                    RawResource {
+                        uri: uri.clone(),
+                        name: entry.filename(uri),
+                        description: Some(description),
+                        mime_type: Some(mime_type.to_string()),
+                        size: entry.cached_data.as_ref().map(|data| data.len() as u32),
+                    },
+                    None,
+                )
+            })
+            .collect();
+
+        let results = self.results.list().map_err(|e| {
+            McpError::internal_error(format!("Failed to list resources: {}", e), None)
+        })?;
+        resources.extend(
+            results
+                .iter()
+                .filter(|result| self.can_see(&result.resource_uri()))
+                .map(|result| {
+                    Resource::new(
+                        RawResource {
+                            uri: result.resource_uri(),
+                            name: result.filename(),
+                            description: Some(result.description.clone()),
+                            mime_type: Some("application/json".to_string()),
+                            size: Some(result.text.len() as u32),
+                        },
+                        None,
+                    )
+                }),
+        );
+
+        // Finished jobs with a result, such as a sync report
+        resources.extend(
+            jobs.iter()
+                .filter(|job| job.status.is_finished() && job.result.is_some())
+                .filter(|job| self.can_see(&job.resource_uri()))
+                .map(|job| {
+                    Resource::new(
+                        RawResource {
+                            uri: job.resource_uri(),
+                            name: format!("{}.json", job.job_id),
+                            description: Some("Result of a background job".to_string()),
+                            mime_type: Some("application/json".to_string()),
+                            size: None,
+                        },
+                        None,
+                    )
+                }),
+        );
+
+        Ok(ListResourcesResult {
+            resources,
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let uri = request.uri;
+        let (image_uri, chunk) = ImageCache::split_chunk(&uri);
+        // Other sessions' resources are reported missing, not forbidden, so
+        // sessions can't probe for each other's files
+        if !self.can_see(image_uri) {
+            return Err(McpError::resource_not_found(
+                format!("Resource not found: {}", uri),
+                None,
+            ));
+        }
+
+        if let Some(result_id) = results::result_id_from_uri(&uri) {
+            let result = self
+                .results
+                .get(result_id)
+                .map_err(|e| {
+                    McpError::internal_error(format!("Failed to get resource: {}", e), None)
+                })?
+                .ok_or_else(|| {
+                    McpError::resource_not_found(format!("Resource not found: {}", uri), None)
+                })?;
+
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::TextResourceContents {
+                    uri: uri.clone(),
+                    mime_type: Some("application/json".to_string()),
+                    text: result.text,
+                }],
+            });
+        }
+
+        if let Some(job_id) = jobs::job_id_from_uri(&uri) {
+            let job = self
+                .jobs
+                .get(job_id)
+                .map_err(|e| {
+                    McpError::internal_error(format!("Failed to get resource: {}", e), None)
+                })?
+                .ok_or_else(|| {
+                    McpError::resource_not_found(format!("Resource not found: {}", uri), None)
+                })?;
+
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::TextResourceContents {
+                    uri: uri.clone(),
+                    mime_type: Some("application/json".to_string()),
+                    text: output::to_json(&job, true),
+                }],
+            });
+        }
+
+        let entry = self
+            .image_cache
+            .get_entry(image_uri)
+            .map_err(|e| McpError::internal_error(format!("Failed to get resource: {}", e), None))?
+            .ok_or_else(|| {
+                McpError::resource_not_found(format!("Resource not found: {}", uri), None)
+            })?;
+
+        let image_data = self
+            .load_image_data(image_uri, &entry, &context.ct)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mime_type = ImageCache::get_mime_type(&entry.format);
+
+        // Clients with message size limits read large images chunk by chunk
+        let chunk_size = self.config.resource_chunk_size;
+        let image_data = match chunk {
+            Some(index) => ImageCache::chunk(&image_data, index, chunk_size).ok_or_else(|| {
+                McpError::resource_not_found(format!("Chunk {} out of range: {}", index, uri), None)
+            })?,
+            None if chunk_size > 0 && image_data.len() > chunk_size => {
+                let manifest =
+                    ImageCache::chunk_manifest(image_uri, mime_type, image_data.len(), chunk_size);
+                return Ok(ReadResourceResult {
+                    contents: vec![ResourceContents::TextResourceContents {
+                        uri: uri.clone(),
+                        mime_type: Some("application/json".to_string()),
+                        text: output::to_json(&manifest, false),
+                    }],
+                });
+            }
+            None => &image_data,
+        };
+
+        // Keyed by the full URI, so each chunk is encoded once too
+        let base64_data = self
+            .image_cache
+            .encode(&uri, entry.export_time, image_data)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::BlobResourceContents {
+                uri: uri.clone(),
+                mime_type: Some(mime_type.to_string()),
+                blob: base64_data,
+            }],
+        })
+    }
+}
+
+// Parameter structs for MCP tools
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct ParseUrlRequest {
+    #[schemars(description = "The Figma URL to parse (file or design URL)")]
+    pub url: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetFileRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(
+        description = "Depth to traverse into the document tree (default: 1). Use 1 for pages only, 2 for pages + top-level objects, etc."
+    )]
+    pub depth: Option<u32>,
+    #[schemars(
+        description = "Fetch even when the response is projected to exceed the node limit (default: false)"
+    )]
+    pub force: Option<bool>,
+    #[schemars(
+        description = "Leave out hidden layers and their descendants (default: FIGMA_MCP_EXCLUDE_HIDDEN, or false)"
+    )]
+    pub exclude_hidden: Option<bool>,
+    #[schemars(
+        description = "Leave out archived and scratch pages and top-level frames (names like 🗑, archive, old) (default: true if FIGMA_MCP_EXCLUDE_NAMES is set)"
+    )]
+    pub exclude_archived: Option<bool>,
+    #[schemars(
+        description = "Comma-separated page names or globs (e.g. Checkout*) to return; other pages are not fetched (default: all pages)"
+    )]
+    pub pages: Option<String>,
+    #[schemars(
+        description = "ID of a version from get_file_versions to read instead of the current state"
+    )]
+    pub version: Option<String>,
+    #[schemars(
+        description = "List the file's branches (key, name, last modified) in a branches field; read one with get_branch (default: false)"
+    )]
+    pub branch_data: Option<bool>,
+    #[schemars(
+        description = "Return the result inline even if it is larger than FIGMA_MCP_INLINE_LIMIT, instead of as a resource link (default: false)"
+    )]
+    pub inline: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetBranchRequest {
+    #[schemars(
+        description = "The branch key, from a branch URL (extract with parse_figma_url) or get_file with branch_data"
+    )]
+    pub branch_key: String,
+    #[schemars(description = "Key of the main file, to check the branch belongs to it (optional)")]
+    pub file_key: Option<String>,
+    #[schemars(
+        description = "Depth to traverse into the document tree (default: 1). Use 1 for pages only, 2 for pages + top-level objects, etc."
+    )]
+    pub depth: Option<u32>,
+    #[schemars(
+        description = "Fetch even when the response is projected to exceed the node limit (default: false)"
+    )]
+    pub force: Option<bool>,
+    #[schemars(
+        description = "Leave out archived and scratch pages and top-level frames (names like 🗑, archive, old) (default: true if FIGMA_MCP_EXCLUDE_NAMES is set)"
+    )]
+    pub exclude_archived: Option<bool>,
+    #[schemars(
+        description = "Return the result inline even if it is larger than FIGMA_MCP_INLINE_LIMIT, instead of as a resource link (default: false)"
+    )]
+    pub inline: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct ExportImageRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated node IDs to export")]
+    pub node_ids: String,
+    #[schemars(description = "Export format: png, jpg, svg, OR pdf")]
+    pub format: Option<String>,
+    #[schemars(
+        description = "Several export formats at once instead of format, e.g. [\"svg\", \"png\"]; images are then grouped by format"
+    )]
+    pub formats: Option<Vec<String>>,
+    #[schemars(
+        description = "Export scale factor between 0.01 and 4 (1.0, 2.0, 4.0); raster formats only"
+    )]
+    pub scale: Option<f64>,
+    #[schemars(
+        description = "Return the images inline as markdown with data: URIs instead of resources (default: false)"
+    )]
+    pub data_uri: Option<bool>,
+    #[schemars(
+        description = "Return the images post-processed for accessibility reviews: protanopia, deuteranopia or tritanopia (color blindness simulation), grayscale, or blur (squint test); png only"
+    )]
+    pub filter: Option<String>,
+    #[schemars(
+        description = "Box the image covers: render (default, includes shadows, blurs and outside strokes) or layout (absoluteBoundingBox)"
+    )]
+    pub bounds: Option<String>,
+    #[schemars(
+        description = "Figma's use_absolute_bounds: export the full layout box, keeping empty space such as an icon's padding (same as bounds: layout)"
+    )]
+    pub use_absolute_bounds: Option<bool>,
+    #[schemars(
+        description = "Leave out overlapping content such as the frame background behind the node (default: true)"
+    )]
+    pub contents_only: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GenerateAltTextRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated node IDs to describe")]
+    pub node_ids: String,
+    #[schemars(
+        description = "Optional context passed to the model, e.g. the screen or flow the nodes belong to"
+    )]
+    pub context: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct LoadWorkspaceRequest {
+    #[schemars(
+        description = "Path of the manifest, relative to the workspace root (default: FIGMA_MCP_WORKSPACE or the nearest figma-mcp.toml above the working directory)"
+    )]
+    pub path: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct EnqueueExportRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated node IDs to export")]
+    pub node_ids: String,
+    #[schemars(description = "Export format: png, jpg, svg, OR pdf")]
+    pub format: Option<String>,
+    #[schemars(
+        description = "Export scale factor between 0.01 and 4 (1.0, 2.0, 4.0); raster formats only"
+    )]
+    pub scale: Option<f64>,
+    #[schemars(
+        description = "Figma's use_absolute_bounds: export the full layout box instead of cropping to what is drawn (default: false)"
+    )]
+    pub use_absolute_bounds: Option<bool>,
+    #[schemars(
+        description = "Leave out overlapping content such as the frame background behind the node (default: true)"
+    )]
+    pub contents_only: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct ListJobsRequest {
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct JobRequest {
+    #[schemars(description = "Job ID returned when the job was started")]
+    pub job_id: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct MeasureNodesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated node IDs to measure")]
+    pub node_ids: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetFileVersionsRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Most versions to return (default: all)")]
+    pub limit: Option<usize>,
+    #[schemars(
+        description = "Only return versions older than this version ID, e.g. next_before from a previous call"
+    )]
+    pub before: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct PostCommentRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "The comment text")]
+    pub message: String,
+    #[schemars(description = "ID of the node to pin the comment to")]
+    pub node_id: Option<String>,
+    #[schemars(
+        description = "Horizontal offset from the node's top-left corner, or the canvas x position without node_id"
+    )]
+    pub x: Option<f64>,
+    #[schemars(
+        description = "Vertical offset from the node's top-left corner, or the canvas y position without node_id"
+    )]
+    pub y: Option<f64>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct ReplyToCommentRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "ID of the top-level comment of the thread")]
+    pub comment_id: String,
+    #[schemars(description = "The reply text")]
+    pub message: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct DeleteCommentRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "ID of the comment to delete")]
+    pub comment_id: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct SchemaReportRequest {
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetDevResourcesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(
+        description = "Comma-separated node IDs to list the dev resources of (default: the whole file)"
+    )]
+    pub node_ids: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct CreateDevResourceRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "ID of the node to attach the link to (e.g. 1:2)")]
+    pub node_id: String,
+    #[schemars(description = "Name shown for the link, e.g. 'PR #42' or 'Button story'")]
+    pub name: String,
+    #[schemars(description = "The URL to link to")]
+    pub url: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct UpdateDevResourceRequest {
+    #[schemars(description = "ID of the dev resource to update (from get_dev_resources)")]
+    pub dev_resource_id: String,
+    #[schemars(description = "New name (default: unchanged)")]
+    pub name: Option<String>,
+    #[schemars(description = "New URL (default: unchanged)")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct DeleteDevResourceRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "ID of the dev resource to delete (from get_dev_resources)")]
+    pub dev_resource_id: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetFileComponentsRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetFileComponentSetsRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetLocalVariablesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(
+        description = "Comma-separated collection names or globs (e.g. Theme, Color*) to return (default: all collections)"
+    )]
+    pub collections: Option<String>,
+    #[schemars(
+        description = "Return the result inline even if it is larger than FIGMA_MCP_INLINE_LIMIT, instead of as a resource link (default: false)"
+    )]
+    pub inline: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetPublishedVariablesRequest {
+    #[schemars(
+        description = "The key of the library file (extract from URL using parse_figma_url)"
+    )]
+    pub file_key: String,
+    #[schemars(
+        description = "Comma-separated collection names or globs (e.g. Theme, Color*) to return (default: all collections)"
+    )]
+    pub collections: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetLibraryActionsRequest {
+    #[schemars(
+        description = "The key of the library file (extract from URL using parse_figma_url)"
+    )]
+    pub file_key: String,
+    #[schemars(description = "Assets to report on: component, style or variable")]
+    pub asset: String,
+    #[schemars(description = "Rows per asset (default) or per team: asset or team")]
+    pub group_by: Option<String>,
+    #[schemars(
+        description = "First day to include, as YYYY-MM-DD (default: one year ago). Figma reports whole weeks"
+    )]
+    pub start_date: Option<String>,
+    #[schemars(description = "Last day to include, as YYYY-MM-DD (default: the latest data)")]
+    pub end_date: Option<String>,
+    #[schemars(
+        description = "Numeric column to sort by, largest first, e.g. detachments or insertions (default: Figma's order)"
+    )]
+    pub sort_by: Option<String>,
+    #[schemars(description = "Maximum number of rows to return, after sorting (default: 100)")]
+    pub limit: Option<usize>,
+    #[schemars(description = "Output format: json (default) or csv")]
+    pub format: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetLibraryUsagesRequest {
+    #[schemars(
+        description = "The key of the library file (extract from URL using parse_figma_url)"
+    )]
+    pub file_key: String,
+    #[schemars(description = "Assets to report on: component, style or variable")]
+    pub asset: String,
+    #[schemars(description = "Rows per asset (default) or per file: asset or file")]
+    pub group_by: Option<String>,
+    #[schemars(
+        description = "Numeric column to sort by, largest first, e.g. usages, teams_using or files_using (default: Figma's order)"
+    )]
+    pub sort_by: Option<String>,
+    #[schemars(description = "Maximum number of rows to return, after sorting (default: 100)")]
+    pub limit: Option<usize>,
+    #[schemars(description = "Output format: json (default) or csv")]
+    pub format: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+/// The parameters `get_library_actions` and `get_library_usages` share.
+struct AnalyticsQuery {
+    file_key: String,
+    asset: String,
+    group_by: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    sort_by: Option<String>,
+    limit: Option<usize>,
+    format: Option<String>,
+    pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetFileStylesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetTeamProjectsRequest {
+    #[schemars(description = "The team ID (extract from a team URL using parse_figma_url)")]
+    pub team_id: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetProjectFilesRequest {
+    #[schemars(
+        description = "The project ID (from get_team_projects, or a project URL using parse_figma_url)"
+    )]
+    pub project_id: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetTeamLibraryRequest {
+    #[schemars(description = "The team ID (extract from a team URL using parse_figma_url)")]
+    pub team_id: String,
+    #[schemars(description = "Most items to return (default: all)")]
+    pub limit: Option<usize>,
+    #[schemars(description = "Continue after this cursor, e.g. next_after from a previous call")]
+    pub after: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetCommentsRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(
+        description = "Comma-separated node IDs; only threads pinned to these nodes are returned (default: all threads)"
+    )]
+    pub node_ids: Option<String>,
+    #[schemars(
+        description = "Only return resolved (true) or unresolved (false) threads (default: both)"
+    )]
+    pub resolved: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct ExtractTextRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated IDs of the nodes whose text to extract")]
+    pub node_ids: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct ExtractTablesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated IDs of the frames to search for tables")]
+    pub node_ids: String,
+    #[schemars(
+        description = "Output format: markdown (default), csv (one content block per table) or json"
+    )]
+    pub format: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct ExtractChartDataRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated IDs of the frames to search for charts")]
+    pub node_ids: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct InferRolesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated IDs of the nodes to label, with their descendants")]
+    pub node_ids: String,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct NodesInRegionRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "ID of the page or frame to search in")]
+    pub node_id: String,
+    #[schemars(
+        description = "Left edge of the region, relative to the frame searched in (canvas coordinates for pages)"
+    )]
+    pub x: Option<f64>,
+    #[schemars(
+        description = "Top edge of the region, relative to the frame searched in (canvas coordinates for pages)"
+    )]
+    pub y: Option<f64>,
+    #[schemars(description = "Width of the region")]
+    pub width: Option<f64>,
+    #[schemars(description = "Height of the region")]
+    pub height: Option<f64>,
+    #[schemars(
+        description = "Use this node's bounding box as the region instead of a rectangle; the node and its ancestors are left out of the results"
+    )]
+    pub reference_node_id: Option<String>,
+    #[schemars(
+        description = "Only return nodes entirely inside the region instead of all overlapping nodes (default: false)"
+    )]
+    pub contained: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
 
-            // Cache the downloaded data
-            let _ = self.image_cache.update_cached_data(&uri, data.clone());
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct SuggestLayerNamesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated node IDs to search below (default: the whole file)")]
+    pub node_ids: Option<String>,
+    #[schemars(
+        description = "Comma-separated page names or globs (e.g. Checkout*) to read when node_ids is not given; other pages are not fetched (default: all pages)"
+    )]
+    pub pages: Option<String>,
+    #[schemars(description = "Output format: json (default) or csv")]
+    pub format: Option<String>,
+    #[schemars(
+        description = "Return the result inline even if it is larger than FIGMA_MCP_INLINE_LIMIT, instead of as a resource link (default: false)"
+    )]
+    pub inline: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
 
-            data
-        };
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct FindHiddenLayersRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated node IDs to search below (default: the whole file)")]
+    pub node_ids: Option<String>,
+    #[schemars(
+        description = "Comma-separated page names or globs (e.g. Checkout*) to read when node_ids is not given; other pages are not fetched (default: all pages)"
+    )]
+    pub pages: Option<String>,
+    #[schemars(
+        description = "Return the result inline even if it is larger than FIGMA_MCP_INLINE_LIMIT, instead of as a resource link (default: false)"
+    )]
+    pub inline: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
 
-        // Convert to base64
-        let base64_data = general_purpose::STANDARD.encode(&image_data);
-        let mime_type = crate::figma::ImageCache::get_mime_type(&entry.format);
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct CompareLocalesRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(
+        description = "Comma-separated IDs of pages or sections holding the locale frames (default: the whole file)"
+    )]
+    pub node_ids: Option<String>,
+    #[schemars(
+        description = "Comma-separated page names or globs (e.g. Checkout*) to read when node_ids is not given; other pages are not fetched (default: all pages)"
+    )]
+    pub pages: Option<String>,
+    #[schemars(
+        description = "Locale to compare the others with, e.g. en or de (default: English if present, otherwise the first frame)"
+    )]
+    pub reference_locale: Option<String>,
+    #[schemars(
+        description = "How many times longer than the reference a translation may be before it is flagged (default: 1.3)"
+    )]
+    pub max_length_ratio: Option<f64>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
 
-        Ok(ReadResourceResult {
-            contents: vec![ResourceContents::BlobResourceContents {
-                uri: uri.clone(),
-                mime_type: Some(mime_type.to_string()),
-                blob: base64_data,
-            }],
-        })
-    }
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct GetBoardGraphRequest {
+    #[schemars(description = "The FigJam file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(
+        description = "Comma-separated IDs of sections or pages to read (default: the whole board)"
+    )]
+    pub node_ids: Option<String>,
+    #[schemars(
+        description = "Comma-separated page names or globs (e.g. Checkout*) to read when node_ids is not given; other pages are not fetched (default: all pages)"
+    )]
+    pub pages: Option<String>,
+    #[schemars(
+        description = "Return the result inline even if it is larger than FIGMA_MCP_INLINE_LIMIT, instead of as a resource link (default: false)"
+    )]
+    pub inline: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
 }
 
-// Parameter structs for MCP tools
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
-struct ParseUrlRequest {
-    #[schemars(description = "The Figma URL to parse (file or design URL)")]
-    pub url: String,
+struct TallyVotesRequest {
+    #[schemars(description = "The FigJam file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(
+        description = "Comma-separated IDs of sections or pages to count (default: the whole board)"
+    )]
+    pub node_ids: Option<String>,
+    #[schemars(
+        description = "Comma-separated page names or globs (e.g. Checkout*) to read when node_ids is not given; other pages are not fetched (default: all pages)"
+    )]
+    pub pages: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
-struct GetFileRequest {
+struct AnnotateContrastRequest {
     #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
     pub file_key: String,
+    #[schemars(description = "ID of the frame to audit")]
+    pub node_id: String,
+    #[schemars(description = "Export scale factor (1.0, 2.0, 4.0)")]
+    pub scale: Option<f64>,
     #[schemars(
-        description = "Depth to traverse into the document tree (default: 1). Use 1 for pages only, 2 for pages + top-level objects, etc."
+        description = "Box the image covers: render (default, includes shadows, blurs and outside strokes) or layout (absoluteBoundingBox)"
     )]
-    pub depth: Option<u32>,
+    pub bounds: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
-struct ExportImageRequest {
+struct ExportAnimationRequest {
     #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
     pub file_key: String,
-    #[schemars(description = "Comma-separated node IDs to export")]
+    #[schemars(
+        description = "Comma-separated IDs of the frames in the order they are shown; a frame may appear more than once"
+    )]
     pub node_ids: String,
-    #[schemars(description = "Export format: png, jpg, svg, OR pdf")]
+    #[schemars(
+        description = "Animation format: gif (default, shown by most clients) or apng (lossless, shown as its first frame where unsupported)"
+    )]
     pub format: Option<String>,
+    #[schemars(description = "How long each frame is shown, in milliseconds (default: 1000)")]
+    pub delay_ms: Option<u32>,
+    #[schemars(description = "Export scale factor (1.0, 2.0, 4.0)")]
+    pub scale: Option<f64>,
+    #[schemars(
+        description = "Box the image covers: render (default, includes shadows, blurs and outside strokes) or layout (absoluteBoundingBox)"
+    )]
+    pub bounds: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct MakeContactSheetRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated IDs of the frames, in the order they are laid out")]
+    pub node_ids: String,
+    #[schemars(description = "Frames per row (default: as square a grid as possible)")]
+    pub columns: Option<u32>,
+    #[schemars(description = "Width of each thumbnail in pixels, 32 to 1024 (default: 320)")]
+    pub thumbnail_width: Option<u32>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct CreatePrAttachmentRequest {
+    #[schemars(description = "The Figma file key (extract from URL using parse_figma_url)")]
+    pub file_key: String,
+    #[schemars(description = "Comma-separated IDs of the frames to include")]
+    pub node_ids: String,
+    #[schemars(
+        description = "Directory to write, or path of the ZIP archive when zip is true, relative to the workspace root (the manifest's directory or the working directory)"
+    )]
+    pub output: String,
+    #[schemars(description = "Write a ZIP archive instead of a directory (default: false)")]
+    pub zip: Option<bool>,
+    #[schemars(description = "Replace files that already exist (default: false)")]
+    pub overwrite: Option<bool>,
     #[schemars(description = "Export scale factor (1.0, 2.0, 4.0)")]
     pub scale: Option<f64>,
+    #[schemars(
+        description = "Box the images and sizes in the spec cover: render (default, includes shadows, blurs and outside strokes) or layout (absoluteBoundingBox)"
+    )]
+    pub bounds: Option<String>,
+    #[schemars(description = "Heading of the spec (default: Design reference)")]
+    pub title: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct SyncWorkspaceRequest {
+    #[schemars(
+        description = "Path of the manifest, relative to the workspace root (default: FIGMA_MCP_WORKSPACE or the nearest figma-mcp.toml above the working directory)"
+    )]
+    pub path: Option<String>,
+    #[schemars(
+        description = "Run as a background job and return its job ID immediately, see job_result (default: false)"
+    )]
+    pub background: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct IndexFilesRequest {
+    #[schemars(
+        description = "Comma-separated file keys to index (extract from URLs using parse_figma_url)"
+    )]
+    pub file_keys: String,
+    #[schemars(
+        description = "Run as a background job and return its job ID immediately, see job_result (default: false)"
+    )]
+    pub background: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct QueryDesignSystemRequest {
+    #[schemars(
+        description = "Asset name to search for (case-insensitive substring) or exact asset key; empty matches everything"
+    )]
+    pub query: String,
+    #[schemars(description = "Limit results to one kind: component, style OR variable")]
+    pub kind: Option<String>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct SwitchAccountRequest {
+    #[schemars(
+        description = "Name of the account to use: 'default', or the lowercased NAME of a FIGMA_TOKEN_<NAME> variable (default: list the accounts)"
+    )]
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct HelpRequest {
+    #[schemars(
+        description = "A workflow (workflow, navigation, resources, urls, authentication) or a tool name (default: an overview of both)"
+    )]
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct PluginStateRequest {
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct SelectionToCodeRequest {
+    #[schemars(description = "Output format: react OR html (default: react)")]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct CreateNodeRequest {
+    #[schemars(description = "Type of node to create: FRAME, TEXT or RECTANGLE")]
+    pub node_type: String,
+    #[schemars(description = "ID of the frame or page to add the node to (default: current page)")]
+    pub parent_id: Option<String>,
+    #[serde(flatten)]
+    pub properties: NodeProperties,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct UpdateNodeRequest {
+    #[schemars(description = "ID of the node to update")]
+    pub node_id: String,
+    #[serde(flatten)]
+    pub properties: NodeProperties,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct RenameLayersRequest {
+    #[schemars(
+        description = "Layers to rename with their new names, e.g. the suggestions of suggest_layer_names"
+    )]
+    pub renames: Vec<LayerRename>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+#[serde(rename_all(serialize = "camelCase"))]
+struct LayerRename {
+    #[schemars(description = "ID of the layer to rename")]
+    pub node_id: String,
+    #[schemars(description = "New layer name")]
+    pub name: String,
+}
+
+/// Node properties the companion plugin can set. Unset properties are left
+/// unchanged.
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+#[serde(rename_all(serialize = "camelCase"))]
+struct NodeProperties {
+    #[schemars(description = "Layer name")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[schemars(description = "X position relative to the parent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    #[schemars(description = "Y position relative to the parent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
+    #[schemars(description = "Width in pixels")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    #[schemars(description = "Height in pixels")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<f64>,
+    #[schemars(description = "Solid fill color as hex, e.g. #1E88E5")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill: Option<String>,
+    #[schemars(description = "Opacity from 0.0 to 1.0")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+    #[schemars(description = "Corner radius in pixels (frames and rectangles)")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corner_radius: Option<f64>,
+    #[schemars(description = "Text content (text nodes)")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[schemars(description = "Font size in pixels (text nodes)")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    #[schemars(description = "Whether the node is visible")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -415,9 +5897,332 @@ struct GetFileNodesRequest {
         description = "Depth to traverse from each node (default: 1). Use 1 for direct children only, 2 for children + grandchildren, etc."
     )]
     pub depth: Option<u32>,
+    #[schemars(
+        description = "Fetch even when the response is projected to exceed the node limit (default: false)"
+    )]
+    pub force: Option<bool>,
+    #[schemars(
+        description = "Leave out hidden layers and their descendants (default: FIGMA_MCP_EXCLUDE_HIDDEN, or false)"
+    )]
+    pub exclude_hidden: Option<bool>,
+    #[schemars(
+        description = "Leave out archived and scratch pages and top-level frames (names like 🗑, archive, old) (default: true if FIGMA_MCP_EXCLUDE_NAMES is set)"
+    )]
+    pub exclude_archived: Option<bool>,
+    #[schemars(
+        description = "ID of a version from get_file_versions to read instead of the current state"
+    )]
+    pub version: Option<String>,
+    #[schemars(
+        description = "Return the result inline even if it is larger than FIGMA_MCP_INLINE_LIMIT, instead of as a resource link (default: false)"
+    )]
+    pub inline: Option<bool>,
+    #[schemars(description = "Pretty-print the JSON response (default: false)")]
+    pub pretty: Option<bool>,
 }
 
 // Helper functions
+/// Fetches a whole file, or only the pages matching `pages` if given.
+async fn fetch_file(
+    client: &FigmaClient,
+    file_key: &str,
+    pages: Option<&NameFilter>,
+    depth: Option<u32>,
+) -> crate::Result<Value> {
+    match pages {
+        Some(pages) => client.get_file_pages(file_key, pages, depth).await,
+        None => client.get_file(file_key, depth).await,
+    }
+}
+
+/// Fetches the rows of library analytics, following the cursor for up to
+/// `analytics::MAX_PAGES` pages. Returns whether all pages were fetched.
+async fn analytics_pages(
+    client: &FigmaClient,
+    file_key: &str,
+    asset: Asset,
+    metric: Metric,
+    params: &[(&str, String)],
+) -> crate::Result<(Vec<Map<String, Value>>, bool)> {
+    let mut rows = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    for _ in 0..analytics::MAX_PAGES {
+        let mut query: Vec<(&str, &str)> = params
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        if let Some(cursor) = &cursor {
+            query.push(("cursor", cursor));
+        }
+
+        let response = client
+            .get_library_analytics(file_key, asset.name(), metric.name(), &query)
+            .await?;
+        rows.extend(analytics::rows(&response));
+        cursor = analytics::next_cursor(&response);
+        if cursor.is_none() {
+            return Ok((rows, true));
+        }
+    }
+
+    Ok((rows, false))
+}
+
+fn parse_node_ids(node_ids: &str) -> Vec<String> {
+    node_ids
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Keys of the files that changed since their data was cached.
+fn stale_files(sources: &[Freshness]) -> Vec<String> {
+    sources
+        .iter()
+        .filter(|source| source.stale == Some(true))
+        .map(|source| source.file_key.clone())
+        .collect()
+}
+
+/// Sends webhook events to the client as `notifications/message` log
+/// messages from the `figma_webhook` logger, until the client goes away.
+async fn forward_events(
+    mut events: broadcast::Receiver<WebhookEvent>,
+    peer: Peer<RoleServer>,
+    log_level: Arc<AtomicU8>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "Dropped {} webhook events the client was slow to take",
+                    skipped
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if (LoggingLevel::Notice as u8) < log_level.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        let Ok(data) = serde_json::to_value(&event) else {
+            continue;
+        };
+        let message = LoggingMessageNotificationParam {
+            level: LoggingLevel::Notice,
+            logger: Some(WEBHOOK_LOGGER.to_string()),
+            data,
+        };
+        if peer.notify_logging_message(message).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// The protocol revision negotiated with the client of a request, the
+/// newest one for requests before initialization.
+fn session_protocol_version(context: &RequestContext<RoleServer>) -> ProtocolVersion {
+    match context.peer.peer_info() {
+        Some(info) => protocol::negotiate(&info.protocol_version),
+        None => ProtocolVersion::LATEST,
+    }
+}
+
+/// Whether an error suggests the request was too heavy for Figma to serve,
+/// rather than being invalid.
+fn is_overload_error(error: &Error) -> bool {
+    match error {
+        Error::Api(error) => error.status >= 500,
+        Error::Network(error) => error.is_timeout(),
+        Error::Timeout(_) => true,
+        _ => false,
+    }
+}
+
+/// Runs an upstream request under a tool's timeout. Dropping the request on
+/// timeout also aborts the underlying HTTP call.
+async fn with_timeout<T>(
+    timeout: Duration,
+    request: impl Future<Output = crate::Result<T>>,
+) -> crate::Result<T> {
+    tokio::time::timeout(timeout, request)
+        .await
+        .map_err(|_| Error::Timeout(timeout))?
+}
+
+/// Stamps the configured watermark on an exported image.
+async fn stamp_watermark(data: Vec<u8>, format: &str, text: &str) -> crate::Result<Vec<u8>> {
+    let (format, text) = (format.to_string(), text.to_string());
+
+    // Decoding and re-encoding is CPU bound
+    tokio::task::spawn_blocking(move || watermark::stamp(&data, &format, &text))
+        .await
+        .map_err(|e| Error::Internal(format!("Watermarking failed: {}", e)))?
+}
+
+fn missing_parameter(name: &str, question: &str) -> Result<CallToolResult, McpError> {
+    let message = format!("Missing required parameter `{}`. {}", name, question);
+
+    tool_error(message)
+}
+
+/// Maps contrast issues to outlines on a frame export whose top-left corner
+/// is at `origin` on the canvas.
+fn contrast_annotations(issues: &[ContrastIssue], origin: Bounds, scale: f64) -> Vec<Annotation> {
+    // Keep the outline clear of the glyphs
+    let padding = 2.0;
+
+    issues
+        .iter()
+        .map(|issue| {
+            let pixels = |value: f64| (value * scale).round() as i64;
+            Annotation {
+                x: pixels(issue.bounds.x - origin.x - padding),
+                y: pixels(issue.bounds.y - origin.y - padding),
+                width: pixels(issue.bounds.width + 2.0 * padding),
+                height: pixels(issue.bounds.height + 2.0 * padding),
+                legend: format!("{}:1 < {}:1", issue.ratio, issue.required),
+            }
+        })
+        .collect()
+}
+
+/// The images exported in one format: Figma's result, the registered
+/// resources by node, and the nodes Figma returned no image for.
+struct FormatExport {
+    result: Value,
+    exports: Vec<(String, String)>,
+    failures: Vec<ExportFailure>,
+}
+
+/// Builds the error message of a failed export. Figma rejects exports of
+/// pages and missing nodes with a bare HTTP 400, so those are looked up to
+/// name the offending nodes.
+async fn explain_export_error(
+    client: &FigmaClient,
+    file_key: &str,
+    node_ids: &[String],
+    error: Error,
+) -> String {
+    let error_msg = format!("Error exporting images: {}", error);
+    if !matches!(&error, Error::Api(api) if api.status == 400) {
+        return error_msg;
+    }
+    let Ok(nodes) = client.get_file_nodes(file_key, node_ids, Some(1)).await else {
+        return error_msg;
+    };
+
+    let reasons: Vec<String> = export::unexportable_nodes(&nodes, node_ids)
+        .into_iter()
+        .map(|(node_id, reason)| format!("{}: {}", node_id, reason))
+        .collect();
+    if reasons.is_empty() {
+        return error_msg;
+    }
+
+    format!("{}. Cannot export {}", error_msg, reasons.join("; "))
+}
+
+/// Reports a started background job to the agent.
+fn job_started(
+    started: crate::Result<Job>,
+    pretty: Option<bool>,
+) -> Result<CallToolResult, McpError> {
+    let job = match started {
+        Ok(job) => job,
+        Err(e) => {
+            let error_msg = format!("Error starting job: {}", e);
+            return tool_error(error_msg);
+        }
+    };
+
+    let result = output::to_json(&job.summary(), pretty.unwrap_or(false));
+
+    tool_success(result)
+}
+
+fn already_exists(paths: &[std::path::PathBuf]) -> Result<CallToolResult, McpError> {
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    let error_msg = format!(
+        "Error: {} already exists; pass overwrite: true to replace it",
+        paths.join(", ")
+    );
+
+    tool_error(error_msg)
+}
+
+fn unknown_job(job_id: &str) -> Result<CallToolResult, McpError> {
+    let error_msg = format!("Error: unknown job '{}', see list_jobs", job_id);
+
+    tool_error(error_msg)
+}
+
+/// Tells the client a job finished through a logging notification, which
+/// clients without notification support simply ignore.
+async fn notify_job_finished(peer: &Peer<RoleServer>, job: &Job) {
+    let level = match job.status {
+        JobStatus::Failed => LoggingLevel::Warning,
+        _ => LoggingLevel::Info,
+    };
+    let notification = LoggingMessageNotificationParam {
+        level,
+        logger: Some("jobs".to_string()),
+        data: job.summary(),
+    };
+
+    if let Err(e) = peer.notify_logging_message(notification).await {
+        tracing::debug!("Could not notify client about job {}: {}", job.job_id, e);
+    }
+}
+
+/// Fetches the exported nodes, shallowly and best effort, to name the
+/// images and explain the ones Figma failed to render. Returns the nodes
+/// and the failures.
+async fn check_exports(
+    client: &FigmaClient,
+    file_key: &str,
+    result: &Value,
+    node_ids: &[String],
+    scale: f64,
+    deadline: Instant,
+) -> (Option<Value>, Vec<ExportFailure>) {
+    let request = client.get_file_nodes(file_key, node_ids, Some(1));
+    let nodes = match tokio::time::timeout_at(deadline, request).await {
+        Ok(Ok(nodes)) => Some(nodes),
+        _ => None,
+    };
+
+    let failed = export::failed_nodes(result, node_ids);
+    if failed.is_empty() {
+        return (nodes, Vec::new());
+    }
+    let failures = export::explain_failures(nodes.as_ref(), &failed, scale);
+
+    (nodes, failures)
+}
+
+/// Parses the `bounds` parameter of export tools, defaulting to the render
+/// bounds Figma crops exports to.
+fn parse_bounds(bounds: Option<&str>) -> std::result::Result<BoundsKind, String> {
+    let Some(name) = bounds else {
+        return Ok(BoundsKind::default());
+    };
+
+    BoundsKind::parse(name).ok_or_else(|| {
+        format!(
+            "Error: unknown bounds '{}', expected {}",
+            name, BOUNDS_NAMES
+        )
+    })
+}
+
 fn tool_error(message: String) -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::error(vec![Content::text(message)]))
 }
@@ -425,3 +6230,117 @@ fn tool_error(message: String) -> Result<CallToolResult, McpError> {
 fn tool_success(content: String) -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::success(vec![Content::text(content)]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(result: &CallToolResult) -> &str {
+        result.content[0]
+            .as_text()
+            .map(|content| content.text.as_str())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_sessions_only_see_their_own_jobs() {
+        let server = FigmaServer::new("test-token".to_string()).unwrap();
+        let (alice, bob) = (server.for_session().unwrap(), server.for_session().unwrap());
+        let job = alice.create_job(JobKind::Export, Some(1)).unwrap();
+        let list = || Parameters(ListJobsRequest { pretty: None });
+        let request = || {
+            Parameters(JobRequest {
+                job_id: job.job_id.clone(),
+                pretty: None,
+            })
+        };
+
+        let listed = bob.list_jobs(list()).await.unwrap();
+        assert!(!text(&listed).contains(&job.job_id));
+        let read = bob.job_result(request()).await.unwrap();
+        assert!(text(&read).contains("unknown job"));
+        let cancelled = bob.cancel_job(request()).await.unwrap();
+        assert!(text(&cancelled).contains("unknown job"));
+
+        // Untouched for the session that created it
+        let listed = alice.list_jobs(list()).await.unwrap();
+        assert!(text(&listed).contains(&job.job_id));
+        let read = alice.job_result(request()).await.unwrap();
+        assert_eq!(read.is_error, Some(false));
+        let job = alice.jobs.get(&job.job_id).unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+    }
+
+    #[tokio::test]
+    async fn test_purge_keeps_other_sessions_data() {
+        let server = FigmaServer::new("test-token".to_string()).unwrap();
+        let (alice, bob) = (server.for_session().unwrap(), server.for_session().unwrap());
+        let generate = |server: &FigmaServer, variant: &str| {
+            let uri = server
+                .image_cache
+                .register_generated("ABC".to_string(), "1:2".to_string(), variant, vec![1])
+                .unwrap();
+            server.claim(&uri);
+            uri
+        };
+        let own = generate(&alice, "heatmap");
+        let shared = generate(&alice, "annotated");
+        generate(&bob, "annotated");
+        let job = bob.create_job(JobKind::Index, None).unwrap();
+
+        let purged = alice.purge_all().await.unwrap();
+        assert!(text(&purged).contains(r#""images":1"#));
+        assert!(alice.image_cache.get_entry(&own).unwrap().is_none());
+        assert!(bob.image_cache.get_entry(&shared).unwrap().is_some());
+        assert!(bob.jobs.get(&job.job_id).unwrap().is_some());
+        assert!(!job.cancellation.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_ended_session_releases_its_data() {
+        let server = FigmaServer::new("test-token".to_string()).unwrap();
+        let (alice, bob) = (server.for_session().unwrap(), server.for_session().unwrap());
+        let generate = |server: &FigmaServer, variant: &str| {
+            let uri = server
+                .image_cache
+                .register_generated("ABC".to_string(), "1:2".to_string(), variant, vec![1])
+                .unwrap();
+            server.claim(&uri);
+            uri
+        };
+        let own = generate(&alice, "heatmap");
+        let shared = generate(&alice, "annotated");
+        generate(&bob, "annotated");
+        let job = alice.create_job(JobKind::Index, None).unwrap();
+
+        // Clones answering requests keep the session alive
+        let request = alice.clone();
+        drop(alice);
+        assert!(server.image_cache.get_entry(&own).unwrap().is_some());
+        drop(request);
+
+        assert!(server.image_cache.get_entry(&own).unwrap().is_none());
+        assert!(server.jobs.get(&job.job_id).unwrap().is_none());
+        assert!(job.cancellation.is_cancelled());
+        assert!(server.image_cache.get_entry(&shared).unwrap().is_some());
+        assert!(bob.can_see(&shared));
+    }
+
+    #[tokio::test]
+    async fn test_workspace_manifest_outside_root_is_refused() {
+        let server = FigmaServer::new("test-token".to_string()).unwrap();
+
+        for path in ["/etc/figma-mcp.toml", "../figma-mcp.toml"] {
+            let result = server
+                .load_workspace(Parameters(LoadWorkspaceRequest {
+                    path: Some(path.to_string()),
+                    pretty: None,
+                }))
+                .await
+                .unwrap();
+            assert_eq!(result.is_error, Some(true));
+            assert!(text(&result).starts_with("Error: path"), "{}", path);
+            assert!(server.tool_path(path).is_err());
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Which session created which resource, for servers shared by several
+//! clients over HTTP or a socket.
+//!
+//! All sessions use the same image cache, stored results and jobs, so an
+//! image exported by one client is downloaded once for everyone. What a
+//! session may list and read is decided here: with `session` visibility
+//! only the resources it created, with `shared` visibility all of them.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::{Error, Result};
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Which resources of other sessions a session can see.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visibility {
+    /// Only the session's own resources
+    #[default]
+    Session,
+    /// All resources of the server
+    Shared,
+}
+
+impl FromStr for Visibility {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "session" => Ok(Visibility::Session),
+            "shared" => Ok(Visibility::Shared),
+            _ => Err(Error::Config(format!(
+                "Invalid resource visibility '{}', expected session or shared",
+                value
+            ))),
+        }
+    }
+}
+
+/// A new session ID, unique within this process.
+pub fn new_session_id() -> String {
+    format!(
+        "session-{}",
+        NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// An HTTP or socket session, shared by the server clones answering it.
+/// When the last of them is dropped, as the session ends by a DELETE, an
+/// idle timeout or a closed socket, `on_end` releases what it created.
+pub struct Session {
+    id: String,
+    on_end: Box<dyn Fn(&str) + Send + Sync>,
+}
+
+impl Session {
+    pub fn new(on_end: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self {
+            id: new_session_id(),
+            on_end: Box::new(on_end),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        (self.on_end)(&self.id);
+    }
+}
+
+/// The sessions that created each resource URI. A resource created by
+/// several sessions, such as the same node exported twice, is visible to
+/// each of them.
+#[derive(Clone, Default)]
+pub struct ResourceOwners {
+    owners: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+}
+
+impl ResourceOwners {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `session` created the resource.
+    pub fn add(&self, uri: &str, session: &str) -> Result<()> {
+        let mut owners = self
+            .owners
+            .write()
+            .map_err(|_| Error::Internal("Resource owners lock poisoned".to_string()))?;
+        owners
+            .entry(uri.to_string())
+            .or_default()
+            .insert(session.to_string());
+
+        Ok(())
+    }
+
+    /// Whether `session` created the resource.
+    pub fn is_owner(&self, uri: &str, session: &str) -> bool {
+        self.owners
+            .read()
+            .map(|owners| {
+                owners
+                    .get(uri)
+                    .is_some_and(|sessions| sessions.contains(session))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Forgets what `session` created. Returns the resources no other
+    /// session created, which nobody owns anymore.
+    pub fn forget_session(&self, session: &str) -> Result<Vec<String>> {
+        let mut owners = self
+            .owners
+            .write()
+            .map_err(|_| Error::Internal("Resource owners lock poisoned".to_string()))?;
+        let mut orphaned = Vec::new();
+        owners.retain(|uri, sessions| {
+            if !sessions.remove(session) || !sessions.is_empty() {
+                return true;
+            }
+            orphaned.push(uri.clone());
+            false
+        });
+
+        Ok(orphaned)
+    }
+
+    /// Forgets all owners, when the resources themselves are dropped.
+    pub fn clear(&self) -> Result<()> {
+        self.owners
+            .write()
+            .map_err(|_| Error::Internal("Resource owners lock poisoned".to_string()))?
+            .clear();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_visibility() {
+        assert_eq!("Shared".parse::<Visibility>().unwrap(), Visibility::Shared);
+        assert_eq!(
+            " session ".parse::<Visibility>().unwrap(),
+            Visibility::Session
+        );
+        assert!("public".parse::<Visibility>().is_err());
+    }
+
+    #[test]
+    fn test_resource_owners() {
+        let owners = ResourceOwners::new();
+        let uri = "figma://file/ABC/node/1:2.png";
+        owners.add(uri, "session-a").unwrap();
+        owners.add(uri, "session-b").unwrap();
+
+        assert!(owners.is_owner(uri, "session-a"));
+        assert!(owners.is_owner(uri, "session-b"));
+        assert!(!owners.is_owner(uri, "session-c"));
+        assert!(!owners.is_owner("figma://result/other-1", "session-a"));
+
+        owners.add("figma://result/other-1", "session-a").unwrap();
+        let orphaned = owners.forget_session("session-a").unwrap();
+        assert_eq!(orphaned, ["figma://result/other-1"]);
+        assert!(!owners.is_owner(uri, "session-a"));
+        assert!(owners.is_owner(uri, "session-b"));
+
+        owners.clear().unwrap();
+        assert!(!owners.is_owner(uri, "session-b"));
+    }
+
+    #[test]
+    fn test_session_ends_when_dropped() {
+        let ended = Arc::new(RwLock::new(Vec::new()));
+        let session = {
+            let ended = Arc::clone(&ended);
+            Arc::new(Session::new(move |id| {
+                ended.write().unwrap().push(id.to_string());
+            }))
+        };
+        let id = session.id().to_string();
+
+        let clone = Arc::clone(&session);
+        drop(session);
+        assert!(ended.read().unwrap().is_empty());
+        drop(clone);
+        assert_eq!(*ended.read().unwrap(), [id]);
+    }
+}
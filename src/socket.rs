@@ -0,0 +1,82 @@
+//! Unix domain socket transport, so local orchestrators can connect to one
+//! running server instead of spawning a child process per client. Each
+//! connection speaks newline-delimited JSON-RPC, like stdio, and gets its
+//! own MCP session.
+
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::Path;
+
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::{Error, Result};
+
+/// Listens on a socket file at `path` that only `mode` may connect to. A
+/// socket left behind by a server that is gone is replaced; one a server
+/// still answers on is an error.
+pub async fn bind(path: &Path, mode: u32) -> Result<UnixListener> {
+    let error = |message: String| Error::Config(format!("{}: {}", path.display(), message));
+
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(error("exists and is not a socket".to_string()));
+        }
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(error("another server is listening on it".to_string()));
+        }
+        fs::remove_file(path).map_err(|e| error(e.to_string()))?;
+    }
+
+    let listener = UnixListener::bind(path).map_err(|e| error(e.to_string()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| {
+        let _ = fs::remove_file(path);
+        error(e.to_string())
+    })?;
+
+    Ok(listener)
+}
+
+/// Parses an octal mode like `660`.
+pub fn parse_mode(value: &str) -> Result<u32> {
+    u32::from_str_radix(value.trim(), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "Invalid FIGMA_MCP_SOCKET_MODE: '{}' is not an octal mode",
+                value
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_sets_mode_and_replaces_stale_socket() {
+        let dir = std::env::temp_dir().join(format!("figma-mcp-socket-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mcp.sock");
+
+        let listener = bind(&path, 0o600).await.unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        assert!(bind(&path, 0o600).await.is_err());
+
+        // The file stays behind when a server exits without cleaning up
+        drop(listener);
+        assert!(bind(&path, 0o660).await.is_ok());
+
+        fs::write(dir.join("file"), "").unwrap();
+        assert!(bind(&dir.join("file"), 0o600).await.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("660").unwrap(), 0o660);
+        assert!(parse_mode("999").is_err());
+        assert!(parse_mode("7777").is_err());
+    }
+}
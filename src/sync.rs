@@ -0,0 +1,352 @@
+//! Runs the export, token and codegen targets of a workspace manifest and
+//! writes the results into the repository. Files are only rewritten when
+//! their content changes, so repeated runs are idempotent.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{
+    figma::{
+        codegen::{self, CodeFormat},
+        pages::{self, NameFilter},
+        roles::RoleRules,
+        tokens::{self, TokenFormat},
+        FigmaClient,
+    },
+    workspace::{CodegenTarget, ExportTarget, TokenTarget, Workspace},
+    Error, Result,
+};
+
+/// Files touched by a sync, relative to the workspace root.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub written: Vec<PathBuf>,
+    pub unchanged: Vec<PathBuf>,
+}
+
+impl SyncReport {
+    fn record(&mut self, workspace: &Workspace, path: PathBuf, written: bool) {
+        let path = path
+            .strip_prefix(&workspace.root)
+            .map(Path::to_path_buf)
+            .unwrap_or(path);
+
+        if written {
+            self.written.push(path);
+        } else {
+            self.unchanged.push(path);
+        }
+    }
+}
+
+/// Runs all targets of `workspace`. In deterministic mode, exported images
+/// are stripped of metadata such as timestamps so unchanged designs produce
+/// byte-identical files. `rules` drive the semantic elements of generated
+/// code, and frames matching `exclude` are left out of it.
+pub async fn sync_workspace(
+    client: &FigmaClient,
+    workspace: &Workspace,
+    deterministic: bool,
+    rules: &RoleRules,
+    exclude: Option<&NameFilter>,
+) -> Result<SyncReport> {
+    let mut report = SyncReport::default();
+
+    for target in &workspace.manifest.exports {
+        sync_exports(client, workspace, target, deterministic, &mut report).await?;
+    }
+
+    for target in &workspace.manifest.tokens {
+        sync_tokens(client, workspace, target, &mut report).await?;
+    }
+
+    for target in &workspace.manifest.codegen {
+        sync_codegen(client, workspace, target, rules, exclude, &mut report).await?;
+    }
+
+    Ok(report)
+}
+
+async fn sync_exports(
+    client: &FigmaClient,
+    workspace: &Workspace,
+    target: &ExportTarget,
+    deterministic: bool,
+    report: &mut SyncReport,
+) -> Result<()> {
+    let file_key = workspace.file_key(&target.file)?;
+    let format = target.format.as_deref().unwrap_or("png");
+
+    let result = client
+        .export_images(&file_key, &target.node_ids, format, target.options())
+        .await?;
+
+    let output = workspace.output_path(&target.output)?;
+    for node_id in &target.node_ids {
+        let url = result
+            .pointer(&format!("/images/{}", node_id))
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                Error::NotFound(format!(
+                    "Figma did not render node {} of {}",
+                    node_id, target.file
+                ))
+            })?;
+
+        let mut data = client.download_image(url, format).await?;
+        if deterministic && format == "png" {
+            data = strip_png_metadata(&data);
+        }
+
+        let path = output.join(format!("{}.{}", file_stem(node_id), format));
+        let written = write_if_changed(&path, &data)?;
+        report.record(workspace, path, written);
+    }
+
+    Ok(())
+}
+
+async fn sync_tokens(
+    client: &FigmaClient,
+    workspace: &Workspace,
+    target: &TokenTarget,
+    report: &mut SyncReport,
+) -> Result<()> {
+    let file_key = workspace.file_key(&target.file)?;
+    let format = token_format(target)?;
+
+    let file = client.get_file(&file_key, None).await?;
+
+    let style_ids: Vec<String> = file
+        .get("styles")
+        .and_then(Value::as_object)
+        .map(|styles| {
+            styles
+                .iter()
+                .filter(|(_, style)| style.get("remote").and_then(Value::as_bool) != Some(true))
+                .map(|(node_id, _)| node_id.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    let style_nodes = if style_ids.is_empty() {
+        json!({})
+    } else {
+        client.get_file_nodes(&file_key, &style_ids, None).await?
+    };
+
+    // Variables are only available on Enterprise plans
+    let variables = client.get_local_variables(&file_key).await.ok();
+
+    let mut tokens = tokens::from_styles(&file, &style_nodes);
+    if let Some(variables) = &variables {
+        tokens.extend(tokens::from_variables(variables));
+    }
+
+    let prefix = workspace.manifest.naming.token_prefix.as_deref();
+    let content = tokens::render(&tokens, format, prefix);
+
+    let path = workspace.output_path(&target.output)?;
+    let written = write_if_changed(&path, content.as_bytes())?;
+    report.record(workspace, path, written);
+
+    Ok(())
+}
+
+async fn sync_codegen(
+    client: &FigmaClient,
+    workspace: &Workspace,
+    target: &CodegenTarget,
+    rules: &RoleRules,
+    exclude: Option<&NameFilter>,
+    report: &mut SyncReport,
+) -> Result<()> {
+    let file_key = workspace.file_key(&target.file)?;
+    let format_name = target.format.as_deref().unwrap_or("react");
+    let format = CodeFormat::parse(format_name).ok_or_else(|| {
+        Error::Config(format!(
+            "Unsupported codegen format '{}', expected html or react",
+            format_name
+        ))
+    })?;
+
+    // Geometry carries the transforms of rotated and skewed layers
+    let mut nodes = client
+        .get_file_nodes_with_geometry(&file_key, &target.node_ids, None)
+        .await?;
+    if let Some(exclude) = exclude {
+        pages::strip_excluded(&mut nodes, exclude);
+    }
+
+    let output = workspace.output_path(&target.output)?;
+    for node_id in &target.node_ids {
+        let node = nodes
+            .get("nodes")
+            .and_then(|nodes| nodes.get(node_id))
+            .and_then(|node| node.get("document"))
+            .ok_or_else(|| {
+                Error::NotFound(format!("Node {} not found in {}", node_id, target.file))
+            })?;
+
+        let name = node.get("name").and_then(Value::as_str).unwrap_or(node_id);
+        let file_name = match format {
+            CodeFormat::React => format!("{}.tsx", codegen::component_name(name)),
+            CodeFormat::Html => format!("{}.html", tokens::token_name(name)),
+        };
+
+        let code = codegen::generate(node, format, rules);
+
+        let path = output.join(file_name);
+        let written = write_if_changed(&path, code.as_bytes())?;
+        report.record(workspace, path, written);
+    }
+
+    Ok(())
+}
+
+fn token_format(target: &TokenTarget) -> Result<TokenFormat> {
+    if let Some(format) = &target.format {
+        return TokenFormat::parse(format).ok_or_else(|| {
+            Error::Config(format!(
+                "Unsupported token format '{}', expected css or json",
+                format
+            ))
+        });
+    }
+
+    let is_json = target
+        .output
+        .extension()
+        .is_some_and(|extension| extension == "json");
+
+    Ok(if is_json {
+        TokenFormat::Json
+    } else {
+        TokenFormat::Css
+    })
+}
+
+/// Writes `content` unless the file already holds it. Returns whether the
+/// file was written.
+fn write_if_changed(path: &Path, content: &[u8]) -> Result<bool> {
+    if fs::read(path).is_ok_and(|existing| existing == content) {
+        return Ok(false);
+    }
+
+    let io_error =
+        |e: std::io::Error| Error::Internal(format!("Failed to write {}: {}", path.display(), e));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    fs::write(path, content).map_err(io_error)?;
+
+    Ok(true)
+}
+
+/// Removes the PNG chunks carrying timestamps and free-form text, keeping
+/// the image data. Data that isn't a well-formed PNG is returned unchanged.
+fn strip_png_metadata(data: &[u8]) -> Vec<u8> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    const METADATA_CHUNKS: &[&[u8]] = &[b"tIME", b"tEXt", b"zTXt", b"iTXt"];
+
+    let Some(mut rest) = data.strip_prefix(SIGNATURE) else {
+        return data.to_vec();
+    };
+
+    let mut stripped = SIGNATURE.to_vec();
+    while !rest.is_empty() {
+        let Some(length) = rest.get(..4) else {
+            return data.to_vec();
+        };
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+        // Length, type, data and CRC
+        let Some(chunk) = rest.get(..length + 12) else {
+            return data.to_vec();
+        };
+
+        if !METADATA_CHUNKS.contains(&&chunk[4..8]) {
+            stripped.extend_from_slice(chunk);
+        }
+        rest = &rest[chunk.len()..];
+    }
+
+    stripped
+}
+
+/// Turns a node ID like `1:2` into a file name stem like `1-2`.
+fn file_stem(node_id: &str) -> String {
+    node_id.replace([':', ';'], "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_if_changed() {
+        let dir = std::env::temp_dir().join(format!("figma-mcp-sync-{}", std::process::id()));
+        let path = dir.join("nested").join("tokens.css");
+
+        assert!(write_if_changed(&path, b":root {}\n").unwrap());
+        assert!(!write_if_changed(&path, b":root {}\n").unwrap());
+        assert!(write_if_changed(&path, b":root { --a: 1; }\n").unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_token_format_from_extension() {
+        let target = TokenTarget {
+            file: "design-system".to_string(),
+            output: PathBuf::from("tokens/tokens.json"),
+            format: None,
+        };
+        assert_eq!(token_format(&target).unwrap(), TokenFormat::Json);
+
+        let target = TokenTarget {
+            output: PathBuf::from("tokens.css"),
+            ..target
+        };
+        assert_eq!(token_format(&target).unwrap(), TokenFormat::Css);
+    }
+
+    #[test]
+    fn test_strip_png_metadata() {
+        fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+            let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+            chunk.extend_from_slice(kind);
+            chunk.extend_from_slice(data);
+            chunk.extend_from_slice(&[0; 4]);
+            chunk
+        }
+
+        let signature = b"\x89PNG\r\n\x1a\n".to_vec();
+        let ihdr = chunk(b"IHDR", &[0; 13]);
+        let idat = chunk(b"IDAT", &[1, 2, 3]);
+        let iend = chunk(b"IEND", &[]);
+        let png = [
+            signature.clone(),
+            ihdr.clone(),
+            chunk(b"tIME", &[7, 232, 1, 1, 0, 0, 0]),
+            idat.clone(),
+            chunk(b"tEXt", b"Software\0Figma"),
+            iend.clone(),
+        ]
+        .concat();
+
+        assert_eq!(
+            strip_png_metadata(&png),
+            [signature, ihdr, idat, iend].concat()
+        );
+        assert_eq!(strip_png_metadata(b"<svg/>"), b"<svg/>");
+    }
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem("1:2"), "1-2");
+        assert_eq!(file_stem("I1:2;3:4"), "I1-2-3-4");
+    }
+}
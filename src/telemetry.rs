@@ -0,0 +1,60 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::{Error, Result};
+
+/// Exports tracing spans over OTLP/HTTP while alive, flushing pending spans
+/// when dropped.
+pub struct OtelGuard {
+    provider: SdkTracerProvider,
+}
+
+impl OtelGuard {
+    /// Builds the OTLP pipeline when `OTEL_EXPORTER_OTLP_ENDPOINT` (or the
+    /// traces-specific variant) is set. The exporter reads the standard
+    /// `OTEL_*` variables for endpoint, headers and timeouts.
+    pub fn from_env() -> Result<Option<Self>> {
+        let configured = std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some()
+            || std::env::var_os("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").is_some();
+        if !configured {
+            return Ok(None);
+        }
+
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .build()
+            .map_err(|e| Error::Config(format!("Failed to create OTLP exporter: {}", e)))?;
+
+        let resource = Resource::builder()
+            .with_service_name(env!("CARGO_PKG_NAME"))
+            .build();
+
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource)
+            .build();
+
+        Ok(Some(Self { provider }))
+    }
+
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let tracer = self.provider.tracer(env!("CARGO_PKG_NAME"));
+
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    }
+}
+
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Receiver for Figma webhook callbacks.
+//!
+//! Figma POSTs an event to a registered URL whenever a file of the webhook's
+//! team, project or file changes or gets a comment. The receiver checks the
+//! passcode the webhook was registered with and hands file updates and
+//! comments to the server, which forwards them to the MCP client as
+//! notifications. Other event types are acknowledged and dropped.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, LengthLimitError, Limited};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+use crate::{Error, Result};
+
+/// Figma event payloads are small; anything larger is not from Figma.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Headers read before giving up on a request.
+const MAX_HEADERS: usize = 100;
+
+/// Time a client gets to send its headers, and then its body.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events kept for a slow subscriber before the oldest are dropped.
+const EVENT_BUFFER: usize = 64;
+
+/// Event types forwarded to clients.
+const FORWARDED_EVENTS: &[&str] = &["FILE_UPDATE", "FILE_COMMENT"];
+
+/// A file update or comment, as sent to clients.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookEvent {
+    pub event_type: String,
+    pub file_key: String,
+    pub file_name: String,
+    pub timestamp: String,
+    /// Text of a new comment, with mentions as `@handle`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_id: Option<String>,
+    /// Handle of the user who commented
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triggered_by: Option<String>,
+}
+
+/// Why a delivery was refused, as an HTTP status and reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejection {
+    pub status: StatusCode,
+    pub reason: &'static str,
+}
+
+/// Reads a delivery's body. Returns the event to forward, `None` for
+/// deliveries that are accepted but not forwarded (such as the `PING`
+/// Figma sends when a webhook is created), or why it was refused.
+pub fn receive(
+    body: &[u8],
+    passcode: &str,
+) -> std::result::Result<Option<WebhookEvent>, Rejection> {
+    let Ok(payload) = serde_json::from_slice::<Value>(body) else {
+        return Err(Rejection {
+            status: StatusCode::BAD_REQUEST,
+            reason: "Bad Request",
+        });
+    };
+
+    let received = payload.get("passcode").and_then(Value::as_str);
+    let matches = |received: &str| !passcode.is_empty() && constant_time_eq(received, passcode);
+    if !received.is_some_and(matches) {
+        return Err(Rejection {
+            status: StatusCode::UNAUTHORIZED,
+            reason: "Unauthorized",
+        });
+    }
+
+    let event_type = field(&payload, "event_type");
+    if !FORWARDED_EVENTS.contains(&event_type.as_str()) {
+        return Ok(None);
+    }
+
+    let comment = (event_type == "FILE_COMMENT").then(|| comment_text(&payload));
+    let optional = |key: &str| Some(field(&payload, key)).filter(|value| !value.is_empty());
+
+    Ok(Some(WebhookEvent {
+        file_key: field(&payload, "file_key"),
+        file_name: field(&payload, "file_name"),
+        timestamp: field(&payload, "timestamp"),
+        comment,
+        comment_id: optional("comment_id"),
+        triggered_by: payload
+            .pointer("/triggered_by/handle")
+            .and_then(Value::as_str)
+            .map(String::from),
+        event_type,
+    }))
+}
+
+/// Listens for webhook deliveries and publishes the events to forward.
+#[derive(Clone)]
+pub struct WebhookReceiver {
+    local_addr: std::net::SocketAddr,
+    events: broadcast::Sender<WebhookEvent>,
+}
+
+impl WebhookReceiver {
+    /// Listens on the given address, e.g. `127.0.0.1:3056`, accepting
+    /// deliveries that carry `passcode`.
+    pub async fn bind(addr: &str, passcode: String) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::Webhook(format!("Failed to listen on {}: {}", addr, e)))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| Error::Webhook(e.to_string()))?;
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+
+        let receiver = Self { local_addr, events };
+
+        tracing::info!("Webhook receiver listening on {}", local_addr);
+        tokio::spawn(receiver.clone().accept_loop(listener, passcode.into()));
+
+        Ok(receiver)
+    }
+
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+        self.local_addr
+    }
+
+    /// Receives the events delivered from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<WebhookEvent> {
+        self.events.subscribe()
+    }
+
+    async fn accept_loop(self, listener: TcpListener, passcode: Arc<str>) {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept webhook connection: {}", e);
+                    continue;
+                }
+            };
+
+            let receiver = self.clone();
+            let passcode = Arc::clone(&passcode);
+            let handler = service_fn(move |request| {
+                let receiver = receiver.clone();
+                let passcode = Arc::clone(&passcode);
+                async move { Ok::<_, Infallible>(receiver.handle(request, &passcode).await) }
+            });
+            let connection = http1::Builder::new()
+                .timer(TokioTimer::new())
+                .header_read_timeout(REQUEST_TIMEOUT)
+                .max_headers(MAX_HEADERS)
+                .serve_connection(TokioIo::new(stream), handler);
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    tracing::debug!("Webhook connection failed: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn handle(&self, request: Request<Incoming>, passcode: &str) -> Response<Empty<Bytes>> {
+        let body = match read_body(request).await {
+            Ok(body) => body,
+            Err(rejection) => return status(rejection.status),
+        };
+
+        match receive(&body, passcode) {
+            Ok(Some(event)) => {
+                tracing::info!(
+                    event_type = %event.event_type,
+                    file_key = %event.file_key,
+                    "Webhook event received"
+                );
+                // Without connected clients there is nobody to tell
+                let _ = self.events.send(event);
+                status(StatusCode::OK)
+            }
+            Ok(None) => status(StatusCode::OK),
+            Err(rejection) => {
+                tracing::warn!("Webhook delivery rejected: {}", rejection.reason);
+                status(rejection.status)
+            }
+        }
+    }
+}
+
+/// Reads the body of a `POST` request, up to `MAX_BODY_BYTES`.
+async fn read_body(request: Request<Incoming>) -> std::result::Result<Bytes, Rejection> {
+    if request.method() != Method::POST {
+        return Err(Rejection {
+            status: StatusCode::METHOD_NOT_ALLOWED,
+            reason: "Method Not Allowed",
+        });
+    }
+
+    let body = Limited::new(request.into_body(), MAX_BODY_BYTES).collect();
+    match tokio::time::timeout(REQUEST_TIMEOUT, body).await {
+        Ok(Ok(body)) => Ok(body.to_bytes()),
+        Ok(Err(e)) if e.is::<LengthLimitError>() => Err(Rejection {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            reason: "Payload Too Large",
+        }),
+        Ok(Err(_)) => Err(Rejection {
+            status: StatusCode::BAD_REQUEST,
+            reason: "Bad Request",
+        }),
+        Err(_) => Err(Rejection {
+            status: StatusCode::REQUEST_TIMEOUT,
+            reason: "Request Timeout",
+        }),
+    }
+}
+
+fn status(status: StatusCode) -> Response<Empty<Bytes>> {
+    let mut response = Response::new(Empty::new());
+    *response.status_mut() = status;
+    response
+}
+
+/// Joins the fragments of a comment, which are text or user mentions.
+fn comment_text(payload: &Value) -> String {
+    let fragments = payload.get("comment").and_then(Value::as_array);
+    let mentions = payload.get("mentions").and_then(Value::as_array);
+
+    fragments
+        .into_iter()
+        .flatten()
+        .map(|fragment| {
+            if let Some(text) = fragment.get("text").and_then(Value::as_str) {
+                return text.to_string();
+            }
+            let id = fragment.get("mention").and_then(Value::as_str);
+            let handle = mentions
+                .into_iter()
+                .flatten()
+                .find(|user| user.get("id").and_then(Value::as_str) == id)
+                .and_then(|user| user.get("handle").and_then(Value::as_str));
+            format!("@{}", handle.or(id).unwrap_or_default())
+        })
+        .collect()
+}
+
+/// Compares passcodes without leaking how much of them matched.
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_receive_comment_and_reject_wrong_passcode() {
+        let body = json!({
+            "event_type": "FILE_COMMENT",
+            "passcode": "secret",
+            "file_key": "abc",
+            "file_name": "App",
+            "timestamp": "2024-05-01T10:00:00Z",
+            "comment_id": "42",
+            "comment": [{ "text": "Ping " }, { "mention": "7" }, { "text": " about the header" }],
+            "mentions": [{ "id": "7", "handle": "Ana" }],
+            "triggered_by": { "id": "8", "handle": "Sam" }
+        });
+        let body = body.to_string();
+
+        let event = receive(body.as_bytes(), "secret").unwrap().unwrap();
+        assert_eq!(event.comment.as_deref(), Some("Ping @Ana about the header"));
+        assert_eq!(event.triggered_by.as_deref(), Some("Sam"));
+
+        let rejection = receive(body.as_bytes(), "other").unwrap_err();
+        assert_eq!(rejection.status, 401);
+
+        let forged = json!({ "event_type": "FILE_UPDATE", "passcode": "" }).to_string();
+        let rejection = receive(forged.as_bytes(), "").unwrap_err();
+        assert_eq!(rejection.status, 401);
+
+        let ping = json!({ "event_type": "PING", "passcode": "secret" }).to_string();
+        assert_eq!(receive(ping.as_bytes(), "secret"), Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_delivery_round_trip() {
+        let receiver = WebhookReceiver::bind("127.0.0.1:0", "secret".to_string())
+            .await
+            .unwrap();
+        let mut events = receiver.subscribe();
+        let url = format!("http://{}/", receiver.local_addr());
+        let client = reqwest::Client::new();
+
+        let update = json!({
+            "event_type": "FILE_UPDATE",
+            "passcode": "secret",
+            "file_key": "abc",
+            "file_name": "App",
+            "timestamp": "2024-05-01T10:00:00Z"
+        });
+        let response = client.post(&url).json(&update).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(events.recv().await.unwrap().file_key, "abc");
+
+        let forged = json!({ "event_type": "FILE_UPDATE", "passcode": "guess" });
+        let response = client.post(&url).json(&forged).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_other_requests_are_refused() {
+        let receiver = WebhookReceiver::bind("127.0.0.1:0", "secret".to_string())
+            .await
+            .unwrap();
+        let url = format!("http://{}/", receiver.local_addr());
+        let client = reqwest::Client::new();
+
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 405);
+
+        let response = client.post(&url).body("not json").send().await.unwrap();
+        assert_eq!(response.status(), 400);
+
+        let oversized = vec![b' '; MAX_BODY_BYTES + 1];
+        let response = client.post(&url).body(oversized).send().await.unwrap();
+        assert_eq!(response.status(), 413);
+    }
+}
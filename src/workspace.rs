@@ -0,0 +1,429 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    figma::{
+        export::{self, ExportOptions},
+        FigmaUrlParser,
+    },
+    Error, Result,
+};
+
+pub const MANIFEST_FILE_NAME: &str = "figma-mcp.toml";
+
+/// A project's `figma-mcp.toml`, describing which designs relate to the
+/// repository and what is generated from them.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    /// Directory containing the manifest; output paths are relative to it
+    pub root: PathBuf,
+    pub manifest_path: PathBuf,
+    pub manifest: Manifest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub files: Vec<FileEntry>,
+    #[serde(default)]
+    pub exports: Vec<ExportTarget>,
+    #[serde(default)]
+    pub tokens: Vec<TokenTarget>,
+    #[serde(default)]
+    pub codegen: Vec<CodegenTarget>,
+    #[serde(default)]
+    pub naming: Naming,
+}
+
+/// A Figma file the repository uses, referred to by `name` in targets.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileEntry {
+    pub name: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Nodes exported as image files into `output`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportTarget {
+    pub file: String,
+    pub node_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    /// Keep the full layout box instead of cropping to what is drawn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_absolute_bounds: Option<bool>,
+    /// Leave out overlapping content such as the frame background
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contents_only: Option<bool>,
+    pub output: PathBuf,
+}
+
+impl ExportTarget {
+    pub fn options(&self) -> ExportOptions {
+        ExportOptions {
+            scale: self.scale,
+            use_absolute_bounds: self.use_absolute_bounds.unwrap_or(false),
+            contents_only: self.contents_only.unwrap_or(true),
+        }
+    }
+}
+
+/// Design tokens generated from a file's styles and variables.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenTarget {
+    pub file: String,
+    pub output: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// Components generated from nodes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CodegenTarget {
+    pub file: String,
+    pub node_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    pub output: PathBuf,
+}
+
+/// Naming conventions agents should follow in generated code.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Naming {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub components: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_prefix: Option<String>,
+}
+
+impl Workspace {
+    /// Looks for a manifest in `start` and its ancestors.
+    pub fn find(start: &Path) -> Result<Self> {
+        let manifest_path = start
+            .ancestors()
+            .map(|dir| dir.join(MANIFEST_FILE_NAME))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                Error::NotFound(format!(
+                    "No {} found in {} or its parent directories",
+                    MANIFEST_FILE_NAME,
+                    start.display()
+                ))
+            })?;
+
+        Self::load(&manifest_path)
+    }
+
+    pub fn load(manifest_path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(manifest_path).map_err(|e| {
+            Error::Config(format!("Failed to read {}: {}", manifest_path.display(), e))
+        })?;
+
+        let manifest = Manifest::parse(&content).map_err(|e| {
+            let message = match e {
+                Error::Config(message) | Error::InvalidUrl(message) => message,
+                e => e.to_string(),
+            };
+            Error::Config(format!("Invalid {}: {}", manifest_path.display(), message))
+        })?;
+
+        // Canonical, like the output paths resolved against it
+        let root = manifest_path
+            .parent()
+            .filter(|root| !root.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+        Ok(Self {
+            root,
+            manifest_path: manifest_path.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Returns the file key of a file entry by name.
+    pub fn file_key(&self, name: &str) -> Result<String> {
+        let entry = self.manifest.file(name)?;
+
+        FigmaUrlParser::new().extract_file_id(&entry.url)
+    }
+
+    /// Resolves a manifest output path against the workspace root, refusing
+    /// ones that lead outside it.
+    pub fn output_path(&self, output: &Path) -> Result<PathBuf> {
+        resolve_within(&self.root, output)
+            .map_err(|message| Error::Config(format!("Invalid output path: {}", message)))
+    }
+}
+
+/// Resolves a path a client passed to a tool against `root`, refusing
+/// absolute paths and ones leading outside `root`, through `..` or through
+/// symlinks. The path need not exist yet.
+pub fn resolve_within(root: &Path, path: &Path) -> std::result::Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("cannot resolve {}: {}", root.display(), e))?;
+    let outside = || format!("{} is outside {}", path.display(), root.display());
+
+    let mut resolved = root.clone();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                return Err(format!(
+                    "{} is absolute, expected a path relative to {}",
+                    path.display(),
+                    root.display()
+                ));
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                // Follows symlinks among the parts that exist
+                if let Ok(canonical) = resolved.canonicalize() {
+                    resolved = canonical;
+                }
+            }
+        }
+        if !resolved.starts_with(&root) {
+            return Err(outside());
+        }
+    }
+
+    Ok(resolved)
+}
+
+impl Manifest {
+    /// Parses and validates a manifest: file URLs must be Figma file URLs,
+    /// every target must refer to a declared file and exports must use a
+    /// format and scale Figma accepts.
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(content).map_err(|e| Error::Config(e.to_string()))?;
+
+        let parser = FigmaUrlParser::new();
+        for entry in &manifest.files {
+            parser.extract_file_id(&entry.url)?;
+        }
+
+        let referenced = manifest
+            .exports
+            .iter()
+            .map(|target| &target.file)
+            .chain(manifest.tokens.iter().map(|target| &target.file))
+            .chain(manifest.codegen.iter().map(|target| &target.file));
+        for name in referenced {
+            manifest.file(name)?;
+        }
+
+        let outputs = manifest
+            .exports
+            .iter()
+            .map(|target| &target.output)
+            .chain(manifest.tokens.iter().map(|target| &target.output))
+            .chain(manifest.codegen.iter().map(|target| &target.output));
+        for output in outputs {
+            check_output(output)?;
+        }
+
+        for target in &manifest.exports {
+            let format = target.format.as_deref().unwrap_or("png");
+            export::validate(format, target.scale).map_err(|message| {
+                Error::Config(format!(
+                    "Invalid export to {}: {}",
+                    target.output.display(),
+                    message
+                ))
+            })?;
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn file(&self, name: &str) -> Result<&FileEntry> {
+        self.files
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| Error::Config(format!("Unknown file '{}', add it to [[files]]", name)))
+    }
+}
+
+/// Refuses output paths that are absolute or climb out of the workspace
+/// root through `..`.
+fn check_output(output: &Path) -> Result<()> {
+    let mut depth = 0usize;
+    for component in output.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                return Err(Error::Config(format!(
+                    "Output {} must be relative to the workspace root",
+                    output.display()
+                )));
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                depth = depth.checked_sub(1).ok_or_else(|| {
+                    Error::Config(format!(
+                        "Output {} is outside the workspace root",
+                        output.display()
+                    ))
+                })?;
+            }
+            Component::Normal(_) => depth += 1,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[[files]]
+name = "design-system"
+url = "https://www.figma.com/design/ABC123/Design-System"
+
+[[exports]]
+file = "design-system"
+node_ids = ["1:2", "1:3"]
+format = "svg"
+output = "assets/icons"
+
+[[tokens]]
+file = "design-system"
+output = "src/styles/tokens.css"
+
+[naming]
+components = "PascalCase"
+"#;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(MANIFEST).unwrap();
+
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.exports[0].node_ids, vec!["1:2", "1:3"]);
+        assert_eq!(
+            manifest.tokens[0].output,
+            PathBuf::from("src/styles/tokens.css")
+        );
+        assert_eq!(manifest.naming.components.as_deref(), Some("PascalCase"));
+    }
+
+    #[test]
+    fn test_unknown_file_reference() {
+        let manifest = MANIFEST.replace(
+            "file = \"design-system\"\nnode_ids",
+            "file = \"app\"\nnode_ids",
+        );
+        assert!(Manifest::parse(&manifest).is_err());
+    }
+
+    #[test]
+    fn test_invalid_export_scale() {
+        let manifest = MANIFEST.replace("format = \"svg\"", "format = \"svg\"\nscale = 2.0");
+        let error = Manifest::parse(&manifest).unwrap_err();
+        assert!(error.to_string().contains("assets/icons"));
+    }
+
+    #[test]
+    fn test_invalid_file_url() {
+        let manifest = MANIFEST.replace(
+            "https://www.figma.com/design/ABC123",
+            "https://example.com/ABC123",
+        );
+        assert!(Manifest::parse(&manifest).is_err());
+    }
+
+    #[test]
+    fn test_outputs_outside_root() {
+        for output in ["/home/u/.config/x", "../../x", "assets/../../x"] {
+            let manifest = MANIFEST.replace("assets/icons", output);
+            let error = Manifest::parse(&manifest).unwrap_err();
+            assert!(error.to_string().contains(output), "{}", output);
+        }
+
+        let manifest = MANIFEST.replace("assets/icons", "assets/../icons");
+        assert!(Manifest::parse(&manifest).is_ok());
+    }
+
+    #[test]
+    fn test_output_path_within_root() {
+        let base = std::env::temp_dir().join(format!("figma-mcp-output-{}", std::process::id()));
+        let root = base.join("repo");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(MANIFEST_FILE_NAME), MANIFEST).unwrap();
+        let workspace = Workspace::load(&root.join(MANIFEST_FILE_NAME)).unwrap();
+        let root = workspace.root.clone();
+
+        let output = workspace.output_path(Path::new("assets/icons")).unwrap();
+        assert_eq!(output, root.join("assets").join("icons"));
+        assert!(workspace.output_path(Path::new("../x")).is_err());
+
+        // Nor through a symlink the manifest cannot see
+        #[cfg(unix)]
+        {
+            fs::create_dir_all(base.join("outside")).unwrap();
+            std::os::unix::fs::symlink(base.join("outside"), root.join("assets")).unwrap();
+            assert!(workspace.output_path(Path::new("assets/icons")).is_err());
+        }
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_find_in_parent_directory() {
+        let root = std::env::temp_dir().join(format!("figma-mcp-workspace-{}", std::process::id()));
+        let nested = root.join("src").join("components");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join(MANIFEST_FILE_NAME), MANIFEST).unwrap();
+
+        let workspace = Workspace::find(&nested).unwrap();
+        assert_eq!(workspace.root, root.canonicalize().unwrap());
+        assert_eq!(workspace.file_key("design-system").unwrap(), "ABC123");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_within_root() {
+        let base = std::env::temp_dir().join(format!("figma-mcp-resolve-{}", std::process::id()));
+        let root = base.join("repo");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        let root = root.canonicalize().unwrap();
+
+        let resolved = resolve_within(&root, Path::new("docs/../design/./checkout.zip")).unwrap();
+        assert_eq!(resolved, root.join("design").join("checkout.zip"));
+
+        assert!(resolve_within(&root, Path::new("/etc/passwd")).is_err());
+        assert!(resolve_within(&root, Path::new("../outside")).is_err());
+        assert!(resolve_within(&root, Path::new("docs/../../repo-sibling")).is_err());
+
+        // A symlink inside the root may not lead out of it
+        #[cfg(unix)]
+        {
+            fs::create_dir_all(base.join("outside")).unwrap();
+            std::os::unix::fs::symlink(base.join("outside"), root.join("link")).unwrap();
+            assert!(resolve_within(&root, Path::new("link/file.md")).is_err());
+        }
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+//! Generated Figma-shaped documents for performance tests and benchmarks.
+//!
+//! Real design files are too large (and too confidential) to commit, so the
+//! corpus is generated deterministically with a realistic node mix: pages of
+//! frames containing groups of text, rectangle and instance nodes.
+
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy)]
+pub struct CorpusSpec {
+    pub name: &'static str,
+    pub pages: usize,
+    pub frames_per_page: usize,
+    pub nodes_per_frame: usize,
+}
+
+#[allow(dead_code)]
+pub const SMALL_FILE: CorpusSpec = CorpusSpec {
+    name: "small_file",
+    pages: 2,
+    frames_per_page: 5,
+    nodes_per_frame: 10,
+};
+
+/// About 100k nodes and 11MB of compact JSON.
+#[allow(dead_code)]
+pub const LARGE_FILE: CorpusSpec = CorpusSpec {
+    name: "large_file",
+    pages: 8,
+    frames_per_page: 60,
+    nodes_per_frame: 210,
+};
+
+impl CorpusSpec {
+    #[allow(dead_code)]
+    pub fn node_count(&self) -> usize {
+        let frames = self.pages * self.frames_per_page;
+
+        1 + self.pages + frames + frames * self.nodes_per_frame
+    }
+}
+
+/// Builds a file response shaped like `GET /v1/files/:key` output.
+pub fn generate(spec: CorpusSpec) -> Value {
+    let pages: Vec<Value> = (0..spec.pages)
+        .map(|page| {
+            let frames: Vec<Value> = (0..spec.frames_per_page)
+                .map(|frame| generate_frame(spec, page, frame))
+                .collect();
+
+            json!({
+                "id": format!("{}:0", page),
+                "name": format!("Page {}", page),
+                "type": "CANVAS",
+                "backgroundColor": { "r": 0.96, "g": 0.96, "b": 0.96, "a": 1.0 },
+                "children": frames,
+            })
+        })
+        .collect();
+
+    json!({
+        "name": format!("Generated {}", spec.name),
+        "lastModified": "2024-01-01T00:00:00Z",
+        "version": "1234567890",
+        "schemaVersion": 0,
+        "document": { "id": "0:0", "name": "Document", "type": "DOCUMENT", "children": pages },
+        "components": {},
+        "componentSets": {},
+        "styles": {},
+    })
+}
+
+fn generate_frame(spec: CorpusSpec, page: usize, frame: usize) -> Value {
+    let children: Vec<Value> = (0..spec.nodes_per_frame)
+        .map(|node| generate_node(&format!("{}:{}:{}", page, frame, node), node))
+        .collect();
+
+    json!({
+        "id": format!("{}:{}", page, frame + 1),
+        "name": format!("Screen {}", frame),
+        "type": "FRAME",
+        "absoluteBoundingBox": { "x": (frame * 1500) as f64, "y": 0.0, "width": 1440.0, "height": 900.0 },
+        "layoutMode": "VERTICAL",
+        "itemSpacing": 16.0,
+        "children": children,
+    })
+}
+
+fn generate_node(id: &str, index: usize) -> Value {
+    let y = (index * 32) as f64;
+
+    match index % 3 {
+        0 => json!({
+            "id": id,
+            "name": "Label",
+            "type": "TEXT",
+            "visible": true,
+            "characters": "Lorem ipsum dolor sit amet",
+            "absoluteBoundingBox": { "x": 24.0, "y": y, "width": 320.0, "height": 24.0 },
+            "fills": [{ "type": "SOLID", "color": { "r": 0.1, "g": 0.1, "b": 0.1, "a": 1.0 } }],
+            "style": { "fontFamily": "Inter", "fontWeight": 400, "fontSize": 16.0, "lineHeightPx": 24.0 },
+        }),
+        1 => json!({
+            "id": id,
+            "name": "Background",
+            "type": "RECTANGLE",
+            "absoluteBoundingBox": { "x": 0.0, "y": y, "width": 1440.0, "height": 32.0 },
+            "fills": [{ "type": "SOLID", "color": { "r": 1.0, "g": 1.0, "b": 1.0, "a": 1.0 } }],
+            "cornerRadius": 8.0,
+        }),
+        _ => json!({
+            "id": id,
+            "name": "Button",
+            "type": "INSTANCE",
+            "componentId": "99:1",
+            "absoluteBoundingBox": { "x": 24.0, "y": y, "width": 120.0, "height": 32.0 },
+            "constraints": { "vertical": "TOP", "horizontal": "LEFT" },
+        }),
+    }
+}
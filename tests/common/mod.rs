@@ -1,17 +1,58 @@
-use std::fs;
-use std::path::Path;
+pub mod corpus;
 
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+
+#[allow(dead_code)]
 pub fn load_fixture(fixture_path: &str) -> String {
     let path = Path::new("tests/fixtures").join(fixture_path);
-    fs::read_to_string(path).expect(&format!("Failed to read fixture: {}", fixture_path))
+    fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to read fixture: {}", fixture_path))
 }
 
+#[allow(dead_code)]
 pub fn load_json_fixture<T>(fixture_path: &str) -> T 
 where
     T: serde::de::DeserializeOwned,
 {
     let content = load_fixture(fixture_path);
-    serde_json::from_str(&content).expect(&format!("Failed to parse JSON fixture: {}", fixture_path))
+    serde_json::from_str(&content).unwrap_or_else(|_| panic!("Failed to parse JSON fixture: {}", fixture_path))
+}
+
+/// Parses a JSON file straight from a memory map, without first copying the
+/// whole file into a `String`.
+#[allow(dead_code)]
+pub fn load_json_mmap<T>(path: &Path) -> T
+where
+    T: serde::de::DeserializeOwned,
+{
+    let file = File::open(path).unwrap_or_else(|_| panic!("Failed to open {}", path.display()));
+    // Safety: corpus files are written once and never modified while mapped
+    let mmap = unsafe { Mmap::map(&file) }
+        .unwrap_or_else(|_| panic!("Failed to map {}", path.display()));
+
+    serde_json::from_slice(&mmap).unwrap_or_else(|_| panic!("Failed to parse {}", path.display()))
+}
+
+/// Returns the path of a generated corpus file, generating it on first use.
+#[allow(dead_code)]
+pub fn corpus_fixture(spec: corpus::CorpusSpec) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("corpus");
+    let path = dir.join(format!("{}.json", spec.name));
+    if path.exists() {
+        return path;
+    }
+
+    fs::create_dir_all(&dir).expect("Failed to create corpus directory");
+
+    // Write to a temporary file first so parallel tests never see a partial file
+    let temp_path = dir.join(format!("{}.{}.tmp", spec.name, std::process::id()));
+    let file = File::create(&temp_path).expect("Failed to create corpus file");
+    serde_json::to_writer(file, &corpus::generate(spec)).expect("Failed to write corpus file");
+    fs::rename(&temp_path, &path).expect("Failed to move corpus file into place");
+
+    path
 }
 
 #[allow(dead_code)]
@@ -1,4 +1,9 @@
-use figma_mcp::figma::FigmaClient;
+use std::time::Duration;
+
+use figma_mcp::{
+    figma::{retry::RetryPolicy, FigmaClient},
+    ApiError, Error,
+};
 
 #[tokio::test]
 async fn test_client_creation() {
@@ -13,11 +18,213 @@ async fn test_invalid_token_format() {
     assert!(client.is_err());
 }
 
-// Note: For now, these tests just verify the client structure
-// In a full implementation, we would:
-// 1. Make the client configurable to use different base URLs
-// 2. Set up proper mock server integration
-// 3. Test actual API calls with mocked responses
+async fn mock_client(figma: &mockito::Server) -> FigmaClient {
+    FigmaClient::builder("test-token".to_string())
+        .base_url(format!("{}/v1", figma.url()))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_get_file_from_base_url() {
+    let mut figma = mockito::Server::new_async().await;
+    let mock = figma
+        .mock("GET", "/v1/files/ABC123")
+        .match_query(mockito::Matcher::Exact("depth=1".into()))
+        .match_header("X-Figma-Token", "test-token")
+        .with_body(r#"{"name":"Design System","document":{"children":[]}}"#)
+        .create_async()
+        .await;
+
+    let file = mock_client(&figma)
+        .await
+        .get_file("ABC123", Some(1))
+        .await
+        .unwrap();
+    assert_eq!(file["name"], "Design System");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_concurrent_identical_reads_share_a_request() {
+    let mut figma = mockito::Server::new_async().await;
+    let mock = figma
+        .mock("GET", "/v1/files/ABC123")
+        .match_query(mockito::Matcher::Exact("depth=1".into()))
+        .with_body(r#"{"name":"Design System","document":{"children":[]}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let other_depth = figma
+        .mock("GET", "/v1/files/ABC123")
+        .match_query(mockito::Matcher::Exact("depth=2".into()))
+        .with_body(r#"{"name":"Design System","document":{"children":[]}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = mock_client(&figma).await;
+    let other = client.clone();
+    let (first, second, deeper) = tokio::join!(
+        client.get_file("ABC123", Some(1)),
+        other.get_file("ABC123", Some(1)),
+        client.get_file("ABC123", Some(2)),
+    );
+
+    assert_eq!(first.unwrap()["name"], "Design System");
+    assert_eq!(second.unwrap()["name"], "Design System");
+    assert!(deeper.is_ok());
+    mock.assert_async().await;
+    other_depth.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_unchanged_file_is_not_downloaded_again() {
+    let mut figma = mockito::Server::new_async().await;
+    let download = figma
+        .mock("GET", "/v1/files/ABC123")
+        .match_header("If-None-Match", mockito::Matcher::Missing)
+        .with_header("ETag", "\"v1\"")
+        .with_body(r#"{"name":"Design System","document":{"children":[]}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let revalidation = figma
+        .mock("GET", "/v1/files/ABC123")
+        .match_header("If-None-Match", "\"v1\"")
+        .with_status(304)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let client = mock_client(&figma).await;
+    let first = client.get_file("ABC123", None).await.unwrap();
+    let second = client.get_file("ABC123", None).await.unwrap();
+    let third = client.clone().get_file("ABC123", None).await.unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(second, third);
+    download.assert_async().await;
+    revalidation.assert_async().await;
+
+    assert_eq!(client.clear_cached_responses().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_cached_responses_are_not_shared_between_tokens() {
+    let mut figma = mockito::Server::new_async().await;
+    let download = figma
+        .mock("GET", "/v1/files/ABC123")
+        .match_header("If-None-Match", mockito::Matcher::Missing)
+        .with_header("ETag", "\"v1\"")
+        .with_body(r#"{"name":"Design System","document":{"children":[]}}"#)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let client = mock_client(&figma).await;
+    client.get_file("ABC123", None).await.unwrap();
+    // A token without access must not be answered from the other's cache
+    let other = client.with_token("other-token".to_string()).unwrap();
+    other.get_file("ABC123", None).await.unwrap();
+
+    download.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_error_from_base_url() {
+    let mut figma = mockito::Server::new_async().await;
+    figma
+        .mock("GET", "/v1/files/ABC123")
+        .with_status(403)
+        .with_body(r#"{"status":403,"err":"Invalid token"}"#)
+        .create_async()
+        .await;
+
+    let result = mock_client(&figma).await.get_file("ABC123", None).await;
+    match result {
+        Err(Error::Api(error)) => {
+            assert_eq!(error.status, 403);
+            assert_eq!(error.message, "Invalid token");
+        }
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+}
+
+fn retrying_client(figma: &mockito::Server) -> FigmaClient {
+    FigmaClient::builder("test-token".to_string())
+        .base_url(format!("{}/v1", figma.url()))
+        .retry(RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_reads_are_retried() {
+    let mut figma = mockito::Server::new_async().await;
+    let unavailable = figma
+        .mock("GET", "/v1/files/ABC123")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    figma
+        .mock("GET", "/v1/files/ABC123")
+        .with_body(r#"{"name":"Design System"}"#)
+        .create_async()
+        .await;
+
+    let file = retrying_client(&figma)
+        .get_file("ABC123", None)
+        .await
+        .unwrap();
+    assert_eq!(file["name"], "Design System");
+    unavailable.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_changes_are_not_retried() {
+    let mut figma = mockito::Server::new_async().await;
+    let unavailable = figma
+        .mock("DELETE", "/v1/files/ABC123/comments/1")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let result = retrying_client(&figma)
+        .delete_comment("ABC123", "1")
+        .await;
+    assert!(matches!(result, Err(Error::Api(error)) if error.status == 503));
+    unavailable.assert_async().await;
+}
+
+#[test]
+fn test_invalid_proxy_is_rejected() {
+    let client = FigmaClient::builder("test-token".to_string())
+        .proxy("not a proxy")
+        .connect_timeout(Duration::from_secs(5))
+        .timeout(Duration::from_secs(30))
+        .build();
+
+    assert!(matches!(client, Err(Error::Config(_))));
+}
+
+#[test]
+fn test_base_url_requires_https() {
+    let client = FigmaClient::new("test-token".to_string()).unwrap();
+
+    assert!(client.with_base_url("http://127.0.0.1:8080/v1/").is_ok());
+    assert!(client.with_base_url("http://figma.example.com/v1").is_err());
+    assert!(FigmaClient::builder("test-token".to_string())
+        .base_url("not a url")
+        .build()
+        .is_err());
+}
 
 #[test]
 fn test_client_token_storage() {
@@ -35,4 +242,89 @@ fn test_client_debug_and_clone() {
     // Ensure Debug trait works
     let debug_output = format!("{:?}", client);
     assert!(debug_output.contains("FigmaClient"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_api_error_from_legacy_body() {
+    let error = ApiError::from_body(403, br#"{"status":403,"err":"Invalid token"}"#);
+
+    assert_eq!(error.status, 403);
+    assert_eq!(error.message, "Invalid token");
+    assert_eq!(error.reason, None);
+    assert!(error.to_string().starts_with("HTTP 403: Invalid token. "));
+    assert!(error.hint().unwrap().contains("scopes"));
+}
+
+#[test]
+fn test_api_error_from_message_body() {
+    let body = br#"{"error":true,"status":404,"message":"Not found","reason":"File deleted"}"#;
+    let error = ApiError::from_body(404, body);
+
+    assert_eq!(error.message, "Not found");
+    assert_eq!(error.reason.as_deref(), Some("File deleted"));
+    assert!(error.to_string().contains("Not found (File deleted)"));
+}
+
+#[test]
+fn test_api_error_from_non_json_body() {
+    let error = ApiError::from_body(502, b"<html>Bad Gateway</html>");
+
+    assert_eq!(error.status, 502);
+    assert_eq!(error.message, "<html>Bad Gateway</html>");
+    assert!(error.hint().is_some());
+}
+
+#[test]
+fn test_api_error_status_from_body_overrides_http_status() {
+    let json = serde_json::json!({ "status": 400, "err": "Invalid parameter: ids" });
+    let error = ApiError::from_json(200, &json);
+
+    assert_eq!(error.status, 400);
+    assert_eq!(error.message, "Invalid parameter: ids");
+}
+
+#[tokio::test]
+async fn test_unmodified_file_is_reused() {
+    let mut figma = mockito::Server::new_async().await;
+    let file = figma
+        .mock("GET", "/v1/files/ABC123")
+        .with_body(r#"{"name":"App","version":"42","lastModified":"2026-01-01T10:00:00Z","document":{"children":[]}}"#)
+        .expect(2)
+        .create_async()
+        .await;
+    let unchanged = figma
+        .mock("GET", "/v1/files/ABC123/meta")
+        .with_body(r#"{"file":{"version":"42","last_touched_at":"2026-01-01T10:00:00Z"}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = FigmaClient::builder("test-token".to_string())
+        .base_url(format!("{}/v1", figma.url()))
+        .response_cache(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let first = client.get_file("ABC123", None).await.unwrap();
+    // Within the TTL, without asking Figma
+    let second = client.get_file("ABC123", None).await.unwrap();
+    assert_eq!(first, second);
+
+    // Past it, once the metadata shows no modification
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let third = client.get_file("ABC123", None).await.unwrap();
+    assert_eq!(first, third);
+    unchanged.assert_async().await;
+
+    // Modified since, so read again
+    unchanged.remove_async().await;
+    figma
+        .mock("GET", "/v1/files/ABC123/meta")
+        .with_body(r#"{"file":{"version":"42","last_touched_at":"2026-01-02T09:30:00Z"}}"#)
+        .create_async()
+        .await;
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    client.get_file("ABC123", None).await.unwrap();
+    file.assert_async().await;
+
+    assert_eq!(client.forget_file("ABC123").unwrap(), 1);
+}
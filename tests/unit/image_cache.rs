@@ -0,0 +1,263 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use figma_mcp::figma::ImageCache;
+
+fn register(cache: &ImageCache, node_id: &str, figma_url: &str) -> String {
+    cache
+        .register_export(
+            "ABC123".to_string(),
+            node_id.to_string(),
+            "png".to_string(),
+            1.0,
+            figma_url.to_string(),
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_register_and_read_entry() {
+    let cache = ImageCache::new();
+
+    let uri = register(&cache, "1:2", "https://figma-images.example/1");
+    assert_eq!(uri, "figma://file/ABC123/node/1:2.png");
+
+    let entry = cache.get_entry(&uri).unwrap().unwrap();
+    assert_eq!(entry.figma_url, "https://figma-images.example/1");
+    assert!(entry.cached_data.is_none());
+}
+
+#[test]
+fn test_update_cached_data_for_current_url() {
+    let cache = ImageCache::new();
+    let uri = register(&cache, "1:2", "https://figma-images.example/1");
+
+    let stored = cache
+        .update_cached_data(&uri, "https://figma-images.example/1", vec![1, 2, 3])
+        .unwrap();
+    assert!(stored);
+
+    let entry = cache.get_entry(&uri).unwrap().unwrap();
+    assert_eq!(entry.cached_data, Some(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_stale_download_is_not_cached_after_reexport() {
+    let cache = ImageCache::new();
+    let uri = register(&cache, "1:2", "https://figma-images.example/old");
+
+    // A read started downloading the old URL, then the node was re-exported
+    register(&cache, "1:2", "https://figma-images.example/new");
+
+    let stored = cache
+        .update_cached_data(&uri, "https://figma-images.example/old", vec![0xde, 0xad])
+        .unwrap();
+    assert!(!stored);
+
+    let entry = cache.get_entry(&uri).unwrap().unwrap();
+    assert_eq!(entry.figma_url, "https://figma-images.example/new");
+    assert!(entry.cached_data.is_none());
+}
+
+#[test]
+fn test_chunked_reads() {
+    let uri = "figma://file/ABC123/node/1:2.pdf";
+    let manifest = ImageCache::chunk_manifest(uri, "application/pdf", 10, 4);
+    assert_eq!(manifest.chunks.len(), 3);
+    assert_eq!(
+        manifest.chunks[2],
+        "figma://file/ABC123/node/1:2.pdf?chunk=2"
+    );
+
+    assert_eq!(ImageCache::split_chunk(&manifest.chunks[2]), (uri, Some(2)));
+    assert_eq!(ImageCache::split_chunk(uri), (uri, None));
+
+    let data: Vec<u8> = (0..10).collect();
+    assert_eq!(ImageCache::chunk(&data, 2, 4), Some(&data[8..]));
+    assert_eq!(ImageCache::chunk(&data, 3, 4), None);
+    assert_eq!(ImageCache::chunk(&data, 0, 0), Some(&data[..]));
+}
+
+#[test]
+fn test_encodings_are_reused_per_export() {
+    let cache = ImageCache::new();
+    let uri = "figma://file/ABC123/node/1:2.png";
+    let exported = SystemTime::now();
+
+    assert_eq!(cache.encode(uri, exported, b"first").unwrap(), "Zmlyc3Q=");
+    // Same export: the earlier encoding is served without encoding again
+    assert_eq!(cache.encode(uri, exported, b"other").unwrap(), "Zmlyc3Q=");
+
+    let reexported = exported + Duration::from_secs(1);
+    assert_eq!(cache.encode(uri, reexported, b"other").unwrap(), "b3RoZXI=");
+}
+
+#[test]
+fn test_clear_drops_entries_and_encodings() {
+    let cache = ImageCache::new();
+    let uri = register(&cache, "1:2", "https://figma.example/1");
+    let exported = cache.get_entry(&uri).unwrap().unwrap().export_time;
+    cache.update_cached_data(&uri, "https://figma.example/1", b"first".to_vec()).unwrap();
+    cache.encode(&uri, exported, b"first").unwrap();
+
+    assert_eq!(cache.clear().unwrap(), 1);
+    assert!(cache.list_all().unwrap().is_empty());
+    // The earlier encoding is gone, so the same export is encoded afresh
+    assert_eq!(cache.encode(&uri, exported, b"other").unwrap(), "b3RoZXI=");
+}
+
+#[test]
+fn test_update_unknown_resource_fails() {
+    let cache = ImageCache::new();
+
+    let result = cache.update_cached_data("figma://file/X/node/1:2.png", "url", vec![]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_concurrent_exports_and_reads() {
+    let cache = Arc::new(ImageCache::new());
+
+    let handles: Vec<_> = (0..16)
+        .map(|worker| {
+            let cache = Arc::clone(&cache);
+            thread::spawn(move || {
+                for round in 0..200 {
+                    let node_id = format!("{}:{}", worker % 4, round % 10);
+                    let figma_url = format!("https://figma-images.example/{}/{}", worker, round);
+                    let uri = register(&cache, &node_id, &figma_url);
+
+                    let data = figma_url.clone().into_bytes();
+                    let _ = cache.update_cached_data(&uri, &figma_url, data).unwrap();
+
+                    let entry = cache.get_entry(&uri).unwrap().unwrap();
+                    if let Some(data) = entry.cached_data {
+                        // Cached bytes must always belong to the entry's current URL
+                        assert_eq!(data, entry.figma_url.into_bytes());
+                    }
+
+                    cache.list_all().unwrap();
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(cache.list_all().unwrap().len(), 40);
+}
+
+#[test]
+fn test_invalidate_file_marks_exports_stale() {
+    let cache = ImageCache::new();
+    let uri = register(&cache, "1:2", "https://figma-images.example/1");
+    cache
+        .update_cached_data(&uri, "https://figma-images.example/1", vec![1, 2, 3])
+        .unwrap();
+    let generated = cache
+        .register_generated("ABC123".to_string(), "1:2".to_string(), "contrast", vec![4])
+        .unwrap();
+    let other = cache
+        .register_export(
+            "DEF456".to_string(),
+            "1:2".to_string(),
+            "png".to_string(),
+            1.0,
+            "https://figma-images.example/2".to_string(),
+        )
+        .unwrap();
+
+    assert_eq!(cache.invalidate_file("ABC123").unwrap(), 2);
+
+    let entry = cache.get_entry(&uri).unwrap().unwrap();
+    assert!(entry.stale);
+    assert!(entry.cached_data.is_none());
+    assert!(cache.get_entry(&generated).unwrap().is_none());
+    assert!(!cache.get_entry(&other).unwrap().unwrap().stale);
+
+    // Exporting again makes the entry current
+    register(&cache, "1:2", "https://figma-images.example/3");
+    assert!(!cache.get_entry(&uri).unwrap().unwrap().stale);
+}
+
+#[test]
+fn test_filename_from_node_name() {
+    let cache = ImageCache::new();
+    let uri = cache
+        .register_export(
+            "ABC123".to_string(),
+            "1:2".to_string(),
+            "png".to_string(),
+            2.0,
+            "https://figma-images.example/1".to_string(),
+        )
+        .unwrap();
+
+    let entry = cache.get_entry(&uri).unwrap().unwrap();
+    assert_eq!(entry.filename(&uri), "1-2@2x.png");
+
+    cache.set_name(&uri, "Checkout Screen").unwrap();
+    let entry = cache.get_entry(&uri).unwrap().unwrap();
+    assert_eq!(entry.filename(&uri), "checkout-screen-1-2@2x.png");
+    assert_eq!(entry.filename(&format!("{}?chunk=1", uri)), "checkout-screen-1-2@2x.png");
+
+    // Re-exports and images generated from the export keep the name
+    let uri = register(&cache, "1:2", "https://figma-images.example/2");
+    cache.set_name(&uri, "Checkout Screen").unwrap();
+    register(&cache, "1:2", "https://figma-images.example/3");
+    let generated = cache
+        .register_generated("ABC123".to_string(), "1:2".to_string(), "contrast", vec![4])
+        .unwrap();
+    let entry = cache.get_entry(&uri).unwrap().unwrap();
+    assert_eq!(entry.filename(&uri), "checkout-screen-1-2.png");
+    let entry = cache.get_entry(&generated).unwrap().unwrap();
+    assert_eq!(entry.filename(&generated), "checkout-screen-1-2.contrast.png");
+}
+
+#[test]
+fn test_fractional_scales_are_distinct_resources() {
+    let cache = ImageCache::new();
+    let export = |scale: f64, url: &str| {
+        cache
+            .register_export(
+                "ABC123".to_string(),
+                "1:2".to_string(),
+                "png".to_string(),
+                scale,
+                url.to_string(),
+            )
+            .unwrap()
+    };
+
+    let double = export(2.0, "https://figma-images.example/2x");
+    let fractional = export(2.5, "https://figma-images.example/2.5x");
+    let half = export(0.5, "https://figma-images.example/0.5x");
+    assert_eq!(double, "figma://file/ABC123/node/1:2@2x.png");
+    assert_eq!(fractional, "figma://file/ABC123/node/1:2@2.5x.png");
+    assert_eq!(half, "figma://file/ABC123/node/1:2@0.5x.png");
+
+    let entry = cache.get_entry(&double).unwrap().unwrap();
+    assert_eq!(entry.figma_url, "https://figma-images.example/2x");
+    let entry = cache.get_entry(&fractional).unwrap().unwrap();
+    assert_eq!(entry.scale, 2.5);
+    assert_eq!(entry.filename(&fractional), "1-2@2.5x.png");
+}
+
+#[test]
+fn test_generated_animation_keeps_format() {
+    let cache = ImageCache::new();
+    let uri = register(&cache, "1:2", "https://figma-images.example/1");
+    cache.set_name(&uri, "Onboarding").unwrap();
+
+    let animation = cache
+        .register_generated_as("ABC123".to_string(), "1:2".to_string(), "animation", "gif", vec![4])
+        .unwrap();
+
+    assert_eq!(animation, "figma://file/ABC123/node/1:2.animation.gif");
+    let entry = cache.get_entry(&animation).unwrap().unwrap();
+    assert_eq!(entry.filename(&animation), "onboarding-1-2.animation.gif");
+    assert_eq!(ImageCache::get_mime_type(&entry.format), "image/gif");
+}
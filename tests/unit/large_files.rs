@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+use figma_mcp::{figma::normalize, output};
+use serde_json::Value;
+
+use crate::common::{corpus, corpus_fixture, load_json_mmap};
+
+// Generous bounds so the tests stay reliable on slow CI machines in debug
+// builds, while still catching accidental quadratic behavior on big files.
+const PARSE_BUDGET: Duration = Duration::from_secs(20);
+const PROCESS_BUDGET: Duration = Duration::from_secs(20);
+
+fn count_nodes(node: &Value) -> usize {
+    let children = node["children"]
+        .as_array()
+        .map_or(0, |children| children.iter().map(count_nodes).sum());
+
+    1 + children
+}
+
+#[test]
+fn test_small_corpus_shape() {
+    let file: Value = load_json_mmap(&corpus_fixture(corpus::SMALL_FILE));
+
+    assert_eq!(
+        count_nodes(&file["document"]),
+        corpus::SMALL_FILE.node_count()
+    );
+    assert_eq!(file["document"]["children"][0]["type"], "CANVAS");
+}
+
+#[test]
+fn test_large_corpus_parse_within_budget() {
+    let path = corpus_fixture(corpus::LARGE_FILE);
+
+    let start = Instant::now();
+    let file: Value = load_json_mmap(&path);
+    let elapsed = start.elapsed();
+
+    assert_eq!(
+        count_nodes(&file["document"]),
+        corpus::LARGE_FILE.node_count()
+    );
+    assert!(elapsed < PARSE_BUDGET, "parsing took {:?}", elapsed);
+}
+
+#[test]
+fn test_large_corpus_canonicalize_and_serialize_within_budget() {
+    let mut file: Value = load_json_mmap(&corpus_fixture(corpus::LARGE_FILE));
+
+    let start = Instant::now();
+    normalize::canonicalize(&mut file);
+    let serialized = output::to_compact_json(&file);
+    let elapsed = start.elapsed();
+
+    assert!(serialized.len() > 10_000_000);
+    assert!(elapsed < PROCESS_BUDGET, "processing took {:?}", elapsed);
+}
@@ -1,2 +1,4 @@
 pub mod url_parsing;
-pub mod api_client;
\ No newline at end of file
+pub mod api_client;
+pub mod image_cache;
+pub mod large_files;
\ No newline at end of file
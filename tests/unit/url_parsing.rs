@@ -35,19 +35,23 @@ fn test_parse_file_url_with_additional_params() {
 }
 
 #[test]
-fn test_parse_project_url_returns_unknown() {
+fn test_parse_project_url() {
     let parser = FigmaUrlParser::new();
     
     let result = parser.parse("https://www.figma.com/files/project/123456").unwrap();
-    assert_eq!(result.url_type, FigmaUrlType::Unknown);
+    assert_eq!(result.url_type, FigmaUrlType::Project {
+        project_id: "123456".to_string(),
+    });
 }
 
 #[test]
-fn test_parse_team_url_returns_unknown() {
+fn test_parse_team_url() {
     let parser = FigmaUrlParser::new();
     
     let result = parser.parse("https://www.figma.com/files/team/789012").unwrap();
-    assert_eq!(result.url_type, FigmaUrlType::Unknown);
+    assert_eq!(result.url_type, FigmaUrlType::Team {
+        team_id: "789012".to_string(),
+    });
 }
 
 #[test]